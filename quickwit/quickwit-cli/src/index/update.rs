@@ -200,6 +200,10 @@ pub async fn update_search_settings_cli(args: SearchSettingsArgs) -> anyhow::Res
     let metadata = qw_client.indexes().get(&args.index_id).await?;
     let search_settings = SearchSettings {
         default_search_fields: args.default_search_fields,
+        term_dict_warmup_policy: metadata
+            .index_config
+            .search_settings
+            .term_dict_warmup_policy,
     };
     println!(
         "New search settings: {}",
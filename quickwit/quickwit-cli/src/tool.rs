@@ -554,6 +554,8 @@ pub async fn local_search_cli(args: LocalSearchArgs) -> anyhow::Result<()> {
         format: BodyFormat::Json,
         sort_by,
         count_all: CountHits::CountAll,
+        collect_split_stats: false,
+        num_slowest_splits: None,
     };
     let search_request =
         search_request_from_api_request(vec![args.index_id], search_request_query_string)?;
@@ -0,0 +1,121 @@
+// Copyright (C) 2024 Quickwit, Inc.
+//
+// Quickwit is offered under the AGPL v3.0 and as commercial software.
+// For commercial licensing, contact us at hello@quickwit.io.
+//
+// AGPL:
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+//! Helpers to manipulate [`Bound`]s, e.g. to intersect ranges.
+
+use std::ops::Bound;
+
+/// Applies `f` to the value of the bound, if any.
+///
+/// Equivalent to `Bound::map`, which is unstable.
+///
+/// ```
+/// use std::ops::Bound;
+///
+/// use quickwit_common::bounds::map_bound;
+///
+/// assert_eq!(map_bound(Bound::Included(1), |value| value * 10), Bound::Included(10));
+/// assert_eq!(map_bound(Bound::<i64>::Unbounded, |value| value * 10), Bound::Unbounded);
+/// ```
+pub fn map_bound<T, U>(bound: Bound<T>, f: impl FnOnce(T) -> U) -> Bound<U> {
+    use Bound::*;
+    match bound {
+        Unbounded => Unbounded,
+        Included(x) => Included(f(x)),
+        Excluded(x) => Excluded(f(x)),
+    }
+}
+
+/// Returns the most restrictive of two lower bounds, that is, the lower bound of the
+/// intersection of two ranges.
+///
+/// On a tie, the excluded bound wins, since it is the most restrictive.
+///
+/// ```
+/// use std::ops::Bound;
+///
+/// use quickwit_common::bounds::max_bound;
+///
+/// assert_eq!(max_bound(Bound::Unbounded, Bound::Included(1)), Bound::Included(1));
+/// assert_eq!(max_bound(Bound::Included(1), Bound::Excluded(2)), Bound::Excluded(2));
+/// assert_eq!(max_bound(Bound::Included(2), Bound::Excluded(2)), Bound::Excluded(2));
+/// assert_eq!(max_bound(Bound::Excluded(1), Bound::Included(2)), Bound::Included(2));
+/// ```
+pub fn max_bound<T: Ord + Copy>(left: Bound<T>, right: Bound<T>) -> Bound<T> {
+    use Bound::*;
+    match (left, right) {
+        (Unbounded, right) => right,
+        (left, Unbounded) => left,
+        (Included(left), Included(right)) => Included(left.max(right)),
+        (Excluded(left), Excluded(right)) => Excluded(left.max(right)),
+        (excluded_total @ Excluded(excluded), included_total @ Included(included)) => {
+            if included > excluded {
+                included_total
+            } else {
+                excluded_total
+            }
+        }
+        (included_total @ Included(included), excluded_total @ Excluded(excluded)) => {
+            if included > excluded {
+                included_total
+            } else {
+                excluded_total
+            }
+        }
+    }
+}
+
+/// Returns the most restrictive of two upper bounds, that is, the upper bound of the
+/// intersection of two ranges.
+///
+/// On a tie, the excluded bound wins, since it is the most restrictive.
+///
+/// ```
+/// use std::ops::Bound;
+///
+/// use quickwit_common::bounds::min_bound;
+///
+/// assert_eq!(min_bound(Bound::Included(1), Bound::Unbounded), Bound::Included(1));
+/// assert_eq!(min_bound(Bound::Included(2), Bound::Excluded(1)), Bound::Excluded(1));
+/// assert_eq!(min_bound(Bound::Excluded(2), Bound::Included(2)), Bound::Excluded(2));
+/// assert_eq!(min_bound(Bound::Excluded(2), Bound::Included(1)), Bound::Included(1));
+/// ```
+pub fn min_bound<T: Ord + Copy>(left: Bound<T>, right: Bound<T>) -> Bound<T> {
+    use Bound::*;
+    match (left, right) {
+        (Unbounded, right) => right,
+        (left, Unbounded) => left,
+        (Included(left), Included(right)) => Included(left.min(right)),
+        (Excluded(left), Excluded(right)) => Excluded(left.min(right)),
+        (excluded_total @ Excluded(excluded), included_total @ Included(included)) => {
+            if included < excluded {
+                included_total
+            } else {
+                excluded_total
+            }
+        }
+        (included_total @ Included(included), excluded_total @ Excluded(excluded)) => {
+            if included < excluded {
+                included_total
+            } else {
+                excluded_total
+            }
+        }
+    }
+}
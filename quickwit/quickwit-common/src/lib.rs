@@ -22,6 +22,7 @@
 mod coolid;
 
 pub mod binary_heap;
+pub mod bounds;
 pub mod fs;
 pub mod io;
 mod kill_switch;
@@ -56,8 +57,10 @@ use std::str::FromStr;
 
 pub use coolid::new_coolid;
 pub use kill_switch::KillSwitch;
+use once_cell::sync::Lazy;
 pub use path_hasher::PathHasher;
 pub use progress::{Progress, ProtectedZoneGuard};
+use regex::Regex;
 pub use stream_utils::{BoxStream, ServiceStream};
 use tracing::{error, info};
 
@@ -141,6 +144,48 @@ pub fn no_color() -> bool {
     matches!(env::var("NO_COLOR"), Ok(value) if !value.is_empty())
 }
 
+/// Checks whether an index ID pattern conforms to Quickwit conventions.
+/// Index ID patterns accept the same characters as identifiers AND accept `*`
+/// chars to allow for glob-like patterns.
+pub fn validate_index_id_pattern(pattern: &str, allow_negative: bool) -> anyhow::Result<()> {
+    static IDENTIFIER_REGEX_WITH_GLOB_PATTERN: Lazy<Regex> = Lazy::new(|| {
+        Regex::new(r"^[a-zA-Z\*][a-zA-Z0-9-_\.\*]{0,254}$")
+            .expect("regular expression should compile")
+    });
+    static IDENTIFIER_REGEX_WITH_GLOB_PATTERN_NEGATIVE: Lazy<Regex> = Lazy::new(|| {
+        Regex::new(r"^-?[a-zA-Z\*][a-zA-Z0-9-_\.\*]{0,254}$")
+            .expect("regular expression should compile")
+    });
+
+    let regex = if allow_negative {
+        &IDENTIFIER_REGEX_WITH_GLOB_PATTERN_NEGATIVE
+    } else {
+        &IDENTIFIER_REGEX_WITH_GLOB_PATTERN
+    };
+
+    if !regex.is_match(pattern) {
+        anyhow::bail!(
+            "index ID pattern `{pattern}` is invalid: patterns must match the following regular \
+             expression: `^[a-zA-Z\\*][a-zA-Z0-9-_\\.\\*]{{0,254}}$`"
+        );
+    }
+    // Forbid multiple stars in the pattern to force the user making simpler patterns
+    // as multiple stars does not bring any value.
+    if pattern.contains("**") {
+        anyhow::bail!(
+            "index ID pattern `{pattern}` is invalid: patterns must not contain multiple \
+             consecutive `*`"
+        );
+    }
+    // If there is no star in the pattern, we need at least 3 characters.
+    if !pattern.contains('*') && pattern.len() < 3 {
+        anyhow::bail!(
+            "index ID pattern `{pattern}` is invalid: an index ID must have at least 3 characters"
+        );
+    }
+    Ok(())
+}
+
 #[macro_export]
 macro_rules! ignore_error_kind {
     ($kind:path, $expr:expr) => {
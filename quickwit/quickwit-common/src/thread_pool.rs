@@ -17,17 +17,36 @@
 // You should have received a copy of the GNU Affero General Public License
 // along with this program. If not, see <http://www.gnu.org/licenses/>.
 
+use std::any::Any;
 use std::fmt;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
 use std::sync::Arc;
+use std::time::Duration;
 
+use futures::future::{self, Either};
 use futures::{Future, TryFutureExt};
 use once_cell::sync::Lazy;
 use prometheus::IntGauge;
+use rayon::prelude::*;
 use tokio::sync::oneshot;
-use tracing::error;
+use tracing::{error, warn};
 
 use crate::metrics::{new_gauge_vec, GaugeGuard, IntGaugeVec, OwnedGaugeGuard};
 
+/// Function restoring a captured context on the thread running a task. The context stays restored
+/// until the returned guard is dropped.
+pub type RestoreContextFn = Box<dyn FnOnce() -> Box<dyn Any> + Send>;
+
+/// Propagates a context from the task submitting a CPU-intensive task to the thread pool worker
+/// running it, e.g. an OpenTelemetry context.
+///
+/// The current `tracing` span is always propagated: this is only required for contexts stored in
+/// thread or task locals, which don't follow the task onto the worker.
+pub trait ContextPropagator: Send + Sync + 'static {
+    /// Captures the context of the submitting task.
+    fn capture(&self) -> RestoreContextFn;
+}
+
 /// An executor backed by a thread pool to run CPU-intensive tasks.
 ///
 /// tokio::spawn_blocking should only used for IO-bound tasks, as it has not limit on its
@@ -37,10 +56,27 @@ pub struct ThreadPool {
     thread_pool: Arc<rayon::ThreadPool>,
     ongoing_tasks: IntGauge,
     pending_tasks: IntGauge,
+    task_counts: Arc<TaskCounts>,
+    context_propagator_opt: Option<Arc<dyn ContextPropagator>>,
+    shutting_down: Arc<AtomicBool>,
+    max_pending_tasks_opt: Option<usize>,
 }
 
 impl ThreadPool {
     pub fn new(name: &'static str, num_threads_opt: Option<usize>) -> ThreadPool {
+        Self::new_with_affinity(name, num_threads_opt, Vec::new())
+    }
+
+    /// Creates a thread pool whose workers are pinned to the given CPU cores.
+    ///
+    /// Workers are assigned to `core_ids` in a round-robin fashion. This makes it possible to
+    /// keep a pool on a single NUMA node, for instance. An empty `core_ids` leaves the workers
+    /// unpinned. Workers that fail to pin log a warning and run unpinned.
+    pub fn new_with_affinity(
+        name: &'static str,
+        num_threads_opt: Option<usize>,
+        core_ids: Vec<usize>,
+    ) -> ThreadPool {
         let mut rayon_pool_builder = rayon::ThreadPoolBuilder::new()
             .thread_name(move |thread_id| format!("quickwit-{name}-{thread_id}"))
             .panic_handler(move |_my_panic| {
@@ -49,6 +85,17 @@ impl ThreadPool {
         if let Some(num_threads) = num_threads_opt {
             rayon_pool_builder = rayon_pool_builder.num_threads(num_threads);
         }
+        if !core_ids.is_empty() {
+            rayon_pool_builder = rayon_pool_builder.start_handler(move |thread_id| {
+                let core_id = core_ids[thread_id % core_ids.len()];
+                if !core_affinity::set_for_current(core_affinity::CoreId { id: core_id }) {
+                    warn!(
+                        "failed to pin thread `quickwit-{name}-{thread_id}` to core {core_id}, \
+                         running unpinned"
+                    );
+                }
+            });
+        }
         let thread_pool = rayon_pool_builder
             .build()
             .expect("failed to spawn the spawning pool");
@@ -58,6 +105,45 @@ impl ThreadPool {
             thread_pool: Arc::new(thread_pool),
             ongoing_tasks,
             pending_tasks,
+            task_counts: Arc::default(),
+            context_propagator_opt: None,
+            shutting_down: Arc::new(AtomicBool::new(false)),
+            max_pending_tasks_opt: None,
+        }
+    }
+
+    /// Sets the propagator carrying the context of the submitting task over to the worker running
+    /// the task.
+    pub fn with_context_propagator(
+        mut self,
+        context_propagator: Arc<dyn ContextPropagator>,
+    ) -> Self {
+        self.context_propagator_opt = Some(context_propagator);
+        self
+    }
+
+    /// Sets the number of pending tasks beyond which `try_run_cpu_intensive` rejects new tasks
+    /// when all the threads of the pool are busy.
+    pub fn with_max_pending_tasks(mut self, max_pending_tasks: usize) -> Self {
+        self.max_pending_tasks_opt = Some(max_pending_tasks);
+        self
+    }
+
+    /// Stops accepting new tasks: subsequent calls to `run_cpu_intensive` fail immediately with
+    /// `ThreadPoolError::ShuttingDown`. Tasks already submitted still run to completion.
+    pub fn begin_shutdown(&self) {
+        self.shutting_down.store(true, Ordering::Release);
+    }
+
+    /// Waits until all the pending and ongoing tasks of the pool have completed.
+    ///
+    /// This is meant to be called after `begin_shutdown`, otherwise new tasks may keep the pool
+    /// busy indefinitely.
+    pub async fn join(self) {
+        const POLL_INTERVAL: Duration = Duration::from_millis(10);
+
+        while !self.task_counts.is_idle() {
+            tokio::time::sleep(POLL_INTERVAL).await;
         }
     }
 
@@ -65,6 +151,20 @@ impl ThreadPool {
         self.thread_pool.clone()
     }
 
+    /// Runs `op` within the pool, so that the rayon parallel iterators it uses execute on the
+    /// threads of this pool rather than on the global rayon pool.
+    ///
+    /// This blocks the calling thread until `op` completes: it is meant to be called from a task
+    /// already running on a CPU thread, e.g. within `run_cpu_intensive`, never from an async
+    /// context.
+    pub fn install<F, R>(&self, op: F) -> R
+    where
+        F: FnOnce() -> R + Send,
+        R: Send,
+    {
+        self.thread_pool.install(op)
+    }
+
     /// Function similar to `tokio::spawn_blocking`.
     ///
     /// Here are two important differences however:
@@ -81,35 +181,188 @@ impl ThreadPool {
     ///
     /// This is nice because it makes work that has been scheduled
     /// but is not running yet "cancellable".
+    ///
+    /// Once `begin_shutdown` has been called, the task is not scheduled and the returned future
+    /// resolves to `ThreadPoolError::ShuttingDown`.
     pub fn run_cpu_intensive<F, R>(
         &self,
         cpu_heavy_task: F,
-    ) -> impl Future<Output = Result<R, Panicked>>
+    ) -> impl Future<Output = Result<R, ThreadPoolError>>
     where
         F: FnOnce() -> R + Send + 'static,
         R: Send + 'static,
     {
+        if self.shutting_down.load(Ordering::Acquire) {
+            return Either::Left(future::ready(Err(ThreadPoolError::ShuttingDown)));
+        }
         let span = tracing::Span::current();
+        let restore_context_opt = self
+            .context_propagator_opt
+            .as_ref()
+            .map(|context_propagator| context_propagator.capture());
         let ongoing_tasks = self.ongoing_tasks.clone();
         let mut pending_tasks_guard: OwnedGaugeGuard =
             OwnedGaugeGuard::from_gauge(self.pending_tasks.clone());
         pending_tasks_guard.add(1i64);
+        let pending_task_count_guard = TaskCountGuard::pending(self.task_counts.clone());
         let (tx, rx) = oneshot::channel();
         self.thread_pool.spawn(move || {
+            // The task is accounted for as ongoing before it stops being pending, so that `join`
+            // never observes an idle pool while a task is about to run.
+            let mut ongoing_task_guard = GaugeGuard::from_gauge(&ongoing_tasks);
+            ongoing_task_guard.add(1i64);
             drop(pending_tasks_guard);
+            let _ongoing_task_count_guard = pending_task_count_guard.into_ongoing();
             if tx.is_closed() {
                 return;
             }
+            let _context_guard_opt = restore_context_opt.map(|restore_context| restore_context());
             let _guard = span.enter();
-            let mut ongoing_task_guard = GaugeGuard::from_gauge(&ongoing_tasks);
-            ongoing_task_guard.add(1i64);
             let result = cpu_heavy_task();
             let _ = tx.send(result);
         });
-        rx.map_err(|_| Panicked)
+        Either::Right(rx.map_err(|_| ThreadPoolError::Panicked))
+    }
+
+    /// Applies `f` to all the `items` in parallel on the pool, returning the results in the same
+    /// order as the items.
+    ///
+    /// This is cheaper than calling `run_cpu_intensive` once per item when the items are many and
+    /// small: the whole batch is submitted as a single task, with a single result channel, and
+    /// every item runs within the span of the caller. The context captured by the context
+    /// propagator, however, is only restored while the batch is being dispatched, not around each
+    /// item.
+    ///
+    /// As with `run_cpu_intensive`, nothing runs if the returned future is dropped before the
+    /// batch gets scheduled. If `f` panics on any item, the future resolves to
+    /// `ThreadPoolError::Panicked`.
+    pub fn map_cpu_intensive<T, R, F>(
+        &self,
+        items: Vec<T>,
+        f: F,
+    ) -> impl Future<Output = Result<Vec<R>, ThreadPoolError>>
+    where
+        T: Send + 'static,
+        R: Send + 'static,
+        F: Fn(T) -> R + Send + Sync + 'static,
+    {
+        let span = tracing::Span::current();
+        self.run_cpu_intensive(move || {
+            items
+                .into_par_iter()
+                .map(|item| span.in_scope(|| f(item)))
+                .collect()
+        })
+    }
+
+    /// Same as `run_cpu_intensive`, but fails fast instead of queuing the task when the pool is
+    /// saturated, i.e. when all of its threads are busy and at least `max_pending_tasks` tasks
+    /// are already waiting. The task is then dropped without ever being enqueued, which lets
+    /// latency-sensitive callers fall back to running the work elsewhere rather than wait.
+    ///
+    /// Without `max_pending_tasks`, tasks are never rejected.
+    pub fn try_run_cpu_intensive<F, R>(
+        &self,
+        cpu_heavy_task: F,
+    ) -> Result<impl Future<Output = Result<R, ThreadPoolError>>, TaskRejected>
+    where
+        F: FnOnce() -> R + Send + 'static,
+        R: Send + 'static,
+    {
+        if let Some(max_pending_tasks) = self.max_pending_tasks_opt {
+            let all_threads_busy = self.task_counts.num_ongoing.load(Ordering::SeqCst)
+                >= self.thread_pool.current_num_threads();
+            if all_threads_busy
+                && self.task_counts.num_pending.load(Ordering::SeqCst) >= max_pending_tasks
+            {
+                return Err(TaskRejected);
+            }
+        }
+        Ok(self.run_cpu_intensive(cpu_heavy_task))
+    }
+}
+
+/// Numbers of pending and ongoing tasks of a single pool.
+///
+/// The `pending_tasks` and `ongoing_tasks` gauges cannot be used to that end: they are shared by
+/// all the pools with the same name.
+#[derive(Default)]
+struct TaskCounts {
+    num_pending: AtomicUsize,
+    num_ongoing: AtomicUsize,
+}
+
+impl TaskCounts {
+    fn is_idle(&self) -> bool {
+        // Tasks become ongoing before they stop being pending, hence the order of the loads.
+        self.num_pending.load(Ordering::SeqCst) == 0 && self.num_ongoing.load(Ordering::SeqCst) == 0
+    }
+}
+
+/// Accounts for a task in the pending or ongoing count of its pool for as long as it lives.
+struct TaskCountGuard {
+    task_counts: Arc<TaskCounts>,
+    is_ongoing: bool,
+}
+
+impl TaskCountGuard {
+    fn pending(task_counts: Arc<TaskCounts>) -> Self {
+        task_counts.num_pending.fetch_add(1, Ordering::SeqCst);
+        Self {
+            task_counts,
+            is_ongoing: false,
+        }
+    }
+
+    /// Moves the task from the pending count to the ongoing count.
+    fn into_ongoing(self) -> Self {
+        self.task_counts.num_ongoing.fetch_add(1, Ordering::SeqCst);
+        Self {
+            task_counts: self.task_counts.clone(),
+            is_ongoing: true,
+        }
+    }
+}
+
+impl Drop for TaskCountGuard {
+    fn drop(&mut self) {
+        let count = if self.is_ongoing {
+            &self.task_counts.num_ongoing
+        } else {
+            &self.task_counts.num_pending
+        };
+        count.fetch_sub(1, Ordering::SeqCst);
     }
 }
 
+/// Pool running the tasks submitted with `run_cpu_intensive`.
+static SMALL_TASKS_EXECUTOR: std::sync::OnceLock<ThreadPool> = std::sync::OnceLock::new();
+
+fn small_tasks_executor() -> &'static ThreadPool {
+    SMALL_TASKS_EXECUTOR.get_or_init(|| {
+        let default_num_threads: usize = (crate::num_cpus() / 3).max(2);
+        let num_threads = crate::get_from_env("QW_SMALL_TASKS_THREADS", default_num_threads);
+        ThreadPool::new("small_tasks", Some(num_threads.max(1)))
+    })
+}
+
+/// Sets the number of threads of the pool running the tasks submitted with `run_cpu_intensive`,
+/// overriding the `QW_SMALL_TASKS_THREADS` environment variable and the default of a third of the
+/// CPUs.
+///
+/// The pool is created on first use: this fails if it was already used, or configured.
+pub fn configure_small_tasks_pool(num_threads: usize) -> anyhow::Result<()> {
+    if num_threads == 0 {
+        anyhow::bail!("the small tasks pool must have at least one thread");
+    }
+    if SMALL_TASKS_EXECUTOR.get().is_some() {
+        anyhow::bail!("the small tasks pool is already initialized");
+    }
+    SMALL_TASKS_EXECUTOR
+        .set(ThreadPool::new("small_tasks", Some(num_threads)))
+        .map_err(|_| anyhow::anyhow!("the small tasks pool is already initialized"))
+}
+
 /// Run a small (<200ms) CPU-intensive task on a dedicated thread pool with a few threads.
 ///
 /// When running blocking io (or side-effects in general), prefer using `tokio::spawn_blocking`
@@ -118,30 +371,51 @@ impl ThreadPool {
 ///
 /// Disclaimer: The function will no be executed if the Future is dropped.
 #[must_use = "run_cpu_intensive will not run if the future it returns is dropped"]
-pub fn run_cpu_intensive<F, R>(cpu_heavy_task: F) -> impl Future<Output = Result<R, Panicked>>
+pub fn run_cpu_intensive<F, R>(
+    cpu_heavy_task: F,
+) -> impl Future<Output = Result<R, ThreadPoolError>>
 where
     F: FnOnce() -> R + Send + 'static,
     R: Send + 'static,
 {
-    static SMALL_TASK_EXECUTOR: std::sync::OnceLock<ThreadPool> = std::sync::OnceLock::new();
-    SMALL_TASK_EXECUTOR
-        .get_or_init(|| {
-            let num_threads: usize = (crate::num_cpus() / 3).max(2);
-            ThreadPool::new("small_tasks", Some(num_threads))
-        })
-        .run_cpu_intensive(cpu_heavy_task)
+    small_tasks_executor().run_cpu_intensive(cpu_heavy_task)
 }
 
+/// Error returned by the tasks submitted to a [`ThreadPool`].
+///
+/// Migration note: this error replaces the former `Panicked` unit struct. Callers that matched on
+/// `Panicked` should match on `ThreadPoolError::Panicked`, and handle
+/// `ThreadPoolError::ShuttingDown`, returned once `ThreadPool::begin_shutdown` has been called.
 #[derive(Clone, Copy, Debug, Eq, PartialEq)]
-pub struct Panicked;
+pub enum ThreadPoolError {
+    /// The task panicked.
+    Panicked,
+    /// The thread pool is shutting down and no longer accepts new tasks.
+    ShuttingDown,
+}
 
-impl fmt::Display for Panicked {
+impl fmt::Display for ThreadPoolError {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(f, "scheduled task panicked")
+        match self {
+            ThreadPoolError::Panicked => write!(f, "scheduled task panicked"),
+            ThreadPoolError::ShuttingDown => write!(f, "thread pool is shutting down"),
+        }
     }
 }
 
-impl std::error::Error for Panicked {}
+impl std::error::Error for ThreadPoolError {}
+
+/// Error returned by `ThreadPool::try_run_cpu_intensive` when the pool is saturated.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct TaskRejected;
+
+impl fmt::Display for TaskRejected {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "thread pool is saturated")
+    }
+}
+
+impl std::error::Error for TaskRejected {}
 
 struct ThreadPoolMetrics {
     ongoing_tasks: IntGaugeVec<1>,
@@ -173,9 +447,7 @@ static THREAD_POOL_METRICS: Lazy<ThreadPoolMetrics> = Lazy::new(ThreadPoolMetric
 
 #[cfg(test)]
 mod tests {
-    use std::sync::atomic::{AtomicU64, Ordering};
-    use std::sync::Arc;
-    use std::time::Duration;
+    use std::sync::atomic::AtomicU64;
 
     use super::*;
 
@@ -184,6 +456,215 @@ mod tests {
         assert_eq!(run_cpu_intensive(|| 1).await, Ok(1));
     }
 
+    #[tokio::test]
+    async fn test_configure_small_tasks_pool() {
+        assert_eq!(
+            configure_small_tasks_pool(0).unwrap_err().to_string(),
+            "the small tasks pool must have at least one thread"
+        );
+        assert_eq!(run_cpu_intensive(|| 1).await, Ok(1));
+        assert_eq!(
+            configure_small_tasks_pool(4).unwrap_err().to_string(),
+            "the small tasks pool is already initialized"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_thread_pool_with_affinity() {
+        let thread_pool = ThreadPool::new_with_affinity("test_affinity", Some(2), vec![0]);
+        assert_eq!(thread_pool.run_cpu_intensive(|| 1).await, Ok(1));
+
+        // Core 1023 does not exist: workers fail to pin but keep running tasks.
+        let thread_pool =
+            ThreadPool::new_with_affinity("test_affinity_failure", Some(2), vec![1023]);
+        assert_eq!(thread_pool.run_cpu_intensive(|| 1).await, Ok(1));
+    }
+
+    thread_local! {
+        static TEST_CONTEXT: std::cell::Cell<u64> = const { std::cell::Cell::new(0) };
+    }
+
+    struct TestContextPropagator;
+
+    struct TestContextGuard;
+
+    impl Drop for TestContextGuard {
+        fn drop(&mut self) {
+            TEST_CONTEXT.with(|context| context.set(0));
+        }
+    }
+
+    impl ContextPropagator for TestContextPropagator {
+        fn capture(&self) -> RestoreContextFn {
+            let context_value = TEST_CONTEXT.with(|context| context.get());
+            Box::new(move || {
+                TEST_CONTEXT.with(|context| context.set(context_value));
+                Box::new(TestContextGuard)
+            })
+        }
+    }
+
+    #[tokio::test]
+    async fn test_thread_pool_install() {
+        let thread_pool = ThreadPool::new("test_install", Some(2));
+        let thread_pool_clone = thread_pool.clone();
+        let thread_names = thread_pool
+            .run_cpu_intensive(move || {
+                thread_pool_clone.install(|| {
+                    (0..16)
+                        .into_par_iter()
+                        .map(|_| {
+                            std::thread::current()
+                                .name()
+                                .unwrap_or_default()
+                                .to_string()
+                        })
+                        .collect::<Vec<String>>()
+                })
+            })
+            .await
+            .unwrap();
+        assert_eq!(thread_names.len(), 16);
+        assert!(thread_names
+            .iter()
+            .all(|thread_name| thread_name.starts_with("quickwit-test_install-")));
+    }
+
+    #[tokio::test]
+    async fn test_thread_pool_map_cpu_intensive() {
+        let thread_pool = ThreadPool::new("test_map_cpu_intensive", Some(2));
+        let squares = thread_pool
+            .map_cpu_intensive((0..100u64).collect(), |item| item * item)
+            .await
+            .unwrap();
+        assert_eq!(
+            squares,
+            (0..100u64).map(|item| item * item).collect::<Vec<_>>()
+        );
+
+        let empty: Vec<u64> = thread_pool
+            .map_cpu_intensive(Vec::new(), |item: u64| item)
+            .await
+            .unwrap();
+        assert!(empty.is_empty());
+
+        let panicked = thread_pool
+            .map_cpu_intensive(vec![1, 2, 3], |item: u64| {
+                assert_ne!(item, 2);
+                item
+            })
+            .await;
+        assert_eq!(panicked, Err(ThreadPoolError::Panicked));
+
+        thread_pool.begin_shutdown();
+        assert_eq!(
+            thread_pool.map_cpu_intensive(vec![1u64], |item| item).await,
+            Err(ThreadPoolError::ShuttingDown)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_thread_pool_context_propagation() {
+        let thread_pool = ThreadPool::new("test_context_propagation", Some(1))
+            .with_context_propagator(Arc::new(TestContextPropagator));
+        let read_context = || TEST_CONTEXT.with(|context| context.get());
+
+        TEST_CONTEXT.with(|context| context.set(42));
+        assert_eq!(thread_pool.run_cpu_intensive(read_context).await, Ok(42));
+        TEST_CONTEXT.with(|context| context.set(0));
+
+        // The context is cleared once the task completes.
+        assert_eq!(thread_pool.run_cpu_intensive(read_context).await, Ok(0));
+
+        // Without a propagator, the context stays on the submitting thread.
+        let thread_pool = ThreadPool::new("test_no_context_propagation", Some(1));
+        TEST_CONTEXT.with(|context| context.set(42));
+        assert_eq!(thread_pool.run_cpu_intensive(read_context).await, Ok(0));
+        TEST_CONTEXT.with(|context| context.set(0));
+    }
+
+    #[tokio::test]
+    async fn test_thread_pool_shutdown() {
+        let thread_pool = ThreadPool::new("test_shutdown", Some(1));
+        let completed = Arc::new(AtomicBool::new(false));
+        let completed_clone = completed.clone();
+        let ongoing_task_fut = thread_pool.run_cpu_intensive(move || {
+            std::thread::sleep(Duration::from_millis(50));
+            completed_clone.store(true, Ordering::SeqCst);
+        });
+        thread_pool.begin_shutdown();
+
+        assert_eq!(
+            thread_pool.run_cpu_intensive(|| 1).await,
+            Err(ThreadPoolError::ShuttingDown)
+        );
+        thread_pool.clone().join().await;
+        assert!(completed.load(Ordering::SeqCst));
+        assert_eq!(ongoing_task_fut.await, Ok(()));
+    }
+
+    #[tokio::test]
+    async fn test_thread_pool_join_ignores_pools_with_the_same_name() {
+        let thread_pool = ThreadPool::new("test_join_same_name", Some(1));
+        let other_thread_pool = ThreadPool::new("test_join_same_name", Some(1));
+        let (unblock_tx, unblock_rx) = std::sync::mpsc::channel::<()>();
+        let other_task_fut =
+            other_thread_pool.run_cpu_intensive(move || unblock_rx.recv().unwrap());
+        while other_thread_pool
+            .task_counts
+            .num_ongoing
+            .load(Ordering::SeqCst)
+            == 0
+        {
+            tokio::time::sleep(Duration::from_millis(1)).await;
+        }
+        // The task of the other pool is accounted for in the shared gauges, but not in the counts
+        // of this pool.
+        assert_eq!(thread_pool.run_cpu_intensive(|| 1).await, Ok(1));
+        thread_pool.begin_shutdown();
+        tokio::time::timeout(Duration::from_secs(1), thread_pool.join())
+            .await
+            .unwrap();
+
+        unblock_tx.send(()).unwrap();
+        assert_eq!(other_task_fut.await, Ok(()));
+    }
+
+    #[tokio::test]
+    async fn test_thread_pool_try_run_cpu_intensive() {
+        let thread_pool =
+            ThreadPool::new("test_try_run_cpu_intensive", Some(1)).with_max_pending_tasks(1);
+        let (unblock_tx, unblock_rx) = std::sync::mpsc::channel::<()>();
+        let ongoing_task_fut = thread_pool
+            .try_run_cpu_intensive(move || unblock_rx.recv().unwrap())
+            .unwrap();
+        while thread_pool.task_counts.num_ongoing.load(Ordering::SeqCst) == 0 {
+            tokio::time::sleep(Duration::from_millis(1)).await;
+        }
+        // The only thread is busy, but the queue still has room for one task.
+        let pending_task_fut = thread_pool.try_run_cpu_intensive(|| 1).unwrap();
+
+        assert!(matches!(
+            thread_pool.try_run_cpu_intensive(|| 2),
+            Err(TaskRejected)
+        ));
+        assert_eq!(
+            thread_pool.task_counts.num_pending.load(Ordering::SeqCst),
+            1
+        );
+
+        unblock_tx.send(()).unwrap();
+        assert_eq!(ongoing_task_fut.await, Ok(()));
+        assert_eq!(pending_task_fut.await, Ok(1));
+
+        // Without a cap, tasks are never rejected.
+        let thread_pool = ThreadPool::new("test_try_run_cpu_intensive_no_cap", Some(1));
+        assert_eq!(
+            thread_pool.try_run_cpu_intensive(|| 1).unwrap().await,
+            Ok(1)
+        );
+    }
+
     #[tokio::test]
     async fn test_run_cpu_intensive_panicks() {
         assert!(run_cpu_intensive(|| panic!("")).await.is_err());
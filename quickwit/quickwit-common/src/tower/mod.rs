@@ -53,7 +53,7 @@ pub use rate::{ConstantRate, Rate};
 pub use rate_estimator::{RateEstimator, SmaRateEstimator};
 pub use rate_limit::{RateLimit, RateLimitLayer};
 pub use retry::{RetryLayer, RetryPolicy};
-pub use transport::{make_channel, warmup_channel, BalanceChannel};
+pub use transport::{make_channel, warmup_channel, BalanceChannel, GRPC_REQUEST_TIMEOUT};
 
 pub type BoxError = Box<dyn error::Error + Send + Sync + 'static>;
 
@@ -197,6 +197,9 @@ where K: Hash + Eq + Clone + Send + Sync + 'static
     }
 }
 
+/// Timeout of the requests sent over the channels created with [`make_channel`].
+pub const GRPC_REQUEST_TIMEOUT: Duration = Duration::from_secs(30);
+
 /// Creates a channel from a socket address.
 ///
 /// The function is marked as `async` because it requires an executor (`connect_lazy`).
@@ -209,7 +212,7 @@ pub async fn make_channel(socket_addr: SocketAddr) -> Channel {
         .expect("provided arguments should be valid");
     Endpoint::from(uri)
         .connect_timeout(Duration::from_secs(5))
-        .timeout(Duration::from_secs(30))
+        .timeout(GRPC_REQUEST_TIMEOUT)
         .connect_lazy()
 }
 
@@ -33,7 +33,7 @@ use humantime::parse_duration;
 use quickwit_common::uri::Uri;
 use quickwit_doc_mapper::{
     DefaultDocMapper, DefaultDocMapperBuilder, DocMapper, FieldMappingEntry, Mode, ModeType,
-    QuickwitJsonOptions, TokenizerEntry,
+    QuickwitJsonOptions, TermDictWarmupPolicy, TokenizerEntry,
 };
 use quickwit_proto::types::IndexId;
 use serde::{Deserialize, Serialize};
@@ -220,6 +220,8 @@ impl Default for IndexingSettings {
 pub struct SearchSettings {
     #[serde(default)]
     pub default_search_fields: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub term_dict_warmup_policy: Option<TermDictWarmupPolicy>,
 }
 
 #[derive(Clone, Debug, Hash, Eq, PartialEq, Serialize, Deserialize, utoipa::ToSchema)]
@@ -384,6 +386,7 @@ impl IndexConfig {
                 r#"attributes.server"#.to_string(),
                 r"attributes.server\.status".to_string(),
             ],
+            term_dict_warmup_policy: None,
         };
         IndexConfig {
             index_id: index_id.to_string(),
@@ -482,6 +485,7 @@ impl TestableForRegression for IndexConfig {
         };
         let search_settings = SearchSettings {
             default_search_fields: vec!["message".to_string()],
+            term_dict_warmup_policy: None,
         };
         IndexConfig {
             index_id: "my-index".to_string(),
@@ -528,6 +532,7 @@ pub fn build_doc_mapper(
         store_source: doc_mapping.store_source,
         index_field_presence: doc_mapping.index_field_presence,
         default_search_fields: search_settings.default_search_fields.clone(),
+        term_dict_warmup_policy: search_settings.term_dict_warmup_policy.clone(),
         timestamp_field: doc_mapping.timestamp_field.clone(),
         field_mappings: doc_mapping.field_mappings.clone(),
         tag_fields: doc_mapping.tag_fields.iter().cloned().collect(),
@@ -650,6 +655,7 @@ mod tests {
             index_config.search_settings,
             SearchSettings {
                 default_search_fields: vec!["severity_text".to_string(), "body".to_string()],
+                term_dict_warmup_policy: None,
             }
         );
     }
@@ -692,6 +698,7 @@ mod tests {
                 index_config.search_settings,
                 SearchSettings {
                     default_search_fields: vec!["body".to_string()],
+                    term_dict_warmup_policy: None,
                 }
             );
         }
@@ -726,6 +733,7 @@ mod tests {
                 index_config.search_settings,
                 SearchSettings {
                     default_search_fields: vec!["body".to_string()],
+                    term_dict_warmup_policy: None,
                 }
             );
         }
@@ -788,6 +796,33 @@ mod tests {
             .contains("failed to parse human-readable duration `x`"));
     }
 
+    #[test]
+    fn test_index_config_with_term_dict_warmup_policy() {
+        let config_yaml = r#"
+            version: 0.8
+            index_id: hdfs-logs
+            index_uri: "s3://my-index"
+            doc_mapping: {}
+            search_settings:
+              term_dict_warmup_policy:
+                deny_list: [trace_id]
+        "#;
+        let index_config = load_index_config_from_user_config(
+            ConfigFormat::Yaml,
+            config_yaml.as_bytes(),
+            &Uri::for_test("s3://my-index"),
+        )
+        .unwrap();
+        let expected_policy = TermDictWarmupPolicy::DenyList(["trace_id".to_string()].into());
+        assert_eq!(
+            index_config.search_settings.term_dict_warmup_policy,
+            Some(expected_policy.clone())
+        );
+        let doc_mapper =
+            build_doc_mapper(&index_config.doc_mapping, &index_config.search_settings).unwrap();
+        assert_eq!(doc_mapper.term_dict_warmup_policy(), Some(&expected_policy));
+    }
+
     #[test]
     fn test_retention_policy_serialization() {
         let retention_policy = RetentionPolicy {
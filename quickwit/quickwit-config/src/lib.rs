@@ -27,6 +27,7 @@ use json_comments::StripComments;
 use once_cell::sync::Lazy;
 use quickwit_common::net::is_valid_hostname;
 use quickwit_common::uri::Uri;
+pub use quickwit_common::validate_index_id_pattern;
 use regex::Regex;
 
 mod cluster_config;
@@ -137,48 +138,6 @@ pub fn validate_identifier(label: &str, value: &str) -> anyhow::Result<()> {
     Ok(())
 }
 
-/// Checks whether an index ID pattern conforms to Quickwit conventions.
-/// Index ID patterns accept the same characters as identifiers AND accept `*`
-/// chars to allow for glob-like patterns.
-pub fn validate_index_id_pattern(pattern: &str, allow_negative: bool) -> anyhow::Result<()> {
-    static IDENTIFIER_REGEX_WITH_GLOB_PATTERN: Lazy<Regex> = Lazy::new(|| {
-        Regex::new(r"^[a-zA-Z\*][a-zA-Z0-9-_\.\*]{0,254}$")
-            .expect("regular expression should compile")
-    });
-    static IDENTIFIER_REGEX_WITH_GLOB_PATTERN_NEGATIVE: Lazy<Regex> = Lazy::new(|| {
-        Regex::new(r"^-?[a-zA-Z\*][a-zA-Z0-9-_\.\*]{0,254}$")
-            .expect("regular expression should compile")
-    });
-
-    let regex = if allow_negative {
-        &IDENTIFIER_REGEX_WITH_GLOB_PATTERN_NEGATIVE
-    } else {
-        &IDENTIFIER_REGEX_WITH_GLOB_PATTERN
-    };
-
-    if !regex.is_match(pattern) {
-        bail!(
-            "index ID pattern `{pattern}` is invalid: patterns must match the following regular \
-             expression: `^[a-zA-Z\\*][a-zA-Z0-9-_\\.\\*]{{0,254}}$`"
-        );
-    }
-    // Forbid multiple stars in the pattern to force the user making simpler patterns
-    // as multiple stars does not bring any value.
-    if pattern.contains("**") {
-        bail!(
-            "index ID pattern `{pattern}` is invalid: patterns must not contain multiple \
-             consecutive `*`"
-        );
-    }
-    // If there is no star in the pattern, we need at least 3 characters.
-    if !pattern.contains('*') && pattern.len() < 3 {
-        bail!(
-            "index ID pattern `{pattern}` is invalid: an index ID must have at least 3 characters"
-        );
-    }
-    Ok(())
-}
-
 pub fn validate_node_id(node_id: &str) -> anyhow::Result<()> {
     if !is_valid_hostname(node_id) {
         bail!(
@@ -211,9 +211,72 @@ pub struct SearcherConfig {
     pub aggregation_bucket_limit: u32,
     pub fast_field_cache_capacity: ByteSize,
     pub split_footer_cache_capacity: ByteSize,
+    /// Capacity of the cache holding the parsed hotcaches of the most recently opened splits.
+    /// Cached hotcaches keep their bytes alive, so this comes on top of
+    /// `split_footer_cache_capacity`.
+    pub hotcache_cache_capacity: ByteSize,
     pub partial_request_cache_capacity: ByteSize,
     pub max_num_concurrent_split_searches: usize,
     pub max_num_concurrent_split_streams: usize,
+    pub max_num_concurrent_aggregations: usize,
+    /// Number of the `max_num_concurrent_split_searches` permits reserved to interactive
+    /// searches. Background searches share the remaining permits, so they cannot starve
+    /// interactive ones.
+    pub num_reserved_interactive_split_searches: usize,
+    /// Maximum number of terms warmed up for a single term range, e.g. for prefix queries.
+    /// `None` means no limit.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_term_range_warmup_limit: Option<u64>,
+    /// Maximum number of bytes a single split is projected to download when warming up, e.g. to
+    /// load whole term dictionaries for a term aggregation. Splits exceeding it fail instead of
+    /// being warmed up. `None` means no limit.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_warmup_bytes_per_split: Option<ByteSize>,
+    /// Maximum size of the hotcache of a split. The limit applies to the split footer, which
+    /// holds the hotcache: splits with a larger footer fail before it is fetched. `None` means no
+    /// limit.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_hotcache_bytes_per_split: Option<ByteSize>,
+    /// Maximum number of splits a single leaf search request can target. Larger requests are
+    /// rejected. `None` means no limit.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_splits_per_leaf_request: Option<usize>,
+    /// Maximum total size of the splits a single leaf search request can target. Larger requests
+    /// are rejected. `None` means no limit.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_split_bytes_per_leaf_request: Option<ByteSize>,
+    /// If true, the footers of the splits of a leaf search are fetched concurrently in the
+    /// background, skipping the splits pruned in the meantime.
+    pub prefetch_split_footers: bool,
+    /// If false, leaf search requests restricting the splits to search with a split ID allowlist
+    /// or denylist are rejected.
+    pub enable_split_id_overrides: bool,
+    /// Timestamp field used to prune splits for indexes whose doc mapper has no timestamp field,
+    /// e.g. indexes created with older doc mappers. `None` disables this fallback.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub default_timestamp_field_name: Option<String>,
+    /// Number of seconds after which the cached split footers are fetched again. This is an
+    /// escape hatch for deployments rewriting splits in place, under the same split ID. The
+    /// parsed hotcaches are not cached either in that case. `None` keeps the split footers until
+    /// they are evicted, which is only correct as long as splits are immutable.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub split_footer_cache_ttl_secs: Option<NonZeroU64>,
+    /// Number of times a split search failing with a transient storage error is retried in place
+    /// before the failure is reported to the root, which would otherwise search all the splits of
+    /// the leaf request again.
+    pub max_split_retries: usize,
+    /// Maximum number of reads in flight while warming up a split. Warming up is IO-bound.
+    /// (defaults to 4 times the number of vCPUs).
+    pub warmup_concurrency: NonZeroUsize,
+    /// Wall-clock budget of a whole leaf search request, in milliseconds. Once it is elapsed, no
+    /// more splits are searched and the partial result is returned, flagged as timed out. `None`
+    /// waits for all the splits.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub leaf_search_max_elapsed_ms: Option<NonZeroU64>,
+    /// If true, the splits on the local split cache are searched alternately with the other
+    /// ones, rather than purely in the order that prunes the most splits. The first results come
+    /// in sooner, but the hits tied on their sort value can vary from one search to the next.
+    pub interleave_cached_splits: bool,
     // Strangely, if None, this will also have the effect of not forwarding
     // to searcher.
     // TODO document and fix if necessary.
@@ -226,9 +289,25 @@ impl Default for SearcherConfig {
         Self {
             fast_field_cache_capacity: ByteSize::gb(1),
             split_footer_cache_capacity: ByteSize::mb(500),
+            hotcache_cache_capacity: ByteSize::mb(100),
             partial_request_cache_capacity: ByteSize::mb(64),
             max_num_concurrent_split_streams: 100,
             max_num_concurrent_split_searches: 100,
+            max_num_concurrent_aggregations: 20,
+            num_reserved_interactive_split_searches: 0,
+            max_term_range_warmup_limit: None,
+            max_warmup_bytes_per_split: None,
+            max_hotcache_bytes_per_split: None,
+            max_splits_per_leaf_request: None,
+            max_split_bytes_per_leaf_request: None,
+            prefetch_split_footers: false,
+            enable_split_id_overrides: true,
+            default_timestamp_field_name: None,
+            split_footer_cache_ttl_secs: None,
+            max_split_retries: 0,
+            warmup_concurrency: Self::default_warmup_concurrency(),
+            leaf_search_max_elapsed_ms: None,
+            interleave_cached_splits: false,
             aggregation_memory_limit: ByteSize::mb(500),
             aggregation_bucket_limit: 65000,
             split_cache: None,
@@ -237,7 +316,39 @@ impl Default for SearcherConfig {
 }
 
 impl SearcherConfig {
-    fn validate(&self) -> anyhow::Result<()> {
+    pub fn default_warmup_concurrency() -> NonZeroUsize {
+        NonZeroUsize::new(quickwit_common::num_cpus() * 4).unwrap_or(NonZeroUsize::new(1).unwrap())
+    }
+
+    pub fn split_footer_cache_ttl(&self) -> Option<Duration> {
+        self.split_footer_cache_ttl_secs
+            .map(|ttl_secs| Duration::from_secs(ttl_secs.get()))
+    }
+
+    pub fn leaf_search_max_elapsed(&self) -> Option<Duration> {
+        self.leaf_search_max_elapsed_ms
+            .map(|max_elapsed_ms| Duration::from_millis(max_elapsed_ms.get()))
+    }
+
+    /// Checks that the settings are consistent with each other.
+    pub fn validate(&self) -> anyhow::Result<()> {
+        if self.max_num_concurrent_split_searches == 0 {
+            anyhow::bail!("max_num_concurrent_split_searches must be strictly positive");
+        }
+        if self.max_num_concurrent_split_streams == 0 {
+            anyhow::bail!("max_num_concurrent_split_streams must be strictly positive");
+        }
+        if self.num_reserved_interactive_split_searches >= self.max_num_concurrent_split_searches {
+            anyhow::bail!(
+                "num_reserved_interactive_split_searches ({}) must be strictly lower than \
+                 max_num_concurrent_split_searches ({})",
+                self.num_reserved_interactive_split_searches,
+                self.max_num_concurrent_split_searches
+            );
+        }
+        if self.max_num_concurrent_aggregations == 0 {
+            anyhow::bail!("max_num_concurrent_aggregations must be strictly positive");
+        }
         if let Some(split_cache_limits) = self.split_cache {
             if self.max_num_concurrent_split_searches
                 > split_cache_limits.max_file_descriptors.get() as usize
@@ -596,4 +707,28 @@ mod tests {
         };
         assert!(grpc_config.validate().is_err());
     }
+
+    #[test]
+    fn test_searcher_config_validate() {
+        let searcher_config = SearcherConfig::default();
+        assert!(searcher_config.validate().is_ok());
+
+        let searcher_config = SearcherConfig {
+            max_num_concurrent_aggregations: 0,
+            ..Default::default()
+        };
+        assert_eq!(
+            searcher_config.validate().unwrap_err().to_string(),
+            "max_num_concurrent_aggregations must be strictly positive"
+        );
+
+        let searcher_config = SearcherConfig {
+            max_num_concurrent_split_streams: 0,
+            ..Default::default()
+        };
+        assert_eq!(
+            searcher_config.validate().unwrap_err().to_string(),
+            "max_num_concurrent_split_streams must be strictly positive"
+        );
+    }
 }
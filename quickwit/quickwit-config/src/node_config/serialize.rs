@@ -607,9 +607,25 @@ mod tests {
                 aggregation_bucket_limit: 500_000,
                 fast_field_cache_capacity: ByteSize::gb(10),
                 split_footer_cache_capacity: ByteSize::gb(1),
+                hotcache_cache_capacity: ByteSize::mb(100),
                 partial_request_cache_capacity: ByteSize::mb(64),
                 max_num_concurrent_split_searches: 150,
                 max_num_concurrent_split_streams: 120,
+                max_num_concurrent_aggregations: 20,
+                num_reserved_interactive_split_searches: 0,
+                max_term_range_warmup_limit: None,
+                max_warmup_bytes_per_split: None,
+                max_hotcache_bytes_per_split: None,
+                max_splits_per_leaf_request: None,
+                max_split_bytes_per_leaf_request: None,
+                prefetch_split_footers: false,
+                enable_split_id_overrides: true,
+                default_timestamp_field_name: None,
+                split_footer_cache_ttl_secs: None,
+                max_split_retries: 0,
+                warmup_concurrency: SearcherConfig::default_warmup_concurrency(),
+                leaf_search_max_elapsed_ms: None,
+                interleave_cached_splits: false,
                 split_cache: None,
             }
         );
@@ -489,6 +489,7 @@ fn convert_metastore_error<T>(
         MetastoreError::AlreadyExists(_)
         | MetastoreError::FailedPrecondition { .. }
         | MetastoreError::Forbidden { .. }
+        | MetastoreError::IncompatibleVersion { .. }
         | MetastoreError::InvalidArgument { .. }
         | MetastoreError::JsonDeserializeError { .. }
         | MetastoreError::JsonSerializeError { .. }
@@ -500,7 +501,7 @@ fn convert_metastore_error<T>(
         | MetastoreError::Internal { .. }
         | MetastoreError::Io { .. }
         | MetastoreError::Timeout { .. }
-        | MetastoreError::Unavailable(_) => false,
+        | MetastoreError::Unavailable { .. } => false,
     };
     if is_transaction_certainly_aborted {
         // If the metastore transaction is certain to have been aborted,
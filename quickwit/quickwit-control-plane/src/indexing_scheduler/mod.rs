@@ -169,6 +169,7 @@ fn get_sources_to_schedule(model: &ControlPlaneModel) -> Vec<SourceToSchedule> {
             | SourceType::Kinesis
             | SourceType::PubSub
             | SourceType::Nats
+            | SourceType::NatsJetStream
             | SourceType::Pulsar => {
                 sources.push(SourceToSchedule {
                     source_uid,
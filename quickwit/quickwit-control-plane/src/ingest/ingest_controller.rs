@@ -1743,9 +1743,7 @@ mod tests {
                 assert_eq!(subrequest.source_id, "test-source");
                 assert_eq!(subrequest.leader_id, "test-ingester");
 
-                Err(MetastoreError::InvalidArgument {
-                    message: "failed to open shards".to_string(),
-                })
+                Err(MetastoreError::invalid_argument("failed to open shards"))
             });
         let metastore = MetastoreServiceClient::from_mock(mock_metastore);
         let ingester_pool = IngesterPool::default();
@@ -1888,9 +1886,7 @@ mod tests {
                 assert_eq!(request.subrequests[0].source_id, INGEST_V2_SOURCE_ID);
                 assert_eq!(request.subrequests[0].leader_id, "test-ingester");
 
-                Err(MetastoreError::InvalidArgument {
-                    message: "failed to open shards".to_string(),
-                })
+                Err(MetastoreError::invalid_argument("failed to open shards"))
             });
         let index_uid_clone = index_uid.clone();
         mock_metastore
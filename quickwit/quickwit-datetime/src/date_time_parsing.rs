@@ -177,6 +177,55 @@ pub fn parse_timestamp(timestamp: i64) -> Result<TantivyDateTime, String> {
     }
 }
 
+/// Parses a date math expression relative to `now`, such as `now`, `now-15m`, or `now+1h`.
+///
+/// Operations can be chained, as in `now-1d+2h`. Supported units are `s`, `m`, `h`, `d`, and
+/// `w`. `now` is passed by the caller, so that all the bounds of a query resolve consistently.
+pub fn parse_date_math(
+    date_math_str: &str,
+    now: TantivyDateTime,
+) -> Result<TantivyDateTime, String> {
+    const NANOS_PER_SEC: i64 = 1_000_000_000;
+
+    let Some(mut operations_str) = date_math_str.trim().strip_prefix("now") else {
+        return Err(format!(
+            "date math expression `{date_math_str}` must start with `now`"
+        ));
+    };
+    let invalid_date_math = || format!("failed to parse date math expression `{date_math_str}`");
+    let mut timestamp_nanos = now.into_timestamp_nanos();
+
+    while let Some(operator) = operations_str.chars().next() {
+        let sign: i64 = match operator {
+            '+' => 1,
+            '-' => -1,
+            _ => return Err(invalid_date_math()),
+        };
+        let amount_and_unit_str = &operations_str[1..];
+        let num_digits = amount_and_unit_str
+            .chars()
+            .take_while(char::is_ascii_digit)
+            .count();
+        let (amount_str, unit_str) = amount_and_unit_str.split_at(num_digits);
+        let amount: i64 = amount_str.parse().map_err(|_| invalid_date_math())?;
+        let mut unit_chars = unit_str.chars();
+        let unit_secs: i64 = match unit_chars.next() {
+            Some('s') => 1,
+            Some('m') => 60,
+            Some('h') | Some('H') => 3_600,
+            Some('d') => 86_400,
+            Some('w') => 604_800,
+            _ => return Err(invalid_date_math()),
+        };
+        timestamp_nanos = amount
+            .checked_mul(sign * unit_secs * NANOS_PER_SEC)
+            .and_then(|delta_nanos| timestamp_nanos.checked_add(delta_nanos))
+            .ok_or_else(invalid_date_math)?;
+        operations_str = unit_chars.as_str();
+    }
+    Ok(TantivyDateTime::from_timestamp_nanos(timestamp_nanos))
+}
+
 #[cfg(test)]
 mod tests {
     use std::str::FromStr;
@@ -568,4 +617,35 @@ mod tests {
             assert_eq!(date_time.into_timestamp_micros(), max_ts_micros);
         }
     }
+
+    #[test]
+    fn test_parse_date_math() {
+        let now = TantivyDateTime::from_timestamp_secs(1_700_000_000);
+        let parse_date_math_secs = |date_math_str: &str| {
+            parse_date_math(date_math_str, now).map(|date_time| date_time.into_timestamp_secs())
+        };
+
+        assert_eq!(parse_date_math_secs("now").unwrap(), 1_700_000_000);
+        assert_eq!(
+            parse_date_math_secs("now-15m").unwrap(),
+            1_700_000_000 - 900
+        );
+        assert_eq!(
+            parse_date_math_secs("now+1h").unwrap(),
+            1_700_000_000 + 3_600
+        );
+        assert_eq!(
+            parse_date_math_secs("now-1d+2h").unwrap(),
+            1_700_000_000 - 86_400 + 7_200
+        );
+        assert_eq!(
+            parse_date_math_secs("now-2w").unwrap(),
+            1_700_000_000 - 1_209_600
+        );
+
+        for invalid_date_math_str in ["", "yesterday", "now-", "now-1", "now-1y", "now*2", "now-h"]
+        {
+            parse_date_math_secs(invalid_date_math_str).unwrap_err();
+        }
+    }
 }
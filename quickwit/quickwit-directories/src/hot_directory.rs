@@ -349,6 +349,39 @@ impl fmt::Debug for StaticSliceCache {
     }
 }
 
+/// Parsed hotcache of a split.
+///
+/// Parsing the hotcache layout costs CPU: a `HotCache` can be kept around and shared by all the
+/// `HotDirectory` instances opened on the same split.
+#[derive(Clone)]
+pub struct HotCache {
+    static_cache: Arc<StaticDirectoryCache>,
+    num_bytes: usize,
+}
+
+impl HotCache {
+    /// Parses a hotcache serialized into `hot_cache_bytes`.
+    pub fn open(hot_cache_bytes: OwnedBytes) -> anyhow::Result<HotCache> {
+        let num_bytes = hot_cache_bytes.len();
+        let static_cache = StaticDirectoryCache::open(hot_cache_bytes)?;
+        Ok(HotCache {
+            static_cache: Arc::new(static_cache),
+            num_bytes,
+        })
+    }
+
+    /// Returns the size of the serialized hotcache, which the parsed hotcache keeps in memory.
+    pub fn num_bytes(&self) -> usize {
+        self.num_bytes
+    }
+}
+
+impl fmt::Debug for HotCache {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "HotCache(num_bytes={})", self.num_bytes)
+    }
+}
+
 /// The hot directory accelerates a given directory,
 /// by placing a static cache in front of a directory.
 ///
@@ -368,14 +401,20 @@ impl HotDirectory {
         underlying: D,
         hot_cache_bytes: OwnedBytes,
     ) -> anyhow::Result<HotDirectory> {
-        let static_cache = StaticDirectoryCache::open(hot_cache_bytes)?;
-        Ok(HotDirectory {
+        let hot_cache = HotCache::open(hot_cache_bytes)?;
+        Ok(HotDirectory::open_with_hot_cache(underlying, hot_cache))
+    }
+
+    /// Wraps an index, with an already parsed static cache.
+    pub fn open_with_hot_cache<D: Directory>(underlying: D, hot_cache: HotCache) -> HotDirectory {
+        HotDirectory {
             inner: Arc::new(InnerHotDirectory {
                 underlying: Box::new(underlying),
-                cache: Arc::new(static_cache),
+                cache: hot_cache.static_cache,
             }),
-        })
+        }
     }
+
     /// Get files and their cached sizes.
     pub fn get_stats_per_file(
         hot_cache_bytes: OwnedBytes,
@@ -45,8 +45,9 @@ use crate::doc_mapper::{JsonObject, Partition};
 use crate::query_builder::build_query;
 use crate::routing_expression::RoutingExpr;
 use crate::{
-    Cardinality, DocMapper, DocParsingError, Mode, QueryParserError, TokenizerEntry, WarmupInfo,
-    DOCUMENT_LEN_FIELD_NAME, DYNAMIC_FIELD_NAME, FIELD_PRESENCE_FIELD_NAME, SOURCE_FIELD_NAME,
+    Cardinality, DocMapper, DocParsingError, Mode, QueryParserError, TermDictWarmupPolicy,
+    TokenizerEntry, WarmupInfo, DOCUMENT_LEN_FIELD_NAME, DYNAMIC_FIELD_NAME,
+    FIELD_PRESENCE_FIELD_NAME, SOURCE_FIELD_NAME,
 };
 
 const FIELD_PRESENCE_FIELD: Field = Field::from_field_id(0u32);
@@ -73,6 +74,8 @@ pub struct DefaultDocMapper {
     document_len_field: Option<Field>,
     /// Default list of field names used for search.
     default_search_field_names: Vec<String>,
+    /// Restricts the fields whose term dictionary can be warmed up.
+    term_dict_warmup_policy: Option<TermDictWarmupPolicy>,
     /// Timestamp field name.
     timestamp_field_name: Option<String>,
     /// Root node of the field mapping tree.
@@ -272,6 +275,7 @@ impl TryFrom<DefaultDocMapperBuilder> for DefaultDocMapper {
             dynamic_field,
             document_len_field,
             default_search_field_names,
+            term_dict_warmup_policy: builder.term_dict_warmup_policy,
             timestamp_field_name: builder.timestamp_field,
             field_mappings,
             concatenate_dynamic_fields,
@@ -383,6 +387,7 @@ impl From<DefaultDocMapper> for DefaultDocMapperBuilder {
             field_mappings: default_doc_mapper.field_mappings.into(),
             tag_fields: default_doc_mapper.tag_field_names.into_iter().collect(),
             default_search_fields: default_doc_mapper.default_search_field_names,
+            term_dict_warmup_policy: default_doc_mapper.term_dict_warmup_policy,
             mode: default_doc_mapper.mode,
             partition_key: partition_key_opt,
             max_num_partitions: default_doc_mapper.max_num_partitions,
@@ -708,6 +713,10 @@ impl DocMapper for DefaultDocMapper {
         &self.default_search_field_names
     }
 
+    fn term_dict_warmup_policy(&self) -> Option<&TermDictWarmupPolicy> {
+        self.term_dict_warmup_policy.as_ref()
+    }
+
     fn schema(&self) -> Schema {
         self.schema.clone()
     }
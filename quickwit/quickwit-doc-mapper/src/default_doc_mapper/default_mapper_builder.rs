@@ -24,7 +24,7 @@ use serde::{Deserialize, Serialize};
 use super::tokenizer_entry::TokenizerEntry;
 use super::FieldMappingEntry;
 use crate::default_doc_mapper::QuickwitJsonOptions;
-use crate::DefaultDocMapper;
+use crate::{DefaultDocMapper, TermDictWarmupPolicy};
 
 /// DefaultDocMapperBuilder is here
 /// to create a valid DocMapper.
@@ -45,6 +45,10 @@ pub struct DefaultDocMapperBuilder {
     /// Name of the fields that are searched by default, unless overridden.
     #[serde(default)]
     pub default_search_fields: Vec<String>,
+    /// Restricts the fields whose term dictionary can be warmed up.
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub term_dict_warmup_policy: Option<TermDictWarmupPolicy>,
     /// Name of the field storing the timestamp of the event for time series data.
     #[serde(default)]
     #[serde(skip_serializing_if = "Option::is_none")]
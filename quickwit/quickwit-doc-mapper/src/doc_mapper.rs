@@ -26,6 +26,7 @@ use anyhow::Context;
 use dyn_clone::{clone_trait_object, DynClone};
 use quickwit_query::query_ast::QueryAst;
 use quickwit_query::tokenizers::TokenizerManager;
+use serde::{Deserialize, Serialize};
 use serde_json::Value as JsonValue;
 use tantivy::query::Query;
 use tantivy::schema::{Field, FieldType, OwnedValue as Value, Schema};
@@ -119,6 +120,11 @@ pub trait DocMapper: Send + Sync + Debug + DynClone + 'static {
     /// (See `UserInputQuery`).
     fn default_search_fields(&self) -> &[String];
 
+    /// Returns the policy restricting which fields can have their term dictionary warmed up.
+    fn term_dict_warmup_policy(&self) -> Option<&TermDictWarmupPolicy> {
+        None
+    }
+
     /// Returns the tag field names
     fn tag_field_names(&self) -> BTreeSet<String> {
         Default::default()
@@ -183,8 +189,14 @@ pub struct WarmupInfo {
     pub term_dict_fields: HashSet<Field>,
     /// Name of fast fields which needs to be loaded
     pub fast_field_names: HashSet<String>,
+    /// Subset of `fast_field_names` which are only needed by one-off requests, such as
+    /// aggregations. Their data is cached with a low priority.
+    pub low_priority_fast_field_names: HashSet<String>,
     /// Whether to warmup field norms. Used mostly for scoring.
     pub field_norms: bool,
+    /// Fields queried by the query, whose field norms are needed to score it. If empty while
+    /// `field_norms` is true, the field norms of all the fields are warmed up.
+    pub field_norms_fields: HashSet<Field>,
     /// Terms to warmup, and whether their position is needed too.
     pub terms_grouped_by_field: HashMap<Field, HashMap<Term, bool>>,
     /// Term ranges to warmup, and whether their position is needed too.
@@ -193,10 +205,19 @@ pub struct WarmupInfo {
 
 impl WarmupInfo {
     /// Merge other WarmupInfo into self.
-    pub fn merge(&mut self, other: WarmupInfo) {
+    pub fn merge(&mut self, mut other: WarmupInfo) {
+        // a fast field stays low priority only if no side needs it with a normal priority.
+        merge_fast_field_subsets(
+            &self.fast_field_names,
+            &mut self.low_priority_fast_field_names,
+            &other.fast_field_names,
+            std::mem::take(&mut other.low_priority_fast_field_names),
+        );
+
         self.term_dict_fields.extend(other.term_dict_fields);
         self.fast_field_names.extend(other.fast_field_names);
         self.field_norms |= other.field_norms;
+        self.field_norms_fields.extend(other.field_norms_fields);
 
         for (field, term_and_pos) in other.terms_grouped_by_field.into_iter() {
             let sub_map = self.terms_grouped_by_field.entry(field).or_default();
@@ -216,6 +237,59 @@ impl WarmupInfo {
         }
     }
 
+    /// Merges all the given WarmupInfo into one, and simplifies the result.
+    ///
+    /// The capacity of the sets and maps is reserved upfront, so that merging many fragments,
+    /// e.g. one per clause of a bool query, does not reallocate them over and over.
+    pub fn merge_all(warmup_infos: impl IntoIterator<Item = WarmupInfo>) -> WarmupInfo {
+        let warmup_infos: Vec<WarmupInfo> = warmup_infos.into_iter().collect();
+        let total_len =
+            |len_fn: fn(&WarmupInfo) -> usize| -> usize { warmup_infos.iter().map(len_fn).sum() };
+        let mut merged_warmup_info = WarmupInfo::default();
+        merged_warmup_info
+            .term_dict_fields
+            .reserve(total_len(|warmup_info| warmup_info.term_dict_fields.len()));
+        merged_warmup_info
+            .fast_field_names
+            .reserve(total_len(|warmup_info| warmup_info.fast_field_names.len()));
+        merged_warmup_info
+            .low_priority_fast_field_names
+            .reserve(total_len(|warmup_info| {
+                warmup_info.low_priority_fast_field_names.len()
+            }));
+        merged_warmup_info
+            .field_norms_fields
+            .reserve(total_len(|warmup_info| {
+                warmup_info.field_norms_fields.len()
+            }));
+        merged_warmup_info
+            .terms_grouped_by_field
+            .reserve(total_len(|warmup_info| {
+                warmup_info.terms_grouped_by_field.len()
+            }));
+        merged_warmup_info
+            .term_ranges_grouped_by_field
+            .reserve(total_len(|warmup_info| {
+                warmup_info.term_ranges_grouped_by_field.len()
+            }));
+        for warmup_info in warmup_infos {
+            merged_warmup_info.merge(warmup_info);
+        }
+        merged_warmup_info.simplify();
+        merged_warmup_info
+    }
+
+    /// Returns the fields whose field norms need to be warmed up.
+    pub fn fields_requiring_field_norms(&self, schema: &Schema) -> Vec<Field> {
+        if !self.field_norms {
+            return Vec::new();
+        }
+        if self.field_norms_fields.is_empty() {
+            return schema.fields().map(|(field, _)| field).collect();
+        }
+        self.field_norms_fields.iter().copied().collect()
+    }
+
     /// Simplify a WarmupInfo, removing some redundant tasks
     pub fn simplify(&mut self) {
         self.terms_grouped_by_field.retain(|field, terms| {
@@ -236,6 +310,87 @@ impl WarmupInfo {
         // TODO we could remove from terms_grouped_by_field for ranges with no `limit` in
         // term_ranges_grouped_by_field
     }
+
+    /// Reports fields whose postings are requested both with and without positions.
+    ///
+    /// This is a diagnostic meant for tests and debug logging, it does not modify the warmup info.
+    pub fn validate(&self) -> Vec<WarmupWarning> {
+        // (positions needed by some request, positions not needed by some request)
+        let mut position_needed_per_field: BTreeMap<Field, (bool, bool)> = BTreeMap::new();
+
+        let terms_position_needed = self
+            .terms_grouped_by_field
+            .iter()
+            .flat_map(|(field, terms)| terms.values().map(move |pos| (*field, *pos)));
+        let term_ranges_position_needed = self
+            .term_ranges_grouped_by_field
+            .iter()
+            .flat_map(|(field, term_ranges)| term_ranges.values().map(move |pos| (*field, *pos)));
+
+        for (field, position_needed) in terms_position_needed.chain(term_ranges_position_needed) {
+            let (with_position, without_position) =
+                position_needed_per_field.entry(field).or_default();
+            if position_needed {
+                *with_position = true;
+            } else {
+                *without_position = true;
+            }
+        }
+        position_needed_per_field
+            .into_iter()
+            .filter(|(_, (with_position, without_position))| *with_position && *without_position)
+            .map(|(field, _)| WarmupWarning::ConflictingPositionNeeded { field })
+            .collect()
+    }
+}
+
+/// Merges `other_subset` into `subset`, the subsets of `fast_field_names` and
+/// `other_fast_field_names` needed for a restricted purpose only, e.g. the low priority ones. A
+/// fast field stays in the merged subset only if no side needs it outside of its subset.
+fn merge_fast_field_subsets(
+    fast_field_names: &HashSet<String>,
+    subset: &mut HashSet<String>,
+    other_fast_field_names: &HashSet<String>,
+    mut other_subset: HashSet<String>,
+) {
+    other_subset.retain(|fast_field_name| {
+        !fast_field_names.contains(fast_field_name) || subset.contains(fast_field_name)
+    });
+    subset.retain(|fast_field_name| {
+        !other_fast_field_names.contains(fast_field_name) || other_subset.contains(fast_field_name)
+    });
+    subset.extend(other_subset);
+}
+
+/// Potential issue in a [`WarmupInfo`], reported by [`WarmupInfo::validate`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WarmupWarning {
+    /// The postings of the field are requested both with and without positions.
+    ///
+    /// Merging warmup infos ORs the position flags of identical terms, so this is harmless, but it
+    /// usually means the query and the collector disagree on what they need.
+    ConflictingPositionNeeded { field: Field },
+}
+
+/// Restricts the fields whose term dictionary can be downloaded in full during warmup, e.g. to
+/// prevent term set queries on fields with millions of unique values.
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize, utoipa::ToSchema)]
+#[serde(deny_unknown_fields, rename_all = "snake_case")]
+pub enum TermDictWarmupPolicy {
+    /// Only the listed fields can be warmed up.
+    AllowList(BTreeSet<String>),
+    /// All fields but the listed ones can be warmed up.
+    DenyList(BTreeSet<String>),
+}
+
+impl TermDictWarmupPolicy {
+    /// Returns whether the term dictionary of the field can be warmed up.
+    pub fn is_field_allowed(&self, field_name: &str) -> bool {
+        match self {
+            TermDictWarmupPolicy::AllowList(field_names) => field_names.contains(field_name),
+            TermDictWarmupPolicy::DenyList(field_names) => !field_names.contains(field_name),
+        }
+    }
 }
 
 #[cfg(test)]
@@ -245,12 +400,13 @@ mod tests {
 
     use quickwit_query::query_ast::{query_ast_from_user_text, UserInputQuery};
     use quickwit_query::BooleanOperand;
-    use tantivy::schema::{Field, FieldType, Term};
+    use tantivy::schema::{Field, FieldType, Schema, Term};
 
     use crate::default_doc_mapper::{FieldMappingType, QuickwitJsonOptions};
     use crate::{
         Cardinality, DefaultDocMapper, DefaultDocMapperBuilder, DocMapper, DocParsingError,
-        FieldMappingEntry, Mode, TermRange, WarmupInfo, DYNAMIC_FIELD_NAME,
+        FieldMappingEntry, Mode, TermDictWarmupPolicy, TermRange, WarmupInfo, WarmupWarning,
+        DYNAMIC_FIELD_NAME,
     };
 
     const JSON_DEFAULT_DOC_MAPPER: &str = r#"
@@ -469,6 +625,8 @@ mod tests {
                 (2, "term1", false),
                 (2, "term2", false),
             ]),
+            low_priority_fast_field_names: HashSet::new(),
+            field_norms_fields: hashset_field(&[1]),
         };
 
         // merging with default has no impact
@@ -486,6 +644,8 @@ mod tests {
                 (3, "term1", false),
                 (2, "term2", true),
             ]),
+            low_priority_fast_field_names: HashSet::new(),
+            field_norms_fields: hashset_field(&[1, 3]),
         };
         wi_base.merge(wi_2.clone());
 
@@ -495,6 +655,7 @@ mod tests {
             hashset(&["fast1", "fast2", "fast3"])
         );
         assert!(wi_base.field_norms);
+        assert_eq!(wi_base.field_norms_fields, hashset_field(&[1, 3]));
 
         let expected_terms = [(1, "term1", false), (1, "term2", true), (2, "term1", false)];
         for (field, term, pos) in expected_terms {
@@ -539,6 +700,73 @@ mod tests {
         assert_eq!(wi_cloned, wi_base);
     }
 
+    #[test]
+    fn test_warmup_info_merge_low_priority_fast_fields() {
+        let mut collector_warmup_info = WarmupInfo {
+            fast_field_names: hashset(&["sort", "agg1", "agg2"]),
+            low_priority_fast_field_names: hashset(&["agg1", "agg2"]),
+            ..WarmupInfo::default()
+        };
+        let query_warmup_info = WarmupInfo {
+            fast_field_names: hashset(&["agg2"]),
+            ..WarmupInfo::default()
+        };
+        collector_warmup_info.merge(query_warmup_info);
+        assert_eq!(
+            collector_warmup_info.fast_field_names,
+            hashset(&["sort", "agg1", "agg2"])
+        );
+        // `agg2` is also needed by the query.
+        assert_eq!(
+            collector_warmup_info.low_priority_fast_field_names,
+            hashset(&["agg1"])
+        );
+    }
+
+    #[test]
+    fn test_warmup_info_merge_all() {
+        assert_eq!(WarmupInfo::merge_all([]), WarmupInfo::default());
+
+        let warmup_infos = [
+            WarmupInfo {
+                term_dict_fields: hashset_field(&[1]),
+                fast_field_names: hashset(&["fast1"]),
+                terms_grouped_by_field: hashmap(&[(1, "term1", true), (2, "term1", false)]),
+                ..WarmupInfo::default()
+            },
+            WarmupInfo {
+                fast_field_names: hashset(&["fast2"]),
+                low_priority_fast_field_names: hashset(&["fast2"]),
+                field_norms: true,
+                field_norms_fields: hashset_field(&[2]),
+                terms_grouped_by_field: hashmap(&[(1, "term2", false), (2, "term2", true)]),
+                ..WarmupInfo::default()
+            },
+            WarmupInfo {
+                term_ranges_grouped_by_field: hashmap_ranges(&[(3, "term1", false)]),
+                ..WarmupInfo::default()
+            },
+        ];
+        let mut expected_warmup_info = WarmupInfo::default();
+        for warmup_info in warmup_infos.clone() {
+            expected_warmup_info.merge(warmup_info);
+        }
+        expected_warmup_info.simplify();
+
+        let merged_warmup_info = WarmupInfo::merge_all(warmup_infos);
+        assert_eq!(merged_warmup_info, expected_warmup_info);
+        // The terms of field 1 are dropped, unless their positions are needed, as its whole
+        // term dictionary is loaded.
+        assert_eq!(
+            merged_warmup_info.terms_grouped_by_field,
+            hashmap(&[(1, "term1", true), (2, "term1", false), (2, "term2", true)])
+        );
+        assert_eq!(
+            merged_warmup_info.low_priority_fast_field_names,
+            hashset(&["fast2"])
+        );
+    }
+
     #[test]
     fn test_warmup_info_simplify() {
         let mut warmup_info = WarmupInfo {
@@ -555,6 +783,8 @@ mod tests {
                 (1, "term2", true),
                 (2, "term3", false),
             ]),
+            low_priority_fast_field_names: HashSet::new(),
+            field_norms_fields: HashSet::new(),
         };
         let expected = WarmupInfo {
             term_dict_fields: hashset_field(&[1]),
@@ -565,12 +795,78 @@ mod tests {
                 (1, "term2", true),
                 (2, "term3", false),
             ]),
+            low_priority_fast_field_names: HashSet::new(),
+            field_norms_fields: HashSet::new(),
         };
 
         warmup_info.simplify();
         assert_eq!(warmup_info, expected);
     }
 
+    #[test]
+    fn test_warmup_info_fields_requiring_field_norms() {
+        let mut schema_builder = Schema::builder();
+        let title_field = schema_builder.add_text_field("title", tantivy::schema::TEXT);
+        let body_field = schema_builder.add_text_field("body", tantivy::schema::TEXT);
+        let schema = schema_builder.build();
+
+        let mut warmup_info = WarmupInfo {
+            field_norms_fields: HashSet::from([body_field]),
+            ..WarmupInfo::default()
+        };
+        assert!(warmup_info.fields_requiring_field_norms(&schema).is_empty());
+
+        warmup_info.field_norms = true;
+        assert_eq!(
+            warmup_info.fields_requiring_field_norms(&schema),
+            [body_field]
+        );
+
+        // Without any queried field, the field norms of all the fields are needed.
+        warmup_info.field_norms_fields.clear();
+        assert_eq!(
+            warmup_info.fields_requiring_field_norms(&schema),
+            [title_field, body_field]
+        );
+    }
+
+    #[test]
+    fn test_term_dict_warmup_policy() {
+        let allow_list: TermDictWarmupPolicy =
+            serde_json::from_str(r#"{"allow_list": ["service"]}"#).unwrap();
+        assert!(allow_list.is_field_allowed("service"));
+        assert!(!allow_list.is_field_allowed("trace_id"));
+
+        let deny_list: TermDictWarmupPolicy =
+            serde_json::from_str(r#"{"deny_list": ["trace_id"]}"#).unwrap();
+        assert!(deny_list.is_field_allowed("service"));
+        assert!(!deny_list.is_field_allowed("trace_id"));
+    }
+
+    #[test]
+    fn test_warmup_info_validate() {
+        let mut query_warmup_info = WarmupInfo {
+            terms_grouped_by_field: hashmap(&[(1, "term1", true), (2, "term1", false)]),
+            ..WarmupInfo::default()
+        };
+        assert!(query_warmup_info.validate().is_empty());
+
+        let collector_warmup_info = WarmupInfo {
+            terms_grouped_by_field: hashmap(&[(1, "term1", false)]),
+            term_ranges_grouped_by_field: hashmap_ranges(&[(1, "term2", false)]),
+            ..WarmupInfo::default()
+        };
+        query_warmup_info.merge(collector_warmup_info);
+
+        let field = Field::from_field_id(1);
+        let term = Term::from_field_text(field, "term1");
+        assert!(query_warmup_info.terms_grouped_by_field[&field][&term]);
+        assert_eq!(
+            query_warmup_info.validate(),
+            [WarmupWarning::ConflictingPositionNeeded { field }]
+        );
+    }
+
     #[test]
     #[cfg(feature = "multilang")]
     fn test_doc_mapper_query_with_multilang_field() {
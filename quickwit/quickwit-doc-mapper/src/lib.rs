@@ -44,7 +44,9 @@ use default_doc_mapper::{
     NgramTokenizerOption, QuickwitTextNormalizer, QuickwitTextTokenizer, RegexTokenizerOption,
     TokenFilterType, TokenizerType,
 };
-pub use doc_mapper::{DocMapper, JsonObject, NamedField, TermRange, WarmupInfo};
+pub use doc_mapper::{
+    DocMapper, JsonObject, NamedField, TermDictWarmupPolicy, TermRange, WarmupInfo, WarmupWarning,
+};
 pub use error::{DocParsingError, QueryParserError};
 use quickwit_common::shared_consts::FIELD_PRESENCE_FIELD_NAME;
 pub use routing_expression::RoutingExpr;
@@ -86,6 +88,7 @@ pub enum Cardinality {
     QuickwitTextNormalizer,
     QuickwitTextTokenizer,
     RegexTokenizerOption,
+    TermDictWarmupPolicy,
     TokenFilterType,
     TokenizerConfig,
     TokenizerEntry,
@@ -114,8 +114,17 @@ pub(crate) fn build_query(
             .or_default() |= need_position;
     });
 
+    // Field norms are only used to score the fields the query reads postings from.
+    let field_norms_fields: HashSet<Field> = terms_grouped_by_field
+        .keys()
+        .chain(term_ranges_grouped_by_field.keys())
+        .chain(term_set_query_fields.iter())
+        .copied()
+        .collect();
+
     let warmup_info = WarmupInfo {
         term_dict_fields: term_set_query_fields,
+        field_norms_fields,
         terms_grouped_by_field,
         term_ranges_grouped_by_field,
         fast_field_names,
@@ -270,6 +279,8 @@ fn extract_prefix_term_ranges(
 
 #[cfg(test)]
 mod test {
+    use std::collections::HashSet;
+
     use quickwit_datetime::{parse_date_time_str, DateTimeInputFormat};
     use quickwit_query::create_default_quickwit_tokenizer_manager;
     use quickwit_query::query_ast::query_ast_from_user_text;
@@ -668,6 +679,10 @@ mod test {
         assert!(warmup_info
             .term_dict_fields
             .contains(&tantivy::schema::Field::from_field_id(1)));
+        assert_eq!(
+            warmup_info.field_norms_fields,
+            HashSet::from([tantivy::schema::Field::from_field_id(1)])
+        );
 
         let (_, warmup_info) = build_query(
             &query_without_set,
@@ -678,5 +693,9 @@ mod test {
         )
         .unwrap();
         assert!(warmup_info.term_dict_fields.is_empty());
+        assert_eq!(
+            warmup_info.field_norms_fields,
+            HashSet::from([tantivy::schema::Field::from_field_id(1)])
+        );
     }
 }
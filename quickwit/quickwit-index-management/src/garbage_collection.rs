@@ -135,13 +135,11 @@ pub async fn run_garbage_collect(
     }
 
     // Schedule all eligible staged splits for delete
-    let split_ids: Vec<SplitId> = deletable_staged_splits
-        .iter()
-        .map(|split| split.split_id.to_string())
-        .collect();
-    if !split_ids.is_empty() {
-        let mark_splits_for_deletion_request =
-            MarkSplitsForDeletionRequest::new(index_uid.clone(), split_ids);
+    if !deletable_staged_splits.is_empty() {
+        let mark_splits_for_deletion_request = MarkSplitsForDeletionRequest::from_iter(
+            index_uid.clone(),
+            deletable_staged_splits.iter().map(|split| &split.split_id),
+        );
         protect_future(
             progress_opt,
             metastore.mark_splits_for_deletion(mark_splits_for_deletion_request),
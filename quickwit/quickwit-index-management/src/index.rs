@@ -239,16 +239,18 @@ impl IndexService {
         for index_id_pattern in &index_id_patterns {
             if index_id_pattern.contains('*') {
                 return Err(IndexServiceError::Metastore(
-                    MetastoreError::InvalidArgument {
-                        message: format!("index_id pattern {} contains *", index_id_pattern),
-                    },
+                    MetastoreError::invalid_argument_for_field(
+                        "index_id_patterns",
+                        format!("index_id pattern {} contains *", index_id_pattern),
+                    ),
                 ));
             }
             if index_id_pattern == "_all" {
                 return Err(IndexServiceError::Metastore(
-                    MetastoreError::InvalidArgument {
-                        message: "index_id pattern _all not supported".to_string(),
-                    },
+                    MetastoreError::invalid_argument_for_field(
+                        "index_id_patterns",
+                        "index_id pattern _all not supported",
+                    ),
                 ));
             }
         }
@@ -482,6 +484,7 @@ impl IndexService {
                 IndexServiceError::Metastore(MetastoreError::NotFound(EntityKind::Source {
                     index_id: index_id.to_string(),
                     source_id: source_id.to_string(),
+                    source_type: None,
                 }))
             })?
             .clone();
@@ -662,7 +662,7 @@ mod tests {
                     return Ok(response);
                 }
                 num_fails -= 1;
-                Err(MetastoreError::Timeout("timeout error".to_string()))
+                Err(MetastoreError::timeout("timeout error"))
             });
         mock_metastore
             .expect_last_delete_opstamp()
@@ -124,6 +124,7 @@ async fn test_update_on_multi_nodes_cluster() {
             IndexUpdates {
                 search_settings: SearchSettings {
                     default_search_fields: vec!["title".to_string(), "body".to_string()],
+                    term_dict_warmup_policy: None,
                 },
                 retention_policy_opt: None,
             },
@@ -27,7 +27,7 @@ use serde_json::Value as JsonValue;
 
 use crate::file_backed::file_backed_index::FileBackedIndex;
 use crate::file_backed::manifest::Manifest;
-use crate::{IndexMetadata, SplitMetadata};
+use crate::{IndexMetadata, SplitMetadata, GLOBAL_QUICKWIT_RESOURCE_VERSION};
 
 /// In order to avoid confusion, we need to make sure that the
 /// resource versions is the same for all resources.
@@ -46,8 +46,6 @@ use crate::{IndexMetadata, SplitMetadata};
 ///     #[serde(rename="0.2")]
 ///     V0_2(MyResourceV1) //< there was no change in this version.
 /// }
-const GLOBAL_QUICKWIT_RESOURCE_VERSION: &str = "0.8";
-
 /// This test makes sure that the resource is using the current `GLOBAL_QUICKWIT_RESOURCE_VERSION`.
 fn test_global_version<T: Serialize>(serializable: &T) -> anyhow::Result<()> {
     let json = serde_json::to_value(serializable).unwrap();
@@ -61,6 +61,10 @@ use quickwit_doc_mapper::tag_pruning::TagFilterAst;
 pub use split_metadata::{Split, SplitInfo, SplitMaturity, SplitMetadata, SplitState};
 pub(crate) use split_metadata_version::{SplitMetadataV0_8, VersionedSplitMetadata};
 
+/// Version of the serialized metastore resources (index metadata, splits, manifest, ...) written
+/// by this version of Quickwit. Resources tagged with a newer version cannot be read.
+pub(crate) const GLOBAL_QUICKWIT_RESOURCE_VERSION: &str = "0.8";
+
 #[derive(utoipa::OpenApi)]
 #[openapi(components(schemas(
     Split,
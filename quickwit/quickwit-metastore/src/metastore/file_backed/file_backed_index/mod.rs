@@ -373,7 +373,7 @@ impl FileBackedIndex {
                     let message = format!(
                         "publish token is required for publishing splits for source `{source_id}`"
                     );
-                    MetastoreError::InvalidArgument { message }
+                    MetastoreError::invalid_argument_for_field("publish_token", message)
                 })?;
                 self.try_apply_delta_v2(checkpoint_delta, publish_token)?;
             } else {
@@ -565,22 +565,34 @@ impl FileBackedIndex {
 
     // Shard API
 
-    fn get_shards_for_source(&self, source_id: &str) -> MetastoreResult<&Shards> {
-        self.per_source_shards.get(source_id).ok_or_else(|| {
-            MetastoreError::NotFound(EntityKind::Source {
-                index_id: self.index_id().to_string(),
-                source_id: source_id.to_string(),
-            })
+    /// Error returned when the shards of a source are not found. The source type is reported
+    /// when the source is configured.
+    fn shards_not_found_error(&self, source_id: &str) -> MetastoreError {
+        MetastoreError::NotFound(EntityKind::Source {
+            index_id: self.index_id().to_string(),
+            source_id: source_id.to_string(),
+            source_type: self
+                .metadata
+                .sources
+                .get(source_id)
+                .map(SourceConfig::source_type),
         })
     }
 
+    fn get_shards_for_source(&self, source_id: &str) -> MetastoreResult<&Shards> {
+        self.per_source_shards
+            .get(source_id)
+            .ok_or_else(|| self.shards_not_found_error(source_id))
+    }
+
     fn get_shards_for_source_mut(&mut self, source_id: &str) -> MetastoreResult<&mut Shards> {
-        self.per_source_shards.get_mut(source_id).ok_or_else(|| {
-            MetastoreError::NotFound(EntityKind::Source {
-                index_id: self.metadata.index_id().to_string(),
-                source_id: source_id.to_string(),
-            })
-        })
+        if !self.per_source_shards.contains_key(source_id) {
+            return Err(self.shards_not_found_error(source_id));
+        }
+        Ok(self
+            .per_source_shards
+            .get_mut(source_id)
+            .expect("the shards of the source should exist"))
     }
 
     pub(crate) fn open_shards(
@@ -716,17 +728,18 @@ fn split_query_predicate(split: &&Split, query: &ListSplitsQuery) -> bool {
 
 #[cfg(test)]
 mod tests {
-    use std::collections::BTreeSet;
+    use std::collections::{BTreeSet, HashMap};
 
+    use quickwit_config::{SourceConfig, SourceParams};
     use quickwit_doc_mapper::tag_pruning::TagFilterAst;
     use quickwit_doc_mapper::{BinaryFormat, FieldMappingType};
     use quickwit_proto::ingest::Shard;
-    use quickwit_proto::metastore::ListShardsSubrequest;
+    use quickwit_proto::metastore::{EntityKind, ListShardsSubrequest, MetastoreError, SourceType};
     use quickwit_proto::types::{IndexUid, SourceId};
 
     use super::FileBackedIndex;
     use crate::file_backed::file_backed_index::split_query_predicate;
-    use crate::{ListSplitsQuery, Split, SplitMetadata, SplitState};
+    use crate::{IndexMetadata, ListSplitsQuery, Split, SplitMetadata, SplitState};
 
     impl FileBackedIndex {
         pub(crate) fn insert_shards(&mut self, source_id: &SourceId, shards: Vec<Shard>) {
@@ -993,4 +1006,30 @@ mod tests {
             }
         }
     }
+
+    #[test]
+    fn test_file_backed_index_shards_not_found_error() {
+        let mut index_metadata = IndexMetadata::for_test("test-index", "ram:///indexes/test-index");
+        index_metadata
+            .add_source(SourceConfig::for_test("test-source", SourceParams::void()))
+            .unwrap();
+        let file_backed_index =
+            FileBackedIndex::new(index_metadata, Vec::new(), HashMap::new(), Vec::new());
+
+        let error = file_backed_index
+            .get_shards_for_source("test-source")
+            .unwrap_err();
+        let MetastoreError::NotFound(EntityKind::Source { source_type, .. }) = error else {
+            panic!("expected a source not found error, got `{error:?}`");
+        };
+        assert_eq!(source_type, Some(SourceType::Void));
+
+        let error = file_backed_index
+            .get_shards_for_source("unknown-source")
+            .unwrap_err();
+        let MetastoreError::NotFound(EntityKind::Source { source_type, .. }) = error else {
+            panic!("expected a source not found error, got `{error:?}`");
+        };
+        assert_eq!(source_type, None);
+    }
 }
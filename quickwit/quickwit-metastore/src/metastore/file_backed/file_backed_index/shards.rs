@@ -259,8 +259,8 @@ impl Shards {
         }
         self.checkpoint
             .check_compatibility(&checkpoint_delta)
-            .map_err(|error| MetastoreError::InvalidArgument {
-                message: error.to_string(),
+            .map_err(|error| {
+                MetastoreError::invalid_argument_for_field("checkpoint_delta", error.to_string())
             })?;
 
         let mut shard_ids = Vec::with_capacity(checkpoint_delta.num_partitions());
@@ -270,8 +270,11 @@ impl Shards {
             let shard = self.get_shard(&shard_id)?;
 
             if shard.publish_token() != publish_token {
-                let message = "failed to apply checkpoint delta: invalid publish token".to_string();
-                return Err(MetastoreError::InvalidArgument { message });
+                let message = "failed to apply checkpoint delta: invalid publish token";
+                return Err(MetastoreError::invalid_argument_for_field(
+                    "publish_token",
+                    message,
+                ));
             }
             let publish_position_inclusive = partition_delta.to;
             shard_ids.push((shard_id, publish_position_inclusive))
@@ -29,6 +29,8 @@ use quickwit_storage::{OwnedBytes, Storage, StorageError, StorageErrorKind, Stor
 use serde::{Deserialize, Serialize};
 use tracing::error;
 
+use crate::GLOBAL_QUICKWIT_RESOURCE_VERSION;
+
 pub(super) const MANIFEST_FILE_NAME: &str = "manifest.json";
 
 // The legacy manifest file was deprecated in 0.8.0, we can drop support for it in 0.10.0 or 0.11.0.
@@ -149,7 +151,10 @@ impl TestableForRegression for Manifest {
 pub(super) async fn load_or_create_manifest(storage: &dyn Storage) -> MetastoreResult<Manifest> {
     if file_exists(storage, MANIFEST_FILE_NAME).await? {
         let manifest_json = get_bytes(storage, MANIFEST_FILE_NAME).await?;
-        let manifest: Manifest = serde_utils::from_json_bytes(&manifest_json)?;
+        let manifest: Manifest = serde_utils::from_json_bytes_versioned(
+            &manifest_json,
+            GLOBAL_QUICKWIT_RESOURCE_VERSION,
+        )?;
         return Ok(manifest);
     }
     if file_exists(storage, LEGACY_MANIFEST_FILE_NAME).await? {
@@ -23,6 +23,7 @@ use quickwit_proto::metastore::{serde_utils, EntityKind, MetastoreError, Metasto
 use quickwit_storage::{Storage, StorageError, StorageErrorKind};
 
 use crate::metastore::file_backed::file_backed_index::FileBackedIndex;
+use crate::GLOBAL_QUICKWIT_RESOURCE_VERSION;
 
 /// Index metastore file managed by [`FileBackedMetastore`](crate::FileBackedMetastore).
 pub(super) const METASTORE_FILE_NAME: &str = "metastore.json";
@@ -58,7 +59,8 @@ pub(super) async fn load_index(
         .await
         .map_err(|storage_err| convert_error(index_id, storage_err))?;
 
-    let index: FileBackedIndex = serde_utils::from_json_bytes(&content)?;
+    let index: FileBackedIndex =
+        serde_utils::from_json_bytes_versioned(&content, GLOBAL_QUICKWIT_RESOURCE_VERSION)?;
 
     if index.index_id() != index_id {
         return Err(MetastoreError::Internal {
@@ -124,9 +124,10 @@ impl IndexMetadata {
     /// Adds a source to the index. Returns an error if the source already exists.
     pub fn add_source(&mut self, source_config: SourceConfig) -> MetastoreResult<()> {
         match self.sources.entry(source_config.source_id.clone()) {
-            Entry::Occupied(_) => Err(MetastoreError::AlreadyExists(EntityKind::Source {
+            Entry::Occupied(entry) => Err(MetastoreError::AlreadyExists(EntityKind::Source {
                 index_id: self.index_id().to_string(),
                 source_id: source_config.source_id,
+                source_type: Some(entry.get().source_type()),
             })),
             Entry::Vacant(entry) => {
                 self.checkpoint.add_source(&source_config.source_id);
@@ -141,6 +142,7 @@ impl IndexMetadata {
             return Err(MetastoreError::NotFound(EntityKind::Source {
                 index_id: self.index_id().to_string(),
                 source_id: source_id.to_string(),
+                source_type: None,
             }));
         };
         let mutation_occurred = source_config.enabled != enable;
@@ -154,6 +156,7 @@ impl IndexMetadata {
             MetastoreError::NotFound(EntityKind::Source {
                 index_id: self.index_id().to_string(),
                 source_id: source_id.to_string(),
+                source_type: None,
             })
         })?;
         self.checkpoint.remove_source(source_id);
@@ -34,6 +34,7 @@ use itertools::Itertools;
 use quickwit_common::thread_pool::run_cpu_intensive;
 use quickwit_config::{IndexConfig, RetentionPolicy, SearchSettings, SourceConfig};
 use quickwit_doc_mapper::tag_pruning::TagFilterAst;
+use quickwit_proto::metastore::serde_utils::CompressionPolicy;
 use quickwit_proto::metastore::{
     serde_utils, AddSourceRequest, CreateIndexRequest, CreateIndexResponse, DeleteTask,
     IndexMetadataFailure, IndexMetadataRequest, IndexMetadataResponse, IndexesMetadataResponse,
@@ -45,7 +46,7 @@ use quickwit_proto::types::{IndexUid, SplitId};
 use time::OffsetDateTime;
 
 use crate::checkpoint::IndexCheckpointDelta;
-use crate::{Split, SplitMetadata, SplitState};
+use crate::{Split, SplitMetadata, SplitState, GLOBAL_QUICKWIT_RESOURCE_VERSION};
 
 /// Splits batch size returned by the stream splits API
 pub(crate) const STREAM_SPLITS_CHUNK_SIZE: usize = 100;
@@ -177,7 +178,10 @@ pub trait CreateIndexResponseExt {
 
 impl CreateIndexResponseExt for CreateIndexResponse {
     fn deserialize_index_metadata(&self) -> MetastoreResult<IndexMetadata> {
-        serde_utils::from_json_str(&self.index_metadata_json)
+        serde_utils::from_json_bytes_versioned(
+            self.index_metadata_json.as_bytes(),
+            GLOBAL_QUICKWIT_RESOURCE_VERSION,
+        )
     }
 }
 
@@ -253,7 +257,10 @@ impl IndexMetadataResponseExt for IndexMetadataResponse {
     }
 
     fn deserialize_index_metadata(&self) -> MetastoreResult<IndexMetadata> {
-        serde_utils::from_json_str(&self.index_metadata_serialized_json)
+        serde_utils::from_json_bytes_versioned(
+            self.index_metadata_serialized_json.as_bytes(),
+            GLOBAL_QUICKWIT_RESOURCE_VERSION,
+        )
     }
 }
 
@@ -288,7 +295,10 @@ impl IndexesMetadataResponseExt for IndexesMetadataResponse {
         failures: Vec<IndexMetadataFailure>,
     ) -> MetastoreResult<Self> {
         let indexes_metadata_json_zstd = run_cpu_intensive(move || {
-            serde_utils::to_json_zstd(&indexes_metadata, 0).map(Bytes::from)
+            // Index metadata is small and read on every search, so it is compressed with the
+            // fast level of the policy (1) rather than zstd's default level (3) as it used to be.
+            let compression_level = CompressionPolicy::default().fast_level;
+            serde_utils::to_json_zstd(&indexes_metadata, compression_level).map(Bytes::from)
         })
         .await
         .map_err(|join_error| MetastoreError::Internal {
@@ -338,7 +348,10 @@ impl ListIndexesMetadataResponseExt for ListIndexesMetadataResponse {
         indexes_metadata: Vec<IndexMetadata>,
     ) -> MetastoreResult<Self> {
         let indexes_metadata_json_zstd = run_cpu_intensive(move || {
-            serde_utils::to_json_zstd(&indexes_metadata, 0).map(Bytes::from)
+            // Index metadata is small and read on every search, so it is compressed with the
+            // fast level of the policy (1) rather than zstd's default level (3) as it used to be.
+            let compression_level = CompressionPolicy::default().fast_level;
+            serde_utils::to_json_zstd(&indexes_metadata, compression_level).map(Bytes::from)
         })
         .await
         .map_err(|join_error| MetastoreError::Internal {
@@ -225,8 +225,11 @@ async fn try_apply_delta_v2(
         if current_publish_token_opt.is_none()
             || current_publish_token_opt.unwrap() != publish_token
         {
-            let message = "failed to apply checkpoint delta: invalid publish token".to_string();
-            return Err(MetastoreError::InvalidArgument { message });
+            let message = "failed to apply checkpoint delta: invalid publish token";
+            return Err(MetastoreError::invalid_argument_for_field(
+                "publish_token",
+                message,
+            ));
         }
         let partition_id = PartitionId::from(shard_id);
         let current_position = Position::from(current_position);
@@ -234,8 +237,8 @@ async fn try_apply_delta_v2(
     }
     current_checkpoint
         .try_apply_delta(checkpoint_delta)
-        .map_err(|error| MetastoreError::InvalidArgument {
-            message: error.to_string(),
+        .map_err(|error| {
+            MetastoreError::invalid_argument_for_field("checkpoint_delta", error.to_string())
         })?;
 
     let mut shard_ids = Vec::with_capacity(num_partitions);
@@ -685,7 +688,7 @@ impl MetastoreService for PostgresqlMetastore {
                             "publish token is required for publishing splits for source \
                              `{source_id}`"
                         );
-                        MetastoreError::InvalidArgument { message }
+                        MetastoreError::invalid_argument_for_field("publish_token", message)
                     })?;
                     try_apply_delta_v2(
                         tx,
@@ -1492,14 +1495,12 @@ impl MetastoreService for PostgresqlMetastore {
         let index_template: IndexTemplate =
             serde_utils::from_json_str(&request.index_template_json)?;
 
-        index_template
-            .validate()
-            .map_err(|error| MetastoreError::InvalidArgument {
-                message: format!(
-                    "invalid index template `{}`: `{error}`",
-                    index_template.template_id
-                ),
-            })?;
+        index_template.validate().map_err(|error| {
+            MetastoreError::invalid_argument(format!(
+                "invalid index template `{}`: `{error}`",
+                index_template.template_id
+            ))
+        })?;
 
         let mut positive_patterns = Vec::new();
         let mut negative_patterns = Vec::new();
@@ -1660,9 +1661,12 @@ async fn open_or_fetch_shard<'e>(
     if let Some(pg_shard) = pg_shard_opt {
         return Ok(pg_shard.into());
     }
+    // The shard could neither be opened nor fetched: the source does not exist, so its type is
+    // unknown.
     Err(MetastoreError::NotFound(EntityKind::Source {
-        index_id: subrequest.index_uid().to_string(),
+        index_id: subrequest.index_uid().index_id.clone(),
         source_id: subrequest.source_id.clone(),
+        source_type: None,
     }))
 }
 
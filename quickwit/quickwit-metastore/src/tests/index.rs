@@ -127,6 +127,7 @@ pub async fn test_metastore_update_index<
             .filter(|f| !current_defaults.contains(&f.name))
             .map(|f| f.name.clone())
             .collect(),
+        term_dict_warmup_policy: None,
     };
 
     let new_retention_policy_opt = Some(RetentionPolicy {
@@ -627,7 +627,7 @@ pub async fn test_metastore_apply_checkpoint_delta_v2_single_shard<
         .await
         .unwrap_err();
     assert!(
-        matches!(error, MetastoreError::InvalidArgument { message } if message.contains("token"))
+        matches!(error, MetastoreError::InvalidArgument { message, .. } if message.contains("token"))
     );
 
     let index_checkpoint_delta_json = serde_json::to_string(&index_checkpoint_delta).unwrap();
@@ -666,7 +666,7 @@ pub async fn test_metastore_apply_checkpoint_delta_v2_single_shard<
         .await
         .unwrap_err();
     assert!(
-        matches!(error, MetastoreError::InvalidArgument { message } if message.contains("checkpoint"))
+        matches!(error, MetastoreError::InvalidArgument { message, .. } if message.contains("checkpoint"))
     );
 
     let mut source_delta = SourceCheckpointDelta::default();
@@ -518,6 +518,8 @@ pub enum SourceType {
     /// Amazon Kinesis
     Kinesis = 7,
     Nats = 8,
+    /// NATS JetStream
+    NatsJetStream = 12,
     /// Google Cloud Pub/Sub
     PubSub = 3,
     /// Apache Pulsar
@@ -540,6 +542,7 @@ impl SourceType {
             SourceType::Kafka => "SOURCE_TYPE_KAFKA",
             SourceType::Kinesis => "SOURCE_TYPE_KINESIS",
             SourceType::Nats => "SOURCE_TYPE_NATS",
+            SourceType::NatsJetStream => "SOURCE_TYPE_NATS_JET_STREAM",
             SourceType::PubSub => "SOURCE_TYPE_PUB_SUB",
             SourceType::Pulsar => "SOURCE_TYPE_PULSAR",
             SourceType::Vec => "SOURCE_TYPE_VEC",
@@ -557,6 +560,7 @@ impl SourceType {
             "SOURCE_TYPE_KAFKA" => Some(Self::Kafka),
             "SOURCE_TYPE_KINESIS" => Some(Self::Kinesis),
             "SOURCE_TYPE_NATS" => Some(Self::Nats),
+            "SOURCE_TYPE_NATS_JET_STREAM" => Some(Self::NatsJetStream),
             "SOURCE_TYPE_PUB_SUB" => Some(Self::PubSub),
             "SOURCE_TYPE_PULSAR" => Some(Self::Pulsar),
             "SOURCE_TYPE_VEC" => Some(Self::Vec),
@@ -191,6 +191,30 @@ pub struct SearchRequest {
     pub search_after: ::core::option::Option<PartialHit>,
     #[prost(enumeration = "CountHits", tag = "17")]
     pub count_hits: i32,
+    /// If set, the leaf search responses will include the `num_slowest_splits`
+    /// splits that took the longest to search, together with their search duration.
+    #[prost(uint32, optional, tag = "18")]
+    pub num_slowest_splits: ::core::option::Option<u32>,
+    /// Timestamp, in nanoseconds since epoch, used to resolve relative time bounds such as `now-1h`.
+    /// It is captured once by the root, so that all the leaves resolve them consistently.
+    #[prost(int64, optional, tag = "19")]
+    pub now_timestamp_nanos: ::core::option::Option<i64>,
+    /// If set, the leaf search responses will include the statistics of every split searched.
+    #[prost(bool, tag = "20")]
+    pub collect_split_stats: bool,
+    /// If set, `end_timestamp` is inclusive: the search covers its entire last second.
+    #[prost(bool, tag = "21")]
+    pub end_timestamp_inclusive: bool,
+    /// Priority class of the search, deciding which split search permits the leaves draw from.
+    #[prost(enumeration = "SearchPriority", tag = "22")]
+    pub priority: i32,
+    /// If not empty, the leaves only search the splits listed here. Meant for debugging.
+    #[prost(string, repeated, tag = "23")]
+    pub split_id_allowlist: ::prost::alloc::vec::Vec<::prost::alloc::string::String>,
+    /// The leaves do not search the splits listed here, even if they are in `split_id_allowlist`.
+    /// Meant for debugging.
+    #[prost(string, repeated, tag = "24")]
+    pub split_id_denylist: ::prost::alloc::vec::Vec<::prost::alloc::string::String>,
 }
 #[derive(Serialize, Deserialize, utoipa::ToSchema)]
 #[derive(Eq, Hash)]
@@ -230,6 +254,22 @@ pub struct SearchResponse {
     /// Scroll Id (only set if scroll_secs was set in the request)
     #[prost(string, optional, tag = "6")]
     pub scroll_id: ::core::option::Option<::prost::alloc::string::String>,
+    /// The statistics of every split searched successfully.
+    /// Only populated if `collect_split_stats` is set in the search request.
+    #[prost(message, repeated, tag = "7")]
+    pub per_split_stats: ::prost::alloc::vec::Vec<SplitStats>,
+    /// The slowest splits searched, sorted by decreasing search duration.
+    /// Only populated if `num_slowest_splits` is set in the search request.
+    #[prost(message, repeated, tag = "8")]
+    pub slowest_splits: ::prost::alloc::vec::Vec<SplitSearchTiming>,
+    /// True if an aggregation was requested and some splits failed: the aggregation result only
+    /// covers the splits searched successfully.
+    #[prost(bool, tag = "9")]
+    pub aggregation_is_partial: bool,
+    /// True if a leaf search ran out of time before searching all of its splits: the response only
+    /// covers the splits searched before the deadline.
+    #[prost(bool, tag = "10")]
+    pub timed_out: bool,
 }
 #[derive(Serialize, Deserialize, utoipa::ToSchema)]
 #[allow(clippy::derive_partial_eq_without_eq)]
@@ -244,6 +284,38 @@ pub struct SplitSearchError {
     /// Flag to indicate if the error can be considered a retryable error
     #[prost(bool, tag = "3")]
     pub retryable_error: bool,
+    /// Kind of the error, to react to it without parsing `error`.
+    #[prost(enumeration = "SplitSearchErrorKind", tag = "4")]
+    pub error_kind: i32,
+}
+#[derive(Serialize, Deserialize, utoipa::ToSchema)]
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct SplitSearchTiming {
+    /// Split id.
+    #[prost(string, tag = "1")]
+    pub split_id: ::prost::alloc::string::String,
+    /// Time spent searching the split, expressed in microseconds.
+    #[prost(uint64, tag = "2")]
+    pub elapsed_time_micros: u64,
+}
+#[derive(Serialize, Deserialize, utoipa::ToSchema)]
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct SplitStats {
+    /// Split id.
+    #[prost(string, tag = "1")]
+    pub split_id: ::prost::alloc::string::String,
+    /// Number of documents of the split matching the query.
+    #[prost(uint64, tag = "2")]
+    pub num_hits: u64,
+    /// Time spent searching the split, expressed in milliseconds.
+    #[prost(uint64, tag = "3")]
+    pub elapsed_ms: u64,
+    /// Estimated number of bytes downloaded to warm up the split. Only estimated when the searcher
+    /// bounds the warmup download with `max_warmup_bytes_per_split`.
+    #[prost(uint64, optional, tag = "4")]
+    pub estimated_warmup_bytes: ::core::option::Option<u64>,
 }
 #[derive(Serialize, Deserialize, utoipa::ToSchema)]
 #[allow(clippy::derive_partial_eq_without_eq)]
@@ -285,6 +357,9 @@ pub struct SplitIdAndFooterOffsets {
     /// The highest timestamp appearing in the split, in seconds since epoch
     #[prost(int64, optional, tag = "5")]
     pub timestamp_end: ::core::option::Option<i64>,
+    /// The number of documents in the split.
+    #[prost(uint64, tag = "6")]
+    pub num_docs: u64,
 }
 /// Hits returned by a FetchDocRequest.
 ///
@@ -411,6 +486,34 @@ pub struct LeafSearchResponse {
     pub intermediate_aggregation_result: ::core::option::Option<
         ::prost::alloc::vec::Vec<u8>,
     >,
+    /// The slowest splits searched, sorted by decreasing search duration.
+    /// Only populated if `num_slowest_splits` is set in the search request.
+    #[prost(message, repeated, tag = "7")]
+    pub slowest_splits: ::prost::alloc::vec::Vec<SplitSearchTiming>,
+    /// The statistics of every split searched successfully.
+    /// Only populated if `collect_split_stats` is set in the search request.
+    #[prost(message, repeated, tag = "8")]
+    pub per_split_stats: ::prost::alloc::vec::Vec<SplitStats>,
+    /// Number of splits searched in full, including the ones that failed. The splits left unsearched
+    /// because of the leaf search deadline are reported as failed, but are not counted.
+    #[prost(uint32, tag = "9")]
+    pub num_splits_searched: u32,
+    /// Number of splits skipped, or whose search was aborted, because they could not contain better
+    /// hits than the ones already collected.
+    #[prost(uint32, tag = "10")]
+    pub num_splits_pruned: u32,
+    /// Number of splits only searched to count their hits, because they could not contain better
+    /// hits than the ones already collected.
+    #[prost(uint32, tag = "11")]
+    pub num_splits_count_only: u32,
+    /// True if an aggregation was requested and some splits failed: the aggregation result only
+    /// covers the splits searched successfully.
+    #[prost(bool, tag = "12")]
+    pub aggregation_is_partial: bool,
+    /// True if the leaf search ran out of time before searching all of its splits: the response only
+    /// covers the splits searched before the deadline.
+    #[prost(bool, tag = "13")]
+    pub timed_out: bool,
 }
 #[derive(Serialize, Deserialize, utoipa::ToSchema)]
 #[allow(clippy::derive_partial_eq_without_eq)]
@@ -673,6 +776,37 @@ impl CountHits {
 #[serde(rename_all = "snake_case")]
 #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, ::prost::Enumeration)]
 #[repr(i32)]
+pub enum SearchPriority {
+    /// Latency-sensitive search, e.g. from a dashboard. It may use the split search permits
+    /// reserved to interactive searches.
+    Interactive = 0,
+    /// Throughput-oriented search, e.g. a scroll. It only uses the shared split search permits.
+    Background = 1,
+}
+impl SearchPriority {
+    /// String value of the enum field names used in the ProtoBuf definition.
+    ///
+    /// The values are not transformed in any way and thus are considered stable
+    /// (if the ProtoBuf definition does not change) and safe for programmatic use.
+    pub fn as_str_name(&self) -> &'static str {
+        match self {
+            SearchPriority::Interactive => "INTERACTIVE",
+            SearchPriority::Background => "BACKGROUND",
+        }
+    }
+    /// Creates an enum from field names used in the ProtoBuf definition.
+    pub fn from_str_name(value: &str) -> ::core::option::Option<Self> {
+        match value {
+            "INTERACTIVE" => Some(Self::Interactive),
+            "BACKGROUND" => Some(Self::Background),
+            _ => None,
+        }
+    }
+}
+#[derive(Serialize, Deserialize, utoipa::ToSchema)]
+#[serde(rename_all = "snake_case")]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, ::prost::Enumeration)]
+#[repr(i32)]
 pub enum SortOrder {
     /// Ascending order.
     Asc = 0,
@@ -732,6 +866,53 @@ impl SortDatetimeFormat {
         }
     }
 }
+/// Kind of error that occurred while searching a split.
+#[derive(Serialize, Deserialize, utoipa::ToSchema)]
+#[serde(rename_all = "snake_case")]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, ::prost::Enumeration)]
+#[repr(i32)]
+pub enum SplitSearchErrorKind {
+    /// Any other error. This is also the kind reported by nodes predating this field.
+    Internal = 0,
+    /// Reading the split from the storage failed.
+    StorageIo = 1,
+    /// The split search timed out.
+    Timeout = 2,
+    /// The split data is corrupted.
+    Corruption = 3,
+    /// The query or the aggregation is invalid for this split.
+    Query = 4,
+    /// The intermediate aggregation result of the split could not be parsed.
+    AggregationParse = 5,
+}
+impl SplitSearchErrorKind {
+    /// String value of the enum field names used in the ProtoBuf definition.
+    ///
+    /// The values are not transformed in any way and thus are considered stable
+    /// (if the ProtoBuf definition does not change) and safe for programmatic use.
+    pub fn as_str_name(&self) -> &'static str {
+        match self {
+            SplitSearchErrorKind::Internal => "INTERNAL",
+            SplitSearchErrorKind::StorageIo => "STORAGE_IO",
+            SplitSearchErrorKind::Timeout => "TIMEOUT",
+            SplitSearchErrorKind::Corruption => "CORRUPTION",
+            SplitSearchErrorKind::Query => "QUERY",
+            SplitSearchErrorKind::AggregationParse => "AGGREGATION_PARSE",
+        }
+    }
+    /// Creates an enum from field names used in the ProtoBuf definition.
+    pub fn from_str_name(value: &str) -> ::core::option::Option<Self> {
+        match value {
+            "INTERNAL" => Some(Self::Internal),
+            "STORAGE_IO" => Some(Self::StorageIo),
+            "TIMEOUT" => Some(Self::Timeout),
+            "CORRUPTION" => Some(Self::Corruption),
+            "QUERY" => Some(Self::Query),
+            "AGGREGATION_PARSE" => Some(Self::AggregationParse),
+            _ => None,
+        }
+    }
+}
 #[derive(Serialize, Deserialize, utoipa::ToSchema)]
 #[serde(rename_all = "snake_case")]
 #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, ::prost::Enumeration)]
@@ -93,9 +93,9 @@ impl From<ControlPlaneError> for MetastoreError {
                 cause: message,
             },
             ControlPlaneError::Metastore(error) => error,
-            ControlPlaneError::Timeout(message) => MetastoreError::Timeout(message),
+            ControlPlaneError::Timeout(message) => MetastoreError::timeout(message),
             ControlPlaneError::TooManyRequests => MetastoreError::TooManyRequests,
-            ControlPlaneError::Unavailable(message) => MetastoreError::Unavailable(message),
+            ControlPlaneError::Unavailable(message) => MetastoreError::unavailable(message),
         }
     }
 }
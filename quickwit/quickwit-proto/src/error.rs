@@ -20,9 +20,11 @@
 use std::convert::Infallible;
 use std::error::Error;
 use std::fmt::Debug;
+use std::time::Duration;
 
 use anyhow::Context;
 use quickwit_actors::AskError;
+use quickwit_common::tower::GRPC_REQUEST_TIMEOUT;
 use serde::de::DeserializeOwned;
 use serde::Serialize;
 use tonic::metadata::BinaryMetadataValue;
@@ -115,6 +117,16 @@ pub trait GrpcServiceError: ServiceError + Serialize + DeserializeOwned + Send +
 
     fn new_timeout(message: String) -> Self;
 
+    /// Same as [`GrpcServiceError::new_timeout`], for errors able to report how long the request
+    /// ran and the timeout it hit, when known. The durations are dropped by default.
+    fn new_timeout_with_durations(
+        message: String,
+        _elapsed: Option<Duration>,
+        _limit: Option<Duration>,
+    ) -> Self {
+        Self::new_timeout(message)
+    }
+
     fn new_too_many_requests() -> Self;
 
     fn new_unavailable(message: String) -> Self;
@@ -140,6 +152,22 @@ where E: GrpcServiceError {
     status
 }
 
+/// Returns whether the status was produced by the client because the request exceeded the timeout
+/// of its channel, by looking for the timeout error of tonic or tower in its source chain.
+fn is_channel_timeout(status: &tonic::Status) -> bool {
+    let mut source_opt = status.source();
+
+    while let Some(source) = source_opt {
+        if source.is::<tonic::transport::TimeoutExpired>()
+            || source.is::<tower::timeout::error::Elapsed>()
+        {
+            return true;
+        }
+        source_opt = source.source();
+    }
+    false
+}
+
 /// Converts a gRPC status into a service error.
 pub fn grpc_status_to_service_error<E>(status: tonic::Status, rpc_name: &'static str) -> E
 where E: GrpcServiceError {
@@ -159,6 +187,11 @@ where E: GrpcServiceError {
     let message = status.message().to_string();
     error!(code = ?status.code(), rpc = rpc_name, "gRPC transport error: {message}");
 
+    if is_channel_timeout(&status) {
+        // The limit is the timeout of the channels created with `make_channel`, but we can't tell
+        // how long the request ran.
+        return E::new_timeout_with_durations(message, None, Some(GRPC_REQUEST_TIMEOUT));
+    }
     match status.code() {
         // `Cancelled` is a client timeout whereas `DeadlineExceeded` is a server timeout. At this
         // stage, we don't distinguish them.
@@ -0,0 +1,380 @@
+// Copyright (C) 2024 Quickwit, Inc.
+//
+// Quickwit is offered under the AGPL v3.0 and as commercial software.
+// For commercial licensing, contact us at hello@quickwit.io.
+//
+// AGPL:
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+
+use super::{EntityKind, MetastoreError, UnavailableReason};
+
+/// Version of the [`MetastoreErrorEnvelope`] format written by this version of Quickwit.
+pub const METASTORE_ERROR_ENVELOPE_VERSION: u32 = 1;
+
+/// Stable, versioned JSON representation of a [`MetastoreError`], suitable for persisting errors.
+///
+/// The derived representation of `MetastoreError`, which is used to propagate errors over gRPC,
+/// follows the shape of the enum and changes whenever a variant is renamed. This one is decoupled
+/// from the enum: variants can evolve as long as they keep mapping to and from the frozen
+/// `MetastoreErrorV1` representation.
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+pub struct MetastoreErrorEnvelope {
+    version: u32,
+    error: MetastoreErrorV1,
+}
+
+impl MetastoreErrorEnvelope {
+    /// Returns the version of the format the envelope was written with.
+    pub fn version(&self) -> u32 {
+        self.version
+    }
+}
+
+/// Frozen representation of a `MetastoreError` in version 1 of the envelope format. Do not rename
+/// or modify variants: add new ones, or introduce a new version instead.
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", content = "details", rename_all = "snake_case")]
+enum MetastoreErrorV1 {
+    AlreadyExists {
+        entity: EntityKind,
+    },
+    Connection {
+        message: String,
+    },
+    Db {
+        message: String,
+    },
+    FailedPrecondition {
+        entity: EntityKind,
+        message: String,
+    },
+    Forbidden {
+        message: String,
+    },
+    IncompatibleVersion {
+        struct_name: String,
+        found: String,
+        supported: String,
+    },
+    Internal {
+        message: String,
+        cause: String,
+    },
+    InvalidArgument {
+        message: String,
+        // Added after the format was frozen: omitted when unknown, so that errors without a
+        // field keep their original representation.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        field: Option<String>,
+    },
+    Io {
+        message: String,
+    },
+    JsonDeserialize {
+        struct_name: String,
+        message: String,
+    },
+    JsonSerialize {
+        struct_name: String,
+        message: String,
+    },
+    NotFound {
+        entity: EntityKind,
+    },
+    Timeout {
+        message: String,
+        // Added after the format was frozen: omitted when unknown, so that errors without
+        // durations keep their original representation.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        elapsed: Option<Duration>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        limit: Option<Duration>,
+    },
+    TooManyRequests,
+    Unavailable {
+        message: String,
+        // Added after the format was frozen: omitted when unknown, so that errors without a
+        // reason keep their original representation.
+        #[serde(default, skip_serializing_if = "UnavailableReason::is_unknown")]
+        reason: UnavailableReason,
+    },
+}
+
+impl From<MetastoreError> for MetastoreErrorEnvelope {
+    fn from(metastore_error: MetastoreError) -> Self {
+        let error = match metastore_error {
+            MetastoreError::AlreadyExists(entity) => MetastoreErrorV1::AlreadyExists { entity },
+            MetastoreError::Connection { message } => MetastoreErrorV1::Connection { message },
+            MetastoreError::Db { message } => MetastoreErrorV1::Db { message },
+            MetastoreError::FailedPrecondition { entity, message } => {
+                MetastoreErrorV1::FailedPrecondition { entity, message }
+            }
+            MetastoreError::Forbidden { message } => MetastoreErrorV1::Forbidden { message },
+            MetastoreError::IncompatibleVersion {
+                struct_name,
+                found,
+                supported,
+            } => MetastoreErrorV1::IncompatibleVersion {
+                struct_name,
+                found,
+                supported,
+            },
+            MetastoreError::Internal { message, cause } => {
+                MetastoreErrorV1::Internal { message, cause }
+            }
+            MetastoreError::InvalidArgument { message, field } => {
+                MetastoreErrorV1::InvalidArgument { message, field }
+            }
+            MetastoreError::Io { message } => MetastoreErrorV1::Io { message },
+            MetastoreError::JsonDeserializeError {
+                struct_name,
+                message,
+            } => MetastoreErrorV1::JsonDeserialize {
+                struct_name,
+                message,
+            },
+            MetastoreError::JsonSerializeError {
+                struct_name,
+                message,
+            } => MetastoreErrorV1::JsonSerialize {
+                struct_name,
+                message,
+            },
+            MetastoreError::NotFound(entity) => MetastoreErrorV1::NotFound { entity },
+            MetastoreError::Timeout {
+                message,
+                elapsed,
+                limit,
+            } => MetastoreErrorV1::Timeout {
+                message,
+                elapsed,
+                limit,
+            },
+            MetastoreError::TooManyRequests => MetastoreErrorV1::TooManyRequests,
+            MetastoreError::Unavailable { message, reason } => {
+                MetastoreErrorV1::Unavailable { message, reason }
+            }
+        };
+        MetastoreErrorEnvelope {
+            version: METASTORE_ERROR_ENVELOPE_VERSION,
+            error,
+        }
+    }
+}
+
+impl From<MetastoreErrorEnvelope> for MetastoreError {
+    fn from(envelope: MetastoreErrorEnvelope) -> Self {
+        match envelope.error {
+            MetastoreErrorV1::AlreadyExists { entity } => MetastoreError::AlreadyExists(entity),
+            MetastoreErrorV1::Connection { message } => MetastoreError::Connection { message },
+            MetastoreErrorV1::Db { message } => MetastoreError::Db { message },
+            MetastoreErrorV1::FailedPrecondition { entity, message } => {
+                MetastoreError::FailedPrecondition { entity, message }
+            }
+            MetastoreErrorV1::Forbidden { message } => MetastoreError::Forbidden { message },
+            MetastoreErrorV1::IncompatibleVersion {
+                struct_name,
+                found,
+                supported,
+            } => MetastoreError::IncompatibleVersion {
+                struct_name,
+                found,
+                supported,
+            },
+            MetastoreErrorV1::Internal { message, cause } => {
+                MetastoreError::Internal { message, cause }
+            }
+            MetastoreErrorV1::InvalidArgument { message, field } => {
+                MetastoreError::InvalidArgument { message, field }
+            }
+            MetastoreErrorV1::Io { message } => MetastoreError::Io { message },
+            MetastoreErrorV1::JsonDeserialize {
+                struct_name,
+                message,
+            } => MetastoreError::JsonDeserializeError {
+                struct_name,
+                message,
+            },
+            MetastoreErrorV1::JsonSerialize {
+                struct_name,
+                message,
+            } => MetastoreError::JsonSerializeError {
+                struct_name,
+                message,
+            },
+            MetastoreErrorV1::NotFound { entity } => MetastoreError::NotFound(entity),
+            MetastoreErrorV1::Timeout {
+                message,
+                elapsed,
+                limit,
+            } => MetastoreError::Timeout {
+                message,
+                elapsed,
+                limit,
+            },
+            MetastoreErrorV1::TooManyRequests => MetastoreError::TooManyRequests,
+            MetastoreErrorV1::Unavailable { message, reason } => {
+                MetastoreError::Unavailable { message, reason }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::metastore::SourceType;
+
+    #[test]
+    fn test_metastore_error_envelope_frozen_fixtures() {
+        let index_entity = || EntityKind::Index {
+            index_id: "test-index".to_string(),
+        };
+        let fixtures = [
+            (
+                r#"{"version":1,"error":{"type":"already_exists","details":{"entity":{"index":{"index_id":"test-index"}}}}}"#,
+                MetastoreError::AlreadyExists(index_entity()),
+            ),
+            (
+                r#"{"version":1,"error":{"type":"connection","details":{"message":"test-message"}}}"#,
+                MetastoreError::Connection {
+                    message: "test-message".to_string(),
+                },
+            ),
+            (
+                r#"{"version":1,"error":{"type":"db","details":{"message":"test-message"}}}"#,
+                MetastoreError::Db {
+                    message: "test-message".to_string(),
+                },
+            ),
+            (
+                r#"{"version":1,"error":{"type":"failed_precondition","details":{"entity":{"index":{"index_id":"test-index"}},"message":"test-message"}}}"#,
+                MetastoreError::FailedPrecondition {
+                    entity: index_entity(),
+                    message: "test-message".to_string(),
+                },
+            ),
+            (
+                r#"{"version":1,"error":{"type":"forbidden","details":{"message":"test-message"}}}"#,
+                MetastoreError::Forbidden {
+                    message: "test-message".to_string(),
+                },
+            ),
+            (
+                r#"{"version":1,"error":{"type":"incompatible_version","details":{"struct_name":"TestStruct","found":"0.9","supported":"0.8"}}}"#,
+                MetastoreError::IncompatibleVersion {
+                    struct_name: "TestStruct".to_string(),
+                    found: "0.9".to_string(),
+                    supported: "0.8".to_string(),
+                },
+            ),
+            (
+                r#"{"version":1,"error":{"type":"internal","details":{"message":"test-message","cause":"test-cause"}}}"#,
+                MetastoreError::Internal {
+                    message: "test-message".to_string(),
+                    cause: "test-cause".to_string(),
+                },
+            ),
+            (
+                r#"{"version":1,"error":{"type":"invalid_argument","details":{"message":"test-message"}}}"#,
+                MetastoreError::invalid_argument("test-message"),
+            ),
+            (
+                r#"{"version":1,"error":{"type":"invalid_argument","details":{"message":"test-message","field":"retention.period"}}}"#,
+                MetastoreError::invalid_argument_for_field("retention.period", "test-message"),
+            ),
+            (
+                r#"{"version":1,"error":{"type":"io","details":{"message":"test-message"}}}"#,
+                MetastoreError::Io {
+                    message: "test-message".to_string(),
+                },
+            ),
+            (
+                r#"{"version":1,"error":{"type":"json_deserialize","details":{"struct_name":"TestStruct","message":"test-message"}}}"#,
+                MetastoreError::JsonDeserializeError {
+                    struct_name: "TestStruct".to_string(),
+                    message: "test-message".to_string(),
+                },
+            ),
+            (
+                r#"{"version":1,"error":{"type":"json_serialize","details":{"struct_name":"TestStruct","message":"test-message"}}}"#,
+                MetastoreError::JsonSerializeError {
+                    struct_name: "TestStruct".to_string(),
+                    message: "test-message".to_string(),
+                },
+            ),
+            (
+                r#"{"version":1,"error":{"type":"not_found","details":{"entity":{"index":{"index_id":"test-index"}}}}}"#,
+                MetastoreError::NotFound(index_entity()),
+            ),
+            (
+                r#"{"version":1,"error":{"type":"not_found","details":{"entity":{"source":{"index_id":"test-index","source_id":"test-source"}}}}}"#,
+                MetastoreError::NotFound(EntityKind::Source {
+                    index_id: "test-index".to_string(),
+                    source_id: "test-source".to_string(),
+                    source_type: None,
+                }),
+            ),
+            (
+                r#"{"version":1,"error":{"type":"not_found","details":{"entity":{"source":{"index_id":"test-index","source_id":"test-source","source_type":"kafka"}}}}}"#,
+                MetastoreError::NotFound(EntityKind::Source {
+                    index_id: "test-index".to_string(),
+                    source_id: "test-source".to_string(),
+                    source_type: Some(SourceType::Kafka),
+                }),
+            ),
+            (
+                r#"{"version":1,"error":{"type":"timeout","details":{"message":"test-message"}}}"#,
+                MetastoreError::timeout("test-message"),
+            ),
+            (
+                r#"{"version":1,"error":{"type":"timeout","details":{"message":"test-message","elapsed":{"secs":5,"nanos":1000},"limit":{"secs":5,"nanos":0}}}}"#,
+                MetastoreError::Timeout {
+                    message: "test-message".to_string(),
+                    elapsed: Some(Duration::new(5, 1_000)),
+                    limit: Some(Duration::from_secs(5)),
+                },
+            ),
+            (
+                r#"{"version":1,"error":{"type":"too_many_requests"}}"#,
+                MetastoreError::TooManyRequests,
+            ),
+            (
+                r#"{"version":1,"error":{"type":"unavailable","details":{"message":"test-message"}}}"#,
+                MetastoreError::unavailable("test-message"),
+            ),
+            (
+                r#"{"version":1,"error":{"type":"unavailable","details":{"message":"test-message","reason":"pool_exhausted"}}}"#,
+                MetastoreError::Unavailable {
+                    message: "test-message".to_string(),
+                    reason: UnavailableReason::PoolExhausted,
+                },
+            ),
+        ];
+        for (envelope_json, expected_metastore_error) in fixtures {
+            let envelope: MetastoreErrorEnvelope = serde_json::from_str(envelope_json).unwrap();
+            assert_eq!(envelope.version(), METASTORE_ERROR_ENVELOPE_VERSION);
+
+            let metastore_error = MetastoreError::from(envelope);
+            assert_eq!(metastore_error, expected_metastore_error);
+
+            let envelope = MetastoreErrorEnvelope::from(metastore_error);
+            assert_eq!(serde_json::to_string(&envelope).unwrap(), envelope_json);
+        }
+    }
+}
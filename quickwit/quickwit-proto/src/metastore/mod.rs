@@ -18,6 +18,7 @@
 // along with this program. If not, see <http://www.gnu.org/licenses/>.
 
 use std::fmt;
+use std::time::Duration;
 
 use quickwit_common::retry::Retryable;
 use quickwit_common::tower::MakeLoadShedError;
@@ -26,10 +27,13 @@ use serde::{Deserialize, Serialize};
 use crate::types::{IndexId, IndexUid, QueueId, SourceId, SplitId};
 use crate::{GrpcServiceError, ServiceError, ServiceErrorCode};
 
+mod error_envelope;
 pub mod events;
 
 include!("../codegen/quickwit/quickwit.metastore.rs");
 
+pub use error_envelope::{MetastoreErrorEnvelope, METASTORE_ERROR_ENVELOPE_VERSION};
+
 pub type MetastoreResult<T> = Result<T, MetastoreError>;
 
 /// Lists the object types stored and managed by the metastore.
@@ -59,6 +63,9 @@ pub enum EntityKind {
         index_id: IndexId,
         /// Source ID.
         source_id: SourceId,
+        /// Source type, when known.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        source_type: Option<SourceType>,
     },
     /// A shard.
     Shard {
@@ -95,7 +102,13 @@ impl fmt::Display for EntityKind {
             EntityKind::Source {
                 index_id,
                 source_id,
+                source_type: None,
             } => write!(f, "source `{index_id}/{source_id}`"),
+            EntityKind::Source {
+                index_id,
+                source_id,
+                source_type: Some(source_type),
+            } => write!(f, "source `{index_id}/{source_id}` ({source_type})"),
             EntityKind::Split { split_id } => write!(f, "split `{split_id}`"),
             EntityKind::Splits { split_ids } => write!(f, "splits `{}`", split_ids.join(", ")),
             EntityKind::IndexTemplate { template_id } => {
@@ -122,11 +135,30 @@ pub enum MetastoreError {
     #[error("access forbidden: {message}")]
     Forbidden { message: String },
 
+    #[error(
+        "failed to deserialize `{struct_name}`: the metastore was written by a newer version of \
+         Quickwit (found version `{found}`, this version supports up to `{supported}`)"
+    )]
+    IncompatibleVersion {
+        struct_name: String,
+        found: String,
+        supported: String,
+    },
+
     #[error("internal error: {message}; cause: `{cause}`")]
     Internal { message: String, cause: String },
 
-    #[error("invalid argument: {message}")]
-    InvalidArgument { message: String },
+    #[error(
+        "invalid argument{}: {message}",
+        .field.as_ref().map(|field| format!(" for `{field}`")).unwrap_or_default()
+    )]
+    InvalidArgument {
+        message: String,
+        /// Path of the offending input, e.g. `retention.period`, when the error is specific to
+        /// one field. Clients may use it to point at the field to fix.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        field: Option<String>,
+    },
 
     #[error("IO error: {message}")]
     Io { message: String },
@@ -146,21 +178,165 @@ pub enum MetastoreError {
     #[error("{0} not found")]
     NotFound(EntityKind),
 
-    #[error("request timed out: {0}")]
-    Timeout(String),
+    #[error("request timed out: {message}")]
+    Timeout {
+        message: String,
+        /// Time spent on the request before it timed out, when known.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        elapsed: Option<Duration>,
+        /// Timeout that was hit, when known. Clients may use it to adjust their own deadlines.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        limit: Option<Duration>,
+    },
 
     #[error("too many requests")]
     TooManyRequests,
 
-    #[error("service unavailable: {0}")]
-    Unavailable(String),
+    #[error("service unavailable: {message}")]
+    Unavailable {
+        message: String,
+        /// Missing from the errors returned by nodes predating it.
+        #[serde(default)]
+        reason: UnavailableReason,
+    },
+}
+
+/// Reason why the metastore is unavailable, when it can be determined. Clients may use it to adapt
+/// their backoff strategy.
+#[derive(Debug, Clone, Copy, Default, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum UnavailableReason {
+    ConnectionRefused,
+    PoolExhausted,
+    NoLeader,
+    ShuttingDown,
+    #[default]
+    Unknown,
+}
+
+impl UnavailableReason {
+    pub fn is_unknown(&self) -> bool {
+        *self == UnavailableReason::Unknown
+    }
+}
+
+/// How a client should retry a request that failed with a given `MetastoreError`.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum RetryPolicy {
+    /// Retrying is pointless: the request would fail the same way.
+    NoRetry,
+    /// The error is likely a one-off: the request can be retried right away.
+    Immediate,
+    /// The metastore is overloaded or unreachable: the request should be retried after waiting
+    /// for `initial`, then for increasingly longer periods.
+    Backoff { initial: Duration },
+}
+
+impl MetastoreError {
+    /// Creates an `InvalidArgument` error that is not specific to one field.
+    pub fn invalid_argument(message: impl Into<String>) -> Self {
+        MetastoreError::InvalidArgument {
+            message: message.into(),
+            field: None,
+        }
+    }
+
+    /// Creates an `InvalidArgument` error pointing at the offending `field`, e.g.
+    /// `retention.period`.
+    pub fn invalid_argument_for_field(
+        field: impl Into<String>,
+        message: impl Into<String>,
+    ) -> Self {
+        MetastoreError::InvalidArgument {
+            message: message.into(),
+            field: Some(field.into()),
+        }
+    }
+
+    /// Creates a `Timeout` error whose elapsed time and limit are unknown.
+    pub fn timeout(message: impl Into<String>) -> Self {
+        MetastoreError::Timeout {
+            message: message.into(),
+            elapsed: None,
+            limit: None,
+        }
+    }
+
+    /// Creates an `Unavailable` error whose reason is unknown.
+    pub fn unavailable(message: impl Into<String>) -> Self {
+        MetastoreError::Unavailable {
+            message: message.into(),
+            reason: UnavailableReason::Unknown,
+        }
+    }
+
+    /// Returns how to retry the failed request.
+    ///
+    /// This is richer than `Retryable::is_retryable`, which only tells whether a request may be
+    /// retried: it also covers the errors only worth retrying after a while, such as
+    /// `TooManyRequests` or `Unavailable`.
+    pub fn retry_policy(&self) -> RetryPolicy {
+        match self {
+            Self::Connection { .. } | Self::Db { .. } | Self::Io { .. } => RetryPolicy::Backoff {
+                initial: Duration::from_millis(100),
+            },
+            Self::Internal { .. } => RetryPolicy::Immediate,
+            Self::TooManyRequests => RetryPolicy::Backoff {
+                initial: Duration::from_secs(1),
+            },
+            Self::Unavailable { reason, .. } => {
+                let initial = match reason {
+                    // Leader elections and connection pool contention typically resolve fast.
+                    UnavailableReason::NoLeader | UnavailableReason::PoolExhausted => {
+                        Duration::from_millis(100)
+                    }
+                    UnavailableReason::ConnectionRefused
+                    | UnavailableReason::ShuttingDown
+                    | UnavailableReason::Unknown => Duration::from_millis(500),
+                };
+                RetryPolicy::Backoff { initial }
+            }
+            Self::AlreadyExists(_)
+            | Self::FailedPrecondition { .. }
+            | Self::Forbidden { .. }
+            | Self::IncompatibleVersion { .. }
+            | Self::InvalidArgument { .. }
+            | Self::JsonDeserializeError { .. }
+            | Self::JsonSerializeError { .. }
+            | Self::NotFound(_)
+            | Self::Timeout { .. } => RetryPolicy::NoRetry,
+        }
+    }
+}
+
+impl From<std::io::Error> for MetastoreError {
+    fn from(error: std::io::Error) -> Self {
+        MetastoreError::Io {
+            message: error.to_string(),
+        }
+    }
 }
 
 #[cfg(feature = "postgres")]
 impl From<sqlx::Error> for MetastoreError {
     fn from(error: sqlx::Error) -> Self {
-        MetastoreError::Db {
+        let reason = match &error {
+            sqlx::Error::PoolTimedOut => UnavailableReason::PoolExhausted,
+            sqlx::Error::PoolClosed => UnavailableReason::ShuttingDown,
+            sqlx::Error::Io(io_error)
+                if io_error.kind() == std::io::ErrorKind::ConnectionRefused =>
+            {
+                UnavailableReason::ConnectionRefused
+            }
+            _ => {
+                return MetastoreError::Db {
+                    message: error.to_string(),
+                };
+            }
+        };
+        MetastoreError::Unavailable {
             message: error.to_string(),
+            reason,
         }
     }
 }
@@ -173,15 +349,16 @@ impl ServiceError for MetastoreError {
             Self::Db { .. } => ServiceErrorCode::Internal,
             Self::FailedPrecondition { .. } => ServiceErrorCode::BadRequest,
             Self::Forbidden { .. } => ServiceErrorCode::Forbidden,
+            Self::IncompatibleVersion { .. } => ServiceErrorCode::Internal,
             Self::Internal { .. } => ServiceErrorCode::Internal,
             Self::InvalidArgument { .. } => ServiceErrorCode::BadRequest,
             Self::Io { .. } => ServiceErrorCode::Internal,
             Self::JsonDeserializeError { .. } => ServiceErrorCode::Internal,
             Self::JsonSerializeError { .. } => ServiceErrorCode::Internal,
             Self::NotFound(_) => ServiceErrorCode::NotFound,
-            Self::Timeout(_) => ServiceErrorCode::Timeout,
+            Self::Timeout { .. } => ServiceErrorCode::Timeout,
             Self::TooManyRequests => ServiceErrorCode::TooManyRequests,
-            Self::Unavailable(_) => ServiceErrorCode::Unavailable,
+            Self::Unavailable { .. } => ServiceErrorCode::Unavailable,
         }
     }
 }
@@ -195,7 +372,19 @@ impl GrpcServiceError for MetastoreError {
     }
 
     fn new_timeout(message: String) -> Self {
-        Self::Timeout(message)
+        MetastoreError::timeout(message)
+    }
+
+    fn new_timeout_with_durations(
+        message: String,
+        elapsed: Option<Duration>,
+        limit: Option<Duration>,
+    ) -> Self {
+        Self::Timeout {
+            message,
+            elapsed,
+            limit,
+        }
     }
 
     fn new_too_many_requests() -> Self {
@@ -203,7 +392,14 @@ impl GrpcServiceError for MetastoreError {
     }
 
     fn new_unavailable(message: String) -> Self {
-        Self::Unavailable(message)
+        // Transport errors only carry a message: the best we can do is to look for the error
+        // reported when the server is not listening.
+        let reason = if message.to_lowercase().contains("connection refused") {
+            UnavailableReason::ConnectionRefused
+        } else {
+            UnavailableReason::Unknown
+        };
+        Self::Unavailable { message, reason }
     }
 }
 
@@ -232,6 +428,7 @@ impl SourceType {
             SourceType::Kafka => "kafka",
             SourceType::Kinesis => "kinesis",
             SourceType::Nats => "nats",
+            SourceType::NatsJetStream => "nats-jetstream",
             SourceType::PubSub => "pubsub",
             SourceType::Pulsar => "pulsar",
             SourceType::Unspecified => "unspecified",
@@ -251,6 +448,7 @@ impl fmt::Display for SourceType {
             SourceType::Kafka => "Apache Kafka",
             SourceType::Kinesis => "Amazon Kinesis",
             SourceType::Nats => "NATS",
+            SourceType::NatsJetStream => "NATS JetStream",
             SourceType::PubSub => "Google Cloud Pub/Sub",
             SourceType::Pulsar => "Apache Pulsar",
             SourceType::Unspecified => "unspecified",
@@ -275,6 +473,17 @@ impl IndexMetadataRequest {
             index_id: None,
         }
     }
+
+    /// Targets the index by its UID if known, and falls back to its ID otherwise.
+    ///
+    /// The ID is always set: metastores report a missing index by its ID, even when it is
+    /// looked up by UID.
+    pub fn for_index_uid_or_id(index_uid_opt: Option<IndexUid>, index_id: IndexId) -> Self {
+        Self {
+            index_uid: index_uid_opt,
+            index_id: Some(index_id),
+        }
+    }
 }
 
 impl MarkSplitsForDeletionRequest {
@@ -284,6 +493,18 @@ impl MarkSplitsForDeletionRequest {
             split_ids,
         }
     }
+
+    /// Creates a request from any iterator of split IDs, e.g.
+    /// `splits_metadata.iter().map(|split_metadata| &split_metadata.split_id)`.
+    pub fn from_iter(
+        index_uid: IndexUid,
+        split_ids: impl IntoIterator<Item = impl Into<String>>,
+    ) -> Self {
+        Self {
+            index_uid: index_uid.into(),
+            split_ids: split_ids.into_iter().map(Into::into).collect(),
+        }
+    }
 }
 
 impl LastDeleteOpstampResponse {
@@ -308,7 +529,84 @@ pub mod serde_utils {
     use serde::{Deserialize, Serialize};
     use serde_json::Value as JsonValue;
 
-    use super::{MetastoreError, MetastoreResult};
+    use super::{EntityKind, MetastoreError, MetastoreResult};
+
+    /// Picks the zstd compression level used to serialize a metastore entity depending on its
+    /// kind.
+    ///
+    /// Small entities that are read often, such as index or source configs, favor fast
+    /// compression and decompression, whereas large, rarely read entities, such as split
+    /// metadata arrays, favor a high compression ratio.
+    #[derive(Debug, Clone, Copy, Eq, PartialEq)]
+    pub struct CompressionPolicy {
+        /// Level used for indexes, sources, shards, index templates, and checkpoint deltas.
+        pub fast_level: i32,
+        /// Level used for splits.
+        pub high_ratio_level: i32,
+    }
+
+    impl Default for CompressionPolicy {
+        fn default() -> Self {
+            Self {
+                fast_level: Self::FAST_LEVEL,
+                high_ratio_level: Self::HIGH_RATIO_LEVEL,
+            }
+        }
+    }
+
+    impl CompressionPolicy {
+        /// Cheap to compress and decompress, for payloads written or read on a hot path.
+        pub const FAST_LEVEL: i32 = 1;
+        /// Good compression ratio for a moderate CPU cost, for large payloads.
+        pub const HIGH_RATIO_LEVEL: i32 = 9;
+
+        /// Returns a policy compressing all the entities with the level of the given profile, e.g.
+        /// `CompressionPolicy::uniform(CompressionProfile::Fast)` to favor speed across the whole
+        /// metastore during a migration.
+        pub const fn uniform(compression_profile: CompressionProfile) -> Self {
+            let compression_level = compression_profile.compression_level();
+            Self {
+                fast_level: compression_level,
+                high_ratio_level: compression_level,
+            }
+        }
+
+        /// Returns the compression level to use for the given entity kind.
+        pub fn compression_level(&self, entity_kind: &EntityKind) -> i32 {
+            match entity_kind {
+                EntityKind::CheckpointDelta { .. }
+                | EntityKind::Index { .. }
+                | EntityKind::Indexes { .. }
+                | EntityKind::IndexTemplate { .. }
+                | EntityKind::Shard { .. }
+                | EntityKind::Source { .. } => self.fast_level,
+                EntityKind::Split { .. } | EntityKind::Splits { .. } => self.high_ratio_level,
+            }
+        }
+    }
+
+    /// Named zstd compression levels, so that call sites express the trade-off between CPU and
+    /// size they are after rather than a raw level.
+    #[derive(Debug, Clone, Copy, Eq, PartialEq)]
+    pub enum CompressionProfile {
+        /// Cheap to compress, for payloads written often or on a hot path.
+        Fast,
+        /// zstd's default level.
+        Balanced,
+        /// Best compression ratio, for large payloads written rarely.
+        Max,
+    }
+
+    impl CompressionProfile {
+        /// Returns the zstd compression level of the profile.
+        pub const fn compression_level(&self) -> i32 {
+            match self {
+                CompressionProfile::Fast => CompressionPolicy::FAST_LEVEL,
+                CompressionProfile::Balanced => 3,
+                CompressionProfile::Max => 19,
+            }
+        }
+    }
 
     pub fn from_json_bytes<'de, T: Deserialize<'de>>(value_bytes: &'de [u8]) -> MetastoreResult<T> {
         serde_json::from_slice(value_bytes).map_err(|error| MetastoreError::JsonDeserializeError {
@@ -317,17 +615,164 @@ pub mod serde_utils {
         })
     }
 
-    pub fn from_json_zstd<T: DeserializeOwned>(value_bytes: &[u8]) -> MetastoreResult<T> {
-        let value_json = zstd::decode_all(value_bytes).map_err(|error| {
-            MetastoreError::JsonDeserializeError {
+    /// Same as [`from_json_bytes`], but turns deserialization failures of records written by a
+    /// newer version of Quickwit into a `MetastoreError::IncompatibleVersion` error.
+    ///
+    /// The version is read from the `version` tag of the versioned serde representation of the
+    /// record and compared component by component with `supported_version`, e.g. `0.10` is newer
+    /// than `0.9`. Records without a version, or whose version is not newer, keep failing with a
+    /// `JsonDeserializeError`.
+    pub fn from_json_bytes_versioned<'de, T: Deserialize<'de>>(
+        value_bytes: &'de [u8],
+        supported_version: &str,
+    ) -> MetastoreResult<T> {
+        let deserialize_error = match from_json_bytes(value_bytes) {
+            Ok(value) => return Ok(value),
+            Err(deserialize_error) => deserialize_error,
+        };
+        #[derive(Deserialize)]
+        struct VersionProbe {
+            #[serde(rename = "version")]
+            version_opt: Option<JsonValue>,
+        }
+        let found_version = match serde_json::from_slice::<VersionProbe>(value_bytes) {
+            Ok(VersionProbe {
+                version_opt: Some(JsonValue::String(version)),
+            }) => version,
+            Ok(VersionProbe {
+                version_opt: Some(JsonValue::Number(version)),
+            }) => version.to_string(),
+            _ => return Err(deserialize_error),
+        };
+        if !is_newer_version(&found_version, supported_version) {
+            return Err(deserialize_error);
+        }
+        Err(MetastoreError::IncompatibleVersion {
+            struct_name: std::any::type_name::<T>().to_string(),
+            found: found_version,
+            supported: supported_version.to_string(),
+        })
+    }
+
+    /// Returns true if the dot-separated numeric `version` is strictly newer than
+    /// `reference_version`. Versions that don't parse are never considered newer.
+    fn is_newer_version(version: &str, reference_version: &str) -> bool {
+        let parse_version = |version: &str| -> Option<Vec<u64>> {
+            version
+                .split('.')
+                .map(|component| component.parse::<u64>().ok())
+                .collect()
+        };
+        match (parse_version(version), parse_version(reference_version)) {
+            (Some(version), Some(reference_version)) => version > reference_version,
+            _ => false,
+        }
+    }
+
+    /// Deserializes a JSON value that may be nested deeper than the 128 levels `serde_json`
+    /// accepts by default.
+    ///
+    /// The nesting depth of the document is checked against `recursion_limit` upfront, then the
+    /// document is parsed with `serde_json`'s own limit disabled. `serde_json` recurses on the
+    /// stack for every nesting level: a high limit lets a crafted payload overflow the stack and
+    /// abort the whole process, so only raise it for payloads from trusted sources and keep it
+    /// well within the stack size of the calling thread. Use [`from_json_bytes`] otherwise.
+    pub fn from_json_bytes_with_limits<'de, T: Deserialize<'de>>(
+        value_bytes: &'de [u8],
+        recursion_limit: usize,
+    ) -> MetastoreResult<T> {
+        let to_deserialize_error =
+            |error: &dyn std::fmt::Display| MetastoreError::JsonDeserializeError {
                 struct_name: std::any::type_name::<T>().to_string(),
                 message: error.to_string(),
+            };
+        if json_nesting_depth_exceeds(value_bytes, recursion_limit) {
+            let message = format!("JSON nesting depth exceeds the limit of {recursion_limit}");
+            return Err(to_deserialize_error(&message));
+        }
+        let mut deserializer = serde_json::Deserializer::from_slice(value_bytes);
+        deserializer.disable_recursion_limit();
+        let value =
+            T::deserialize(&mut deserializer).map_err(|error| to_deserialize_error(&error))?;
+        deserializer
+            .end()
+            .map_err(|error| to_deserialize_error(&error))?;
+        Ok(value)
+    }
+
+    /// Returns true if the arrays and objects of the JSON document are nested deeper than
+    /// `max_depth`. Brackets within strings are ignored, the document is not validated.
+    fn json_nesting_depth_exceeds(json_bytes: &[u8], max_depth: usize) -> bool {
+        let mut depth: usize = 0;
+        let mut in_string = false;
+        let mut escaped = false;
+
+        for byte in json_bytes {
+            if in_string {
+                match byte {
+                    _ if escaped => escaped = false,
+                    b'\\' => escaped = true,
+                    b'"' => in_string = false,
+                    _ => {}
+                }
+                continue;
             }
-        })?;
-        serde_json::from_slice(&value_json).map_err(|error| MetastoreError::JsonDeserializeError {
-            struct_name: std::any::type_name::<T>().to_string(),
-            message: error.to_string(),
-        })
+            match byte {
+                b'"' => in_string = true,
+                b'[' | b'{' => {
+                    depth += 1;
+                    if depth > max_depth {
+                        return true;
+                    }
+                }
+                b']' | b'}' => depth = depth.saturating_sub(1),
+                _ => {}
+            }
+        }
+        false
+    }
+
+    /// Same as [`from_json_bytes`], with `serde_json`'s `arbitrary_precision` number handling.
+    ///
+    /// Numbers are preserved as their literal text until the target type coerces them, instead
+    /// of being rounded to an `f64` when they exceed the range of `i64` and `u64`, or when they are
+    /// high-precision floats. This matters for payloads that go through a `serde_json::Value`,
+    /// e.g. checkpoint deltas holding large Kafka offsets.
+    #[cfg(feature = "arbitrary-precision")]
+    pub fn from_json_bytes_arbitrary_precision<'de, T: Deserialize<'de>>(
+        value_bytes: &'de [u8],
+    ) -> MetastoreResult<T> {
+        let mut deserializer = serde_json::Deserializer::from_slice(value_bytes);
+        let to_deserialize_error =
+            |error: serde_json::Error| MetastoreError::JsonDeserializeError {
+                struct_name: std::any::type_name::<T>().to_string(),
+                message: error.to_string(),
+            };
+        let value = T::deserialize(&mut deserializer).map_err(to_deserialize_error)?;
+        deserializer.end().map_err(to_deserialize_error)?;
+        Ok(value)
+    }
+
+    pub fn from_json_zstd<T: DeserializeOwned>(value_bytes: &[u8]) -> MetastoreResult<T> {
+        from_json_zstd_reader(value_bytes)
+    }
+
+    /// Deserializes a zstd-compressed JSON value, parsing the JSON as it gets decompressed. The
+    /// decompressed JSON is never held in memory in its entirety.
+    pub fn from_json_zstd_reader<T: DeserializeOwned>(
+        value_reader: impl std::io::Read,
+    ) -> MetastoreResult<T> {
+        let to_deserialize_error =
+            |error: &dyn std::fmt::Display| MetastoreError::JsonDeserializeError {
+                struct_name: std::any::type_name::<T>().to_string(),
+                message: error.to_string(),
+            };
+        let decoder =
+            zstd::Decoder::new(value_reader).map_err(|error| to_deserialize_error(&error))?;
+        // `serde_json` reads its input byte by byte: buffering the decompressed bytes avoids
+        // calling into the decoder for each of them.
+        serde_json::from_reader(std::io::BufReader::new(decoder))
+            .map_err(|error| to_deserialize_error(&error))
     }
 
     pub fn from_json_str<'de, T: Deserialize<'de>>(value_str: &'de str) -> MetastoreResult<T> {
@@ -375,12 +820,62 @@ pub mod serde_utils {
         })
     }
 
+    /// Serializes the value as zstd-compressed JSON, with the compression level of the given
+    /// profile.
+    pub fn to_json_zstd_profile<T: Serialize>(
+        value: &T,
+        compression_profile: CompressionProfile,
+    ) -> Result<Vec<u8>, MetastoreError> {
+        to_json_zstd(value, compression_profile.compression_level())
+    }
+
+    /// Serializes the value as zstd-compressed JSON, picking the compression level from the
+    /// `compression_policy` for the given entity kind.
+    pub fn to_json_zstd_for_entity<T: Serialize>(
+        value: &T,
+        entity_kind: &EntityKind,
+        compression_policy: &CompressionPolicy,
+    ) -> Result<Vec<u8>, MetastoreError> {
+        to_json_zstd(value, compression_policy.compression_level(entity_kind))
+    }
+
     pub fn to_json_bytes_pretty<T: Serialize>(value: &T) -> Result<Vec<u8>, MetastoreError> {
         serde_json::to_vec_pretty(value).map_err(|error| MetastoreError::JsonSerializeError {
             struct_name: std::any::type_name::<T>().to_string(),
             message: error.to_string(),
         })
     }
+
+    /// Serializes the values as newline-delimited JSON, one value per line.
+    pub fn to_jsonl_bytes<T: Serialize>(values: &[T]) -> Result<Vec<u8>, MetastoreError> {
+        let mut jsonl_bytes = Vec::new();
+
+        for value in values {
+            serde_json::to_writer(&mut jsonl_bytes, value).map_err(|error| {
+                MetastoreError::JsonSerializeError {
+                    struct_name: std::any::type_name::<T>().to_string(),
+                    message: error.to_string(),
+                }
+            })?;
+            jsonl_bytes.push(b'\n');
+        }
+        Ok(jsonl_bytes)
+    }
+
+    /// Deserializes newline-delimited JSON, one value per line. Blank lines are skipped.
+    pub fn from_jsonl_bytes<T: DeserializeOwned>(value_bytes: &[u8]) -> MetastoreResult<Vec<T>> {
+        value_bytes
+            .split(|byte| *byte == b'\n')
+            .enumerate()
+            .filter(|(_, line)| !line.iter().all(u8::is_ascii_whitespace))
+            .map(|(line_idx, line)| {
+                serde_json::from_slice(line).map_err(|error| MetastoreError::JsonDeserializeError {
+                    struct_name: std::any::type_name::<T>().to_string(),
+                    message: format!("line {}: {error}", line_idx + 1),
+                })
+            })
+            .collect()
+    }
 }
 
 impl ListIndexesMetadataRequest {
@@ -389,4 +884,564 @@ impl ListIndexesMetadataRequest {
             index_id_patterns: vec!["*".to_string()],
         }
     }
+
+    /// Builds a request from a list of index ID patterns, rejecting malformed patterns upfront
+    /// instead of letting them silently match no index.
+    pub fn try_from_patterns(index_id_patterns: Vec<String>) -> MetastoreResult<Self> {
+        for index_id_pattern in &index_id_patterns {
+            quickwit_common::validate_index_id_pattern(index_id_pattern, true).map_err(
+                |error| {
+                    MetastoreError::invalid_argument_for_field(
+                        "index_id_patterns",
+                        error.to_string(),
+                    )
+                },
+            )?;
+        }
+        Ok(ListIndexesMetadataRequest { index_id_patterns })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use quickwit_common::tower::GRPC_REQUEST_TIMEOUT;
+
+    use super::*;
+
+    #[test]
+    fn test_metastore_error_invalid_argument_display() {
+        let error = MetastoreError::invalid_argument("index ID is empty");
+        assert_eq!(error.to_string(), "invalid argument: index ID is empty");
+
+        let error = MetastoreError::invalid_argument_for_field(
+            "retention.period",
+            "failed to parse period `forever`",
+        );
+        assert_eq!(
+            error.to_string(),
+            "invalid argument for `retention.period`: failed to parse period `forever`"
+        );
+    }
+
+    #[test]
+    fn test_metastore_error_from_io_error() {
+        fn read_manifest() -> MetastoreResult<()> {
+            let io_result: std::io::Result<()> = Err(std::io::Error::new(
+                std::io::ErrorKind::PermissionDenied,
+                "permission denied",
+            ));
+            io_result?;
+            Ok(())
+        }
+        let error = read_manifest().unwrap_err();
+        assert_eq!(
+            error,
+            MetastoreError::Io {
+                message: "permission denied".to_string()
+            }
+        );
+        assert!(matches!(error.retry_policy(), RetryPolicy::Backoff { .. }));
+    }
+
+    #[test]
+    fn test_index_metadata_request_for_index_uid_or_id() {
+        let index_uid = IndexUid::for_test("test-index", 0);
+        let request = IndexMetadataRequest::for_index_uid_or_id(
+            Some(index_uid.clone()),
+            "test-index".to_string(),
+        );
+        assert_eq!(request.index_uid, Some(index_uid));
+        assert_eq!(request.index_id.unwrap(), "test-index");
+
+        let request = IndexMetadataRequest::for_index_uid_or_id(None, "test-index".to_string());
+        assert!(request.index_uid.is_none());
+        assert_eq!(request.index_id.unwrap(), "test-index");
+    }
+
+    #[test]
+    fn test_mark_splits_for_deletion_request_from_iter() {
+        let index_uid = IndexUid::for_test("test-index", 0);
+        let split_ids = ["split-1".to_string(), "split-2".to_string()];
+        let request = MarkSplitsForDeletionRequest::from_iter(index_uid.clone(), &split_ids);
+        assert_eq!(
+            request,
+            MarkSplitsForDeletionRequest::new(index_uid.clone(), split_ids.to_vec())
+        );
+
+        let request = MarkSplitsForDeletionRequest::from_iter(index_uid, ["split-1"]);
+        assert_eq!(request.split_ids, ["split-1"]);
+    }
+
+    #[test]
+    fn test_list_indexes_metadata_request_try_from_patterns() {
+        let index_id_patterns = vec!["test-index-*".to_string(), "-test-index-foo".to_string()];
+        let request =
+            ListIndexesMetadataRequest::try_from_patterns(index_id_patterns.clone()).unwrap();
+        assert_eq!(request.index_id_patterns, index_id_patterns);
+
+        let error = ListIndexesMetadataRequest::try_from_patterns(vec![
+            "test-index-*".to_string(),
+            "test-index-[foo".to_string(),
+        ])
+        .unwrap_err();
+        let MetastoreError::InvalidArgument { message, field } = error else {
+            panic!("expected `InvalidArgument` error, got `{error:?}`");
+        };
+        assert!(message.contains("`test-index-[foo`"));
+        assert_eq!(field.as_deref(), Some("index_id_patterns"));
+    }
+
+    #[test]
+    fn test_metastore_error_unavailable_reason() {
+        let error = MetastoreError::new_unavailable(
+            "tcp connect error: Connection refused (os error 111)".to_string(),
+        );
+        assert!(matches!(
+            error,
+            MetastoreError::Unavailable {
+                reason: UnavailableReason::ConnectionRefused,
+                ..
+            }
+        ));
+        assert_eq!(
+            error.to_string(),
+            "service unavailable: tcp connect error: Connection refused (os error 111)"
+        );
+
+        let error = MetastoreError::new_unavailable("service is overloaded".to_string());
+        assert_eq!(error, MetastoreError::unavailable("service is overloaded"));
+
+        // Errors returned by nodes predating the reason still decode.
+        let error_json = r#"{"Unavailable": {"message": "service is overloaded"}}"#;
+        let error: MetastoreError = serde_json::from_str(error_json).unwrap();
+        assert_eq!(error, MetastoreError::unavailable("service is overloaded"));
+    }
+
+    #[test]
+    fn test_entity_kind_source_display() {
+        let mut source_entity = EntityKind::Source {
+            index_id: "test-index".to_string(),
+            source_id: "test-source".to_string(),
+            source_type: None,
+        };
+        assert_eq!(source_entity.to_string(), "source `test-index/test-source`");
+
+        if let EntityKind::Source { source_type, .. } = &mut source_entity {
+            *source_type = Some(SourceType::Kafka);
+        }
+        assert_eq!(
+            source_entity.to_string(),
+            "source `test-index/test-source` (Apache Kafka)"
+        );
+        assert_eq!(
+            MetastoreError::AlreadyExists(source_entity).to_string(),
+            "source `test-index/test-source` (Apache Kafka) already exist(s)"
+        );
+    }
+
+    #[test]
+    fn test_metastore_error_timeout_durations() {
+        let error = MetastoreError::new_timeout_with_durations(
+            "list splits timed out".to_string(),
+            Some(Duration::from_millis(5_100)),
+            Some(Duration::from_secs(5)),
+        );
+        assert_eq!(
+            error.to_string(),
+            "request timed out: list splits timed out"
+        );
+
+        let status = crate::error::grpc_error_to_grpc_status(error.clone());
+        let decoded_error: MetastoreError =
+            crate::error::grpc_status_to_service_error(status, "list_splits");
+        assert_eq!(decoded_error, error);
+        let MetastoreError::Timeout { limit, .. } = decoded_error else {
+            panic!("expected a timeout error");
+        };
+        assert_eq!(limit, Some(Duration::from_secs(5)));
+
+        let error = MetastoreError::new_timeout("list splits timed out".to_string());
+        assert_eq!(error, MetastoreError::timeout("list splits timed out"));
+
+        // Requests cut by the timeout of their channel report it.
+        let status = tonic::Status::from_error(Box::new(tower::timeout::error::Elapsed::new()));
+        let error: MetastoreError =
+            crate::error::grpc_status_to_service_error(status, "list_splits");
+        let MetastoreError::Timeout { elapsed, limit, .. } = error else {
+            panic!("expected a timeout error");
+        };
+        assert_eq!(elapsed, None);
+        assert_eq!(limit, Some(GRPC_REQUEST_TIMEOUT));
+
+        // Statuses merely carrying the same message as a channel timeout don't.
+        let status = tonic::Status::cancelled("Timeout expired");
+        let error: MetastoreError =
+            crate::error::grpc_status_to_service_error(status, "list_splits");
+        assert_eq!(error, MetastoreError::timeout("Timeout expired"));
+
+        let status = tonic::Status::deadline_exceeded("deadline exceeded");
+        let error: MetastoreError =
+            crate::error::grpc_status_to_service_error(status, "list_splits");
+        assert_eq!(error, MetastoreError::timeout("deadline exceeded"));
+
+        // Errors returned by nodes predating the durations still decode.
+        let error_json = r#"{"Timeout": {"message": "list splits timed out"}}"#;
+        let error: MetastoreError = serde_json::from_str(error_json).unwrap();
+        assert_eq!(error, MetastoreError::timeout("list splits timed out"));
+    }
+
+    #[cfg(feature = "postgres")]
+    #[test]
+    fn test_metastore_error_from_sqlx_error() {
+        let reason = |error: sqlx::Error| match MetastoreError::from(error) {
+            MetastoreError::Unavailable { reason, .. } => Some(reason),
+            _ => None,
+        };
+        assert_eq!(
+            reason(sqlx::Error::PoolTimedOut),
+            Some(UnavailableReason::PoolExhausted)
+        );
+        assert_eq!(
+            reason(sqlx::Error::PoolClosed),
+            Some(UnavailableReason::ShuttingDown)
+        );
+        assert_eq!(
+            reason(sqlx::Error::Io(
+                std::io::ErrorKind::ConnectionRefused.into()
+            )),
+            Some(UnavailableReason::ConnectionRefused)
+        );
+        assert_eq!(reason(sqlx::Error::RowNotFound), None);
+    }
+
+    #[test]
+    fn test_serde_utils_jsonl() {
+        let values = vec![
+            EntityKind::Index {
+                index_id: "test-index-foo".to_string(),
+            },
+            EntityKind::Split {
+                split_id: "test-split".to_string(),
+            },
+        ];
+        let jsonl_bytes = serde_utils::to_jsonl_bytes(&values).unwrap();
+        let jsonl_str = std::str::from_utf8(&jsonl_bytes).unwrap();
+        assert_eq!(jsonl_str.lines().count(), 2);
+        assert!(jsonl_str.ends_with('\n'));
+
+        let deserialized_values: Vec<EntityKind> =
+            serde_utils::from_jsonl_bytes(&jsonl_bytes).unwrap();
+        assert_eq!(deserialized_values, values);
+
+        let empty_values: Vec<EntityKind> = serde_utils::from_jsonl_bytes(b"").unwrap();
+        assert!(empty_values.is_empty());
+
+        let invalid_jsonl_bytes = b"{\"index\":{\"index_id\":\"test-index\"}}\n\n{\"index\":";
+        let error = serde_utils::from_jsonl_bytes::<EntityKind>(invalid_jsonl_bytes).unwrap_err();
+        let MetastoreError::JsonDeserializeError { message, .. } = error else {
+            panic!("expected `JsonDeserializeError`, got `{error:?}`");
+        };
+        assert!(message.starts_with("line 3:"));
+    }
+
+    #[test]
+    fn test_serde_utils_compression_policy() {
+        let compression_policy = serde_utils::CompressionPolicy {
+            fast_level: 2,
+            high_ratio_level: 15,
+        };
+        let entity_kinds = [
+            (
+                EntityKind::CheckpointDelta {
+                    index_id: "test-index".to_string(),
+                    source_id: "test-source".to_string(),
+                },
+                2,
+            ),
+            (
+                EntityKind::Index {
+                    index_id: "test-index".to_string(),
+                },
+                2,
+            ),
+            (
+                EntityKind::Indexes {
+                    index_ids: vec!["test-index".to_string()],
+                },
+                2,
+            ),
+            (
+                EntityKind::IndexTemplate {
+                    template_id: "test-template".to_string(),
+                },
+                2,
+            ),
+            (
+                EntityKind::Shard {
+                    queue_id: "test-index:0/test-source/0".to_string(),
+                },
+                2,
+            ),
+            (
+                EntityKind::Source {
+                    index_id: "test-index".to_string(),
+                    source_id: "test-source".to_string(),
+                    source_type: None,
+                },
+                2,
+            ),
+            (
+                EntityKind::Split {
+                    split_id: "test-split".to_string(),
+                },
+                15,
+            ),
+            (
+                EntityKind::Splits {
+                    split_ids: vec!["test-split".to_string()],
+                },
+                15,
+            ),
+        ];
+        for (entity_kind, expected_compression_level) in entity_kinds {
+            assert_eq!(
+                compression_policy.compression_level(&entity_kind),
+                expected_compression_level,
+                "unexpected compression level for {entity_kind}"
+            );
+            let json_zstd_bytes = serde_utils::to_json_zstd_for_entity(
+                &entity_kind,
+                &entity_kind,
+                &compression_policy,
+            )
+            .unwrap();
+            let deserialized_entity_kind: EntityKind =
+                serde_utils::from_json_zstd(&json_zstd_bytes).unwrap();
+            assert_eq!(deserialized_entity_kind, entity_kind);
+        }
+    }
+
+    #[test]
+    fn test_to_json_zstd_profile() {
+        let value = vec!["test-split".to_string(); 100];
+        for compression_profile in [
+            serde_utils::CompressionProfile::Fast,
+            serde_utils::CompressionProfile::Balanced,
+            serde_utils::CompressionProfile::Max,
+        ] {
+            let json_zstd_bytes =
+                serde_utils::to_json_zstd_profile(&value, compression_profile).unwrap();
+            let expected_json_zstd_bytes =
+                serde_utils::to_json_zstd(&value, compression_profile.compression_level()).unwrap();
+            assert_eq!(json_zstd_bytes, expected_json_zstd_bytes);
+
+            let deserialized_value: Vec<String> =
+                serde_utils::from_json_zstd(&json_zstd_bytes).unwrap();
+            assert_eq!(deserialized_value, value);
+        }
+    }
+
+    #[test]
+    fn test_serde_utils_uniform_compression_policy() {
+        let compression_policy =
+            serde_utils::CompressionPolicy::uniform(serde_utils::CompressionProfile::Fast);
+        let index_kind = EntityKind::Index {
+            index_id: "test-index".to_string(),
+        };
+        let splits_kind = EntityKind::Splits {
+            split_ids: vec!["test-split".to_string()],
+        };
+        assert_eq!(compression_policy.compression_level(&index_kind), 1);
+        assert_eq!(compression_policy.compression_level(&splits_kind), 1);
+    }
+
+    #[test]
+    fn test_from_json_bytes_versioned() {
+        #[derive(Debug, Deserialize)]
+        struct TestRecord {
+            #[allow(dead_code)]
+            name: String,
+        }
+        serde_utils::from_json_bytes_versioned::<TestRecord>(
+            br#"{"version": "0.9", "name": "test"}"#,
+            "0.8",
+        )
+        .unwrap();
+
+        let error = serde_utils::from_json_bytes_versioned::<TestRecord>(
+            br#"{"version": "0.10", "renamed": "test"}"#,
+            "0.9",
+        )
+        .unwrap_err();
+        let MetastoreError::IncompatibleVersion {
+            found, supported, ..
+        } = error
+        else {
+            panic!("expected `IncompatibleVersion` error, got `{error:?}`");
+        };
+        assert_eq!(found, "0.10");
+        assert_eq!(supported, "0.9");
+
+        let error = serde_utils::from_json_bytes_versioned::<TestRecord>(
+            br#"{"version": 3, "renamed": "test"}"#,
+            "2",
+        )
+        .unwrap_err();
+        assert!(matches!(error, MetastoreError::IncompatibleVersion { .. }));
+
+        for (record_json, supported_version) in [
+            (r#"{"renamed": "test"}"#, "0.9"),
+            (r#"{"version": "0.8", "renamed": "test"}"#, "0.9"),
+            (r#"{"version": "0.9", "renamed": "test"}"#, "0.9"),
+            (r#"{"version": "next", "renamed": "test"}"#, "0.9"),
+            (r#"{"version": "0.10", "renamed": "test""#, "0.9"),
+        ] {
+            let error = serde_utils::from_json_bytes_versioned::<TestRecord>(
+                record_json.as_bytes(),
+                supported_version,
+            )
+            .unwrap_err();
+            assert!(
+                matches!(error, MetastoreError::JsonDeserializeError { .. }),
+                "{record_json}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_from_json_bytes_with_limits() {
+        let nested_json = |depth: usize| format!("{}{}", "[".repeat(depth), "]".repeat(depth));
+
+        let shallow_json = nested_json(10);
+        let value: serde_json::Value =
+            serde_utils::from_json_bytes_with_limits(shallow_json.as_bytes(), 10).unwrap();
+        assert!(value.is_array());
+
+        // Beyond `serde_json`'s default limit.
+        let deep_json = nested_json(1_000);
+        serde_utils::from_json_bytes::<serde_json::Value>(deep_json.as_bytes()).unwrap_err();
+        serde_utils::from_json_bytes_with_limits::<serde_json::Value>(deep_json.as_bytes(), 1_000)
+            .unwrap();
+
+        let error = serde_utils::from_json_bytes_with_limits::<serde_json::Value>(
+            deep_json.as_bytes(),
+            999,
+        )
+        .unwrap_err();
+        assert!(matches!(error, MetastoreError::JsonDeserializeError { .. }));
+        assert!(error.to_string().contains("exceeds the limit of 999"));
+
+        // Brackets within strings don't count.
+        let value: Vec<String> =
+            serde_utils::from_json_bytes_with_limits(br#"["[[[", "\"{{{"]"#, 1).unwrap();
+        assert_eq!(value, ["[[[", "\"{{{"]);
+
+        // Trailing characters are still rejected.
+        serde_utils::from_json_bytes_with_limits::<Vec<u32>>(b"[1] [2]", 10).unwrap_err();
+    }
+
+    #[cfg(feature = "arbitrary-precision")]
+    #[test]
+    fn test_from_json_bytes_arbitrary_precision() {
+        let json =
+            br#"{"offset":123456789012345678901234567890,"ratio":0.12345678901234567890123}"#;
+        let value: serde_json::Value =
+            serde_utils::from_json_bytes_arbitrary_precision(json).unwrap();
+        assert_eq!(
+            value["offset"].to_string(),
+            "123456789012345678901234567890"
+        );
+        assert_eq!(value["ratio"].to_string(), "0.12345678901234567890123");
+        assert_eq!(serde_json::to_vec(&value).unwrap(), json);
+
+        let offsets: Vec<u64> =
+            serde_utils::from_json_bytes_arbitrary_precision(b"[18446744073709551615]").unwrap();
+        assert_eq!(offsets, [u64::MAX]);
+
+        let error =
+            serde_utils::from_json_bytes_arbitrary_precision::<Vec<u64>>(b"[1] [2]").unwrap_err();
+        assert!(matches!(error, MetastoreError::JsonDeserializeError { .. }));
+    }
+
+    #[test]
+    fn test_from_json_zstd_reader() {
+        let value = vec!["test-split".to_string(); 10_000];
+        let json_zstd_bytes = serde_utils::to_json_zstd(&value, 3).unwrap();
+        let deserialized_value: Vec<String> =
+            serde_utils::from_json_zstd_reader(&json_zstd_bytes[..]).unwrap();
+        assert_eq!(deserialized_value, value);
+
+        let error = serde_utils::from_json_zstd::<Vec<String>>(b"not zstd").unwrap_err();
+        assert!(matches!(
+            error,
+            MetastoreError::JsonDeserializeError { struct_name, .. }
+                if struct_name == std::any::type_name::<Vec<String>>()
+        ));
+
+        let json_zstd_bytes = serde_utils::to_json_zstd(&"not a list", 3).unwrap();
+        let error = serde_utils::from_json_zstd::<Vec<String>>(&json_zstd_bytes).unwrap_err();
+        assert!(matches!(error, MetastoreError::JsonDeserializeError { .. }));
+    }
+
+    #[test]
+    fn test_metastore_error_retry_policy() {
+        let index_entity = || EntityKind::Index {
+            index_id: "test-index".to_string(),
+        };
+        let errors = [
+            MetastoreError::AlreadyExists(index_entity()),
+            MetastoreError::Connection {
+                message: "".to_string(),
+            },
+            MetastoreError::Db {
+                message: "".to_string(),
+            },
+            MetastoreError::FailedPrecondition {
+                entity: index_entity(),
+                message: "".to_string(),
+            },
+            MetastoreError::Internal {
+                message: "".to_string(),
+                cause: "".to_string(),
+            },
+            MetastoreError::NotFound(index_entity()),
+            MetastoreError::timeout(""),
+            MetastoreError::TooManyRequests,
+            MetastoreError::unavailable(""),
+        ];
+        for error in &errors {
+            // Errors deemed retryable always come with a retry policy.
+            if error.is_retryable() {
+                assert_ne!(error.retry_policy(), RetryPolicy::NoRetry, "{error}");
+            }
+        }
+        assert_eq!(
+            MetastoreError::NotFound(index_entity()).retry_policy(),
+            RetryPolicy::NoRetry
+        );
+        assert_eq!(
+            MetastoreError::Internal {
+                message: "".to_string(),
+                cause: "".to_string(),
+            }
+            .retry_policy(),
+            RetryPolicy::Immediate
+        );
+        assert_eq!(
+            MetastoreError::TooManyRequests.retry_policy(),
+            RetryPolicy::Backoff {
+                initial: Duration::from_secs(1)
+            }
+        );
+        let error = MetastoreError::Unavailable {
+            message: "".to_string(),
+            reason: UnavailableReason::NoLeader,
+        };
+        assert_eq!(
+            error.retry_policy(),
+            RetryPolicy::Backoff {
+                initial: Duration::from_millis(100)
+            }
+        );
+    }
 }
@@ -26,15 +26,31 @@ pub use sort_by_value::SortValue;
 
 include!("../codegen/quickwit/quickwit.search.rs");
 
+const NANOS_PER_SEC: i64 = 1_000_000_000;
+
 impl SearchRequest {
     pub fn time_range(&self) -> impl std::ops::RangeBounds<i64> {
         use std::ops::Bound;
         (
             self.start_timestamp
                 .map_or(Bound::Unbounded, Bound::Included),
-            self.end_timestamp.map_or(Bound::Unbounded, Bound::Excluded),
+            self.end_timestamp_exclusive()
+                .map_or(Bound::Unbounded, Bound::Excluded),
         )
     }
+
+    /// Returns the exclusive end of the requested time range, in seconds.
+    ///
+    /// Timestamps are expressed in seconds, so an inclusive `end_timestamp` covers its entire
+    /// last second.
+    pub fn end_timestamp_exclusive(&self) -> Option<i64> {
+        if self.end_timestamp_inclusive {
+            self.end_timestamp
+                .map(|end_timestamp| end_timestamp.saturating_add(1))
+        } else {
+            self.end_timestamp
+        }
+    }
 }
 
 impl SplitIdAndFooterOffsets {
@@ -46,6 +62,28 @@ impl SplitIdAndFooterOffsets {
             self.timestamp_end.map_or(Bound::Unbounded, Bound::Included),
         )
     }
+
+    /// Returns the lowest timestamp that may appear in the split, in nanoseconds since epoch.
+    ///
+    /// Split time ranges are stored in seconds, with the sub-second part truncated: the split may
+    /// contain timestamps from the very beginning of its start second.
+    pub fn timestamp_start_nanos(&self) -> Option<i64> {
+        self.timestamp_start
+            .map(|timestamp_secs| timestamp_secs.saturating_mul(NANOS_PER_SEC))
+    }
+
+    /// Returns the highest timestamp that may appear in the split, in nanoseconds since epoch.
+    ///
+    /// Split time ranges are stored in seconds, with the sub-second part truncated: the split may
+    /// contain timestamps up to the very end of its end second.
+    pub fn timestamp_end_nanos(&self) -> Option<i64> {
+        self.timestamp_end.map(|timestamp_secs| {
+            timestamp_secs
+                .saturating_add(1)
+                .saturating_mul(NANOS_PER_SEC)
+                .saturating_sub(1)
+        })
+    }
 }
 
 impl fmt::Display for SplitSearchError {
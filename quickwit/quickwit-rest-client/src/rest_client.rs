@@ -719,6 +719,10 @@ mod test {
             aggregations: None,
             elapsed_time_micros: 100,
             errors: Vec::new(),
+            per_split_stats: Vec::new(),
+            slowest_splits: Vec::new(),
+            aggregation_is_partial: false,
+            timed_out: false,
         };
         Mock::given(method("POST"))
             .and(path("/api/v1/my-index/search"))
@@ -0,0 +1,87 @@
+// Copyright (C) 2024 Quickwit, Inc.
+//
+// Quickwit is offered under the AGPL v3.0 and as commercial software.
+// For commercial licensing, contact us at hello@quickwit.io.
+//
+// AGPL:
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+use quickwit_indexing::TestSandbox;
+use quickwit_proto::search::SearchRequest;
+use quickwit_query::query_ast::qast_json_helper;
+use quickwit_search::single_node_search;
+use serde_json::{json, Value as JsonValue};
+
+const NUM_DOCS: usize = 10_000;
+
+/// Searches an index made of a single split, which is the case served by the leaf search shortcut
+/// that skips merging the split search responses on the search thread pool.
+///
+/// Compare the results with the ones obtained on a revision without the shortcut to measure its
+/// win.
+fn single_split_search_benchmark(c: &mut Criterion) {
+    let runtime = tokio::runtime::Builder::new_multi_thread()
+        .enable_all()
+        .build()
+        .unwrap();
+    let index_id = "single-split-bench";
+    let doc_mapping_yaml = r#"
+            field_mappings:
+              - name: body
+                type: text
+              - name: severity
+                type: u64
+                fast: true
+        "#;
+    let test_sandbox = runtime.block_on(async {
+        let test_sandbox = TestSandbox::create(index_id, doc_mapping_yaml, "{}", &["body"])
+            .await
+            .unwrap();
+        let docs: Vec<JsonValue> = (0..NUM_DOCS)
+            .map(|doc_id| json!({"body": format!("log line {doc_id}"), "severity": doc_id % 8}))
+            .collect();
+        // A single batch of documents results in a single split.
+        test_sandbox.add_documents(docs).await.unwrap();
+        test_sandbox
+    });
+    let mut group = c.benchmark_group("single_split_search");
+    for max_hits in [0, 10, 100] {
+        let search_request = SearchRequest {
+            index_id_patterns: vec![index_id.to_string()],
+            query_ast: qast_json_helper("body:line", &[]),
+            max_hits,
+            ..Default::default()
+        };
+        group.bench_with_input(
+            BenchmarkId::new("max_hits", max_hits),
+            &search_request,
+            |b, search_request| {
+                b.to_async(&runtime).iter(|| async {
+                    single_node_search(
+                        search_request.clone(),
+                        test_sandbox.metastore(),
+                        test_sandbox.storage_resolver(),
+                    )
+                    .await
+                    .unwrap()
+                })
+            },
+        );
+    }
+    group.finish();
+}
+
+criterion_group!(benches, single_split_search_benchmark);
+criterion_main!(benches);
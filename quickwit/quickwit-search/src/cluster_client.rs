@@ -33,6 +33,7 @@ use tokio::sync::mpsc::{unbounded_channel, UnboundedSender};
 use tokio_stream::wrappers::UnboundedReceiverStream;
 use tracing::{debug, error, info, warn};
 
+use crate::collector::merge_slowest_splits;
 use crate::retry::search::LeafSearchRetryPolicy;
 use crate::retry::search_stream::{LeafSearchStreamRetryPolicy, SuccessfulSplitIds};
 use crate::retry::{retry_client, DefaultRetryPolicy, RetryPolicy};
@@ -92,6 +93,11 @@ impl ClusterClient {
         request: LeafSearchRequest,
         mut client: SearchServiceClient,
     ) -> crate::Result<LeafSearchResponse> {
+        let num_slowest_splits = request
+            .search_request
+            .as_ref()
+            .and_then(|search_request| search_request.num_slowest_splits)
+            .unwrap_or(0) as usize;
         let mut response_res = client.leaf_search(request.clone()).await;
         let retry_policy = LeafSearchRetryPolicy {};
         if let Some(retry_request) = retry_policy.retry_request(request, &response_res) {
@@ -107,7 +113,8 @@ impl ClusterClient {
                 response_res, retry_request, client
             );
             let retry_result = client.leaf_search(retry_request).await;
-            response_res = merge_leaf_search_results(response_res, retry_result);
+            response_res =
+                merge_leaf_search_results(response_res, retry_result, num_slowest_splits);
         }
         response_res
     }
@@ -277,10 +284,16 @@ fn merge_intermediate_aggregation(left: &[u8], right: &[u8]) -> crate::Result<Ve
 fn merge_leaf_search_response(
     mut left_response: LeafSearchResponse,
     right_response: LeafSearchResponse,
+    num_slowest_splits: usize,
 ) -> crate::Result<LeafSearchResponse> {
     left_response
         .partial_hits
         .extend(right_response.partial_hits);
+    // The splits that failed in the left response are the only ones searched again in the right
+    // one, so the statistics of both sides cover distinct splits.
+    left_response
+        .per_split_stats
+        .extend(right_response.per_split_stats);
     let intermediate_aggregation_result: Option<Vec<u8>> = match (
         left_response.intermediate_aggregation_result,
         right_response.intermediate_aggregation_result,
@@ -301,6 +314,21 @@ fn merge_leaf_search_response(
             + right_response.num_attempted_splits,
         failed_splits: right_response.failed_splits,
         partial_hits: left_response.partial_hits,
+        slowest_splits: merge_slowest_splits(
+            left_response
+                .slowest_splits
+                .into_iter()
+                .chain(right_response.slowest_splits),
+            num_slowest_splits,
+        ),
+        per_split_stats: left_response.per_split_stats,
+        num_splits_searched: left_response.num_splits_searched + right_response.num_splits_searched,
+        num_splits_pruned: left_response.num_splits_pruned + right_response.num_splits_pruned,
+        num_splits_count_only: left_response.num_splits_count_only
+            + right_response.num_splits_count_only,
+        // The failed splits of the left response were retried in the right one.
+        aggregation_is_partial: right_response.aggregation_is_partial,
+        timed_out: left_response.timed_out || right_response.timed_out,
     })
 }
 
@@ -308,10 +336,11 @@ fn merge_leaf_search_response(
 fn merge_leaf_search_results(
     left_search_response_result: crate::Result<LeafSearchResponse>,
     right_search_response_result: crate::Result<LeafSearchResponse>,
+    num_slowest_splits: usize,
 ) -> crate::Result<LeafSearchResponse> {
     match (left_search_response_result, right_search_response_result) {
         (Ok(left_response), Ok(right_response)) => {
-            merge_leaf_search_response(left_response, right_response)
+            merge_leaf_search_response(left_response, right_response, num_slowest_splits)
         }
         (Ok(single_valid_response), Err(_)) => Ok(single_valid_response),
         (Err(_), Ok(single_valid_response)) => Ok(single_valid_response),
@@ -352,7 +381,7 @@ mod tests {
 
     use quickwit_proto::search::{
         PartialHit, SearchRequest, SearchStreamRequest, SortValue, SplitIdAndFooterOffsets,
-        SplitSearchError,
+        SplitSearchError, SplitSearchErrorKind, SplitSearchTiming, SplitStats,
     };
     use quickwit_query::query_ast::qast_json_helper;
 
@@ -380,6 +409,7 @@ mod tests {
                 split_footer_start: 0,
                 timestamp_start: None,
                 timestamp_end: None,
+                num_docs: 0,
             }],
             ..Default::default()
         }
@@ -403,6 +433,7 @@ mod tests {
                     split_footer_end: 100,
                     timestamp_start: None,
                     timestamp_end: None,
+                    num_docs: 0,
                 },
                 SplitIdAndFooterOffsets {
                     split_id: "split_2".to_string(),
@@ -410,6 +441,7 @@ mod tests {
                     split_footer_end: 100,
                     timestamp_start: None,
                     timestamp_end: None,
+                    num_docs: 0,
                 },
             ],
         }
@@ -437,6 +469,7 @@ mod tests {
                     split_footer_end: 100,
                     timestamp_start: None,
                     timestamp_end: None,
+                    num_docs: 0,
                 },
                 SplitIdAndFooterOffsets {
                     split_id: "split_2".to_string(),
@@ -444,6 +477,7 @@ mod tests {
                     split_footer_end: 100,
                     timestamp_start: None,
                     timestamp_end: None,
+                    num_docs: 0,
                 },
             ],
         }
@@ -563,6 +597,7 @@ mod tests {
                         error: "mock_error".to_string(),
                         split_id: "split_2".to_string(),
                         retryable_error: true,
+                        error_kind: SplitSearchErrorKind::Internal.into(),
                     }],
                     num_attempted_splits: 1,
                     ..Default::default()
@@ -579,6 +614,7 @@ mod tests {
                         error: "mock_error".to_string(),
                         split_id: "split_3".to_string(),
                         retryable_error: true,
+                        error_kind: SplitSearchErrorKind::Internal.into(),
                     }],
                     num_attempted_splits: 1,
                     ..Default::default()
@@ -602,12 +638,22 @@ mod tests {
             error: "error".to_string(),
             split_id: "split_2".to_string(),
             retryable_error: true,
+            error_kind: SplitSearchErrorKind::Internal.into(),
         };
         let leaf_response = LeafSearchResponse {
             num_hits: 1,
             partial_hits: vec![mock_partial_hit("split_1", 3, 1)],
             failed_splits: vec![split_error],
             num_attempted_splits: 1,
+            per_split_stats: vec![SplitStats {
+                split_id: "split_1".to_string(),
+                num_hits: 1,
+                ..Default::default()
+            }],
+            slowest_splits: vec![SplitSearchTiming {
+                split_id: "split_1".to_string(),
+                elapsed_time_micros: 10,
+            }],
             ..Default::default()
         };
         let leaf_response_retry = LeafSearchResponse {
@@ -615,14 +661,35 @@ mod tests {
             partial_hits: vec![mock_partial_hit("split_2", 3, 1)],
             failed_splits: Vec::new(),
             num_attempted_splits: 1,
+            per_split_stats: vec![SplitStats {
+                split_id: "split_2".to_string(),
+                num_hits: 1,
+                ..Default::default()
+            }],
+            slowest_splits: vec![SplitSearchTiming {
+                split_id: "split_2".to_string(),
+                elapsed_time_micros: 20,
+            }],
             ..Default::default()
         };
         let merged_leaf_search_response =
-            merge_leaf_search_results(Ok(leaf_response), Ok(leaf_response_retry)).unwrap();
+            merge_leaf_search_results(Ok(leaf_response), Ok(leaf_response_retry), 1).unwrap();
         assert_eq!(merged_leaf_search_response.num_attempted_splits, 2);
         assert_eq!(merged_leaf_search_response.num_hits, 2);
         assert_eq!(merged_leaf_search_response.partial_hits.len(), 2);
         assert_eq!(merged_leaf_search_response.failed_splits.len(), 0);
+        let split_ids: Vec<&str> = merged_leaf_search_response
+            .per_split_stats
+            .iter()
+            .map(|split_stats| split_stats.split_id.as_str())
+            .collect();
+        assert_eq!(split_ids, ["split_1", "split_2"]);
+        // Only the slowest split of both responses is kept.
+        assert_eq!(merged_leaf_search_response.slowest_splits.len(), 1);
+        assert_eq!(
+            merged_leaf_search_response.slowest_splits[0].split_id,
+            "split_2"
+        );
         Ok(())
     }
 
@@ -632,6 +699,7 @@ mod tests {
             error: "error".to_string(),
             split_id: "split_2".to_string(),
             retryable_error: true,
+            error_kind: SplitSearchErrorKind::Internal.into(),
         };
         let leaf_response = LeafSearchResponse {
             num_hits: 1,
@@ -643,6 +711,7 @@ mod tests {
         let merged_result = merge_leaf_search_results(
             Err(SearchError::Internal("error".to_string())),
             Ok(leaf_response),
+            0,
         )
         .unwrap();
         assert_eq!(merged_result.num_attempted_splits, 1);
@@ -657,6 +726,7 @@ mod tests {
         let merge_error = merge_leaf_search_results(
             Err(SearchError::Internal("error".to_string())),
             Err(SearchError::Internal("retry error".to_string())),
+            0,
         )
         .unwrap_err();
         assert_eq!(merge_error.to_string(), "internal error: `error`");
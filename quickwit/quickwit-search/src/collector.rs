@@ -18,15 +18,16 @@
 // along with this program. If not, see <http://www.gnu.org/licenses/>.
 
 use std::borrow::Cow;
-use std::cmp::Ordering;
+use std::cmp::{Ordering, Reverse};
 use std::collections::HashSet;
+use std::sync::Arc;
 
 use itertools::Itertools;
 use quickwit_common::binary_heap::{SortKeyMapper, TopK};
 use quickwit_doc_mapper::WarmupInfo;
 use quickwit_proto::search::{
     LeafSearchResponse, PartialHit, SearchRequest, SortByValue, SortOrder, SortValue,
-    SplitSearchError,
+    SplitSearchError, SplitSearchTiming, SplitStats,
 };
 use serde::Deserialize;
 use tantivy::aggregation::agg_req::{get_fast_field_names, Aggregations};
@@ -633,6 +634,13 @@ impl SegmentCollector for QuickwitSegmentCollector {
             partial_hits,
             failed_splits: Vec::new(),
             num_attempted_splits: 1,
+            slowest_splits: Vec::new(),
+            per_split_stats: Vec::new(),
+            num_splits_searched: 0,
+            num_splits_pruned: 0,
+            num_splits_count_only: 0,
+            aggregation_is_partial: false,
+            timed_out: false,
         })
     }
 }
@@ -699,6 +707,21 @@ impl QuickwitIncrementalAggregations {
         Ok(())
     }
 
+    /// Returns a rough estimate of the memory used by the intermediate aggregation results
+    /// accumulated so far, in bytes.
+    fn memory_usage(&self) -> u64 {
+        match self {
+            QuickwitIncrementalAggregations::FindTraceIdsAggregation(_, state) => state
+                .iter()
+                .map(|spans| (spans.len() * std::mem::size_of::<Span>()) as u64)
+                .sum(),
+            QuickwitIncrementalAggregations::TantivyAggregations(_, state) => {
+                state.iter().map(|bytes| bytes.len() as u64).sum()
+            }
+            QuickwitIncrementalAggregations::NoAggregation => 0,
+        }
+    }
+
     fn virtual_worst_hit(&self) -> Option<PartialHit> {
         match self {
             QuickwitIncrementalAggregations::FindTraceIdsAggregation(collector, state) => {
@@ -738,7 +761,7 @@ impl QuickwitIncrementalAggregations {
             }
             QuickwitIncrementalAggregations::TantivyAggregations(aggregation, state) => {
                 merge_intermediate_aggregation_result(
-                    &Some(QuickwitAggregations::TantivyAggregations(aggregation)),
+                    Some(&QuickwitAggregations::TantivyAggregations(aggregation)),
                     state.iter().map(|vec| vec.as_slice()),
                 )
             }
@@ -757,9 +780,11 @@ pub(crate) struct QuickwitCollector {
     pub start_offset: usize,
     pub max_hits: usize,
     pub sort_by: SortByPair,
-    pub aggregation: Option<QuickwitAggregations>,
+    pub aggregation: Option<Arc<QuickwitAggregations>>,
     pub aggregation_limits: AggregationLimits,
     search_after: Option<PartialHit>,
+    /// Number of slowest splits to keep when merging leaf responses.
+    pub num_slowest_splits: usize,
 }
 
 impl QuickwitCollector {
@@ -778,10 +803,29 @@ impl QuickwitCollector {
     pub fn warmup_info(&self) -> WarmupInfo {
         WarmupInfo {
             fast_field_names: self.fast_field_names(),
+            low_priority_fast_field_names: self.aggregation_only_fast_field_names(),
             field_norms: self.requires_scoring(),
             ..WarmupInfo::default()
         }
     }
+
+    /// Fast fields used by the aggregations but not to sort hits. Aggregations tend to be
+    /// one-off requests, so their data should not evict the one used to collect hits.
+    fn aggregation_only_fast_field_names(&self) -> HashSet<String> {
+        let Some(aggregations) = &self.aggregation else {
+            return HashSet::default();
+        };
+        let mut sort_fast_field_names = HashSet::default();
+        self.sort_by
+            .first
+            .add_fast_field(&mut sort_fast_field_names);
+        if let Some(sort_by_second) = &self.sort_by.second {
+            sort_by_second.add_fast_field(&mut sort_fast_field_names);
+        }
+        let mut fast_field_names = aggregations.fast_field_names();
+        fast_field_names.retain(|fast_field_name| !sort_fast_field_names.contains(fast_field_name));
+        fast_field_names
+    }
 }
 
 impl Collector for QuickwitCollector {
@@ -797,7 +841,7 @@ impl Collector for QuickwitCollector {
         // starting from 0 for every leaves.
         let leaf_max_hits = self.max_hits + self.start_offset;
 
-        let aggregation = match &self.aggregation {
+        let aggregation = match self.aggregation.as_deref() {
             Some(QuickwitAggregations::FindTraceIdsAggregation(collector)) => {
                 Some(AggregationSegmentCollectors::FindTraceIdsSegmentCollector(
                     Box::new(collector.for_segment(0, segment_reader)?),
@@ -865,11 +909,12 @@ impl Collector for QuickwitCollector {
         let num_hits = self.start_offset + self.max_hits;
         let (sort_order1, sort_order2) = self.sort_by.sort_orders();
         let mut merged_leaf_response = merge_leaf_responses(
-            &self.aggregation,
+            self.aggregation.as_deref(),
             segment_fruits?,
             sort_order1,
             sort_order2,
             num_hits,
+            self.num_slowest_splits,
         )?;
         // ... and drop the first [..start_offsets) hits.
         // note that self.start_offset is 0 when merging from leaf_search, and is only set when
@@ -892,7 +937,7 @@ fn map_error(err: postcard::Error) -> TantivyError {
 
 /// Merges a set of Leaf Results.
 fn merge_intermediate_aggregation_result<'a>(
-    aggregations_opt: &Option<QuickwitAggregations>,
+    aggregations_opt: Option<&QuickwitAggregations>,
     intermediate_aggregation_results: impl Iterator<Item = &'a [u8]>,
 ) -> tantivy::Result<Option<Vec<u8>>> {
     let merged_intermediate_aggregation_result = match aggregations_opt {
@@ -936,11 +981,12 @@ fn merge_intermediate_aggregation_result<'a>(
 
 /// Merges a set of Leaf Results.
 fn merge_leaf_responses(
-    aggregations_opt: &Option<QuickwitAggregations>,
+    aggregations_opt: Option<&QuickwitAggregations>,
     mut leaf_responses: Vec<LeafSearchResponse>,
     sort_order1: SortOrder,
     sort_order2: SortOrder,
     max_hits: usize,
+    num_slowest_splits: usize,
 ) -> tantivy::Result<LeafSearchResponse> {
     // Optimization: No merging needed if there is only one result.
     if leaf_responses.len() == 1 {
@@ -958,6 +1004,18 @@ fn merge_leaf_responses(
         .iter()
         .map(|leaf_response| leaf_response.num_attempted_splits)
         .sum();
+    let num_splits_searched = leaf_responses
+        .iter()
+        .map(|leaf_response| leaf_response.num_splits_searched)
+        .sum();
+    let num_splits_pruned = leaf_responses
+        .iter()
+        .map(|leaf_response| leaf_response.num_splits_pruned)
+        .sum();
+    let num_splits_count_only = leaf_responses
+        .iter()
+        .map(|leaf_response| leaf_response.num_splits_count_only)
+        .sum();
     let num_hits: u64 = leaf_responses
         .iter()
         .map(|leaf_response| leaf_response.num_hits)
@@ -967,6 +1025,25 @@ fn merge_leaf_responses(
         .flat_map(|leaf_response| leaf_response.failed_splits.iter())
         .cloned()
         .collect_vec();
+    let aggregation_is_partial = leaf_responses
+        .iter()
+        .any(|leaf_response| leaf_response.aggregation_is_partial)
+        || (aggregations_opt.is_some() && !failed_splits.is_empty());
+    let timed_out = leaf_responses
+        .iter()
+        .any(|leaf_response| leaf_response.timed_out);
+    let slowest_splits = merge_slowest_splits(
+        leaf_responses
+            .iter()
+            .flat_map(|leaf_response| leaf_response.slowest_splits.iter())
+            .cloned(),
+        num_slowest_splits,
+    );
+    let per_split_stats: Vec<SplitStats> = leaf_responses
+        .iter()
+        .flat_map(|leaf_response| leaf_response.per_split_stats.iter())
+        .cloned()
+        .collect();
     let all_partial_hits: Vec<PartialHit> = leaf_responses
         .into_iter()
         .flat_map(|leaf_response| leaf_response.partial_hits)
@@ -983,9 +1060,28 @@ fn merge_leaf_responses(
         partial_hits: top_k_partial_hits,
         failed_splits,
         num_attempted_splits,
+        slowest_splits,
+        per_split_stats,
+        num_splits_searched,
+        num_splits_pruned,
+        num_splits_count_only,
+        aggregation_is_partial,
+        timed_out,
     })
 }
 
+/// Keeps the `num_slowest_splits` slowest of the split timings reported by several leaves, sorted
+/// by decreasing search duration.
+pub(crate) fn merge_slowest_splits(
+    slowest_splits: impl Iterator<Item = SplitSearchTiming>,
+    num_slowest_splits: usize,
+) -> Vec<SplitSearchTiming> {
+    let mut slowest_splits: Vec<SplitSearchTiming> = slowest_splits.collect();
+    slowest_splits.sort_unstable_by_key(|split_timing| Reverse(split_timing.elapsed_time_micros));
+    slowest_splits.truncate(num_slowest_splits);
+    slowest_splits
+}
+
 /// Mutates partial_hits so that it contains the top-num_hitso hits,
 /// and so that these elements are sorted.
 ///
@@ -1042,18 +1138,31 @@ pub(crate) fn sort_by_from_request(search_request: &SearchRequest) -> SortByPair
     }
 }
 
+/// Parses the aggregation request of a search request, if any.
+///
+/// Leaf searches parse it once, and share it with their merge collector and the collector of
+/// each split.
+pub(crate) fn parse_aggregation_request(
+    search_request: &SearchRequest,
+) -> crate::Result<Option<Arc<QuickwitAggregations>>> {
+    match &search_request.aggregation_request {
+        Some(aggregation) => Ok(Some(Arc::new(serde_json::from_str(aggregation)?))),
+        None => Ok(None),
+    }
+}
+
 /// Builds the QuickwitCollector, in function of the information that was requested by the user.
+///
+/// `aggregation` is the parsed aggregation request of `search_request`, see
+/// [`parse_aggregation_request`].
 pub(crate) fn make_collector_for_split(
     split_id: String,
     search_request: &SearchRequest,
+    aggregation: Option<Arc<QuickwitAggregations>>,
     aggregation_limits: AggregationLimits,
-) -> crate::Result<QuickwitCollector> {
-    let aggregation = match &search_request.aggregation_request {
-        Some(aggregation) => Some(serde_json::from_str(aggregation)?),
-        None => None,
-    };
+) -> QuickwitCollector {
     let sort_by = sort_by_from_request(search_request);
-    Ok(QuickwitCollector {
+    QuickwitCollector {
         split_id,
         start_offset: search_request.start_offset as usize,
         max_hits: search_request.max_hits as usize,
@@ -1061,7 +1170,8 @@ pub(crate) fn make_collector_for_split(
         aggregation,
         aggregation_limits,
         search_after: search_request.search_after.clone(),
-    })
+        num_slowest_splits: search_request.num_slowest_splits.unwrap_or(0) as usize,
+    }
 }
 
 /// Builds a QuickwitCollector that's only useful for merging fruits.
@@ -1069,20 +1179,27 @@ pub(crate) fn make_merge_collector(
     search_request: &SearchRequest,
     aggregation_limits: &AggregationLimits,
 ) -> crate::Result<QuickwitCollector> {
-    let aggregation = match &search_request.aggregation_request {
-        Some(aggregation) => Some(serde_json::from_str(aggregation)?),
-        None => None,
-    };
-    let sort_by = sort_by_from_request(search_request);
-    Ok(QuickwitCollector {
-        split_id: String::default(),
-        start_offset: search_request.start_offset as usize,
-        max_hits: search_request.max_hits as usize,
-        sort_by,
+    let aggregation = parse_aggregation_request(search_request)?;
+    Ok(make_merge_collector_with_aggregation(
+        search_request,
         aggregation,
-        aggregation_limits: aggregation_limits.clone(),
-        search_after: search_request.search_after.clone(),
-    })
+        aggregation_limits,
+    ))
+}
+
+/// Same as [`make_merge_collector`], for an aggregation request already parsed with
+/// [`parse_aggregation_request`].
+pub(crate) fn make_merge_collector_with_aggregation(
+    search_request: &SearchRequest,
+    aggregation: Option<Arc<QuickwitAggregations>>,
+    aggregation_limits: &AggregationLimits,
+) -> QuickwitCollector {
+    make_collector_for_split(
+        String::default(),
+        search_request,
+        aggregation,
+        aggregation_limits.clone(),
+    )
 }
 
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
@@ -1204,7 +1321,16 @@ pub(crate) struct IncrementalCollector {
     num_hits: u64,
     failed_splits: Vec<SplitSearchError>,
     num_attempted_splits: u64,
+    slowest_splits: Vec<SplitSearchTiming>,
+    num_slowest_splits: usize,
+    per_split_stats: Vec<SplitStats>,
+    num_splits_searched: u32,
+    num_splits_pruned: u32,
+    num_splits_count_only: u32,
+    aggregation_is_partial: bool,
+    timed_out: bool,
     start_offset: usize,
+    num_contributing_splits: usize,
 }
 
 impl IncrementalCollector {
@@ -1212,7 +1338,7 @@ impl IncrementalCollector {
     pub(crate) fn new(collector: QuickwitCollector) -> Self {
         let incremental_aggregation = collector
             .aggregation
-            .as_ref()
+            .as_deref()
             .map(QuickwitAggregations::maybe_incremental_aggregator)
             .unwrap_or(QuickwitIncrementalAggregations::NoAggregation);
         let (order1, order2) = collector.sort_by.sort_orders();
@@ -1224,6 +1350,15 @@ impl IncrementalCollector {
             num_hits: 0,
             failed_splits: Vec::new(),
             num_attempted_splits: 0,
+            slowest_splits: Vec::new(),
+            num_slowest_splits: collector.num_slowest_splits,
+            per_split_stats: Vec::new(),
+            num_splits_searched: 0,
+            num_splits_pruned: 0,
+            num_splits_count_only: 0,
+            aggregation_is_partial: false,
+            timed_out: false,
+            num_contributing_splits: 0,
         }
     }
 
@@ -1235,12 +1370,29 @@ impl IncrementalCollector {
             failed_splits,
             num_attempted_splits,
             intermediate_aggregation_result,
+            slowest_splits,
+            per_split_stats,
+            num_splits_searched,
+            num_splits_pruned,
+            num_splits_count_only,
+            aggregation_is_partial,
+            timed_out,
         } = leaf_response;
 
+        if !partial_hits.is_empty() || intermediate_aggregation_result.is_some() {
+            self.num_contributing_splits += 1;
+        }
         self.num_hits += num_hits;
         self.top_k_hits.add_entries(partial_hits.into_iter());
         self.failed_splits.extend(failed_splits);
         self.num_attempted_splits += num_attempted_splits;
+        self.slowest_splits.extend(slowest_splits);
+        self.per_split_stats.extend(per_split_stats);
+        self.num_splits_searched += num_splits_searched;
+        self.num_splits_pruned += num_splits_pruned;
+        self.num_splits_count_only += num_splits_count_only;
+        self.aggregation_is_partial |= aggregation_is_partial;
+        self.timed_out |= timed_out;
         if let Some(intermediate_aggregation_result) = intermediate_aggregation_result {
             self.incremental_aggregation
                 .add(intermediate_aggregation_result)?;
@@ -1253,6 +1405,15 @@ impl IncrementalCollector {
         self.failed_splits.push(split_error)
     }
 
+    /// Returns an estimate of the memory used by the aggregation buckets merged so far, in bytes.
+    ///
+    /// The estimate is based on the size of the serialized intermediate results, and is meant to
+    /// detect pathological high-cardinality aggregations early, before the aggregation limits
+    /// are hit when finalizing.
+    pub(crate) fn aggregation_memory_usage(&self) -> u64 {
+        self.incremental_aggregation.memory_usage()
+    }
+
     /// Get the worst top-hit. Can be used to skip splits if they can't possibly do better.
     ///
     /// Only returns a result if enough hits were recorded already.
@@ -1271,8 +1432,35 @@ impl IncrementalCollector {
         }
     }
 
+    /// Returns the best hits merged so far, without finalizing the merge.
+    ///
+    /// Hits are sorted, and the first `start_offset` hits are skipped, as in the finalized
+    /// response.
+    pub(crate) fn snapshot_top_hits(&self) -> Vec<PartialHit> {
+        let mut partial_hits = self.top_k_hits.clone().finalize();
+        if self.start_offset != 0 {
+            partial_hits.drain(0..self.start_offset.min(partial_hits.len()));
+        }
+        partial_hits
+    }
+
+    /// Returns true if finalizing is cheap enough to be done on the caller's thread: there is no
+    /// aggregation to merge, and at most one split returned hits, which are already sorted.
+    pub(crate) fn is_finalize_trivial(&self) -> bool {
+        matches!(
+            self.incremental_aggregation,
+            QuickwitIncrementalAggregations::NoAggregation
+        ) && self.num_contributing_splits <= 1
+    }
+
     /// Finalize the merge, creating a LeafSearchResponse.
     pub(crate) fn finalize(self) -> tantivy::Result<LeafSearchResponse> {
+        let has_aggregation = !matches!(
+            self.incremental_aggregation,
+            QuickwitIncrementalAggregations::NoAggregation
+        );
+        let aggregation_is_partial =
+            self.aggregation_is_partial || (has_aggregation && !self.failed_splits.is_empty());
         let intermediate_aggregation_result = self.incremental_aggregation.finalize()?;
         let mut partial_hits = self.top_k_hits.finalize();
         if self.start_offset != 0 {
@@ -1284,6 +1472,16 @@ impl IncrementalCollector {
             failed_splits: self.failed_splits,
             num_attempted_splits: self.num_attempted_splits,
             intermediate_aggregation_result,
+            slowest_splits: merge_slowest_splits(
+                self.slowest_splits.into_iter(),
+                self.num_slowest_splits,
+            ),
+            per_split_stats: self.per_split_stats,
+            num_splits_searched: self.num_splits_searched,
+            num_splits_pruned: self.num_splits_pruned,
+            num_splits_count_only: self.num_splits_count_only,
+            aggregation_is_partial,
+            timed_out: self.timed_out,
         })
     }
 }
@@ -1294,7 +1492,7 @@ mod tests {
 
     use quickwit_proto::search::{
         LeafSearchResponse, PartialHit, SearchRequest, SortByValue, SortField, SortOrder,
-        SortValue, SplitSearchError,
+        SortValue, SplitSearchError, SplitSearchErrorKind,
     };
     use tantivy::collector::Collector;
     use tantivy::TantivyDocument;
@@ -1664,9 +1862,9 @@ mod tests {
                 let collector = super::make_collector_for_split(
                     "fake_split_id".to_string(),
                     &make_request(slice_len as u64, sort_str),
+                    None,
                     Default::default(),
-                )
-                .unwrap();
+                );
                 let res = searcher
                     .search(&tantivy::query::AllQuery, &collector)
                     .unwrap();
@@ -1760,9 +1958,9 @@ mod tests {
             let collector = super::make_collector_for_split(
                 "fake_split_id".to_string(),
                 &request,
+                None,
                 Default::default(),
-            )
-            .unwrap();
+            );
             let res = searcher
                 .search(&tantivy::query::AllQuery, &collector)
                 .unwrap();
@@ -1798,9 +1996,9 @@ mod tests {
             let collector = super::make_collector_for_split(
                 "fake_split_id1".to_string(),
                 &request,
+                None,
                 Default::default(),
-            )
-            .unwrap();
+            );
             let res = searcher
                 .search(&tantivy::query::AllQuery, &collector)
                 .unwrap();
@@ -1812,9 +2010,9 @@ mod tests {
             let collector = super::make_collector_for_split(
                 "fake_split_id2".to_string(),
                 &request,
+                None,
                 Default::default(),
-            )
-            .unwrap();
+            );
             let res = searcher
                 .search(&tantivy::query::AllQuery, &collector)
                 .unwrap();
@@ -1825,9 +2023,9 @@ mod tests {
             let collector = super::make_collector_for_split(
                 "fake_split_id3".to_string(),
                 &request,
+                None,
                 Default::default(),
-            )
-            .unwrap();
+            );
             let res = searcher
                 .search(&tantivy::query::AllQuery, &collector)
                 .unwrap();
@@ -1853,7 +2051,9 @@ mod tests {
             incremental_collector.add_split(split_result).unwrap();
         }
 
+        let snapshot_top_hits = incremental_collector.snapshot_top_hits();
         let incremental_result = incremental_collector.finalize().unwrap();
+        assert_eq!(snapshot_top_hits, incremental_result.partial_hits);
         assert_eq!(result, incremental_result);
         result
     }
@@ -1884,6 +2084,13 @@ mod tests {
                 failed_splits: Vec::new(),
                 num_attempted_splits: 3,
                 intermediate_aggregation_result: None,
+                slowest_splits: Vec::new(),
+                per_split_stats: Vec::new(),
+                num_splits_searched: 0,
+                num_splits_pruned: 0,
+                num_splits_count_only: 0,
+                aggregation_is_partial: false,
+                timed_out: false,
             }],
         );
 
@@ -1900,7 +2107,14 @@ mod tests {
                 }],
                 failed_splits: Vec::new(),
                 num_attempted_splits: 3,
-                intermediate_aggregation_result: None
+                intermediate_aggregation_result: None,
+                slowest_splits: Vec::new(),
+                per_split_stats: Vec::new(),
+                num_splits_searched: 0,
+                num_splits_pruned: 0,
+                num_splits_count_only: 0,
+                aggregation_is_partial: false,
+                timed_out: false,
             }
         );
 
@@ -1938,6 +2152,13 @@ mod tests {
                     failed_splits: Vec::new(),
                     num_attempted_splits: 3,
                     intermediate_aggregation_result: None,
+                    slowest_splits: Vec::new(),
+                    per_split_stats: Vec::new(),
+                    num_splits_searched: 0,
+                    num_splits_pruned: 0,
+                    num_splits_count_only: 0,
+                    aggregation_is_partial: false,
+                    timed_out: false,
                 },
                 LeafSearchResponse {
                     num_hits: 10,
@@ -1952,9 +2173,17 @@ mod tests {
                         error: "fake error".to_string(),
                         split_id: "3".to_string(),
                         retryable_error: true,
+                        error_kind: SplitSearchErrorKind::Internal.into(),
                     }],
                     num_attempted_splits: 2,
                     intermediate_aggregation_result: None,
+                    slowest_splits: Vec::new(),
+                    per_split_stats: Vec::new(),
+                    num_splits_searched: 0,
+                    num_splits_pruned: 0,
+                    num_splits_count_only: 0,
+                    aggregation_is_partial: false,
+                    timed_out: false,
                 },
             ],
         );
@@ -1983,9 +2212,17 @@ mod tests {
                     error: "fake error".to_string(),
                     split_id: "3".to_string(),
                     retryable_error: true,
+                    error_kind: SplitSearchErrorKind::Internal.into(),
                 }],
                 num_attempted_splits: 5,
-                intermediate_aggregation_result: None
+                intermediate_aggregation_result: None,
+                slowest_splits: Vec::new(),
+                per_split_stats: Vec::new(),
+                num_splits_searched: 0,
+                num_splits_pruned: 0,
+                num_splits_count_only: 0,
+                aggregation_is_partial: false,
+                timed_out: false,
             }
         );
 
@@ -2024,6 +2261,13 @@ mod tests {
                     failed_splits: Vec::new(),
                     num_attempted_splits: 3,
                     intermediate_aggregation_result: None,
+                    slowest_splits: Vec::new(),
+                    per_split_stats: Vec::new(),
+                    num_splits_searched: 0,
+                    num_splits_pruned: 0,
+                    num_splits_count_only: 0,
+                    aggregation_is_partial: false,
+                    timed_out: false,
                 },
                 LeafSearchResponse {
                     num_hits: 10,
@@ -2038,9 +2282,17 @@ mod tests {
                         error: "fake error".to_string(),
                         split_id: "3".to_string(),
                         retryable_error: true,
+                        error_kind: SplitSearchErrorKind::Internal.into(),
                     }],
                     num_attempted_splits: 2,
                     intermediate_aggregation_result: None,
+                    slowest_splits: Vec::new(),
+                    per_split_stats: Vec::new(),
+                    num_splits_searched: 0,
+                    num_splits_pruned: 0,
+                    num_splits_count_only: 0,
+                    aggregation_is_partial: false,
+                    timed_out: false,
                 },
             ],
         );
@@ -2069,11 +2321,251 @@ mod tests {
                     error: "fake error".to_string(),
                     split_id: "3".to_string(),
                     retryable_error: true,
+                    error_kind: SplitSearchErrorKind::Internal.into(),
                 }],
                 num_attempted_splits: 5,
-                intermediate_aggregation_result: None
+                intermediate_aggregation_result: None,
+                slowest_splits: Vec::new(),
+                per_split_stats: Vec::new(),
+                num_splits_searched: 0,
+                num_splits_pruned: 0,
+                num_splits_count_only: 0,
+                aggregation_is_partial: false,
+                timed_out: false,
             }
         );
         // TODO would be nice to test aggregation too.
     }
+
+    #[test]
+    fn test_incremental_collector_is_finalize_trivial() {
+        let request = SearchRequest {
+            max_hits: 1,
+            start_offset: 1,
+            ..Default::default()
+        };
+        let collector = make_merge_collector(&request, &Default::default()).unwrap();
+        let mut incremental_collector = IncrementalCollector::new(collector);
+        assert!(incremental_collector.is_finalize_trivial());
+
+        let partial_hit = |split_id: &str, doc_id: u32| PartialHit {
+            split_id: split_id.to_string(),
+            doc_id,
+            ..Default::default()
+        };
+        incremental_collector
+            .add_split(LeafSearchResponse {
+                num_hits: 3,
+                partial_hits: vec![
+                    partial_hit("split_1", 2),
+                    partial_hit("split_1", 1),
+                    partial_hit("split_1", 0),
+                ],
+                num_attempted_splits: 1,
+                ..Default::default()
+            })
+            .unwrap();
+        // Empty responses don't count as contributions.
+        incremental_collector
+            .add_split(LeafSearchResponse {
+                num_attempted_splits: 1,
+                ..Default::default()
+            })
+            .unwrap();
+        assert!(incremental_collector.is_finalize_trivial());
+
+        let mut other_incremental_collector = incremental_collector.clone();
+        other_incremental_collector
+            .add_split(LeafSearchResponse {
+                num_hits: 1,
+                partial_hits: vec![partial_hit("split_2", 0)],
+                num_attempted_splits: 1,
+                ..Default::default()
+            })
+            .unwrap();
+        assert!(!other_incremental_collector.is_finalize_trivial());
+
+        // `max_hits` and `start_offset` are still honored.
+        let leaf_search_response = incremental_collector.finalize().unwrap();
+        assert_eq!(leaf_search_response.num_hits, 3);
+        assert_eq!(leaf_search_response.num_attempted_splits, 2);
+        assert_eq!(leaf_search_response.partial_hits.len(), 1);
+
+        let aggregation_request = SearchRequest {
+            aggregation_request: Some(r#"{"avg_price": {"avg": {"field": "price"}}}"#.to_string()),
+            ..Default::default()
+        };
+        let collector = make_merge_collector(&aggregation_request, &Default::default()).unwrap();
+        assert!(!IncrementalCollector::new(collector).is_finalize_trivial());
+    }
+
+    #[test]
+    fn test_incremental_collector_aggregation_memory_usage() {
+        let request = SearchRequest {
+            aggregation_request: Some(r#"{"avg_price": {"avg": {"field": "price"}}}"#.to_string()),
+            ..Default::default()
+        };
+        let collector = make_merge_collector(&request, &Default::default()).unwrap();
+        let mut incremental_collector = IncrementalCollector::new(collector);
+        assert_eq!(incremental_collector.aggregation_memory_usage(), 0);
+
+        let leaf_response = LeafSearchResponse {
+            intermediate_aggregation_result: Some(vec![0u8; 100]),
+            ..Default::default()
+        };
+        incremental_collector
+            .add_split(leaf_response.clone())
+            .unwrap();
+        assert_eq!(incremental_collector.aggregation_memory_usage(), 100);
+
+        incremental_collector.add_split(leaf_response).unwrap();
+        assert_eq!(incremental_collector.aggregation_memory_usage(), 200);
+    }
+
+    #[test]
+    fn test_split_counts_are_summed() {
+        let request = SearchRequest {
+            max_hits: 10,
+            ..Default::default()
+        };
+        let leaf_responses = vec![
+            LeafSearchResponse {
+                num_attempted_splits: 3,
+                num_splits_searched: 1,
+                num_splits_pruned: 2,
+                ..Default::default()
+            },
+            LeafSearchResponse {
+                num_attempted_splits: 4,
+                num_splits_searched: 2,
+                num_splits_pruned: 1,
+                num_splits_count_only: 1,
+                ..Default::default()
+            },
+        ];
+        let collector = make_merge_collector(&request, &Default::default()).unwrap();
+        let merged_response = collector
+            .merge_fruits(leaf_responses.iter().cloned().map(Ok).collect())
+            .unwrap();
+        assert_eq!(merged_response.num_attempted_splits, 7);
+        assert_eq!(merged_response.num_splits_searched, 3);
+        assert_eq!(merged_response.num_splits_pruned, 3);
+        assert_eq!(merged_response.num_splits_count_only, 1);
+
+        let collector = make_merge_collector(&request, &Default::default()).unwrap();
+        let mut incremental_collector = IncrementalCollector::new(collector);
+        for leaf_response in leaf_responses {
+            incremental_collector.add_split(leaf_response).unwrap();
+        }
+        let finalized_response = incremental_collector.finalize().unwrap();
+        assert_eq!(finalized_response.num_splits_searched, 3);
+        assert_eq!(finalized_response.num_splits_pruned, 3);
+        assert_eq!(finalized_response.num_splits_count_only, 1);
+    }
+
+    #[test]
+    fn test_aggregation_is_partial() {
+        let failed_split = quickwit_proto::search::SplitSearchError {
+            split_id: "split2".to_string(),
+            error: "timeout".to_string(),
+            retryable_error: true,
+            ..Default::default()
+        };
+        let aggregation_request = SearchRequest {
+            max_hits: 10,
+            aggregation_request: Some(r#"{"avg_price": {"avg": {"field": "price"}}}"#.to_string()),
+            ..Default::default()
+        };
+        let hits_request = SearchRequest {
+            max_hits: 10,
+            ..Default::default()
+        };
+        let aggregation_is_partial = |request: &SearchRequest, failed_splits: Vec<_>| {
+            let collector = make_merge_collector(request, &Default::default()).unwrap();
+            let mut incremental_collector = IncrementalCollector::new(collector);
+            incremental_collector
+                .add_split(LeafSearchResponse::default())
+                .unwrap();
+            for failed_split in failed_splits {
+                incremental_collector.add_failed_split(failed_split);
+            }
+            incremental_collector
+                .finalize()
+                .unwrap()
+                .aggregation_is_partial
+        };
+        assert!(!aggregation_is_partial(&aggregation_request, Vec::new()));
+        assert!(aggregation_is_partial(
+            &aggregation_request,
+            vec![failed_split.clone()]
+        ));
+        assert!(!aggregation_is_partial(
+            &hits_request,
+            vec![failed_split.clone()]
+        ));
+
+        // The flag of the merged responses is preserved.
+        let leaf_responses = vec![
+            LeafSearchResponse {
+                aggregation_is_partial: true,
+                ..Default::default()
+            },
+            LeafSearchResponse::default(),
+        ];
+        let collector = make_merge_collector(&aggregation_request, &Default::default()).unwrap();
+        let merged_response = collector
+            .merge_fruits(leaf_responses.iter().cloned().map(Ok).collect())
+            .unwrap();
+        assert!(merged_response.aggregation_is_partial);
+
+        let collector = make_merge_collector(&aggregation_request, &Default::default()).unwrap();
+        let mut incremental_collector = IncrementalCollector::new(collector);
+        for leaf_response in leaf_responses {
+            incremental_collector.add_split(leaf_response).unwrap();
+        }
+        assert!(
+            incremental_collector
+                .finalize()
+                .unwrap()
+                .aggregation_is_partial
+        );
+    }
+
+    #[test]
+    fn test_warmup_info_aggregation_fast_fields_are_low_priority() {
+        let search_request = SearchRequest {
+            max_hits: 10,
+            sort_fields: vec![SortField {
+                field_name: "timestamp".to_string(),
+                sort_order: SortOrder::Desc.into(),
+                sort_datetime_format: None,
+            }],
+            aggregation_request: Some(
+                r#"{
+                    "avg_response": { "avg": { "field": "response" } },
+                    "per_hour": { "histogram": { "field": "timestamp", "interval": 3600 } }
+                }"#
+                .to_string(),
+            ),
+            ..SearchRequest::default()
+        };
+        let collector = super::make_collector_for_split(
+            "split1".to_string(),
+            &search_request,
+            super::parse_aggregation_request(&search_request).unwrap(),
+            Default::default(),
+        );
+        let warmup_info = collector.warmup_info();
+        assert_eq!(
+            warmup_info.fast_field_names,
+            ["response".to_string(), "timestamp".to_string()]
+                .into_iter()
+                .collect()
+        );
+        // `timestamp` is also used to sort hits.
+        assert_eq!(
+            warmup_info.low_priority_fast_field_names,
+            ["response".to_string()].into_iter().collect()
+        );
+    }
 }
@@ -20,6 +20,7 @@
 use quickwit_doc_mapper::QueryParserError;
 use quickwit_proto::error::grpc_error_to_grpc_status;
 use quickwit_proto::metastore::{EntityKind, MetastoreError};
+use quickwit_proto::search::SplitSearchErrorKind;
 use quickwit_proto::{tonic, GrpcServiceError, ServiceError, ServiceErrorCode};
 use quickwit_storage::StorageResolverError;
 use serde::{Deserialize, Serialize};
@@ -32,6 +33,16 @@ use tokio::task::JoinError;
 #[derive(Error, Debug, Serialize, Deserialize, Clone)]
 #[serde(rename_all = "snake_case")]
 pub enum SearchError {
+    #[error(
+        "aggregation memory usage estimate ({memory_usage_bytes} bytes) exceeds the limit \
+         ({memory_limit_bytes} bytes)"
+    )]
+    AggregationMemoryLimitExceeded {
+        memory_usage_bytes: u64,
+        memory_limit_bytes: u64,
+    },
+    #[error("search was cancelled")]
+    Cancelled,
     #[error("could not find indexes matching the IDs `{index_ids:?}`")]
     IndexesNotFound { index_ids: Vec<String> },
     #[error("internal error: `{0}`")]
@@ -42,28 +53,63 @@ pub enum SearchError {
     InvalidArgument(String),
     #[error("{0}")]
     InvalidQuery(String),
+    #[error(
+        "footer of split `{split_id}` is too large ({footer_num_bytes} bytes, the limit is \
+         {max_footer_num_bytes} bytes)"
+    )]
+    SplitFooterTooLarge {
+        split_id: String,
+        footer_num_bytes: u64,
+        max_footer_num_bytes: u64,
+    },
+    #[error("split `{split_id}` not found in storage `{storage_uri}`")]
+    SplitNotFound {
+        split_id: String,
+        storage_uri: String,
+    },
     #[error("storage not found: `{0}`)")]
     StorageResolver(#[from] StorageResolverError),
+    #[error("storage request timed out: {0}")]
+    StorageTimeout(String),
+    #[error("storage access denied: {0}")]
+    StorageUnauthorized(String),
     #[error("request timed out: {0}")]
     Timeout(String),
     #[error("too many requests")]
     TooManyRequests,
     #[error("service unavailable: {0}")]
     Unavailable(String),
+    #[error(
+        "projected warmup download ({warmup_num_bytes} bytes) exceeds the per-split limit \
+         ({max_warmup_num_bytes} bytes)"
+    )]
+    WarmupBudgetExceeded {
+        warmup_num_bytes: u64,
+        max_warmup_num_bytes: u64,
+    },
 }
 
 impl ServiceError for SearchError {
     fn error_code(&self) -> ServiceErrorCode {
         match self {
+            Self::AggregationMemoryLimitExceeded { .. } => ServiceErrorCode::BadRequest,
+            // Like gRPC's `Cancelled` status, a cancellation is reported as a timeout: the search
+            // did not fail, its caller gave up on it.
+            Self::Cancelled => ServiceErrorCode::Timeout,
             Self::IndexesNotFound { .. } => ServiceErrorCode::NotFound,
             Self::Internal(_) => ServiceErrorCode::Internal,
             Self::InvalidAggregationRequest(_) => ServiceErrorCode::BadRequest,
             Self::InvalidArgument(_) => ServiceErrorCode::BadRequest,
             Self::InvalidQuery(_) => ServiceErrorCode::BadRequest,
+            Self::SplitFooterTooLarge { .. } => ServiceErrorCode::Internal,
+            Self::SplitNotFound { .. } => ServiceErrorCode::NotFound,
             Self::StorageResolver(_) => ServiceErrorCode::Internal,
+            Self::StorageTimeout(_) => ServiceErrorCode::Timeout,
+            Self::StorageUnauthorized(_) => ServiceErrorCode::Forbidden,
             Self::Timeout(_) => ServiceErrorCode::Timeout,
             Self::TooManyRequests => ServiceErrorCode::TooManyRequests,
             Self::Unavailable(_) => ServiceErrorCode::Unavailable,
+            Self::WarmupBudgetExceeded { .. } => ServiceErrorCode::BadRequest,
         }
     }
 }
@@ -99,6 +145,46 @@ pub fn parse_grpc_error(grpc_error: &tonic::Status) -> SearchError {
         .unwrap_or_else(|_| SearchError::Internal(grpc_error.message().to_string()))
 }
 
+impl SearchError {
+    /// Returns the kind of the `SplitSearchError` reported when searching a split fails with
+    /// this error.
+    pub(crate) fn split_search_error_kind(&self) -> SplitSearchErrorKind {
+        match self {
+            SearchError::StorageTimeout(_) | SearchError::Timeout(_) => {
+                SplitSearchErrorKind::Timeout
+            }
+            SearchError::AggregationMemoryLimitExceeded { .. }
+            | SearchError::InvalidAggregationRequest(_)
+            | SearchError::InvalidArgument(_)
+            | SearchError::InvalidQuery(_)
+            | SearchError::WarmupBudgetExceeded { .. } => SplitSearchErrorKind::Query,
+            SearchError::SplitFooterTooLarge { .. } => SplitSearchErrorKind::Corruption,
+            SearchError::SplitNotFound { .. }
+            | SearchError::StorageResolver(_)
+            | SearchError::StorageUnauthorized(_) => SplitSearchErrorKind::StorageIo,
+            SearchError::Cancelled
+            | SearchError::IndexesNotFound { .. }
+            | SearchError::Internal(_)
+            | SearchError::TooManyRequests
+            | SearchError::Unavailable(_) => SplitSearchErrorKind::Internal,
+        }
+    }
+
+    /// Returns whether searching the split again, possibly on another searcher, may succeed
+    /// after failing with this error. Invalid queries, the splits that were deleted from the
+    /// storage, that the searchers are not allowed to read, or whose footer is too large, will fail
+    /// the same way.
+    pub(crate) fn is_retryable_split_error(&self) -> bool {
+        self.split_search_error_kind() != SplitSearchErrorKind::Query
+            && !matches!(
+                self,
+                SearchError::SplitFooterTooLarge { .. }
+                    | SearchError::SplitNotFound { .. }
+                    | SearchError::StorageUnauthorized(_)
+            )
+    }
+}
+
 impl From<TantivyError> for SearchError {
     fn from(tantivy_error: TantivyError) -> Self {
         SearchError::Internal(format!("tantivy error: {tantivy_error}"))
@@ -119,7 +205,10 @@ impl From<serde_json::Error> for SearchError {
 
 impl From<anyhow::Error> for SearchError {
     fn from(any_error: anyhow::Error) -> Self {
-        SearchError::Internal(any_error.to_string())
+        match any_error.downcast::<SearchError>() {
+            Ok(search_error) => search_error,
+            Err(any_error) => SearchError::Internal(any_error.to_string()),
+        }
     }
 }
 
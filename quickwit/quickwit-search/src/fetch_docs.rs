@@ -27,7 +27,7 @@ use quickwit_doc_mapper::DocMapper;
 use quickwit_proto::search::{
     FetchDocsResponse, PartialHit, SnippetRequest, SplitIdAndFooterOffsets,
 };
-use quickwit_storage::Storage;
+use quickwit_storage::{CachePriority, Storage};
 use tantivy::query::Query;
 use tantivy::schema::{Document as DocumentTrait, Field, OwnedValue, TantivyDocument, Value};
 use tantivy::snippet::SnippetGenerator;
@@ -179,12 +179,14 @@ async fn fetch_docs_in_split(
         split,
         Some(doc_mapper.tokenizer_manager()),
         false,
+        CachePriority::Normal,
     )
     .await
     .context("open-index-for-split")?;
     // we add an executor here, we could add it in open_index_with_caches, though we should verify
     // the side-effect before
-    let tantivy_executor = crate::search_thread_pool()
+    let tantivy_executor = searcher_context
+        .search_thread_pool()
         .get_underlying_rayon_thread_pool()
         .into();
     index.set_executor(tantivy_executor);
@@ -0,0 +1,137 @@
+// Copyright (C) 2024 Quickwit, Inc.
+//
+// Quickwit is offered under the AGPL v3.0 and as commercial software.
+// For commercial licensing, contact us at hello@quickwit.io.
+//
+// AGPL:
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+use std::sync::Mutex;
+
+use lru::LruCache;
+use quickwit_directories::HotCache;
+
+/// A cache holding the parsed hotcache of the most recently opened splits, so that searching a
+/// split again skips parsing its hotcache layout.
+///
+/// Entries are accounted for the size of their hotcache bytes, which they keep alive even once
+/// evicted from the split footer cache. The cache has its own capacity, on top of the one of the
+/// split footer cache.
+pub struct HotcacheCache {
+    inner: Mutex<InnerHotcacheCache>,
+}
+
+struct InnerHotcacheCache {
+    hot_caches: LruCache<String, HotCache>,
+    num_bytes: usize,
+    capacity_in_bytes: usize,
+}
+
+impl HotcacheCache {
+    pub fn with_capacity_in_bytes(capacity_in_bytes: usize) -> HotcacheCache {
+        HotcacheCache {
+            inner: Mutex::new(InnerHotcacheCache {
+                hot_caches: LruCache::unbounded(),
+                num_bytes: 0,
+                capacity_in_bytes,
+            }),
+        }
+    }
+
+    /// Returns the parsed hotcache cached under `cache_key`, which identifies a split the same
+    /// way as the split footer cache does, i.e. including the URI of its index storage.
+    pub fn get(&self, cache_key: &str) -> Option<HotCache> {
+        self.inner
+            .lock()
+            .unwrap()
+            .hot_caches
+            .get(cache_key)
+            .cloned()
+    }
+
+    /// Inserts the parsed hotcache of a split, evicting the least recently used ones if the
+    /// capacity is exceeded. Hotcaches larger than the capacity are not cached.
+    pub fn put(&self, cache_key: String, hot_cache: HotCache) {
+        let mut inner = self.inner.lock().unwrap();
+        if hot_cache.num_bytes() > inner.capacity_in_bytes {
+            return;
+        }
+        inner.num_bytes += hot_cache.num_bytes();
+
+        if let Some((_, replaced_hot_cache)) = inner.hot_caches.push(cache_key, hot_cache) {
+            inner.num_bytes -= replaced_hot_cache.num_bytes();
+        }
+        while inner.num_bytes > inner.capacity_in_bytes {
+            let Some((_, evicted_hot_cache)) = inner.hot_caches.pop_lru() else {
+                break;
+            };
+            inner.num_bytes -= evicted_hot_cache.num_bytes();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use quickwit_directories::write_hotcache;
+    use quickwit_storage::OwnedBytes;
+    use tantivy::directory::RamDirectory;
+    use tantivy::schema::{Schema, TEXT};
+    use tantivy::{Index, IndexSettings};
+
+    use super::*;
+
+    fn build_hot_cache() -> HotCache {
+        let mut schema_builder = Schema::builder();
+        schema_builder.add_text_field("body", TEXT);
+        let directory = RamDirectory::create();
+        Index::create(
+            directory.clone(),
+            schema_builder.build(),
+            IndexSettings::default(),
+        )
+        .unwrap();
+        let mut hotcache_bytes = Vec::new();
+        write_hotcache(directory, &mut hotcache_bytes).unwrap();
+        HotCache::open(OwnedBytes::new(hotcache_bytes)).unwrap()
+    }
+
+    #[test]
+    fn test_hotcache_cache() {
+        let hot_cache = build_hot_cache();
+        let num_bytes = hot_cache.num_bytes();
+        assert!(num_bytes > 0);
+
+        let hotcache_cache = HotcacheCache::with_capacity_in_bytes(2 * num_bytes);
+        assert!(hotcache_cache.get("split-1").is_none());
+
+        hotcache_cache.put("split-1".to_string(), hot_cache.clone());
+        hotcache_cache.put("split-2".to_string(), hot_cache.clone());
+        assert!(hotcache_cache.get("split-1").is_some());
+        assert!(hotcache_cache.get("split-2").is_some());
+
+        // Replacing an entry does not evict anything.
+        hotcache_cache.put("split-2".to_string(), hot_cache.clone());
+        assert!(hotcache_cache.get("split-1").is_some());
+
+        // `split-2` is now the least recently used entry.
+        hotcache_cache.put("split-3".to_string(), hot_cache.clone());
+        assert!(hotcache_cache.get("split-1").is_some());
+        assert!(hotcache_cache.get("split-2").is_none());
+        assert!(hotcache_cache.get("split-3").is_some());
+
+        let small_hotcache_cache = HotcacheCache::with_capacity_in_bytes(num_bytes - 1);
+        small_hotcache_cache.put("split-1".to_string(), hot_cache);
+        assert!(small_hotcache_cache.get("split-1").is_none());
+    }
+}
@@ -17,43 +17,91 @@
 // You should have received a copy of the GNU Affero General Public License
 // along with this program. If not, see <http://www.gnu.org/licenses/>.
 
-use std::collections::{HashMap, HashSet};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::future::Future;
+use std::num::NonZeroUsize;
 use std::ops::Bound;
 use std::path::PathBuf;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicUsize, Ordering};
 use std::sync::{Arc, Mutex};
+use std::task::{ready, Context as TaskContext, Poll};
+use std::time::{Duration, Instant};
 
 use anyhow::Context;
+use bytesize::ByteSize;
 use futures::future::try_join_all;
+use futures::stream::FuturesUnordered;
+use futures::{StreamExt, TryStreamExt};
+use itertools::Itertools;
+use once_cell::sync::Lazy;
+use quickwit_common::binary_heap::{SortKeyMapper, TopK};
+use quickwit_common::bounds::{map_bound, max_bound, min_bound};
+use quickwit_common::metrics::{GaugeGuard, IntCounter};
 use quickwit_common::pretty::PrettySample;
-use quickwit_directories::{CachingDirectory, HotDirectory, StorageDirectory};
-use quickwit_doc_mapper::{DocMapper, TermRange, WarmupInfo};
+use quickwit_common::retry::RetryParams;
+use quickwit_common::thread_pool::ThreadPoolError;
+use quickwit_common::uri::Uri;
+use quickwit_config::SearcherConfig;
+use quickwit_directories::{CachingDirectory, HotCache, HotDirectory, StorageDirectory};
+use quickwit_doc_mapper::{
+    DocMapper, QueryParserError, TermDictWarmupPolicy, TermRange, WarmupInfo, DYNAMIC_FIELD_NAME,
+};
 use quickwit_proto::search::{
-    CountHits, LeafSearchResponse, PartialHit, SearchRequest, SortOrder, SortValue,
-    SplitIdAndFooterOffsets, SplitSearchError,
+    CountHits, LeafSearchResponse, PartialHit, SearchPriority, SearchRequest, SortOrder, SortValue,
+    SplitIdAndFooterOffsets, SplitSearchError, SplitSearchErrorKind, SplitSearchTiming, SplitStats,
 };
 use quickwit_query::query_ast::{BoolQuery, QueryAst, QueryAstTransformer, RangeQuery, TermQuery};
 use quickwit_query::tokenizers::TokenizerManager;
+use quickwit_query::MatchAllOrNone;
 use quickwit_storage::{
-    wrap_storage_with_cache, BundleStorage, MemorySizedCache, OwnedBytes, SplitCache, Storage,
+    with_cache_priority, wrap_storage_with_cache, BundleStorage, CachePriority, MemorySizedCache,
+    OwnedBytes, SplitCache, Storage, StorageError, StorageErrorKind,
 };
+use serde::Serialize;
+use tantivy::aggregation::agg_req::{AggregationVariants, Aggregations};
 use tantivy::directory::FileSlice;
 use tantivy::fastfield::FastFieldReaders;
-use tantivy::schema::Field;
-use tantivy::{DateTime, Index, ReloadPolicy, Searcher, Term};
+use tantivy::query::{EnableScoring, Query};
+use tantivy::schema::{Field, FieldType, Schema};
+use tantivy::{
+    DateTime, DocSet, HasLen, Index, ReloadPolicy, Searcher, TantivyError, Term, TERMINATED,
+};
+use tokio::sync::{Semaphore, SemaphorePermit};
+use tokio::task::{AbortHandle, JoinError, JoinHandle};
+use tokio_util::sync::CancellationToken;
 use tracing::*;
 
-use crate::collector::{make_collector_for_split, make_merge_collector, IncrementalCollector};
+use crate::collector::{
+    make_collector_for_split, make_merge_collector_with_aggregation, parse_aggregation_request,
+    IncrementalCollector,
+};
 use crate::service::SearcherContext;
-use crate::SearchError;
+use crate::{QuickwitAggregations, SearchError};
+
+/// Returns the key of a split footer in the split footer cache.
+///
+/// Split IDs are only unique within a storage: the same split may for instance exist in the
+/// storages of a replicated index. Keying footers on the storage URI as well keeps them apart.
+fn split_footer_cache_key(index_storage: &dyn Storage, split_id: &str) -> String {
+    format!("{}/{split_id}", index_storage.uri())
+}
 
 #[instrument(skip_all)]
 async fn get_split_footer_from_cache_or_fetch(
     index_storage: Arc<dyn Storage>,
     split_and_footer_offsets: &SplitIdAndFooterOffsets,
     footer_cache: &MemorySizedCache<String>,
+    footer_cache_ttl_opt: Option<Duration>,
 ) -> anyhow::Result<OwnedBytes> {
+    let cache_key = split_footer_cache_key(&*index_storage, &split_and_footer_offsets.split_id);
+    // Hits and misses are accounted for by the cache itself, in the `splitfooter` cache metrics.
     {
-        let possible_val = footer_cache.get(&split_and_footer_offsets.split_id);
+        let possible_val = if let Some(footer_cache_ttl) = footer_cache_ttl_opt {
+            footer_cache.get_unexpired(&cache_key, footer_cache_ttl)
+        } else {
+            footer_cache.get(&cache_key)
+        };
         if let Some(footer_data) = possible_val {
             return Ok(footer_data);
         }
@@ -66,35 +114,146 @@ async fn get_split_footer_from_cache_or_fetch(
                 ..split_and_footer_offsets.split_footer_end as usize,
         )
         .await
-        .with_context(|| {
-            format!(
-                "failed to fetch hotcache and footer from {} for split `{}`",
+        .map_err(|storage_error| {
+            footer_fetch_error(
+                storage_error,
                 index_storage.uri(),
-                split_and_footer_offsets.split_id
+                &split_and_footer_offsets.split_id,
             )
         })?;
+    crate::SEARCH_METRICS
+        .split_footer_fetched_num_bytes
+        .inc_by(footer_data_opt.len() as u64);
 
-    footer_cache.put(
-        split_and_footer_offsets.split_id.to_owned(),
-        footer_data_opt.clone(),
-    );
+    footer_cache.put(cache_key, footer_data_opt.clone());
 
     Ok(footer_data_opt)
 }
 
+/// Turns the storage errors that the root can act upon into typed search errors: a split that
+/// is not found was most likely garbage collected and should not be retried.
+fn footer_fetch_error(
+    storage_error: StorageError,
+    storage_uri: &Uri,
+    split_id: &str,
+) -> anyhow::Error {
+    let context =
+        format!("failed to fetch hotcache and footer from {storage_uri} for split `{split_id}`");
+    match storage_error.kind() {
+        StorageErrorKind::NotFound => SearchError::SplitNotFound {
+            split_id: split_id.to_string(),
+            storage_uri: storage_uri.to_string(),
+        }
+        .into(),
+        StorageErrorKind::Unauthorized => {
+            SearchError::StorageUnauthorized(format!("{context}: {storage_error}")).into()
+        }
+        StorageErrorKind::Timeout => {
+            SearchError::StorageTimeout(format!("{context}: {storage_error}")).into()
+        }
+        _ => anyhow::Error::new(storage_error).context(context),
+    }
+}
+
+/// Fetches the footers of `splits` concurrently, in order, to populate the split footer cache, so
+/// that they are already local by the time each split gets searched.
+///
+/// Unless `run_all_splits` is set, splits that `split_filter` prunes by the time their footer
+/// would be fetched are skipped. Each fetch holds a permit of the split footer prefetch semaphore
+/// rather than a split search permit, so prefetching never holds back split searches. Failures are
+/// only logged: the split search fetches the footer again and reports the error.
+async fn prefetch_split_footers(
+    searcher_context: Arc<SearcherContext>,
+    index_storage: Arc<dyn Storage>,
+    splits: Vec<SplitIdAndFooterOffsets>,
+    split_filter: Arc<Mutex<CanSplitDoBetter>>,
+    run_all_splits: bool,
+) {
+    let searcher_context = &searcher_context;
+    futures::stream::iter(splits)
+        // The permit is acquired before checking the split filter, so that splits are pruned
+        // based on the freshest worst hit.
+        .then(|split| async move {
+            let prefetch_permit = searcher_context
+                .split_footer_prefetch_semaphore
+                .acquire()
+                .await
+                .expect("the split footer prefetch semaphore should never be closed");
+            (split, prefetch_permit)
+        })
+        .filter(|(split, _prefetch_permit)| {
+            let can_be_better = split_filter.lock().unwrap().can_be_better(split);
+            futures::future::ready(run_all_splits || can_be_better)
+        })
+        // The concurrency is bounded by the prefetch permits.
+        .for_each_concurrent(None, |(split, _prefetch_permit)| {
+            let index_storage = &index_storage;
+            async move {
+                if let Err(error) = get_split_footer_from_cache_or_fetch(
+                    index_storage.clone(),
+                    &split,
+                    &searcher_context.split_footer_cache,
+                    searcher_context.searcher_config.split_footer_cache_ttl(),
+                )
+                .await
+                {
+                    debug!(
+                        split_id = %split.split_id,
+                        error = ?error,
+                        "failed to prefetch split footer"
+                    );
+                }
+            }
+        })
+        .await;
+}
+
+/// Aborts a task when dropped, e.g. when the leaf search that spawned it returns early.
+struct AbortOnDrop(AbortHandle);
+
+impl Drop for AbortOnDrop {
+    fn drop(&mut self) {
+        self.0.abort();
+    }
+}
+
+/// Returns the priority of the reads of a search in the split cache.
+///
+/// Background searches, e.g. scrolls, are typically one-off scans that should not evict the
+/// splits of the interactive searches from the split cache.
+fn split_cache_priority(search_request: &SearchRequest) -> CachePriority {
+    if search_request.priority() == SearchPriority::Background
+        || search_request.scroll_ttl_secs.is_some()
+    {
+        CachePriority::Low
+    } else {
+        CachePriority::Normal
+    }
+}
+
 /// Returns hotcache_bytes and the split directory (`BundleStorage`) with cache layer:
 /// - A split footer cache given by `SearcherContext.split_footer_cache`.
+///
+/// Reads are served by the split cache, if any, with the given `split_cache_priority`.
 #[instrument(skip_all, fields(split_footer_start=split_and_footer_offsets.split_footer_start, split_footer_end=split_and_footer_offsets.split_footer_end))]
 pub(crate) async fn open_split_bundle(
     searcher_context: &SearcherContext,
     index_storage: Arc<dyn Storage>,
     split_and_footer_offsets: &SplitIdAndFooterOffsets,
+    split_cache_priority: CachePriority,
 ) -> anyhow::Result<(FileSlice, BundleStorage)> {
+    check_split_footer_size(
+        split_and_footer_offsets,
+        searcher_context
+            .searcher_config
+            .max_hotcache_bytes_per_split,
+    )?;
     let split_file = PathBuf::from(format!("{}.split", split_and_footer_offsets.split_id));
     let footer_data = get_split_footer_from_cache_or_fetch(
         index_storage.clone(),
         split_and_footer_offsets,
         &searcher_context.split_footer_cache,
+        searcher_context.searcher_config.split_footer_cache_ttl(),
     )
     .await?;
 
@@ -102,7 +261,11 @@ pub(crate) async fn open_split_bundle(
     // This is before the bundle storage: at this point, this storage is reading `.split` files.
     let index_storage_with_split_cache =
         if let Some(split_cache) = searcher_context.split_cache_opt.as_ref() {
-            SplitCache::wrap_storage(split_cache.clone(), index_storage.clone())
+            SplitCache::wrap_storage_with_priority(
+                split_cache.clone(),
+                index_storage.clone(),
+                split_cache_priority,
+            )
         } else {
             index_storage.clone()
         };
@@ -116,6 +279,109 @@ pub(crate) async fn open_split_bundle(
     Ok((hotcache_bytes, bundle_storage))
 }
 
+/// Fails with `SearchError::SplitFooterTooLarge` if the footer of the split exceeds
+/// `max_hotcache_bytes_opt`, before it gets fetched.
+///
+/// The footer is made of the hotcache and of the small list of the files of the split, so it is a
+/// slightly conservative bound on the size of the hotcache, known from the split offsets alone.
+fn check_split_footer_size(
+    split_and_footer_offsets: &SplitIdAndFooterOffsets,
+    max_hotcache_bytes_opt: Option<ByteSize>,
+) -> Result<(), SearchError> {
+    let Some(max_hotcache_bytes) = max_hotcache_bytes_opt else {
+        return Ok(());
+    };
+    let footer_num_bytes = split_and_footer_offsets
+        .split_footer_end
+        .saturating_sub(split_and_footer_offsets.split_footer_start);
+    if footer_num_bytes > max_hotcache_bytes.as_u64() {
+        return Err(SearchError::SplitFooterTooLarge {
+            split_id: split_and_footer_offsets.split_id.clone(),
+            footer_num_bytes,
+            max_footer_num_bytes: max_hotcache_bytes.as_u64(),
+        });
+    }
+    Ok(())
+}
+
+/// Removes the splits appearing more than once, e.g. when the split sets of overlapping index
+/// patterns were concatenated, so that each split is opened and searched only once. All the
+/// splits of a leaf request belong to the same index storage, so the split ID identifies them.
+fn dedup_splits(splits: &mut Vec<SplitIdAndFooterOffsets>) {
+    let num_splits = splits.len();
+    let mut split_ids: HashSet<String> = HashSet::with_capacity(num_splits);
+    splits.retain(|split| split_ids.insert(split.split_id.clone()));
+    let num_duplicate_splits = num_splits - splits.len();
+    if num_duplicate_splits > 0 {
+        debug!(
+            num_duplicate_splits,
+            "removed duplicate splits from leaf search request"
+        );
+    }
+}
+
+/// Restricts the splits to search to the split ID allowlist of the request, if any, minus its split
+/// ID denylist. The denylist takes precedence.
+fn apply_split_id_overrides(
+    splits: &mut Vec<SplitIdAndFooterOffsets>,
+    request: &SearchRequest,
+    searcher_config: &SearcherConfig,
+) -> crate::Result<()> {
+    if request.split_id_allowlist.is_empty() && request.split_id_denylist.is_empty() {
+        return Ok(());
+    }
+    if !searcher_config.enable_split_id_overrides {
+        return Err(SearchError::InvalidArgument(
+            "split ID allowlists and denylists are disabled on this searcher".to_string(),
+        ));
+    }
+    let allowlist: HashSet<&str> = request
+        .split_id_allowlist
+        .iter()
+        .map(String::as_str)
+        .collect();
+    let denylist: HashSet<&str> = request
+        .split_id_denylist
+        .iter()
+        .map(String::as_str)
+        .collect();
+    splits.retain(|split| {
+        let split_id = split.split_id.as_str();
+        (allowlist.is_empty() || allowlist.contains(split_id)) && !denylist.contains(split_id)
+    });
+    Ok(())
+}
+
+/// Rejects leaf requests targeting more splits, or more split bytes, than allowed by the searcher
+/// config.
+fn check_leaf_request_size(
+    splits: &[SplitIdAndFooterOffsets],
+    searcher_config: &SearcherConfig,
+) -> crate::Result<()> {
+    if let Some(max_splits) = searcher_config.max_splits_per_leaf_request {
+        if splits.len() > max_splits {
+            return Err(SearchError::InvalidArgument(format!(
+                "leaf search request targets too many splits ({} splits, the limit is \
+                 {max_splits} splits)",
+                splits.len()
+            )));
+        }
+    }
+    if let Some(max_split_bytes) = searcher_config.max_split_bytes_per_leaf_request {
+        // The footer is located at the end of the split file, so its end offset is the size of
+        // the split.
+        let split_num_bytes: u64 = splits.iter().map(|split| split.split_footer_end).sum();
+        if split_num_bytes > max_split_bytes.as_u64() {
+            return Err(SearchError::InvalidArgument(format!(
+                "leaf search request targets too many split bytes ({split_num_bytes} bytes, the \
+                 limit is {} bytes)",
+                max_split_bytes.as_u64()
+            )));
+        }
+    }
+    Ok(())
+}
+
 /// Opens a `tantivy::Index` for the given split with several cache layers:
 /// - A split footer cache given by `SearcherContext.split_footer_cache`.
 /// - A fast fields cache given by `SearcherContext.storage_long_term_cache`.
@@ -127,9 +393,17 @@ pub(crate) async fn open_index_with_caches(
     split_and_footer_offsets: &SplitIdAndFooterOffsets,
     tokenizer_manager: Option<&TokenizerManager>,
     ephemeral_unbounded_cache: bool,
+    split_cache_priority: CachePriority,
 ) -> anyhow::Result<Index> {
-    let (hotcache_bytes, bundle_storage) =
-        open_split_bundle(searcher_context, index_storage, split_and_footer_offsets).await?;
+    let hotcache_cache_key =
+        split_footer_cache_key(&*index_storage, &split_and_footer_offsets.split_id);
+    let (hotcache_bytes, bundle_storage) = open_split_bundle(
+        searcher_context,
+        index_storage,
+        split_and_footer_offsets,
+        split_cache_priority,
+    )
+    .await?;
 
     let bundle_storage_with_cache = wrap_storage_with_cache(
         searcher_context.fast_fields_cache.clone(),
@@ -137,11 +411,29 @@ pub(crate) async fn open_index_with_caches(
     );
     let directory = StorageDirectory::new(bundle_storage_with_cache);
 
+    // A cached hotcache could outlive the footer it was parsed from, so it is not used when split
+    // footers expire.
+    let hotcache_cache_opt = searcher_context
+        .searcher_config
+        .split_footer_cache_ttl_secs
+        .is_none()
+        .then_some(&searcher_context.hotcache_cache);
+    let hot_cache = if let Some(hot_cache) =
+        hotcache_cache_opt.and_then(|hotcache_cache| hotcache_cache.get(&hotcache_cache_key))
+    {
+        hot_cache
+    } else {
+        let hot_cache = HotCache::open(hotcache_bytes.read_bytes()?)?;
+        if let Some(hotcache_cache) = hotcache_cache_opt {
+            hotcache_cache.put(hotcache_cache_key, hot_cache.clone());
+        }
+        hot_cache
+    };
     let hot_directory = if ephemeral_unbounded_cache {
         let caching_directory = CachingDirectory::new_unbounded(Arc::new(directory));
-        HotDirectory::open(caching_directory, hotcache_bytes.read_bytes()?)?
+        HotDirectory::open_with_hot_cache(caching_directory, hot_cache)
     } else {
-        HotDirectory::open(directory, hotcache_bytes.read_bytes()?)?
+        HotDirectory::open_with_hot_cache(directory, hot_cache)
     };
 
     let mut index = Index::open(hot_directory)?;
@@ -156,6 +448,29 @@ pub(crate) async fn open_index_with_caches(
     Ok(index)
 }
 
+/// Opens a split with an arbitrary tokenizer manager, which does not have to match the one of the
+/// doc mapper the split was indexed with.
+///
+/// This is meant for inspection tooling, e.g. to check what a proposed analyzer would produce on
+/// the data of an existing split. The returned index is backed by an ephemeral unbounded cache,
+/// and its data must be read asynchronously or warmed up before being searched.
+pub async fn open_split_for_inspection(
+    searcher_context: &SearcherContext,
+    index_storage: Arc<dyn Storage>,
+    split_and_footer_offsets: &SplitIdAndFooterOffsets,
+    tokenizer_manager: &TokenizerManager,
+) -> anyhow::Result<Index> {
+    open_index_with_caches(
+        searcher_context,
+        index_storage,
+        split_and_footer_offsets,
+        Some(tokenizer_manager),
+        true,
+        CachePriority::Normal,
+    )
+    .await
+}
+
 /// Tantivy search does not make it possible to fetch data asynchronously during
 /// search.
 ///
@@ -171,24 +486,43 @@ pub(crate) async fn open_index_with_caches(
 /// * `term_dict_field_names` - A list of fields, where the whole dictionary needs to be loaded.
 /// This is e.g. required for term aggregation, since we don't know in advance which terms are going
 /// to be hit.
+///
+/// * `max_term_range_limit_opt` - Caps the number of terms warmed up for each term range.
 #[instrument(skip_all)]
-pub(crate) async fn warmup(searcher: &Searcher, warmup_info: &WarmupInfo) -> anyhow::Result<()> {
+pub(crate) async fn warmup(
+    searcher: &Searcher,
+    warmup_info: &WarmupInfo,
+    max_term_range_limit_opt: Option<u64>,
+    warmup_concurrency: NonZeroUsize,
+) -> anyhow::Result<()> {
     debug!(warmup_info=?warmup_info);
-    let warm_up_terms_future = warm_up_terms(searcher, &warmup_info.terms_grouped_by_field)
-        .instrument(debug_span!("warm_up_terms"));
-    let warm_up_term_ranges_future =
-        warm_up_term_ranges(searcher, &warmup_info.term_ranges_grouped_by_field)
-            .instrument(debug_span!("warm_up_term_ranges"));
+    // Bounds the number of reads in flight across all the kinds of data warmed up below.
+    let warmup_semaphore = Semaphore::new(warmup_concurrency.get());
+    let warm_up_terms_future = warm_up_terms(
+        searcher,
+        &warmup_info.terms_grouped_by_field,
+        &warmup_semaphore,
+    )
+    .instrument(debug_span!("warm_up_terms"));
+    let warm_up_term_ranges_future = warm_up_term_ranges(
+        searcher,
+        &warmup_info.term_ranges_grouped_by_field,
+        max_term_range_limit_opt,
+        &warmup_semaphore,
+    )
+    .instrument(debug_span!("warm_up_term_ranges"));
     let warm_up_term_dict_future =
-        warm_up_term_dict_fields(searcher, &warmup_info.term_dict_fields)
-            .instrument(debug_span!("warm_up_term_dicts"));
-    let warm_up_fastfields_future = warm_up_fastfields(searcher, &warmup_info.fast_field_names)
-        .instrument(debug_span!("warm_up_fastfields"));
-    let warm_up_fieldnorms_future = warm_up_fieldnorms(searcher, warmup_info.field_norms)
+        warm_up_term_dicts_and_postings(searcher, &warmup_info.term_dict_fields, &warmup_semaphore)
+            .instrument(debug_span!("warm_up_term_dicts_and_postings"));
+    let warm_up_fastfields_future = warm_up_fastfields(
+        searcher,
+        &warmup_info.fast_field_names,
+        &warmup_info.low_priority_fast_field_names,
+        &warmup_semaphore,
+    )
+    .instrument(debug_span!("warm_up_fastfields"));
+    let warm_up_fieldnorms_future = warm_up_fieldnorms(searcher, warmup_info, &warmup_semaphore)
         .instrument(debug_span!("warm_up_fieldnorms"));
-    // TODO merge warm_up_postings into warm_up_term_dict_fields
-    let warm_up_postings_future = warm_up_postings(searcher, &warmup_info.term_dict_fields)
-        .instrument(debug_span!("warm_up_postings"));
 
     tokio::try_join!(
         warm_up_terms_future,
@@ -196,39 +530,120 @@ pub(crate) async fn warmup(searcher: &Searcher, warmup_info: &WarmupInfo) -> any
         warm_up_fastfields_future,
         warm_up_term_dict_future,
         warm_up_fieldnorms_future,
-        warm_up_postings_future,
     )?;
 
     Ok(())
 }
 
-async fn warm_up_term_dict_fields(
+/// Returns a projection of the number of bytes `warmup` is going to download for
+/// `warmup_info`.
+///
+/// Only term dictionaries loaded in their entirety are accounted for: their sizes are known from
+/// the split footer, and they are the only reads that are not bounded by the query itself, e.g.
+/// for a term aggregation on a high-cardinality field.
+fn estimate_warmup_num_bytes(searcher: &Searcher, warmup_info: &WarmupInfo) -> crate::Result<u64> {
+    let mut warmup_num_bytes = 0;
+    for field in &warmup_info.term_dict_fields {
+        for segment_reader in searcher.segment_readers() {
+            let inverted_index = segment_reader.inverted_index(*field)?;
+            warmup_num_bytes += inverted_index.terms().file_slice_for_range(.., None).len() as u64;
+        }
+    }
+    Ok(warmup_num_bytes)
+}
+
+/// Returns the estimated warmup download for `warmup_info`, or `None` if `max_warmup_bytes_opt`
+/// does not bound it, in which case the estimate is not computed.
+///
+/// Fails with `SearchError::WarmupBudgetExceeded` if it exceeds `max_warmup_bytes_opt`, before
+/// any read is issued.
+pub(crate) fn check_warmup_budget(
+    searcher: &Searcher,
+    warmup_info: &WarmupInfo,
+    max_warmup_bytes_opt: Option<ByteSize>,
+) -> crate::Result<Option<u64>> {
+    let Some(max_warmup_bytes) = max_warmup_bytes_opt else {
+        return Ok(None);
+    };
+    let warmup_num_bytes = estimate_warmup_num_bytes(searcher, warmup_info)?;
+    if warmup_num_bytes > max_warmup_bytes.as_u64() {
+        return Err(SearchError::WarmupBudgetExceeded {
+            warmup_num_bytes,
+            max_warmup_num_bytes: max_warmup_bytes.as_u64(),
+        });
+    }
+    Ok(Some(warmup_num_bytes))
+}
+
+/// Fails with `SearchError::InvalidArgument` if `warmup_info` requires downloading the term
+/// dictionary of a field that the index search settings don't allow, before any read is issued.
+pub(crate) fn check_term_dict_warmup_policy(
+    searcher: &Searcher,
+    warmup_info: &WarmupInfo,
+    term_dict_warmup_policy_opt: Option<&TermDictWarmupPolicy>,
+) -> crate::Result<()> {
+    let Some(term_dict_warmup_policy) = term_dict_warmup_policy_opt else {
+        return Ok(());
+    };
+    let schema = searcher.schema();
+    let disallowed_field_name_opt = warmup_info
+        .term_dict_fields
+        .iter()
+        .map(|field| schema.get_field_name(*field))
+        .filter(|field_name| !term_dict_warmup_policy.is_field_allowed(field_name))
+        .min();
+    if let Some(disallowed_field_name) = disallowed_field_name_opt {
+        return Err(SearchError::InvalidArgument(format!(
+            "the term dictionary of field `{disallowed_field_name}` cannot be warmed up, as per \
+             the search settings of the index"
+        )));
+    }
+    Ok(())
+}
+
+/// Runs `warm_up_futures` to completion, with at most as many of them in flight as
+/// `warmup_semaphore` has permits.
+async fn try_join_all_with_permits<T, E>(
+    warmup_semaphore: &Semaphore,
+    warm_up_futures: Vec<impl Future<Output = Result<T, E>>>,
+) -> Result<Vec<T>, E> {
+    try_join_all(
+        warm_up_futures
+            .into_iter()
+            .map(|warm_up_future| async move {
+                let _warmup_permit = warmup_semaphore
+                    .acquire()
+                    .await
+                    .expect("the warmup semaphore should never be closed");
+                warm_up_future.await
+            }),
+    )
+    .await
+}
+
+/// Warms up the whole term dictionary and postings of the given fields.
+///
+/// The inverted index of each field is opened once per segment, and both reads are issued from
+/// the same future.
+async fn warm_up_term_dicts_and_postings(
     searcher: &Searcher,
     term_dict_fields: &HashSet<Field>,
+    warmup_semaphore: &Semaphore,
 ) -> anyhow::Result<()> {
     let mut warm_up_futures = Vec::new();
     for field in term_dict_fields {
         for segment_reader in searcher.segment_readers() {
-            let inverted_index = segment_reader.inverted_index(*field)?.clone();
+            let inverted_index = segment_reader.inverted_index(*field)?;
             warm_up_futures.push(async move {
                 let dict = inverted_index.terms();
-                dict.warm_up_dictionary().await
+                tokio::try_join!(
+                    dict.warm_up_dictionary(),
+                    inverted_index.warm_postings_full(false),
+                )
             });
         }
     }
-    try_join_all(warm_up_futures).await?;
-    Ok(())
-}
-
-async fn warm_up_postings(searcher: &Searcher, fields: &HashSet<Field>) -> anyhow::Result<()> {
-    let mut warm_up_futures = Vec::new();
-    for field in fields {
-        for segment_reader in searcher.segment_readers() {
-            let inverted_index = segment_reader.inverted_index(*field)?.clone();
-            warm_up_futures.push(async move { inverted_index.warm_postings_full(false).await });
-        }
-    }
-    try_join_all(warm_up_futures).await?;
+    try_join_all_with_permits(warmup_semaphore, warm_up_futures).await?;
     Ok(())
 }
 
@@ -250,25 +665,39 @@ async fn warm_up_fastfield(
 
 /// Populates the short-lived cache with the data for
 /// all of the fast fields passed as argument.
+///
+/// The data of `low_priority_fast_field_names` is put in the fast fields cache with a low
+/// priority.
 async fn warm_up_fastfields(
     searcher: &Searcher,
     fast_field_names: &HashSet<String>,
+    low_priority_fast_field_names: &HashSet<String>,
+    warmup_semaphore: &Semaphore,
 ) -> anyhow::Result<()> {
     let mut warm_up_futures = Vec::new();
     for segment_reader in searcher.segment_readers() {
         let fast_field_reader = segment_reader.fast_fields();
         for fast_field_name in fast_field_names {
-            let warm_up_fut = warm_up_fastfield(fast_field_reader, fast_field_name);
+            let cache_priority = if low_priority_fast_field_names.contains(fast_field_name) {
+                CachePriority::Low
+            } else {
+                CachePriority::Normal
+            };
+            let warm_up_fut = with_cache_priority(
+                cache_priority,
+                warm_up_fastfield(fast_field_reader, fast_field_name),
+            );
             warm_up_futures.push(Box::pin(warm_up_fut));
         }
     }
-    futures::future::try_join_all(warm_up_futures).await?;
+    try_join_all_with_permits(warmup_semaphore, warm_up_futures).await?;
     Ok(())
 }
 
 async fn warm_up_terms(
     searcher: &Searcher,
     terms_grouped_by_field: &HashMap<Field, HashMap<Term, bool>>,
+    warmup_semaphore: &Semaphore,
 ) -> anyhow::Result<()> {
     let mut warm_up_futures = Vec::new();
     for (field, terms) in terms_grouped_by_field {
@@ -281,13 +710,31 @@ async fn warm_up_terms(
             }
         }
     }
-    try_join_all(warm_up_futures).await?;
+    try_join_all_with_permits(warmup_semaphore, warm_up_futures).await?;
     Ok(())
 }
 
+/// Returns the number of terms to warm up for a term range, capped to `max_limit_opt`.
+///
+/// A range without limit is capped too.
+fn clamp_term_range_limit(limit_opt: Option<u64>, max_limit_opt: Option<u64>) -> Option<u64> {
+    let Some(max_limit) = max_limit_opt else {
+        return limit_opt;
+    };
+    match limit_opt {
+        Some(limit) if limit <= max_limit => Some(limit),
+        _ => {
+            debug!(limit=?limit_opt, max_limit, "clamping term range warmup limit");
+            Some(max_limit)
+        }
+    }
+}
+
 async fn warm_up_term_ranges(
     searcher: &Searcher,
     terms_grouped_by_field: &HashMap<Field, HashMap<TermRange, bool>>,
+    max_term_range_limit_opt: Option<u64>,
+    warmup_semaphore: &Semaphore,
 ) -> anyhow::Result<()> {
     let mut warm_up_futures = Vec::new();
     for (field, terms) in terms_grouped_by_field {
@@ -296,75 +743,258 @@ async fn warm_up_term_ranges(
             for (term_range, position_needed) in terms.iter() {
                 let inv_idx_clone = inv_idx.clone();
                 let range = (term_range.start.as_ref(), term_range.end.as_ref());
+                let limit_opt = clamp_term_range_limit(term_range.limit, max_term_range_limit_opt);
                 warm_up_futures.push(async move {
                     inv_idx_clone
-                        .warm_postings_range(range, term_range.limit, *position_needed)
+                        .warm_postings_range(range, limit_opt, *position_needed)
                         .await
                 });
             }
         }
     }
-    try_join_all(warm_up_futures).await?;
+    try_join_all_with_permits(warmup_semaphore, warm_up_futures).await?;
     Ok(())
 }
 
-async fn warm_up_fieldnorms(searcher: &Searcher, requires_scoring: bool) -> anyhow::Result<()> {
-    if !requires_scoring {
-        return Ok(());
-    }
+async fn warm_up_fieldnorms(
+    searcher: &Searcher,
+    warmup_info: &WarmupInfo,
+    warmup_semaphore: &Semaphore,
+) -> anyhow::Result<()> {
     let mut warm_up_futures = Vec::new();
-    for field in searcher.schema().fields() {
+    for field in warmup_info.fields_requiring_field_norms(searcher.schema()) {
         for segment_reader in searcher.segment_readers() {
             let fieldnorm_readers = segment_reader.fieldnorms_readers();
-            let file_handle_opt = fieldnorm_readers.get_inner_file().open_read(field.0);
+            let file_handle_opt = fieldnorm_readers.get_inner_file().open_read(field);
             if let Some(file_handle) = file_handle_opt {
                 warm_up_futures.push(async move { file_handle.read_bytes_async().await })
             }
         }
     }
-    try_join_all(warm_up_futures).await?;
+    try_join_all_with_permits(warmup_semaphore, warm_up_futures).await?;
     Ok(())
 }
 
-/// Apply a leaf search on a single split.
-#[instrument(skip_all, fields(split_id = split.split_id))]
+/// Error returned by the search of a single split, along with the kind reported in the
+/// resulting `SplitSearchError`.
+#[derive(Debug)]
+struct LeafSplitSearchError {
+    kind: SplitSearchErrorKind,
+    error: SearchError,
+}
+
+impl From<SearchError> for LeafSplitSearchError {
+    fn from(error: SearchError) -> Self {
+        LeafSplitSearchError {
+            kind: error.split_search_error_kind(),
+            error,
+        }
+    }
+}
+
+impl From<QueryParserError> for LeafSplitSearchError {
+    fn from(error: QueryParserError) -> Self {
+        SearchError::from(error).into()
+    }
+}
+
+impl From<TantivyError> for LeafSplitSearchError {
+    fn from(error: TantivyError) -> Self {
+        LeafSplitSearchError {
+            kind: tantivy_error_kind(&error),
+            error: error.into(),
+        }
+    }
+}
+
+impl From<anyhow::Error> for LeafSplitSearchError {
+    fn from(error: anyhow::Error) -> Self {
+        let kind = error
+            .chain()
+            .find_map(|cause| {
+                if let Some(search_error) = cause.downcast_ref::<SearchError>() {
+                    Some(search_error.split_search_error_kind())
+                } else if let Some(storage_error) = cause.downcast_ref::<StorageError>() {
+                    Some(storage_error_kind(storage_error))
+                } else if let Some(tantivy_error) = cause.downcast_ref::<TantivyError>() {
+                    Some(tantivy_error_kind(tantivy_error))
+                } else {
+                    cause.downcast_ref::<std::io::Error>().map(io_error_kind)
+                }
+            })
+            .unwrap_or(SplitSearchErrorKind::Internal);
+        LeafSplitSearchError {
+            kind,
+            error: error.into(),
+        }
+    }
+}
+
+/// Builds the error reported for a split that failed to open before being searched.
+pub(crate) fn split_open_error(split_id: &str, error: anyhow::Error) -> SplitSearchError {
+    let error = LeafSplitSearchError::from(error);
+    SplitSearchError {
+        split_id: split_id.to_string(),
+        error: format!("{}", error.error),
+        retryable_error: error.is_retryable(),
+        error_kind: error.kind.into(),
+    }
+}
+
+impl LeafSplitSearchError {
+    /// Returns whether searching the split again, possibly on another searcher, may succeed. Query
+    /// errors are not retryable, including the tantivy errors that are only identified as such by
+    /// their kind.
+    fn is_retryable(&self) -> bool {
+        self.kind != SplitSearchErrorKind::Query && self.error.is_retryable_split_error()
+    }
+
+    /// Returns true if the error is likely transient, in which case the split search is worth
+    /// retrying in place. Errors due to the query or the split itself would fail the same way, and
+    /// a split search that ran out of time would not have more time on a second attempt.
+    fn is_retryable_in_place(&self) -> bool {
+        matches!(
+            self.kind,
+            SplitSearchErrorKind::StorageIo | SplitSearchErrorKind::Timeout
+        ) && !matches!(self.error, SearchError::Timeout(_))
+            && self.is_retryable()
+    }
+}
+
+/// Backoff between the attempts of a split search failing with a transient error.
+const SPLIT_SEARCH_RETRY_PARAMS: RetryParams = RetryParams {
+    base_delay: Duration::from_millis(50),
+    max_delay: Duration::from_secs(1),
+    max_attempts: usize::MAX,
+};
+
+fn storage_error_kind(storage_error: &StorageError) -> SplitSearchErrorKind {
+    match storage_error.kind() {
+        StorageErrorKind::Timeout => SplitSearchErrorKind::Timeout,
+        _ => SplitSearchErrorKind::StorageIo,
+    }
+}
+
+fn io_error_kind(io_error: &std::io::Error) -> SplitSearchErrorKind {
+    // io errors may wrap a storage error, which knows better.
+    if let Some(storage_error) = io_error
+        .get_ref()
+        .and_then(|inner_error| inner_error.downcast_ref::<StorageError>())
+    {
+        return storage_error_kind(storage_error);
+    }
+    if io_error.kind() == std::io::ErrorKind::TimedOut {
+        SplitSearchErrorKind::Timeout
+    } else {
+        SplitSearchErrorKind::StorageIo
+    }
+}
+
+fn tantivy_error_kind(tantivy_error: &TantivyError) -> SplitSearchErrorKind {
+    match tantivy_error {
+        TantivyError::DataCorruption(_) => SplitSearchErrorKind::Corruption,
+        TantivyError::IoError(io_error) => io_error_kind(io_error),
+        TantivyError::OpenDirectoryError(_) | TantivyError::OpenReadError(_) => {
+            SplitSearchErrorKind::StorageIo
+        }
+        TantivyError::AggregationError(_)
+        | TantivyError::FieldNotFound(_)
+        | TantivyError::InvalidArgument(_)
+        | TantivyError::SchemaError(_) => SplitSearchErrorKind::Query,
+        _ => SplitSearchErrorKind::Internal,
+    }
+}
+
+/// Per-phase metrics of a split search, recorded as attributes of the split search span so that
+/// they get exported along with the trace.
+///
+/// The metrics of a split search answered from the leaf search cache are all zeros.
+#[derive(Debug, Default)]
+struct SplitSearchSpanMetrics {
+    /// Estimated number of bytes downloaded by the warmup, only computed when the warmup download
+    /// is bounded.
+    estimated_warmup_num_bytes_opt: Option<u64>,
+    warmup_duration: Duration,
+    search_duration: Duration,
+}
+
+impl SplitSearchSpanMetrics {
+    /// Records the metrics on `span`, which must declare the corresponding fields.
+    fn record(&self, span: &Span) {
+        if let Some(estimated_warmup_num_bytes) = self.estimated_warmup_num_bytes_opt {
+            span.record("estimated_warmup_num_bytes", estimated_warmup_num_bytes);
+        }
+        span.record(
+            "warmup_duration_micros",
+            self.warmup_duration.as_micros() as u64,
+        );
+        span.record(
+            "search_duration_micros",
+            self.search_duration.as_micros() as u64,
+        );
+    }
+}
+
+/// Apply a leaf search on a single split, returning the response along with the metrics of the
+/// split search.
+///
+/// `opened_index_opt` is the index of the split, if the caller already opened it.
+#[instrument(skip_all, fields(
+    split_id = split.split_id,
+    estimated_warmup_num_bytes = field::Empty,
+    warmup_duration_micros = field::Empty,
+    search_duration_micros = field::Empty,
+))]
 async fn leaf_search_single_split(
     searcher_context: &SearcherContext,
     mut search_request: SearchRequest,
     storage: Arc<dyn Storage>,
     split: SplitIdAndFooterOffsets,
     doc_mapper: Arc<dyn DocMapper>,
-) -> crate::Result<LeafSearchResponse> {
+    aggregation_opt: Option<Arc<QuickwitAggregations>>,
+    opened_index_opt: Option<Index>,
+) -> Result<(LeafSearchResponse, SplitSearchSpanMetrics), LeafSplitSearchError> {
     rewrite_request(
         &mut search_request,
         &split,
         doc_mapper.timestamp_field_name(),
+        aggregation_opt.as_deref(),
     );
     if let Some(cached_answer) = searcher_context
         .leaf_search_cache
-        .get(split.clone(), search_request.clone())
+        .get(&split, &search_request)
     {
-        return Ok(cached_answer);
+        return Ok((cached_answer, SplitSearchSpanMetrics::default()));
     }
 
     let split_id = split.split_id.to_string();
-    let index = open_index_with_caches(
-        searcher_context,
-        storage,
-        &split,
-        Some(doc_mapper.tokenizer_manager()),
-        true,
-    )
-    .await?;
-    let split_schema = index.schema();
-
-    let quickwit_collector = make_collector_for_split(
-        split_id.clone(),
-        &search_request,
-        searcher_context.get_aggregation_limits(),
-    )?;
     let query_ast: QueryAst = serde_json::from_str(search_request.query_ast.as_str())
         .map_err(|err| SearchError::InvalidQuery(err.to_string()))?;
+    if !split_metadata_may_match(&split, &search_request, &query_ast) {
+        debug!(split_id = %split_id, "split cannot match the request, skipping it");
+        return Ok((skipped_split_response(), SplitSearchSpanMetrics::default()));
+    }
+    let index = if let Some(opened_index) = opened_index_opt {
+        opened_index
+    } else {
+        open_index_with_caches(
+            searcher_context,
+            storage,
+            &split,
+            Some(doc_mapper.tokenizer_manager()),
+            true,
+            split_cache_priority(&search_request),
+        )
+        .await?
+    };
+    let split_schema = index.schema();
+
+    // The fields of the split are only known once its footer is opened: the split is not warmed
+    // up nor searched if it has none of the queried fields.
+    if !query_may_match_split(&query_ast, &split_schema) {
+        debug!(split_id = %split_id, "split has none of the queried fields, skipping it");
+        return Ok((skipped_split_response(), SplitSearchSpanMetrics::default()));
+    }
     let (query, mut warmup_info) = doc_mapper.query(split_schema, &query_ast, false)?;
     let reader = index
         .reader_builder()
@@ -372,106 +1002,529 @@ async fn leaf_search_single_split(
         .try_into()?;
     let searcher = reader.searcher();
 
-    let collector_warmup_info = quickwit_collector.warmup_info();
-    warmup_info.merge(collector_warmup_info);
-    warmup_info.simplify();
+    if is_count_only_request(&search_request) {
+        // We only warm up what the query needs: no collector, no fast fields.
+        warmup_info.simplify();
+        check_term_dict_warmup_policy(
+            &searcher,
+            &warmup_info,
+            doc_mapper.term_dict_warmup_policy(),
+        )?;
+        let estimated_warmup_num_bytes_opt = check_warmup_budget(
+            &searcher,
+            &warmup_info,
+            searcher_context.searcher_config.max_warmup_bytes_per_split,
+        )?;
+        let warmup_start = Instant::now();
+        warmup(
+            &searcher,
+            &warmup_info,
+            searcher_context.searcher_config.max_term_range_warmup_limit,
+            searcher_context.searcher_config.warmup_concurrency,
+        )
+        .await?;
+        let warmup_duration = warmup_start.elapsed();
+        let search_start = Instant::now();
+        let span = info_span!("tantivy_count");
+        let num_hits = searcher_context
+            .search_thread_pool()
+            .run_cpu_intensive(move || {
+                let _span_guard = span.enter();
+                query.count(&searcher)
+            })
+            .await
+            .map_err(|error| thread_pool_error_to_search_error(error, "leaf count", &split_id))??;
+        let split_search_metrics = SplitSearchSpanMetrics {
+            estimated_warmup_num_bytes_opt,
+            warmup_duration,
+            search_duration: search_start.elapsed(),
+        };
+        split_search_metrics.record(&Span::current());
+        let leaf_search_response = LeafSearchResponse {
+            num_hits: num_hits as u64,
+            partial_hits: Vec::new(),
+            failed_splits: Vec::new(),
+            num_attempted_splits: 1,
+            intermediate_aggregation_result: None,
+            slowest_splits: Vec::new(),
+            per_split_stats: Vec::new(),
+            num_splits_searched: 0,
+            num_splits_pruned: 0,
+            num_splits_count_only: 0,
+            aggregation_is_partial: false,
+            timed_out: false,
+        };
+        searcher_context.leaf_search_cache.put(
+            &split,
+            &search_request,
+            leaf_search_response.clone(),
+        );
+        return Ok((leaf_search_response, split_search_metrics));
+    }
+
+    let quickwit_collector = make_collector_for_split(
+        split_id.clone(),
+        &search_request,
+        aggregation_opt,
+        searcher_context.get_aggregation_limits(),
+    );
+    let warmup_info = WarmupInfo::merge_all([warmup_info, quickwit_collector.warmup_info()]);
+    if enabled!(Level::DEBUG) {
+        for warmup_warning in warmup_info.validate() {
+            debug!(split_id = %split_id, warmup_warning = ?warmup_warning, "conflicting warmup requests");
+        }
+    }
 
-    warmup(&searcher, &warmup_info).await?;
+    check_term_dict_warmup_policy(
+        &searcher,
+        &warmup_info,
+        doc_mapper.term_dict_warmup_policy(),
+    )?;
+    let estimated_warmup_num_bytes_opt = check_warmup_budget(
+        &searcher,
+        &warmup_info,
+        searcher_context.searcher_config.max_warmup_bytes_per_split,
+    )?;
+    let warmup_start = Instant::now();
+    warmup(
+        &searcher,
+        &warmup_info,
+        searcher_context.searcher_config.max_term_range_warmup_limit,
+        searcher_context.searcher_config.warmup_concurrency,
+    )
+    .await?;
+    let warmup_duration = warmup_start.elapsed();
+    let search_start = Instant::now();
     let span = info_span!("tantivy_search");
-    let leaf_search_response = crate::search_thread_pool()
+    let doc_order_num_hits_opt = is_doc_order_early_termination_request(&search_request)
+        .then_some((search_request.start_offset + search_request.max_hits) as usize);
+    let split_id_clone = split_id.clone();
+    let leaf_search_response = searcher_context
+        .search_thread_pool()
         .run_cpu_intensive(move || {
             let _span_guard = span.enter();
+            if let Some(num_hits) = doc_order_num_hits_opt {
+                return collect_first_docs_in_doc_order(
+                    &searcher,
+                    query.as_ref(),
+                    split_id_clone,
+                    num_hits,
+                );
+            }
             searcher.search(&query, &quickwit_collector)
         })
         .await
-        .map_err(|_| {
-            crate::SearchError::Internal(format!("leaf search panicked. split={split_id}"))
-        })??;
+        .map_err(|error| thread_pool_error_to_search_error(error, "leaf search", &split_id))??;
+    let split_search_metrics = SplitSearchSpanMetrics {
+        estimated_warmup_num_bytes_opt,
+        warmup_duration,
+        search_duration: search_start.elapsed(),
+    };
+    split_search_metrics.record(&Span::current());
 
     searcher_context
         .leaf_search_cache
-        .put(split, search_request, leaf_search_response.clone());
-    Ok(leaf_search_response)
+        .put(&split, &search_request, leaf_search_response.clone());
+    Ok((leaf_search_response, split_search_metrics))
 }
 
-/// Rewrite a request removing parts which incure additional download or computation with no
-/// effect.
+/// Converts the error of a split search task run on the search thread pool into a search error.
 ///
-/// This include things such as sorting result by a field or _score when no document is requested,
-/// or applying date range when the range covers the entire split.
-fn rewrite_request(
-    search_request: &mut SearchRequest,
-    split: &SplitIdAndFooterOffsets,
-    timestamp_field: Option<&str>,
-) {
-    if search_request.max_hits == 0 {
-        search_request.sort_fields = Vec::new();
+/// A pool shutting down is not a failure of the split: the search is reported as unavailable so
+/// that it gets retried on another searcher.
+fn thread_pool_error_to_search_error(
+    thread_pool_error: ThreadPoolError,
+    task_name: &str,
+    split_id: &str,
+) -> SearchError {
+    match thread_pool_error {
+        ThreadPoolError::Panicked => {
+            SearchError::Internal(format!("{task_name} panicked. split={split_id}"))
+        }
+        ThreadPoolError::ShuttingDown => SearchError::Unavailable(format!(
+            "{task_name} rejected: the search thread pool is shutting down. split={split_id}"
+        )),
     }
-    if let Some(timestamp_field) = timestamp_field {
-        remove_redundant_timestamp_range(search_request, split, timestamp_field);
+}
+
+/// Returns true if the request asks for any matching documents, in doc ID order, without
+/// counting them all.
+///
+/// Such requests are served by collecting the first matching documents of the split and stopping
+/// right there, rather than going through all the matching documents.
+fn is_doc_order_early_termination_request(search_request: &SearchRequest) -> bool {
+    let [sort_field] = &search_request.sort_fields[..] else {
+        return false;
+    };
+    search_request.max_hits > 0
+        && search_request.count_hits() == CountHits::Underestimate
+        && search_request.aggregation_request.is_none()
+        && search_request.search_after.is_none()
+        && sort_field.field_name == "_doc"
+        && sort_field.sort_order() == SortOrder::Asc
+}
+
+/// Collects the `num_hits` first documents matching the query, in doc ID order, without scoring
+/// them.
+///
+/// Segments are visited in order, and the iteration stops as soon as `num_hits` documents are
+/// found. The reported number of hits is therefore an underestimate.
+fn collect_first_docs_in_doc_order(
+    searcher: &Searcher,
+    query: &dyn Query,
+    split_id: String,
+    num_hits: usize,
+) -> tantivy::Result<LeafSearchResponse> {
+    let weight = query.weight(EnableScoring::disabled_from_searcher(searcher))?;
+    let mut partial_hits = Vec::with_capacity(num_hits);
+
+    for (segment_ord, segment_reader) in searcher.segment_readers().iter().enumerate() {
+        if partial_hits.len() >= num_hits {
+            break;
+        }
+        let alive_bitset_opt = segment_reader.alive_bitset();
+        let mut scorer = weight.scorer(segment_reader, 1.0)?;
+        let mut doc_id = scorer.doc();
+
+        while doc_id != TERMINATED && partial_hits.len() < num_hits {
+            if alive_bitset_opt.map_or(true, |alive_bitset| alive_bitset.is_alive(doc_id)) {
+                partial_hits.push(PartialHit {
+                    sort_value: None,
+                    sort_value2: None,
+                    split_id: split_id.clone(),
+                    segment_ord: segment_ord as u32,
+                    doc_id,
+                });
+            }
+            doc_id = scorer.advance();
+        }
     }
+    Ok(LeafSearchResponse {
+        num_hits: partial_hits.len() as u64,
+        partial_hits,
+        num_attempted_splits: 1,
+        ..Default::default()
+    })
+}
+
+/// Projected warmup of a leaf search, as returned by [`leaf_search_explain`].
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct LeafSearchPlan {
+    pub splits: Vec<SplitWarmupPlan>,
 }
 
-// equivalent to Bound::map, which is unstable
-pub fn map_bound<T, U>(bound: Bound<T>, f: impl FnOnce(T) -> U) -> Bound<U> {
-    use Bound::*;
-    match bound {
-        Unbounded => Unbounded,
-        Included(x) => Included(f(x)),
-        Excluded(x) => Excluded(f(x)),
+/// Projected warmup of a single split.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct SplitWarmupPlan {
+    pub split_id: String,
+    /// Sum of the byte counts of `fields`.
+    pub warmup_num_bytes: u64,
+    /// Fields touched by the warmup, sorted by name.
+    pub fields: Vec<FieldWarmupPlan>,
+}
+
+/// Projected warmup of a single field of a split.
+///
+/// Byte counts are only reported for the reads whose size is known from the split footer.
+/// Postings are read lazily from the term dictionary, so only the number of terms and term
+/// ranges to warm up is reported for them.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct FieldWarmupPlan {
+    pub field_name: String,
+    pub term_dict_num_bytes: u64,
+    pub fast_field_num_bytes: u64,
+    pub fieldnorm_num_bytes: u64,
+    /// Whether the posting lists of the field are loaded in their entirety, along with its term
+    /// dictionary.
+    pub full_postings: bool,
+    pub num_terms: usize,
+    pub num_term_ranges: usize,
+}
+
+impl FieldWarmupPlan {
+    fn num_bytes(&self) -> u64 {
+        self.term_dict_num_bytes + self.fast_field_num_bytes + self.fieldnorm_num_bytes
     }
 }
 
-// returns the max of left and right, that isn't unbounded. Useful for making
-// the intersection of lower bound of ranges
-fn max_bound<T: Ord + Copy>(left: Bound<T>, right: Bound<T>) -> Bound<T> {
-    use Bound::*;
-    match (left, right) {
-        (Unbounded, right) => right,
-        (left, Unbounded) => left,
-        (Included(left), Included(right)) => Included(left.max(right)),
-        (Excluded(left), Excluded(right)) => Excluded(left.max(right)),
-        (excluded_total @ Excluded(excluded), included_total @ Included(included)) => {
-            if included > excluded {
-                included_total
-            } else {
-                excluded_total
-            }
+/// Reports what a leaf search would warm up on each split, without running it.
+///
+/// Splits are opened and the query is built exactly as in `leaf_search`, but we stop right
+/// before the warmup: no posting list, fast field or term dictionary is downloaded.
+pub async fn leaf_search_explain(
+    searcher_context: &SearcherContext,
+    search_request: &SearchRequest,
+    storage: Arc<dyn Storage>,
+    splits: Vec<SplitIdAndFooterOffsets>,
+    doc_mapper: Arc<dyn DocMapper>,
+) -> crate::Result<LeafSearchPlan> {
+    let aggregation_opt = parse_aggregation_request(search_request)?;
+    // Explaining a split opens it, so the number of splits explained concurrently is bounded like
+    // for split searches.
+    let max_num_concurrent_split_searches = searcher_context
+        .searcher_config
+        .max_num_concurrent_split_searches;
+    let splits: Vec<SplitWarmupPlan> = futures::stream::iter(splits)
+        .map(|split| {
+            explain_single_split(
+                searcher_context,
+                search_request.clone(),
+                storage.clone(),
+                split,
+                doc_mapper.clone(),
+                aggregation_opt.clone(),
+            )
+        })
+        .buffered(max_num_concurrent_split_searches)
+        .try_collect()
+        .await?;
+    Ok(LeafSearchPlan { splits })
+}
+
+async fn explain_single_split(
+    searcher_context: &SearcherContext,
+    mut search_request: SearchRequest,
+    storage: Arc<dyn Storage>,
+    split: SplitIdAndFooterOffsets,
+    doc_mapper: Arc<dyn DocMapper>,
+    aggregation_opt: Option<Arc<QuickwitAggregations>>,
+) -> crate::Result<SplitWarmupPlan> {
+    rewrite_request(
+        &mut search_request,
+        &split,
+        doc_mapper.timestamp_field_name(),
+        aggregation_opt.as_deref(),
+    );
+    let index = open_index_with_caches(
+        searcher_context,
+        storage,
+        &split,
+        Some(doc_mapper.tokenizer_manager()),
+        true,
+        split_cache_priority(&search_request),
+    )
+    .await?;
+    let split_schema = index.schema();
+    let query_ast: QueryAst = serde_json::from_str(search_request.query_ast.as_str())
+        .map_err(|err| SearchError::InvalidQuery(err.to_string()))?;
+    let (_query, query_warmup_info) = doc_mapper.query(split_schema, &query_ast, false)?;
+    let mut warmup_infos = vec![query_warmup_info];
+    if !is_count_only_request(&search_request) {
+        let quickwit_collector = make_collector_for_split(
+            split.split_id.clone(),
+            &search_request,
+            aggregation_opt,
+            searcher_context.get_aggregation_limits(),
+        );
+        warmup_infos.push(quickwit_collector.warmup_info());
+    }
+    let warmup_info = WarmupInfo::merge_all(warmup_infos);
+    let reader = index
+        .reader_builder()
+        .reload_policy(ReloadPolicy::Manual)
+        .try_into()?;
+    let searcher = reader.searcher();
+    let fields = plan_field_warmups(&searcher, &warmup_info).await?;
+    let warmup_num_bytes = fields.iter().map(FieldWarmupPlan::num_bytes).sum();
+    Ok(SplitWarmupPlan {
+        split_id: split.split_id,
+        warmup_num_bytes,
+        fields,
+    })
+}
+
+async fn plan_field_warmups(
+    searcher: &Searcher,
+    warmup_info: &WarmupInfo,
+) -> anyhow::Result<Vec<FieldWarmupPlan>> {
+    let schema = searcher.schema();
+    let mut field_plans: BTreeMap<String, FieldWarmupPlan> = BTreeMap::new();
+    for field in &warmup_info.term_dict_fields {
+        let plan = field_plan(&mut field_plans, schema.get_field_name(*field));
+        plan.full_postings = true;
+        for segment_reader in searcher.segment_readers() {
+            let inverted_index = segment_reader.inverted_index(*field)?;
+            plan.term_dict_num_bytes +=
+                inverted_index.terms().file_slice_for_range(.., None).len() as u64;
         }
-        (included_total @ Included(included), excluded_total @ Excluded(excluded)) => {
-            if included > excluded {
-                included_total
-            } else {
-                excluded_total
+    }
+    for (field, terms) in &warmup_info.terms_grouped_by_field {
+        field_plan(&mut field_plans, schema.get_field_name(*field)).num_terms += terms.len();
+    }
+    for (field, term_ranges) in &warmup_info.term_ranges_grouped_by_field {
+        field_plan(&mut field_plans, schema.get_field_name(*field)).num_term_ranges +=
+            term_ranges.len();
+    }
+    for fast_field_name in &warmup_info.fast_field_names {
+        let plan = field_plan(&mut field_plans, fast_field_name);
+        for segment_reader in searcher.segment_readers() {
+            let columns = segment_reader
+                .fast_fields()
+                .list_dynamic_column_handles(fast_field_name)
+                .await?;
+            plan.fast_field_num_bytes += columns
+                .iter()
+                .map(|column| column.file_slice().len() as u64)
+                .sum::<u64>();
+        }
+    }
+    for field in warmup_info.fields_requiring_field_norms(schema) {
+        let field_name = schema.get_field_name(field);
+        for segment_reader in searcher.segment_readers() {
+            let fieldnorm_readers = segment_reader.fieldnorms_readers();
+            if let Some(file_slice) = fieldnorm_readers.get_inner_file().open_read(field) {
+                field_plan(&mut field_plans, field_name).fieldnorm_num_bytes +=
+                    file_slice.len() as u64;
             }
         }
     }
+    Ok(field_plans.into_values().collect())
 }
 
-// returns the min of left and right, that isn't unbounded. Useful for making
-// the intersection of upper bound of ranges
-fn min_bound<T: Ord + Copy>(left: Bound<T>, right: Bound<T>) -> Bound<T> {
-    use Bound::*;
-    match (left, right) {
-        (Unbounded, right) => right,
-        (left, Unbounded) => left,
-        (Included(left), Included(right)) => Included(left.min(right)),
-        (Excluded(left), Excluded(right)) => Excluded(left.min(right)),
-        (excluded_total @ Excluded(excluded), included_total @ Included(included)) => {
-            if included < excluded {
-                included_total
-            } else {
-                excluded_total
-            }
+fn field_plan<'a>(
+    field_plans: &'a mut BTreeMap<String, FieldWarmupPlan>,
+    field_name: &str,
+) -> &'a mut FieldWarmupPlan {
+    field_plans
+        .entry(field_name.to_string())
+        .or_insert_with(|| FieldWarmupPlan {
+            field_name: field_name.to_string(),
+            ..Default::default()
+        })
+}
+
+/// Returns the response of a split skipped because none of its documents can match the request.
+fn skipped_split_response() -> LeafSearchResponse {
+    LeafSearchResponse {
+        num_attempted_splits: 1,
+        ..Default::default()
+    }
+}
+
+/// Returns false if, judging from the split metadata alone, no document of the split can match
+/// the request, so that the split is skipped without fetching its footer.
+///
+/// This is the case when the time range of the request does not overlap the one of the split, or
+/// when the query cannot match any document whatever the fields of the split. The number of
+/// documents of the split is not used: nodes predating it send 0.
+fn split_metadata_may_match(
+    split: &SplitIdAndFooterOffsets,
+    search_request: &SearchRequest,
+    query_ast: &QueryAst,
+) -> bool {
+    // Split timestamps are truncated to the second: the split may contain documents up to the
+    // very end of its end second.
+    if let (Some(start_timestamp), Some(split_timestamp_end)) =
+        (search_request.start_timestamp, split.timestamp_end)
+    {
+        if start_timestamp > split_timestamp_end {
+            return false;
         }
-        (included_total @ Included(included), excluded_total @ Excluded(excluded)) => {
-            if included < excluded {
-                included_total
-            } else {
-                excluded_total
+    }
+    if let (Some(end_timestamp_exclusive), Some(split_timestamp_start)) = (
+        search_request.end_timestamp_exclusive(),
+        split.timestamp_start,
+    ) {
+        if end_timestamp_exclusive <= split_timestamp_start {
+            return false;
+        }
+    }
+    query_may_match(query_ast, &|_field_name| true)
+}
+
+/// Returns false if no document of a split with the given schema can match the query, because
+/// the split has none of the fields the query requires.
+///
+/// This errs on the side of caution: the fields of splits with a dynamic field, or nested in a
+/// JSON field, are considered present.
+fn query_may_match_split(query_ast: &QueryAst, split_schema: &Schema) -> bool {
+    if split_schema.get_field(DYNAMIC_FIELD_NAME).is_ok() {
+        return true;
+    }
+    query_may_match(query_ast, &|field_name| {
+        split_schema.find_field(field_name).is_some()
+    })
+}
+
+fn query_may_match(query_ast: &QueryAst, has_field: &dyn Fn(&str) -> bool) -> bool {
+    match query_ast {
+        QueryAst::Bool(bool_query) => {
+            let mut required_clauses = bool_query.must.iter().chain(&bool_query.filter);
+            if !required_clauses.all(|clause| query_may_match(clause, has_field)) {
+                return false;
+            }
+            // Without required clauses, at least one of the optional clauses has to match.
+            if bool_query.must.is_empty()
+                && bool_query.filter.is_empty()
+                && !bool_query.should.is_empty()
+            {
+                return bool_query
+                    .should
+                    .iter()
+                    .any(|clause| query_may_match(clause, has_field));
             }
+            true
+        }
+        QueryAst::Term(term_query) => has_field(&term_query.field),
+        QueryAst::TermSet(term_set_query) => term_set_query
+            .terms_per_field
+            .keys()
+            .any(|field_name| has_field(field_name)),
+        QueryAst::FieldPresence(field_presence_query) => has_field(&field_presence_query.field),
+        QueryAst::FullText(full_text_query) => {
+            // Queries without any term once tokenized may match all the documents.
+            full_text_query.params.zero_terms_query == MatchAllOrNone::MatchAll
+                || has_field(&full_text_query.field)
+        }
+        QueryAst::PhrasePrefix(phrase_prefix_query) => has_field(&phrase_prefix_query.field),
+        QueryAst::Range(range_query) => has_field(&range_query.field),
+        QueryAst::Wildcard(wildcard_query) => has_field(&wildcard_query.field),
+        QueryAst::Boost { underlying, .. } => query_may_match(underlying, has_field),
+        QueryAst::MatchNone => false,
+        QueryAst::MatchAll | QueryAst::UserInput(_) => true,
+    }
+}
+
+/// Returns true if the request only needs the number of documents matching the query.
+///
+/// Such requests don't need a collector: we can count the matching documents directly and skip
+/// the fast field warmup altogether.
+fn is_count_only_request(search_request: &SearchRequest) -> bool {
+    search_request.max_hits == 0
+        && search_request.count_hits() == CountHits::CountAll
+        && search_request.aggregation_request.is_none()
+        && search_request.sort_fields.is_empty()
+        && search_request.search_after.is_none()
+}
+
+/// Rewrite a request removing parts which incure additional download or computation with no
+/// effect.
+///
+/// This include things such as sorting result by a field or _score when no document is requested,
+/// or applying date range when the range covers the entire split.
+fn rewrite_request(
+    search_request: &mut SearchRequest,
+    split: &SplitIdAndFooterOffsets,
+    timestamp_field: Option<&str>,
+    aggregation_opt: Option<&QuickwitAggregations>,
+) {
+    if search_request.max_hits == 0 {
+        if let Some(QuickwitAggregations::FindTraceIdsAggregation(_)) = aggregation_opt {
+            remove_hits_from_trace_ids_request(search_request);
         }
+        search_request.sort_fields = Vec::new();
     }
+    if let Some(timestamp_field) = timestamp_field {
+        remove_redundant_timestamp_range(search_request, split, timestamp_field);
+    }
+}
+
+/// Clears everything related to fetching hits from a trace IDs aggregation request that does not
+/// ask for any, so that the requests only differing by those share cache entries.
+fn remove_hits_from_trace_ids_request(search_request: &mut SearchRequest) {
+    search_request.start_offset = 0;
+    search_request.search_after = None;
+    search_request.snippet_fields.clear();
 }
 
 /// remove timestamp range that would be present both in QueryAst and SearchRequest
@@ -494,72 +1547,32 @@ fn remove_redundant_timestamp_range(
         .map(Bound::Included)
         .unwrap_or(Bound::Unbounded);
     let end_timestamp = search_request
-        .end_timestamp
+        .end_timestamp_exclusive()
         .map(DateTime::from_timestamp_secs)
         .map(Bound::Excluded)
         .unwrap_or(Bound::Unbounded);
+    let now_opt = search_request
+        .now_timestamp_nanos
+        .map(DateTime::from_timestamp_nanos);
 
     let mut visitor = RemoveTimestampRange {
         timestamp_field,
         start_timestamp,
         end_timestamp,
+        now_opt,
     };
     let mut new_ast = visitor
         .transform(query_ast)
         .expect("can't fail unwrapping Infallible")
         .unwrap_or(QueryAst::MatchAll);
 
-    let final_start_timestamp = match (
-        visitor.start_timestamp,
-        split.timestamp_start.map(DateTime::from_timestamp_secs),
-    ) {
-        (Bound::Included(query_ts), Some(split_ts)) => {
-            if query_ts > split_ts {
-                Bound::Included(query_ts)
-            } else {
-                Bound::Unbounded
-            }
-        }
-        (Bound::Excluded(query_ts), Some(split_ts)) => {
-            if query_ts >= split_ts {
-                Bound::Excluded(query_ts)
-            } else {
-                Bound::Unbounded
-            }
-        }
-        (Bound::Unbounded, Some(_)) => Bound::Unbounded,
-        (timestamp, None) => timestamp,
-    };
-    let final_end_timestamp = match (
-        visitor.end_timestamp,
-        split.timestamp_end.map(DateTime::from_timestamp_secs),
-    ) {
-        (Bound::Included(query_ts), Some(split_ts)) => {
-            if query_ts < split_ts {
-                Bound::Included(query_ts)
-            } else {
-                Bound::Unbounded
-            }
-        }
-        (Bound::Excluded(query_ts), Some(split_ts)) => {
-            if query_ts <= split_ts {
-                Bound::Excluded(query_ts)
-            } else {
-                Bound::Unbounded
-            }
-        }
-        (Bound::Unbounded, Some(_)) => Bound::Unbounded,
-        (timestamp, None) => timestamp,
-    };
+    let (final_start_timestamp, final_end_timestamp) =
+        split.clamp_timestamp_bounds(visitor.start_timestamp, visitor.end_timestamp);
     if final_start_timestamp != Bound::Unbounded || final_end_timestamp != Bound::Unbounded {
         let range = RangeQuery {
             field: timestamp_field.to_string(),
-            lower_bound: map_bound(final_start_timestamp, |bound| {
-                bound.into_timestamp_nanos().into()
-            }),
-            upper_bound: map_bound(final_end_timestamp, |bound| {
-                bound.into_timestamp_nanos().into()
-            }),
+            lower_bound: timestamp_bound_to_query_literal(final_start_timestamp),
+            upper_bound: timestamp_bound_to_query_literal(final_end_timestamp),
         };
         new_ast = if let QueryAst::Bool(mut bool_query) = new_ast {
             if bool_query.must.is_empty()
@@ -591,6 +1604,13 @@ fn remove_redundant_timestamp_range(
     search_request.query_ast = serde_json::to_string(&new_ast).unwrap();
     search_request.start_timestamp = None;
     search_request.end_timestamp = None;
+    search_request.end_timestamp_inclusive = false;
+}
+
+/// Converts a timestamp bound into the bound of a timestamp `RangeQuery`, expressed in
+/// nanoseconds.
+fn timestamp_bound_to_query_literal(bound: Bound<DateTime>) -> Bound<quickwit_query::JsonLiteral> {
+    map_bound(bound, |timestamp| timestamp.into_timestamp_nanos().into())
 }
 
 /// Remove all `must` and `filter timestamp ranges, and summarize them
@@ -599,16 +1619,29 @@ struct RemoveTimestampRange<'a> {
     timestamp_field: &'a str,
     start_timestamp: Bound<DateTime>,
     end_timestamp: Bound<DateTime>,
+    /// Anchor of relative time bounds such as `now-1h`, captured by the root.
+    now_opt: Option<DateTime>,
 }
 
 impl<'a> RemoveTimestampRange<'a> {
+    fn interpret_time_bound(&self, time_bound: &quickwit_query::JsonLiteral) -> Option<DateTime> {
+        use quickwit_query::InterpretUserInput;
+        if let (Some(now), quickwit_query::JsonLiteral::String(time_bound_str)) =
+            (self.now_opt, time_bound)
+        {
+            if let Ok(date_time) = quickwit_datetime::parse_date_math(time_bound_str, now) {
+                return Some(date_time);
+            }
+        }
+        DateTime::interpret_json(time_bound)
+    }
+
     fn update_start_timestamp(
         &mut self,
         lower_bound: &quickwit_query::JsonLiteral,
         included: bool,
     ) {
-        use quickwit_query::InterpretUserInput;
-        let Some(lower_bound) = DateTime::interpret_json(lower_bound) else {
+        let Some(lower_bound) = self.interpret_time_bound(lower_bound) else {
             // we shouldn't be able to get here, we would have errored much earlier in root search
             warn!("unparseable time bound in leaf search: {lower_bound:?}");
             return;
@@ -623,8 +1656,7 @@ impl<'a> RemoveTimestampRange<'a> {
     }
 
     fn update_end_timestamp(&mut self, upper_bound: &quickwit_query::JsonLiteral, included: bool) {
-        use quickwit_query::InterpretUserInput;
-        let Some(upper_bound) = DateTime::interpret_json(upper_bound) else {
+        let Some(upper_bound) = self.interpret_time_bound(upper_bound) else {
             // we shouldn't be able to get here, we would have errored much earlier in root search
             warn!("unparseable time bound in leaf search: {upper_bound:?}");
             return;
@@ -687,8 +1719,15 @@ impl<'a> QueryAstTransformer for RemoveTimestampRange<'a> {
     }
 
     fn transform_term(&mut self, term_query: TermQuery) -> Result<Option<QueryAst>, Self::Err> {
-        // TODO we could remove query bounds, this point query surely is more precise, and it
-        // doesn't require loading a fastfield
+        if term_query.field == self.timestamp_field {
+            // a point query is the tightest bound we can get. We still keep the term query, the
+            // bounds are only used to drop what the split can't possibly contain.
+            let json_term = quickwit_query::JsonLiteral::String(term_query.value.clone());
+            if let Some(timestamp) = self.interpret_time_bound(&json_term) {
+                self.start_timestamp = max_bound(self.start_timestamp, Bound::Included(timestamp));
+                self.end_timestamp = min_bound(self.end_timestamp, Bound::Included(timestamp));
+            }
+        }
         Ok(Some(QueryAst::Term(term_query)))
     }
 }
@@ -696,21 +1735,100 @@ impl<'a> QueryAstTransformer for RemoveTimestampRange<'a> {
 pub(crate) fn rewrite_start_end_time_bounds(
     start_timestamp_opt: &mut Option<i64>,
     end_timestamp_opt: &mut Option<i64>,
+    end_timestamp_inclusive: bool,
     split: &SplitIdAndFooterOffsets,
 ) {
-    if let (Some(split_start), Some(split_end)) = (split.timestamp_start, split.timestamp_end) {
-        if let Some(start_timestamp) = start_timestamp_opt {
-            // both starts are inclusive
-            if *start_timestamp <= split_start {
-                *start_timestamp_opt = None;
-            }
+    let date_time_from_secs = |timestamp_secs: i64| {
+        DateTime::from_timestamp_nanos(timestamp_secs.saturating_mul(1_000_000_000))
+    };
+
+    let start_timestamp = map_bound(
+        start_timestamp_opt.map_or(Bound::Unbounded, Bound::Included),
+        date_time_from_secs,
+    );
+    // an inclusive end covers its entire last second
+    let end_timestamp = match *end_timestamp_opt {
+        Some(end_timestamp) if end_timestamp_inclusive => {
+            Bound::Excluded(date_time_from_secs(end_timestamp.saturating_add(1)))
         }
-        if let Some(end_timestamp) = end_timestamp_opt {
-            // search end is exclusive, split end is inclusive
-            if *end_timestamp > split_end {
-                *end_timestamp_opt = None;
+        Some(end_timestamp) => Bound::Excluded(date_time_from_secs(end_timestamp)),
+        None => Bound::Unbounded,
+    };
+    let (start_timestamp, end_timestamp) =
+        split.clamp_timestamp_bounds(start_timestamp, end_timestamp);
+
+    if start_timestamp == Bound::Unbounded {
+        *start_timestamp_opt = None;
+    }
+    if end_timestamp == Bound::Unbounded {
+        *end_timestamp_opt = None;
+    }
+}
+
+/// Intersects time bounds with the time range of a split.
+trait ClampTimestampBounds {
+    /// Returns the bounds that still filter out documents of the split: a bound is replaced with
+    /// `Unbounded` when the whole split lies on its inner side. Bounds are left untouched if the
+    /// split has no time range.
+    fn clamp_timestamp_bounds(
+        &self,
+        start: Bound<DateTime>,
+        end: Bound<DateTime>,
+    ) -> (Bound<DateTime>, Bound<DateTime>);
+}
+
+impl ClampTimestampBounds for SplitIdAndFooterOffsets {
+    fn clamp_timestamp_bounds(
+        &self,
+        start: Bound<DateTime>,
+        end: Bound<DateTime>,
+    ) -> (Bound<DateTime>, Bound<DateTime>) {
+        // split bounds are inclusive, down to the nanosecond
+        let clamped_start = match (
+            start,
+            self.timestamp_start_nanos()
+                .map(DateTime::from_timestamp_nanos),
+        ) {
+            (Bound::Included(query_ts), Some(split_ts)) => {
+                if query_ts > split_ts {
+                    Bound::Included(query_ts)
+                } else {
+                    Bound::Unbounded
+                }
             }
-        }
+            (Bound::Excluded(query_ts), Some(split_ts)) => {
+                if query_ts >= split_ts {
+                    Bound::Excluded(query_ts)
+                } else {
+                    Bound::Unbounded
+                }
+            }
+            (Bound::Unbounded, Some(_)) => Bound::Unbounded,
+            (timestamp, None) => timestamp,
+        };
+        let clamped_end = match (
+            end,
+            self.timestamp_end_nanos()
+                .map(DateTime::from_timestamp_nanos),
+        ) {
+            (Bound::Included(query_ts), Some(split_ts)) => {
+                if query_ts < split_ts {
+                    Bound::Included(query_ts)
+                } else {
+                    Bound::Unbounded
+                }
+            }
+            (Bound::Excluded(query_ts), Some(split_ts)) => {
+                if query_ts <= split_ts {
+                    Bound::Excluded(query_ts)
+                } else {
+                    Bound::Unbounded
+                }
+            }
+            (Bound::Unbounded, Some(_)) => Bound::Unbounded,
+            (timestamp, None) => timestamp,
+        };
+        (clamped_start, clamped_end)
     }
 }
 
@@ -718,9 +1836,14 @@ pub(crate) fn rewrite_start_end_time_bounds(
 enum CanSplitDoBetter {
     Uninformative,
     SplitIdHigher(Option<String>),
+    // Timestamps are expressed in nanoseconds, so that pruning remains exact for sub-second
+    // sort values.
     SplitTimestampHigher(Option<i64>),
     SplitTimestampLower(Option<i64>),
     FindTraceIdsAggregation(Option<i64>),
+    /// Date histogram aggregation on the timestamp field. Splits can't be skipped, but processing
+    /// them in chronological order makes it possible to emit completed time buckets progressively.
+    DateHistogramAggregation,
 }
 
 impl CanSplitDoBetter {
@@ -728,15 +1851,24 @@ impl CanSplitDoBetter {
     fn from_request(request: &SearchRequest, timestamp_field_name: Option<&str>) -> Self {
         if request.max_hits == 0 {
             if let Some(aggregation) = &request.aggregation_request {
-                if let Ok(crate::QuickwitAggregations::FindTraceIdsAggregation(
-                    find_trace_aggregation,
-                )) = serde_json::from_str(aggregation)
-                {
-                    if Some(find_trace_aggregation.span_timestamp_field_name.as_str())
-                        == timestamp_field_name
-                    {
-                        return CanSplitDoBetter::FindTraceIdsAggregation(None);
+                match serde_json::from_str(aggregation) {
+                    Ok(crate::QuickwitAggregations::FindTraceIdsAggregation(
+                        find_trace_aggregation,
+                    )) => {
+                        if Some(find_trace_aggregation.span_timestamp_field_name.as_str())
+                            == timestamp_field_name
+                        {
+                            return CanSplitDoBetter::FindTraceIdsAggregation(None);
+                        }
                     }
+                    Ok(crate::QuickwitAggregations::TantivyAggregations(aggregations)) => {
+                        if let Some(timestamp_field_name) = timestamp_field_name {
+                            if is_date_histogram_on_field(&aggregations, timestamp_field_name) {
+                                return CanSplitDoBetter::DateHistogramAggregation;
+                            }
+                        }
+                    }
+                    Err(_) => (),
                 }
             }
         }
@@ -760,6 +1892,97 @@ impl CanSplitDoBetter {
         }
     }
 
+    /// Enables timestamp pruning on `default_timestamp_field_name_opt` for a request sorted on
+    /// that field, although the doc mapper has no timestamp field.
+    ///
+    /// To stay on the safe side, the fallback only engages if the field is a fast date field of
+    /// the schema and all the splits have timestamp bounds. Otherwise, `self` is returned as is.
+    fn fallback_to_default_timestamp_field(
+        self,
+        request: &SearchRequest,
+        default_timestamp_field_name_opt: Option<&str>,
+        schema: &Schema,
+        splits: &[SplitIdAndFooterOffsets],
+    ) -> Self {
+        if !matches!(self, CanSplitDoBetter::Uninformative) {
+            return self;
+        }
+        let Some(default_timestamp_field_name) = default_timestamp_field_name_opt else {
+            return self;
+        };
+        let is_fast_date_field = schema
+            .get_field(default_timestamp_field_name)
+            .map(|field| {
+                let field_entry = schema.get_field_entry(field);
+                matches!(field_entry.field_type(), FieldType::Date(_)) && field_entry.is_fast()
+            })
+            .unwrap_or(false);
+        if !is_fast_date_field {
+            return self;
+        }
+        if splits.is_empty()
+            || splits
+                .iter()
+                .any(|split| split.timestamp_start.is_none() || split.timestamp_end.is_none())
+        {
+            return self;
+        }
+        match CanSplitDoBetter::from_request(request, Some(default_timestamp_field_name)) {
+            split_filter @ (CanSplitDoBetter::SplitTimestampHigher(_)
+            | CanSplitDoBetter::SplitTimestampLower(_)) => {
+                debug!(
+                    timestamp_field = default_timestamp_field_name,
+                    "pruning splits on the default timestamp field"
+                );
+                split_filter
+            }
+            _ => self,
+        }
+    }
+
+    /// Falls back to `Uninformative` if splits get ordered and pruned on their timestamp bounds
+    /// but some splits have none, e.g. the splits of an index created before these bounds were
+    /// recorded. Such splits would be processed last and pruned, as if they held the worst
+    /// documents.
+    ///
+    /// A warning is logged the first time this happens for a given index.
+    fn check_split_timestamp_bounds(
+        self,
+        splits: &[SplitIdAndFooterOffsets],
+        index_uri: &Uri,
+    ) -> Self {
+        if !matches!(
+            self,
+            CanSplitDoBetter::SplitTimestampHigher(_)
+                | CanSplitDoBetter::SplitTimestampLower(_)
+                | CanSplitDoBetter::FindTraceIdsAggregation(_)
+        ) {
+            return self;
+        }
+        let num_splits_without_timestamp_bounds = splits
+            .iter()
+            .filter(|split| split.timestamp_start.is_none() || split.timestamp_end.is_none())
+            .count();
+        if num_splits_without_timestamp_bounds == 0 {
+            return self;
+        }
+        static WARNED_INDEX_URIS: Lazy<Mutex<HashSet<String>>> = Lazy::new(Default::default);
+
+        if WARNED_INDEX_URIS
+            .lock()
+            .unwrap()
+            .insert(index_uri.to_string())
+        {
+            warn!(
+                index_uri=%index_uri,
+                num_splits_without_timestamp_bounds,
+                "some splits have no timestamp bounds, split pruning on the timestamp field is \
+                 disabled for this index"
+            );
+        }
+        CanSplitDoBetter::Uninformative
+    }
+
     /// Optimize the order in which splits will get processed based on how it can skip the most
     /// splits.
     ///
@@ -770,18 +1993,31 @@ impl CanSplitDoBetter {
     /// In the future, as split get more metadata per column, we may be able to do this more than
     /// just for timestamp and "unsorted" request.
     fn optimize_split_order(&self, splits: &mut [SplitIdAndFooterOffsets]) {
+        // Ties are broken on the split id, so that the order, and therefore which of the tied
+        // hits make it to the top K, is reproducible.
         match self {
             CanSplitDoBetter::SplitIdHigher(_) => {
                 splits.sort_unstable_by(|a, b| b.split_id.cmp(&a.split_id))
             }
             CanSplitDoBetter::SplitTimestampHigher(_)
-            | CanSplitDoBetter::FindTraceIdsAggregation(_) => {
-                splits.sort_unstable_by_key(|split| std::cmp::Reverse(split.timestamp_end()))
-            }
-            CanSplitDoBetter::SplitTimestampLower(_) => {
-                splits.sort_unstable_by_key(|split| split.timestamp_start())
-            }
-            CanSplitDoBetter::Uninformative => (),
+            | CanSplitDoBetter::FindTraceIdsAggregation(_) => splits.sort_unstable_by(|a, b| {
+                b.timestamp_end_nanos()
+                    .cmp(&a.timestamp_end_nanos())
+                    .then_with(|| a.split_id.cmp(&b.split_id))
+            }),
+            CanSplitDoBetter::SplitTimestampLower(_)
+            | CanSplitDoBetter::DateHistogramAggregation => splits.sort_unstable_by(|a, b| {
+                a.timestamp_start_nanos()
+                    .cmp(&b.timestamp_start_nanos())
+                    .then_with(|| a.split_id.cmp(&b.split_id))
+            }),
+            // Nothing tells us which splits hold the best documents, but bigger splits are
+            // likelier to contain matches: searching them first fills the top K sooner.
+            CanSplitDoBetter::Uninformative => splits.sort_unstable_by(|a, b| {
+                b.num_docs
+                    .cmp(&a.num_docs)
+                    .then_with(|| a.split_id.cmp(&b.split_id))
+            }),
         }
     }
 
@@ -792,10 +2028,10 @@ impl CanSplitDoBetter {
             CanSplitDoBetter::SplitIdHigher(Some(split_id)) => split.split_id >= *split_id,
             CanSplitDoBetter::SplitTimestampHigher(Some(timestamp))
             | CanSplitDoBetter::FindTraceIdsAggregation(Some(timestamp)) => {
-                split.timestamp_end() >= *timestamp
+                split.timestamp_end_nanos().unwrap_or_default() >= *timestamp
             }
             CanSplitDoBetter::SplitTimestampLower(Some(timestamp)) => {
-                split.timestamp_start() <= *timestamp
+                split.timestamp_start_nanos().unwrap_or_default() <= *timestamp
             }
             _ => true,
         }
@@ -806,415 +2042,3113 @@ impl CanSplitDoBetter {
     /// funciton if you have at least max_hits documents already.
     fn record_new_worst_hit(&mut self, hit: &PartialHit) {
         match self {
-            CanSplitDoBetter::Uninformative => (),
+            CanSplitDoBetter::Uninformative | CanSplitDoBetter::DateHistogramAggregation => (),
             CanSplitDoBetter::SplitIdHigher(split_id) => *split_id = Some(hit.split_id.clone()),
             CanSplitDoBetter::SplitTimestampHigher(timestamp)
-            | CanSplitDoBetter::FindTraceIdsAggregation(timestamp) => {
-                if let Some(SortValue::I64(timestamp_ns)) = hit.sort_value() {
-                    // if we get a timestamp of, says 1.5s, we need to check up to 2s to make
-                    // sure we don't throw away something like 1.2s, so we should round up while
-                    // dividing.
-                    *timestamp = Some(quickwit_common::div_ceil(timestamp_ns, 1_000_000_000));
-                }
-            }
-            CanSplitDoBetter::SplitTimestampLower(timestamp) => {
+            | CanSplitDoBetter::FindTraceIdsAggregation(timestamp)
+            | CanSplitDoBetter::SplitTimestampLower(timestamp) => {
                 if let Some(SortValue::I64(timestamp_ns)) = hit.sort_value() {
-                    // if we get a timestamp of, says 1.5s, we need to check down to 1s to make
-                    // sure we don't throw away something like 1.7s, so we should truncate,
-                    // which is the default behavior of division
-                    let timestamp_s = timestamp_ns / 1_000_000_000;
-                    *timestamp = Some(timestamp_s);
+                    *timestamp = Some(timestamp_ns);
                 }
             }
         }
     }
 }
 
-/// `leaf` step of search.
-///
-/// The leaf search collects all kind of information, and returns a set of
-/// [PartialHit](quickwit_proto::search::PartialHit) candidates. The root will be in
-/// charge to consolidate, identify the actual final top hits to display, and
-/// fetch the actual documents to convert the partial hits into actual Hits.
-#[instrument(skip_all, fields(index = ?request.index_id_patterns))]
-pub async fn leaf_search(
-    searcher_context: Arc<SearcherContext>,
-    request: Arc<SearchRequest>,
-    index_storage: Arc<dyn Storage>,
-    mut splits: Vec<SplitIdAndFooterOffsets>,
-    doc_mapper: Arc<dyn DocMapper>,
-) -> Result<LeafSearchResponse, SearchError> {
-    info!(splits_num = splits.len(), split_offsets = ?PrettySample::new(&splits, 5));
-
-    let split_filter = CanSplitDoBetter::from_request(&request, doc_mapper.timestamp_field_name());
-    split_filter.optimize_split_order(&mut splits);
+/// Split searches currently running, which get aborted as soon as they can no longer give
+/// documents better than the ones already collected.
+#[derive(Default)]
+struct InFlightSplitSearches {
+    split_searches: Mutex<HashMap<String, (SplitIdAndFooterOffsets, AbortHandle)>>,
+    // Splits whose search was aborted by `abort_if_cannot_be_better`. An aborted task may still
+    // run to completion, so this only tells why a task turned out to be cancelled.
+    pruned_split_ids: Mutex<HashSet<String>>,
+}
 
-    // if client wants full count, or we are doing an aggregation, we want to run every splits.
-    // However if the aggregation is the tracing aggregation, we don't actually need all splits.
-    let run_all_splits = request.count_hits() == CountHits::CountAll
-        || (request.aggregation_request.is_some()
-            && !matches!(split_filter, CanSplitDoBetter::FindTraceIdsAggregation(_)));
+impl InFlightSplitSearches {
+    /// Spawns the search of `split` with `spawn_split_search`, and registers it.
+    ///
+    /// A split search unregisters itself once done: it is registered before it gets a chance to
+    /// run, so that it does not leave a stale entry behind.
+    fn spawn_registered(
+        &self,
+        split: SplitIdAndFooterOffsets,
+        spawn_split_search: impl FnOnce() -> JoinHandle<()>,
+    ) -> JoinHandle<()> {
+        let mut split_searches = self.split_searches.lock().unwrap();
+        let join_handle = spawn_split_search();
+        split_searches.insert(split.split_id.clone(), (split, join_handle.abort_handle()));
+        join_handle
+    }
 
-    // Creates a collector which merges responses into one
-    let merge_collector =
-        make_merge_collector(&request, &searcher_context.get_aggregation_limits())?;
-    let incremental_merge_collector = IncrementalCollector::new(merge_collector);
+    fn unregister(&self, split_id: &str) {
+        self.split_searches.lock().unwrap().remove(split_id);
+    }
 
-    let split_filter = Arc::new(Mutex::new(split_filter));
-    let incremental_merge_collector = Arc::new(Mutex::new(incremental_merge_collector));
+    /// Aborts the split searches that can no longer do better according to `split_filter`, and
+    /// returns how many were aborted.
+    fn abort_if_cannot_be_better(&self, split_filter: &CanSplitDoBetter) -> usize {
+        let mut split_searches = self.split_searches.lock().unwrap();
+        let mut pruned_split_ids = self.pruned_split_ids.lock().unwrap();
+        let num_split_searches_before = split_searches.len();
+        split_searches.retain(|split_id, (split, abort_handle)| {
+            if split_filter.can_be_better(split) {
+                return true;
+            }
+            debug!(split_id = %split_id, "aborting split search that can no longer do better");
+            abort_handle.abort();
+            pruned_split_ids.insert(split_id.clone());
+            false
+        });
+        num_split_searches_before - split_searches.len()
+    }
 
-    let mut leaf_search_single_split_futures: Vec<_> = Vec::with_capacity(splits.len());
+    /// Returns true if the search of the split was aborted because it could no longer do better.
+    fn is_pruned(&self, split_id: &str) -> bool {
+        self.pruned_split_ids.lock().unwrap().contains(split_id)
+    }
+}
 
-    for split in splits {
-        let leaf_split_search_permit = searcher_context.leaf_search_split_semaphore
-            .clone()
-            .acquire_owned()
-            .await
-            .expect("Failed to acquire permit. This should never happen! Please, report on https://github.com/quickwit-oss/quickwit/issues.");
+/// Returns true if one of the top-level aggregations is a date histogram on the given field.
+fn is_date_histogram_on_field(aggregations: &Aggregations, field_name: &str) -> bool {
+    aggregations.values().any(|aggregation| {
+        matches!(
+            &aggregation.agg,
+            AggregationVariants::DateHistogram(date_histogram) if date_histogram.field == field_name
+        )
+    })
+}
 
-        let mut request = (*request).clone();
+/// Acquires a permit from the aggregation semaphore if the request contains an aggregation.
+///
+/// Aggregations are memory-heavy and, unlike hit collection, can't be cheaply pruned. The split
+/// semaphore counts splits, not aggregation memory, so we bound the number of concurrent
+/// aggregations separately. Requests without aggregations are not throttled.
+async fn acquire_aggregation_permit<'a>(
+    searcher_context: &'a SearcherContext,
+    request: &SearchRequest,
+) -> Option<SemaphorePermit<'a>> {
+    request.aggregation_request.as_ref()?;
+    let aggregation_permit = searcher_context
+        .aggregation_semaphore
+        .acquire()
+        .await
+        .expect("the aggregation semaphore should never be closed");
+    Some(aggregation_permit)
+}
 
-        if !split_filter.lock().unwrap().can_be_better(&split) {
-            if !run_all_splits {
-                continue;
-            }
-            request.max_hits = 0;
-            request.start_offset = 0;
-            request.sort_fields.clear();
+#[derive(Clone)]
+struct ElapsedTimeSortKeyMapper;
+
+impl SortKeyMapper<SplitSearchTiming> for ElapsedTimeSortKeyMapper {
+    type Key = u64;
+
+    fn get_sort_key(&self, split_timing: &SplitSearchTiming) -> u64 {
+        split_timing.elapsed_time_micros
+    }
+}
+
+/// Keeps track of the `N` splits that took the longest to search.
+///
+/// This is much cheaper than keeping the timings of all the splits, and answers directly the
+/// question "which splits made this query slow".
+#[derive(Clone)]
+struct SlowestSplits {
+    top_k: TopK<SplitSearchTiming, u64, ElapsedTimeSortKeyMapper>,
+}
+
+impl SlowestSplits {
+    fn new(num_splits: usize) -> Self {
+        SlowestSplits {
+            top_k: TopK::new(num_splits, ElapsedTimeSortKeyMapper),
         }
+    }
 
-        leaf_search_single_split_futures.push(tokio::spawn(
-            leaf_search_single_split_wrapper(
-                request,
-                searcher_context.clone(),
-                index_storage.clone(),
-                doc_mapper.clone(),
-                split,
-                split_filter.clone(),
-                incremental_merge_collector.clone(),
-                leaf_split_search_permit,
-            )
-            .in_current_span(),
-        ));
+    fn record(&mut self, split_id: &str, elapsed: Duration) {
+        self.top_k.add_entry(SplitSearchTiming {
+            split_id: split_id.to_string(),
+            elapsed_time_micros: elapsed.as_micros() as u64,
+        });
     }
 
-    // TODO we could cancel running splits when !run_all_splits and the running split can no longer
-    // give better results after some other split answered.
-    let split_search_results: Vec<Result<(), _>> =
-        futures::future::join_all(leaf_search_single_split_futures).await;
+    /// Returns the slowest splits, sorted by decreasing search duration.
+    fn into_sorted_vec(self) -> Vec<SplitSearchTiming> {
+        self.top_k.finalize()
+    }
+}
 
-    // we can't use unwrap_or_clone because mutexes aren't Clone
-    let mut incremental_merge_collector = match Arc::try_unwrap(incremental_merge_collector) {
-        Ok(filter_merger) => filter_merger.into_inner().unwrap(),
-        Err(filter_merger) => filter_merger.lock().unwrap().clone(),
-    };
+/// Receives the best hits found so far while a leaf search is running.
+///
+/// See [`LeafSearchOptions`].
+#[derive(Clone)]
+pub struct LeafSearchProgress {
+    /// The callback is invoked every time this many splits have been searched.
+    pub num_splits_between_updates: NonZeroUsize,
+    /// Callback receiving a snapshot of the current top hits.
+    pub callback: Arc<dyn Fn(Vec<PartialHit>) + Send + Sync>,
+}
 
-    for result in split_search_results {
-        // splits that did not panic were already added to the collector
-        if let Err(e) = result {
-            incremental_merge_collector.add_failed_split(SplitSearchError {
-                // we could reasonably add a wrapper to the JoinHandle to give us the
-                // split_id anyway
-                split_id: "unknown".to_string(),
-                error: format!("{}", SearchError::from(e)),
-                retryable_error: true,
-            })
+/// Observes what a leaf search decides to do with each of its splits.
+///
+/// The methods are called from the leaf search tasks, and must return quickly. They do nothing by
+/// default.
+///
+/// See [`LeafSearchOptions`].
+pub trait SplitDecisionObserver: Send + Sync {
+    /// The split was skipped because it can't contain hits better than the ones already
+    /// collected. This is also called for a split whose running search got aborted for that
+    /// reason, unless the search completed before the abort took effect.
+    fn on_pruned(&self, _split_id: &str) {}
+
+    /// The split can't contain better hits, but is searched to count its hits or to run the
+    /// aggregation, with `max_hits` set to 0.
+    fn on_count_only(&self, _split_id: &str) {}
+
+    /// The split is searched with the original request.
+    fn on_full(&self, _split_id: &str) {}
+
+    /// The search of the split failed.
+    fn on_failed(&self, _split_id: &str, _error: &str) {}
+}
+
+/// [`SplitDecisionObserver`] ignoring all the decisions.
+pub struct NoopSplitDecisionObserver;
+
+impl SplitDecisionObserver for NoopSplitDecisionObserver {}
+
+/// Optional hooks of a leaf search. See [`leaf_search_with_options`].
+#[derive(Clone)]
+pub struct LeafSearchOptions {
+    /// Receives the best hits found so far, as splits get searched.
+    pub progress_opt: Option<LeafSearchProgress>,
+    /// Told which splits get pruned, searched, or fail.
+    pub split_decision_observer: Arc<dyn SplitDecisionObserver>,
+    /// When cancelled, no new split search is started, the in-flight ones are aborted, and the
+    /// leaf search fails with `SearchError::Cancelled`.
+    pub cancellation_token_opt: Option<CancellationToken>,
+}
+
+impl Default for LeafSearchOptions {
+    fn default() -> Self {
+        LeafSearchOptions {
+            progress_opt: None,
+            split_decision_observer: Arc::new(NoopSplitDecisionObserver),
+            cancellation_token_opt: None,
+        }
+    }
+}
+
+/// Counts the searched splits of a leaf search to report its progress periodically.
+struct LeafSearchProgressReporter {
+    progress: LeafSearchProgress,
+    num_searched_splits: AtomicUsize,
+}
+
+impl LeafSearchProgressReporter {
+    fn new(progress: LeafSearchProgress) -> Self {
+        LeafSearchProgressReporter {
+            progress,
+            num_searched_splits: AtomicUsize::new(0),
         }
     }
 
-    crate::search_thread_pool()
-        .run_cpu_intensive(|| incremental_merge_collector.finalize().map_err(Into::into))
-        .instrument(info_span!("incremental_merge_finalize"))
+    /// Records that a split was searched, and returns whether progress should be reported.
+    fn record_searched_split(&self) -> bool {
+        let num_searched_splits = self.num_searched_splits.fetch_add(1, Ordering::Relaxed) + 1;
+        num_searched_splits % self.progress.num_splits_between_updates.get() == 0
+    }
+}
+
+/// Returns a cheap upper bound of the number of documents matching the request's query in each
+/// split, in the order of `splits`.
+///
+/// Only the split footers and the term dictionary blocks of the query terms are read: posting
+/// lists are never downloaded. The bound is the sum of the document frequencies of the query
+/// terms, so it can be far off for conjunctions. Splits for which the query is not bounded by its
+/// terms, e.g. range or negated queries, or for which the estimate fails, fall back to their
+/// number of documents.
+pub async fn leaf_search_estimate(
+    searcher_context: Arc<SearcherContext>,
+    request: &SearchRequest,
+    index_storage: Arc<dyn Storage>,
+    splits: &[SplitIdAndFooterOffsets],
+    doc_mapper: Arc<dyn DocMapper>,
+) -> Vec<(String, u64)> {
+    let max_num_concurrent_estimates = searcher_context
+        .searcher_config
+        .max_num_concurrent_split_searches;
+    futures::stream::iter(splits)
+        .map(|split| {
+            let searcher_context = &searcher_context;
+            let index_storage = index_storage.clone();
+            let doc_mapper = &doc_mapper;
+            async move {
+                let num_docs_upper_bound = estimate_split_num_matching_docs(
+                    searcher_context,
+                    request,
+                    index_storage,
+                    split,
+                    doc_mapper.as_ref(),
+                )
+                .await
+                .unwrap_or_else(|error| {
+                    warn!(split_id = %split.split_id, error = ?error, "failed to estimate split");
+                    split.num_docs
+                });
+                (split.split_id.clone(), num_docs_upper_bound)
+            }
+        })
+        .buffered(max_num_concurrent_estimates)
+        .collect()
         .await
-        .context("failed to merge split search responses")?
 }
 
-#[allow(clippy::too_many_arguments)]
-async fn leaf_search_single_split_wrapper(
-    request: SearchRequest,
+async fn estimate_split_num_matching_docs(
+    searcher_context: &SearcherContext,
+    request: &SearchRequest,
+    index_storage: Arc<dyn Storage>,
+    split: &SplitIdAndFooterOffsets,
+    doc_mapper: &dyn DocMapper,
+) -> anyhow::Result<u64> {
+    let query_ast: QueryAst = serde_json::from_str(&request.query_ast)?;
+    if !split_metadata_may_match(split, request, &query_ast) {
+        return Ok(0);
+    }
+    let index = open_index_with_caches(
+        searcher_context,
+        index_storage,
+        split,
+        Some(doc_mapper.tokenizer_manager()),
+        true,
+        split_cache_priority(request),
+    )
+    .await?;
+    let split_schema = index.schema();
+    if !query_may_match_split(&query_ast, &split_schema) {
+        return Ok(0);
+    }
+    let reader = index
+        .reader_builder()
+        .reload_policy(ReloadPolicy::Manual)
+        .try_into()?;
+    let searcher = reader.searcher();
+    let num_docs = searcher.num_docs();
+
+    let is_indexed_field = |field_name: &str| {
+        split_schema
+            .find_field(field_name)
+            .map(|(field, _)| field)
+            .or_else(|| split_schema.get_field(DYNAMIC_FIELD_NAME).ok())
+            .map(|field| split_schema.get_field_entry(field).is_indexed())
+            .unwrap_or(false)
+    };
+    if !is_query_bounded_by_terms(&query_ast, &is_indexed_field) {
+        return Ok(num_docs);
+    }
+    let (_query, warmup_info) = doc_mapper.query(split_schema.clone(), &query_ast, false)?;
+    if !warmup_info.term_ranges_grouped_by_field.is_empty() {
+        return Ok(num_docs);
+    }
+    let mut num_docs_upper_bound = 0;
+
+    for (field, terms) in &warmup_info.terms_grouped_by_field {
+        for segment_reader in searcher.segment_readers() {
+            let inverted_index = segment_reader.inverted_index(*field)?;
+            for term in terms.keys() {
+                // This only reads the term dictionary block holding the term.
+                let term_info_opt = inverted_index
+                    .terms()
+                    .get_async(term.serialized_value_bytes())
+                    .await?;
+                if let Some(term_info) = term_info_opt {
+                    num_docs_upper_bound += term_info.doc_freq as u64;
+                }
+            }
+        }
+    }
+    Ok(num_docs_upper_bound.min(num_docs))
+}
+
+/// Returns true if every document matching the query contains one of its terms, so that the sum
+/// of the document frequencies of the terms is an upper bound of the number of matches.
+///
+/// `is_indexed_field` must return false for fields without an inverted index, which are queried
+/// through their fast field.
+fn is_query_bounded_by_terms(
+    query_ast: &QueryAst,
+    is_indexed_field: &dyn Fn(&str) -> bool,
+) -> bool {
+    match query_ast {
+        QueryAst::Bool(bool_query) => {
+            if !bool_query.must_not.is_empty() {
+                return false;
+            }
+            let mut clauses = bool_query
+                .must
+                .iter()
+                .chain(&bool_query.should)
+                .chain(&bool_query.filter)
+                .peekable();
+            // A boolean query without positive clauses matches all the documents.
+            clauses.peek().is_some()
+                && clauses.all(|clause| is_query_bounded_by_terms(clause, is_indexed_field))
+        }
+        QueryAst::Term(term_query) => is_indexed_field(&term_query.field),
+        QueryAst::TermSet(term_set_query) => term_set_query
+            .terms_per_field
+            .keys()
+            .all(|field_name| is_indexed_field(field_name)),
+        QueryAst::FullText(full_text_query) => {
+            full_text_query.params.zero_terms_query == MatchAllOrNone::MatchNone
+                && is_indexed_field(&full_text_query.field)
+        }
+        QueryAst::PhrasePrefix(phrase_prefix_query) => is_indexed_field(&phrase_prefix_query.field),
+        QueryAst::Boost { underlying, .. } => {
+            is_query_bounded_by_terms(underlying, is_indexed_field)
+        }
+        QueryAst::MatchNone => true,
+        QueryAst::FieldPresence(_)
+        | QueryAst::Range(_)
+        | QueryAst::Wildcard(_)
+        | QueryAst::MatchAll
+        | QueryAst::UserInput(_) => false,
+    }
+}
+
+/// `leaf` step of search.
+///
+/// The leaf search collects all kind of information, and returns a set of
+/// [PartialHit](quickwit_proto::search::PartialHit) candidates. The root will be in
+/// charge to consolidate, identify the actual final top hits to display, and
+/// fetch the actual documents to convert the partial hits into actual Hits.
+///
+/// When `cancellation_token_opt` gets cancelled, no new split search is started, the in-flight
+/// ones are aborted, and the leaf search fails with `SearchError::Cancelled`.
+pub async fn leaf_search(
     searcher_context: Arc<SearcherContext>,
+    request: Arc<SearchRequest>,
     index_storage: Arc<dyn Storage>,
+    splits: Vec<SplitIdAndFooterOffsets>,
     doc_mapper: Arc<dyn DocMapper>,
-    split: SplitIdAndFooterOffsets,
-    split_filter: Arc<Mutex<CanSplitDoBetter>>,
-    incremental_merge_collector: Arc<Mutex<IncrementalCollector>>,
-    leaf_split_search_permit: tokio::sync::OwnedSemaphorePermit,
-) {
-    crate::SEARCH_METRICS.leaf_searches_splits_total.inc();
-    let timer = crate::SEARCH_METRICS
-        .leaf_search_split_duration_secs
-        .start_timer();
-    let leaf_search_single_split_res = leaf_search_single_split(
-        &searcher_context,
+    cancellation_token_opt: Option<CancellationToken>,
+) -> Result<LeafSearchResponse, SearchError> {
+    let options = LeafSearchOptions {
+        cancellation_token_opt,
+        ..Default::default()
+    };
+    leaf_search_with_options(
+        searcher_context,
         request,
         index_storage,
-        split.clone(),
+        splits,
         doc_mapper,
+        options,
     )
-    .await;
+    .await
+}
 
-    // We explicitly drop it, to highlight it to the reader
-    std::mem::drop(leaf_split_search_permit);
+/// Same as [`leaf_search`], with the optional hooks described in [`LeafSearchOptions`].
+pub async fn leaf_search_with_options(
+    searcher_context: Arc<SearcherContext>,
+    request: Arc<SearchRequest>,
+    index_storage: Arc<dyn Storage>,
+    splits: Vec<SplitIdAndFooterOffsets>,
+    doc_mapper: Arc<dyn DocMapper>,
+    options: LeafSearchOptions,
+) -> Result<LeafSearchResponse, SearchError> {
+    leaf_search_inner(
+        searcher_context,
+        request,
+        index_storage,
+        splits,
+        doc_mapper,
+        options,
+        None,
+    )
+    .await
+}
 
-    if leaf_search_single_split_res.is_ok() {
-        timer.observe_duration();
+/// Runs the leaf search. Splits found in `opened_indexes_opt` are searched without being
+/// opened again.
+#[instrument(skip_all, fields(
+    index = ?request.index_id_patterns,
+    num_splits_searched = field::Empty,
+    num_splits_pruned = field::Empty,
+))]
+pub(crate) async fn leaf_search_inner(
+    searcher_context: Arc<SearcherContext>,
+    request: Arc<SearchRequest>,
+    index_storage: Arc<dyn Storage>,
+    mut splits: Vec<SplitIdAndFooterOffsets>,
+    doc_mapper: Arc<dyn DocMapper>,
+    options: LeafSearchOptions,
+    opened_indexes_opt: Option<Arc<HashMap<String, Index>>>,
+) -> Result<LeafSearchResponse, SearchError> {
+    let LeafSearchOptions {
+        progress_opt,
+        split_decision_observer,
+        cancellation_token_opt,
+    } = options;
+    info!(splits_num = splits.len(), split_offsets = ?PrettySample::new(&splits, 5));
+
+    // Past this deadline, no more splits are searched and the partial result is returned.
+    let overall_deadline_opt: Option<tokio::time::Instant> = searcher_context
+        .searcher_config
+        .leaf_search_max_elapsed()
+        .map(|max_elapsed| tokio::time::Instant::now() + max_elapsed);
+
+    dedup_splits(&mut splits);
+    apply_split_id_overrides(&mut splits, &request, &searcher_context.searcher_config)?;
+    check_leaf_request_size(&splits, &searcher_context.searcher_config)?;
+
+    let mut split_filter =
+        CanSplitDoBetter::from_request(&request, doc_mapper.timestamp_field_name());
+    if doc_mapper.timestamp_field_name().is_none() {
+        split_filter = split_filter.fallback_to_default_timestamp_field(
+            &request,
+            searcher_context
+                .searcher_config
+                .default_timestamp_field_name
+                .as_deref(),
+            &doc_mapper.schema(),
+            &splits,
+        );
+    }
+    let split_filter = split_filter.check_split_timestamp_bounds(&splits, index_storage.uri());
+    split_filter.optimize_split_order(&mut splits);
+    if searcher_context.searcher_config.interleave_cached_splits {
+        if let Some(split_cache) = &searcher_context.split_cache_opt {
+            interleave_cached_splits(&mut splits, |split| split_cache.is_on_disk(&split.split_id));
+        }
     }
 
-    let mut locked_incremental_merge_collector = incremental_merge_collector.lock().unwrap();
-    match leaf_search_single_split_res {
-        Ok(split_search_res) => {
-            if let Err(err) = locked_incremental_merge_collector.add_split(split_search_res) {
-                locked_incremental_merge_collector.add_failed_split(SplitSearchError {
-                    split_id: split.split_id.clone(),
-                    error: format!("Error parsing aggregation result: {err}"),
-                    retryable_error: true,
+    // if client wants full count, or we are doing an aggregation, we want to run every splits.
+    // However if the aggregation is the tracing aggregation, we don't actually need all splits.
+    let run_all_splits = request.count_hits() == CountHits::CountAll
+        || (request.aggregation_request.is_some()
+            && !matches!(split_filter, CanSplitDoBetter::FindTraceIdsAggregation(_)));
+
+    // The aggregation request is parsed once, and shared by all the collectors.
+    let aggregation_opt = parse_aggregation_request(&request)?;
+
+    // Creates a collector which merges responses into one
+    let merge_collector = make_merge_collector_with_aggregation(
+        &request,
+        aggregation_opt.clone(),
+        &searcher_context.get_aggregation_limits(),
+    );
+    let incremental_merge_collector = IncrementalCollector::new(merge_collector);
+
+    // When we don't need to run all splits, running split searches that can no longer give
+    // better results are aborted.
+    let in_flight_split_searches_opt: Option<Arc<InFlightSplitSearches>> =
+        (!run_all_splits).then(Default::default);
+
+    let split_filter = Arc::new(Mutex::new(split_filter));
+    let incremental_merge_collector = Arc::new(Mutex::new(incremental_merge_collector));
+    let slowest_splits_opt: Option<Arc<Mutex<SlowestSplits>>> =
+        request.num_slowest_splits.map(|num_slowest_splits| {
+            Arc::new(Mutex::new(SlowestSplits::new(num_slowest_splits as usize)))
+        });
+
+    let progress_reporter_opt: Option<Arc<LeafSearchProgressReporter>> =
+        progress_opt.map(|progress| Arc::new(LeafSearchProgressReporter::new(progress)));
+
+    let aggregation_memory_limit_bytes = searcher_context
+        .searcher_config
+        .aggregation_memory_limit
+        .as_u64();
+
+    // A token that is never cancelled saves us from handling the `None` case below.
+    let cancellation_token = cancellation_token_opt.unwrap_or_default();
+
+    // Low priority searches, e.g. scrolls, only peek at the split cache: the splits they scan
+    // must not be prefetched into it either.
+    let record_split_accesses = split_cache_priority(&request) != CachePriority::Low;
+
+    // Footers are prefetched in the background, so that the first split searches are not held
+    // back by the prefetching of the last ones.
+    let _prefetch_split_footers_guard_opt: Option<AbortOnDrop> =
+        if searcher_context.searcher_config.prefetch_split_footers {
+            // Splits that are already opened don't need their footer.
+            let splits_to_prefetch: Vec<SplitIdAndFooterOffsets> = splits
+                .iter()
+                .filter(|split| {
+                    opened_indexes_opt.as_ref().map_or(true, |opened_indexes| {
+                        !opened_indexes.contains_key(&split.split_id)
+                    })
+                })
+                .cloned()
+                .collect();
+            let prefetch_handle = tokio::spawn(
+                prefetch_split_footers(
+                    searcher_context.clone(),
+                    index_storage.clone(),
+                    splits_to_prefetch,
+                    split_filter.clone(),
+                    run_all_splits,
+                )
+                .in_current_span(),
+            );
+            Some(AbortOnDrop(prefetch_handle.abort_handle()))
+        } else {
+            None
+        };
+
+    // Split searches are consumed as they complete, while later splits are still waiting for a
+    // permit. This way, failures are accounted for right away and the split filter used to prune
+    // the remaining splits reflects the freshest worst hit.
+    let mut split_search_handles: FuturesUnordered<SplitSearchHandle> = FuturesUnordered::new();
+    let mut num_splits_spawned: u64 = 0;
+    let mut num_splits_pruned: u64 = 0;
+    let mut num_splits_count_only: u64 = 0;
+    // Split searches aborted because they could no longer do better.
+    let mut num_split_searches_pruned: u64 = 0;
+    // Split searches aborted because the deadline was exceeded.
+    let mut num_split_searches_interrupted: u64 = 0;
+    let mut timed_out = false;
+    // Splits left unsearched because of the deadline, whether their search was interrupted or
+    // never started. They are reported as failed.
+    let mut timed_out_split_ids: Vec<String> = Vec::new();
+    let num_splits = splits.len();
+
+    // Like split search permits, the aggregation permit is not waited for past the deadline or
+    // once the search is cancelled.
+    let aggregation_permit_fut = acquire_aggregation_permit(&searcher_context, &request);
+    let _aggregation_permit_opt = tokio::select! {
+        biased;
+        _ = cancellation_token.cancelled() => {
+            return Err(SearchError::Cancelled);
+        }
+        _ = sleep_until_deadline(overall_deadline_opt) => {
+            timed_out = true;
+            None
+        }
+        aggregation_permit_opt = aggregation_permit_fut => aggregation_permit_opt,
+    };
+
+    let mut splits_iter = splits.into_iter();
+    if timed_out {
+        // None of the splits can be searched.
+        timed_out_split_ids.extend(splits_iter.by_ref().map(|split| split.split_id));
+    }
+    'spawn_split_searches: for split in splits_iter.by_ref() {
+        let permit_fut = searcher_context.acquire_split_search_permit(request.priority());
+        tokio::pin!(permit_fut);
+
+        let leaf_split_search_permit = loop {
+            tokio::select! {
+                biased;
+                _ = cancellation_token.cancelled() => {
+                    abort_split_searches(&split_search_handles);
+                    return Err(SearchError::Cancelled);
+                }
+                Some(split_search_join_res) = split_search_handles.next() => {
+                    let split_search_outcome = record_split_search_join_result(
+                        &incremental_merge_collector,
+                        in_flight_split_searches_opt.as_deref(),
+                        split_decision_observer.as_ref(),
+                        split_search_join_res,
+                    );
+                    if split_search_outcome == SplitSearchTaskOutcome::Pruned {
+                        num_split_searches_pruned += 1;
+                    }
+                }
+                _ = sleep_until_deadline(overall_deadline_opt) => {
+                    timed_out = true;
+                    timed_out_split_ids.push(split.split_id.clone());
+                    break 'spawn_split_searches;
+                }
+                permit = &mut permit_fut => {
+                    break permit;
+                }
+            }
+        };
+
+        // Stop early if the aggregation already looks too large: there is no point in
+        // warming up and scanning more splits if the merge is going to fail anyway.
+        if request.aggregation_request.is_some() {
+            let aggregation_memory_usage_bytes = incremental_merge_collector
+                .lock()
+                .unwrap()
+                .aggregation_memory_usage();
+            if aggregation_memory_usage_bytes > aggregation_memory_limit_bytes {
+                abort_split_searches(&split_search_handles);
+                return Err(SearchError::AggregationMemoryLimitExceeded {
+                    memory_usage_bytes: aggregation_memory_usage_bytes,
+                    memory_limit_bytes: aggregation_memory_limit_bytes,
                 });
             }
         }
-        Err(err) => locked_incremental_merge_collector.add_failed_split(SplitSearchError {
-            split_id: split.split_id.clone(),
-            error: format!("{err}"),
-            retryable_error: true,
-        }),
-    }
-    if let Some(last_hit) = locked_incremental_merge_collector.peek_worst_hit() {
-        split_filter
-            .lock()
-            .unwrap()
-            .record_new_worst_hit(last_hit.as_ref());
-    }
-}
 
-#[cfg(test)]
-mod tests {
-    use std::ops::Bound;
+        let mut request = (*request).clone();
+        let mut is_count_only = false;
 
-    use super::*;
+        if !split_filter.lock().unwrap().can_be_better(&split) {
+            crate::SEARCH_METRICS.leaf_search_splits_pruned_total.inc();
+            if !run_all_splits {
+                split_decision_observer.on_pruned(&split.split_id);
+                num_splits_pruned += 1;
+                continue;
+            }
+            split_decision_observer.on_count_only(&split.split_id);
+            num_splits_count_only += 1;
+            is_count_only = true;
+            request.max_hits = 0;
+            request.start_offset = 0;
+            request.sort_fields.clear();
+        } else {
+            split_decision_observer.on_full(&split.split_id);
+        }
 
-    fn bool_filter(ast: impl Into<QueryAst>) -> QueryAst {
-        BoolQuery {
-            must: vec![QueryAst::MatchAll],
-            filter: vec![ast.into()],
-            ..Default::default()
+        if let Some(split_cache_warmer) = searcher_context
+            .split_cache_warmer_opt
+            .as_ref()
+            .filter(|_| record_split_accesses)
+        {
+            split_cache_warmer.record_split_access(&split.split_id, index_storage.uri());
         }
-        .into()
+        let opened_index_opt = opened_indexes_opt
+            .as_ref()
+            .and_then(|opened_indexes| opened_indexes.get(&split.split_id).cloned());
+        let in_flight_split_opt = in_flight_split_searches_opt
+            .as_ref()
+            .map(|in_flight_split_searches| (in_flight_split_searches.clone(), split.clone()));
+
+        let split_id = split.split_id.clone();
+        let split_search_fut = leaf_search_single_split_wrapper(
+            request,
+            searcher_context.clone(),
+            index_storage.clone(),
+            doc_mapper.clone(),
+            aggregation_opt.clone(),
+            split,
+            split_filter.clone(),
+            incremental_merge_collector.clone(),
+            slowest_splits_opt.clone(),
+            progress_reporter_opt.clone(),
+            in_flight_split_searches_opt.clone(),
+            split_decision_observer.clone(),
+            opened_index_opt,
+            leaf_split_search_permit,
+        )
+        .in_current_span();
+        let join_handle = if let Some((in_flight_split_searches, split)) = in_flight_split_opt {
+            in_flight_split_searches.spawn_registered(split, || tokio::spawn(split_search_fut))
+        } else {
+            tokio::spawn(split_search_fut)
+        };
+        split_search_handles.push(SplitSearchHandle {
+            split_id,
+            is_count_only,
+            join_handle,
+        });
+        num_splits_spawned += 1;
     }
+    timed_out_split_ids.extend(splits_iter.map(|split| split.split_id));
 
-    #[track_caller]
-    fn assert_ast_eq(got: &SearchRequest, expected: &QueryAst) {
-        let got_ast: QueryAst = serde_json::from_str(&got.query_ast).unwrap();
-        assert_eq!(&got_ast, expected);
-        assert!(got.start_timestamp.is_none());
-        assert!(got.end_timestamp.is_none());
+    loop {
+        tokio::select! {
+            biased;
+            _ = cancellation_token.cancelled() => {
+                abort_split_searches(&split_search_handles);
+                return Err(SearchError::Cancelled);
+            }
+            split_search_res_opt = split_search_handles.next() => {
+                let Some(split_search_join_res) = split_search_res_opt else {
+                    break;
+                };
+                let split_search_outcome = record_split_search_join_result(
+                    &incremental_merge_collector,
+                    in_flight_split_searches_opt.as_deref(),
+                    split_decision_observer.as_ref(),
+                    split_search_join_res,
+                );
+                if split_search_outcome == SplitSearchTaskOutcome::Pruned {
+                    num_split_searches_pruned += 1;
+                }
+            }
+            _ = sleep_until_deadline(overall_deadline_opt) => {
+                timed_out = true;
+                break;
+            }
+        }
     }
 
-    #[track_caller]
-    fn remove_timestamp_test_case(
-        request: &SearchRequest,
-        split: &SplitIdAndFooterOffsets,
-        expected: Option<RangeQuery>,
-    ) {
-        let timestamp_field = "timestamp";
+    if timed_out {
+        // The split searches still running are aborted. Waiting for them to wind down is quick,
+        // and tells apart the splits that completed right before the deadline.
+        abort_split_searches(&split_search_handles);
+        while let Some(split_search_join_res) = split_search_handles.next().await {
+            let split_id = split_search_join_res.split_id.clone();
+            let is_count_only = split_search_join_res.is_count_only;
+            let split_search_outcome = record_split_search_join_result(
+                &incremental_merge_collector,
+                in_flight_split_searches_opt.as_deref(),
+                split_decision_observer.as_ref(),
+                split_search_join_res,
+            );
+            match split_search_outcome {
+                SplitSearchTaskOutcome::Completed => {}
+                // The split was pruned before the deadline: it did not time out.
+                SplitSearchTaskOutcome::Pruned => {
+                    num_split_searches_pruned += 1;
+                }
+                SplitSearchTaskOutcome::Aborted => {
+                    num_split_searches_interrupted += 1;
+                    if is_count_only {
+                        num_splits_count_only -= 1;
+                    }
+                    timed_out_split_ids.push(split_id);
+                }
+            }
+        }
+        warn!(
+            num_splits,
+            num_splits_timed_out = timed_out_split_ids.len(),
+            num_split_searches_interrupted,
+            "leaf search deadline exceeded, returning partial result"
+        );
+        let mut locked_incremental_merge_collector = incremental_merge_collector.lock().unwrap();
+        for split_id in timed_out_split_ids {
+            let error = "leaf search deadline exceeded before the split was searched".to_string();
+            split_decision_observer.on_failed(&split_id, &error);
+            locked_incremental_merge_collector.add_failed_split(SplitSearchError {
+                split_id,
+                error,
+                // Searching the split elsewhere would blow the deadline anyway.
+                retryable_error: false,
+                error_kind: SplitSearchErrorKind::Timeout.into(),
+            });
+        }
+    }
+
+    // we can't use unwrap_or_clone because mutexes aren't Clone
+    let incremental_merge_collector = match Arc::try_unwrap(incremental_merge_collector) {
+        Ok(filter_merger) => filter_merger.into_inner().unwrap(),
+        Err(filter_merger) => filter_merger.lock().unwrap().clone(),
+    };
+
+    // With a single contributing split and no aggregation, there is nothing to merge: the
+    // round-trip to the search thread pool would cost more than finalizing in place.
+    let mut leaf_search_response = if incremental_merge_collector.is_finalize_trivial() {
+        finalize_catching_panic(incremental_merge_collector, &request, num_splits)?
+    } else {
+        searcher_context
+            .search_thread_pool()
+            .run_cpu_intensive(|| {
+                finalize_catching_panic(incremental_merge_collector, &request, num_splits)
+            })
+            .instrument(info_span!("incremental_merge_finalize"))
+            .await
+            .context("failed to merge split search responses")??
+    };
+
+    if let Some(slowest_splits) = slowest_splits_opt {
+        let slowest_splits = match Arc::try_unwrap(slowest_splits) {
+            Ok(slowest_splits) => slowest_splits.into_inner().unwrap(),
+            Err(slowest_splits) => slowest_splits.lock().unwrap().clone(),
+        };
+        leaf_search_response.slowest_splits = slowest_splits.into_sorted_vec();
+    }
+    // Split searches aborted because they could no longer do better are accounted as pruned,
+    // while the ones interrupted by the deadline are only accounted as failed. The splits that
+    // completed before the deadline keep their result and are accounted as searched.
+    let num_splits_searched = num_splits_spawned
+        - num_splits_count_only
+        - num_split_searches_pruned
+        - num_split_searches_interrupted;
+    let num_splits_pruned = num_splits_pruned + num_split_searches_pruned;
+
+    let current_span = Span::current();
+    current_span.record("num_splits_searched", num_splits_searched);
+    current_span.record("num_splits_pruned", num_splits_pruned);
+
+    leaf_search_response.num_splits_searched = num_splits_searched as u32;
+    leaf_search_response.num_splits_pruned = num_splits_pruned as u32;
+    leaf_search_response.num_splits_count_only = num_splits_count_only as u32;
+    leaf_search_response.timed_out |= timed_out;
+    Ok(leaf_search_response)
+}
+
+/// Reorders `splits` so that the cached splits alternate with the other ones, starting with a
+/// cached one. The relative order within each group is preserved, and the splits left once a
+/// group is exhausted are appended as is.
+fn interleave_cached_splits(
+    splits: &mut Vec<SplitIdAndFooterOffsets>,
+    is_cached: impl Fn(&SplitIdAndFooterOffsets) -> bool,
+) {
+    let (cached_splits, uncached_splits): (Vec<_>, Vec<_>) =
+        splits.drain(..).partition(|split| is_cached(split));
+    splits.extend(cached_splits.into_iter().interleave(uncached_splits));
+}
+
+/// Resolves once `deadline_opt` is reached, never if it is `None`.
+async fn sleep_until_deadline(deadline_opt: Option<tokio::time::Instant>) {
+    match deadline_opt {
+        Some(deadline) => tokio::time::sleep_until(deadline).await,
+        None => std::future::pending().await,
+    }
+}
+
+/// A spawned split search, along with the split it searches.
+struct SplitSearchHandle {
+    split_id: String,
+    is_count_only: bool,
+    join_handle: JoinHandle<()>,
+}
+
+struct SplitSearchJoinResult {
+    split_id: String,
+    is_count_only: bool,
+    join_res: Result<(), JoinError>,
+}
+
+impl Future for SplitSearchHandle {
+    type Output = SplitSearchJoinResult;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut TaskContext<'_>) -> Poll<Self::Output> {
+        let join_res = ready!(Pin::new(&mut self.join_handle).poll(cx));
+        Poll::Ready(SplitSearchJoinResult {
+            split_id: std::mem::take(&mut self.split_id),
+            is_count_only: self.is_count_only,
+            join_res,
+        })
+    }
+}
+
+fn abort_split_searches(split_search_handles: &FuturesUnordered<SplitSearchHandle>) {
+    for split_search_handle in split_search_handles.iter() {
+        split_search_handle.join_handle.abort();
+    }
+}
+
+/// How a split search task ended.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum SplitSearchTaskOutcome {
+    /// The task ran to completion or panicked: the split is accounted for in the collector.
+    Completed,
+    /// The task was aborted because the split could no longer give better hits.
+    Pruned,
+    /// The task was aborted for another reason, e.g. the leaf search deadline.
+    Aborted,
+}
+
+/// Records the outcome of a split search task that did not complete normally.
+///
+/// Splits that did not panic were already added to the collector, and splits that were aborted
+/// have nothing to add.
+fn record_split_search_join_result(
+    incremental_merge_collector: &Mutex<IncrementalCollector>,
+    in_flight_split_searches_opt: Option<&InFlightSplitSearches>,
+    split_decision_observer: &dyn SplitDecisionObserver,
+    split_search_join_res: SplitSearchJoinResult,
+) -> SplitSearchTaskOutcome {
+    let Err(join_error) = split_search_join_res.join_res else {
+        return SplitSearchTaskOutcome::Completed;
+    };
+    if join_error.is_cancelled() {
+        let split_id = &split_search_join_res.split_id;
+        if in_flight_split_searches_opt
+            .is_some_and(|in_flight_split_searches| in_flight_split_searches.is_pruned(split_id))
+        {
+            split_decision_observer.on_pruned(split_id);
+            return SplitSearchTaskOutcome::Pruned;
+        }
+        return SplitSearchTaskOutcome::Aborted;
+    }
+    incremental_merge_collector
+        .lock()
+        .unwrap()
+        .add_failed_split(SplitSearchError {
+            split_id: split_search_join_res.split_id,
+            error: format!("{}", SearchError::from(join_error)),
+            retryable_error: true,
+            error_kind: SplitSearchErrorKind::Internal.into(),
+        });
+    SplitSearchTaskOutcome::Completed
+}
+
+/// Runs `task`, turning a panic into a non-retryable `SearchError::Internal` that carries the
+/// panic message, and incrementing `panic_counter`.
+///
+/// Without this, the panic is only observed as an opaque `ThreadPoolError::Panicked` error by the
+/// caller of `run_cpu_intensive`, which makes bugs in the finalization step hard to diagnose.
+fn catch_panic_as_internal_error<T>(
+    task_name: &str,
+    panic_counter: &IntCounter,
+    task: impl FnOnce() -> Result<T, SearchError>,
+) -> Result<T, SearchError> {
+    std::panic::catch_unwind(std::panic::AssertUnwindSafe(task)).unwrap_or_else(|panic_payload| {
+        panic_counter.inc();
+        let panic_message = if let Some(message) = panic_payload.downcast_ref::<&str>() {
+            message.to_string()
+        } else if let Some(message) = panic_payload.downcast_ref::<String>() {
+            message.clone()
+        } else {
+            "unknown panic payload".to_string()
+        };
+        error!(panic_message=%panic_message, "{task_name} panicked");
+        Err(SearchError::Internal(format!(
+            "{task_name} panicked: {panic_message}"
+        )))
+    })
+}
+
+/// Finalizes the merge of the split search responses.
+///
+/// Merging aggregation trees can panic on malformed intermediate results. The resulting error
+/// mentions the index patterns and the number of splits of the request, so that the panic can be
+/// traced back to a query shape.
+fn finalize_catching_panic(
+    incremental_merge_collector: IncrementalCollector,
+    request: &SearchRequest,
+    num_splits: usize,
+) -> Result<LeafSearchResponse, SearchError> {
+    let task_name = format!(
+        "merging split search responses (index_patterns={:?}, num_splits={num_splits})",
+        request.index_id_patterns
+    );
+    catch_panic_as_internal_error(
+        &task_name,
+        &crate::SEARCH_METRICS.finalize_panics_total,
+        || {
+            incremental_merge_collector
+                .finalize()
+                .map_err(SearchError::from)
+        },
+    )
+}
+
+#[allow(clippy::too_many_arguments)]
+async fn leaf_search_single_split_wrapper(
+    request: SearchRequest,
+    searcher_context: Arc<SearcherContext>,
+    index_storage: Arc<dyn Storage>,
+    doc_mapper: Arc<dyn DocMapper>,
+    aggregation_opt: Option<Arc<QuickwitAggregations>>,
+    split: SplitIdAndFooterOffsets,
+    split_filter: Arc<Mutex<CanSplitDoBetter>>,
+    incremental_merge_collector: Arc<Mutex<IncrementalCollector>>,
+    slowest_splits_opt: Option<Arc<Mutex<SlowestSplits>>>,
+    progress_reporter_opt: Option<Arc<LeafSearchProgressReporter>>,
+    in_flight_split_searches_opt: Option<Arc<InFlightSplitSearches>>,
+    split_decision_observer: Arc<dyn SplitDecisionObserver>,
+    opened_index_opt: Option<Index>,
+    mut leaf_split_search_permit: tokio::sync::OwnedSemaphorePermit,
+) {
+    let start = Instant::now();
+    crate::SEARCH_METRICS.leaf_searches_splits_total.inc();
+    let mut in_flight_leaf_search_guard =
+        GaugeGuard::from_gauge(&crate::SEARCH_METRICS.in_flight_leaf_searches);
+    in_flight_leaf_search_guard.add(1);
+    let timer = crate::SEARCH_METRICS
+        .leaf_search_split_duration_secs
+        .start_timer();
+    let collect_split_stats = request.collect_split_stats;
+    let aggregation_request_opt = request.aggregation_request.clone();
+    let mut num_attempts = 0;
+    let leaf_search_single_split_res = loop {
+        num_attempts += 1;
+        let leaf_search_single_split_res = leaf_search_single_split(
+            &searcher_context,
+            request.clone(),
+            index_storage.clone(),
+            split.clone(),
+            doc_mapper.clone(),
+            aggregation_opt.clone(),
+            opened_index_opt.clone(),
+        )
+        .await;
+        match &leaf_search_single_split_res {
+            Err(err)
+                if num_attempts <= searcher_context.searcher_config.max_split_retries
+                    && err.is_retryable_in_place() =>
+            {
+                let delay = SPLIT_SEARCH_RETRY_PARAMS.compute_delay(num_attempts);
+                debug!(
+                    split_id = %split.split_id,
+                    num_attempts,
+                    delay_ms = delay.as_millis() as u64,
+                    error = %err.error,
+                    "retrying split search"
+                );
+                // The permit is released while backing off, so that other splits can be searched
+                // in the meantime.
+                std::mem::drop(leaf_split_search_permit);
+                tokio::time::sleep(delay).await;
+                leaf_split_search_permit = searcher_context
+                    .acquire_split_search_permit(request.priority())
+                    .await;
+            }
+            _ => break leaf_search_single_split_res,
+        }
+    };
+
+    // We explicitly drop it, to highlight it to the reader
+    std::mem::drop(in_flight_leaf_search_guard);
+
+    if let Some(in_flight_split_searches) = &in_flight_split_searches_opt {
+        in_flight_split_searches.unregister(&split.split_id);
+    }
+
+    if leaf_search_single_split_res.is_ok() {
+        timer.observe_duration();
+    }
+    if let Some(slowest_splits) = slowest_splits_opt {
+        slowest_splits
+            .lock()
+            .unwrap()
+            .record(&split.split_id, start.elapsed());
+    }
+
+    let mut locked_incremental_merge_collector = incremental_merge_collector.lock().unwrap();
+    match leaf_search_single_split_res {
+        Ok((mut split_search_res, split_search_metrics)) => {
+            if collect_split_stats {
+                split_search_res.per_split_stats = vec![SplitStats {
+                    split_id: split.split_id.clone(),
+                    num_hits: split_search_res.num_hits,
+                    elapsed_ms: start.elapsed().as_millis() as u64,
+                    estimated_warmup_bytes: split_search_metrics.estimated_warmup_num_bytes_opt,
+                }];
+            }
+            if let Err(err) = locked_incremental_merge_collector.add_split(split_search_res) {
+                debug!(
+                    split_id = %split.split_id,
+                    aggregation_request = ?aggregation_request_opt,
+                    error = %err,
+                    "failed to parse aggregation result"
+                );
+                // Parsing the aggregation result is deterministic: retrying the split elsewhere
+                // would fail the same way.
+                let error = format!("Error parsing aggregation result: {err}");
+                split_decision_observer.on_failed(&split.split_id, &error);
+                locked_incremental_merge_collector.add_failed_split(SplitSearchError {
+                    split_id: split.split_id.clone(),
+                    error,
+                    retryable_error: false,
+                    error_kind: SplitSearchErrorKind::AggregationParse.into(),
+                });
+            }
+        }
+        Err(err) => {
+            let error = format!("{}", err.error);
+            split_decision_observer.on_failed(&split.split_id, &error);
+            locked_incremental_merge_collector.add_failed_split(SplitSearchError {
+                split_id: split.split_id.clone(),
+                error,
+                retryable_error: err.is_retryable(),
+                error_kind: err.kind.into(),
+            });
+        }
+    }
+    if let Some(last_hit) = locked_incremental_merge_collector.peek_worst_hit() {
+        let mut locked_split_filter = split_filter.lock().unwrap();
+        locked_split_filter.record_new_worst_hit(last_hit.as_ref());
+
+        if let Some(in_flight_split_searches) = &in_flight_split_searches_opt {
+            in_flight_split_searches.abort_if_cannot_be_better(&locked_split_filter);
+        }
+    }
+    // The permit is only released once the result of the split is recorded, so that the next
+    // split search, which is spawned with this permit, is pruned against the freshest worst hit.
+    std::mem::drop(leaf_split_search_permit);
+    if let Some(progress_reporter) = progress_reporter_opt {
+        if progress_reporter.record_searched_split() {
+            let top_hits = locked_incremental_merge_collector.snapshot_top_hits();
+            // We don't want to hold the lock while running the user callback.
+            drop(locked_incremental_merge_collector);
+            (progress_reporter.progress.callback)(top_hits);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::ops::Bound;
+    use std::path::Path;
+
+    use quickwit_proto::search::SortField;
+    use quickwit_query::query_ast::qast_helper;
+
+    use super::*;
+    use crate::collector::make_merge_collector;
+    use crate::find_trace_ids_collector::FindTraceIdsCollector;
+
+    fn bool_filter(ast: impl Into<QueryAst>) -> QueryAst {
+        BoolQuery {
+            must: vec![QueryAst::MatchAll],
+            filter: vec![ast.into()],
+            ..Default::default()
+        }
+        .into()
+    }
+
+    #[track_caller]
+    fn assert_ast_eq(got: &SearchRequest, expected: &QueryAst) {
+        let got_ast: QueryAst = serde_json::from_str(&got.query_ast).unwrap();
+        assert_eq!(&got_ast, expected);
+        assert!(got.start_timestamp.is_none());
+        assert!(got.end_timestamp.is_none());
+    }
+
+    #[track_caller]
+    fn remove_timestamp_test_case(
+        request: &SearchRequest,
+        split: &SplitIdAndFooterOffsets,
+        expected: Option<RangeQuery>,
+    ) {
+        let timestamp_field = "timestamp";
+
+        // test the query directly
+        let mut request_direct = request.clone();
+        remove_redundant_timestamp_range(&mut request_direct, split, timestamp_field);
+        let expected_direct = expected
+            .clone()
+            .map(bool_filter)
+            .unwrap_or(QueryAst::MatchAll);
+        assert_ast_eq(&request_direct, &expected_direct);
+    }
+
+    #[test]
+    fn test_rewrite_request_trace_ids_aggregation() {
+        let split = SplitIdAndFooterOffsets {
+            split_id: "split".to_string(),
+            ..Default::default()
+        };
+        let search_request = SearchRequest {
+            index_id_patterns: vec!["otel-traces".to_string()],
+            query_ast: serde_json::to_string(&QueryAst::MatchAll).unwrap(),
+            max_hits: 0,
+            start_offset: 10,
+            sort_fields: vec![SortField {
+                field_name: "span_start_timestamp_nanos".to_string(),
+                sort_order: SortOrder::Desc as i32,
+                sort_datetime_format: None,
+            }],
+            search_after: Some(PartialHit::default()),
+            snippet_fields: vec!["span_name".to_string()],
+            ..Default::default()
+        };
+        let find_trace_ids_aggregation =
+            QuickwitAggregations::FindTraceIdsAggregation(FindTraceIdsCollector {
+                num_traces: 10,
+                trace_id_field_name: "trace_id".to_string(),
+                span_timestamp_field_name: "span_start_timestamp_nanos".to_string(),
+            });
+
+        let mut trace_ids_request = search_request.clone();
+        rewrite_request(
+            &mut trace_ids_request,
+            &split,
+            None,
+            Some(&find_trace_ids_aggregation),
+        );
+        assert_eq!(trace_ids_request.max_hits, 0);
+        assert_eq!(trace_ids_request.start_offset, 0);
+        assert!(trace_ids_request.sort_fields.is_empty());
+        assert!(trace_ids_request.search_after.is_none());
+        assert!(trace_ids_request.snippet_fields.is_empty());
+        assert_eq!(trace_ids_request.query_ast, search_request.query_ast);
+
+        // Without the trace IDs aggregation, only the sort fields go away.
+        let mut hits_request = search_request.clone();
+        rewrite_request(&mut hits_request, &split, None, None);
+        assert_eq!(hits_request.start_offset, 10);
+        assert!(hits_request.sort_fields.is_empty());
+        assert!(hits_request.search_after.is_some());
+
+        // A trace IDs aggregation request asking for hits gets them.
+        let trace_ids_and_hits_request = SearchRequest {
+            max_hits: 20,
+            ..search_request.clone()
+        };
+        let mut rewritten_request = trace_ids_and_hits_request.clone();
+        rewrite_request(
+            &mut rewritten_request,
+            &split,
+            None,
+            Some(&find_trace_ids_aggregation),
+        );
+        assert_eq!(rewritten_request, trace_ids_and_hits_request);
+    }
+
+    #[test]
+    fn test_remove_timestamp_range() {
+        const S_TO_NS: i64 = 1_000_000_000;
+        let time1 = 1700001000;
+        let time2 = 1700002000;
+        let time3 = 1700003000;
+        let time4 = 1700004000;
+
+        let timestamp_field = "timestamp".to_string();
+
+        // cases where the bounds are larger than the split: no bound is emited
+        let split = SplitIdAndFooterOffsets {
+            timestamp_start: Some(time2),
+            timestamp_end: Some(time3),
+            ..SplitIdAndFooterOffsets::default()
+        };
+
+        let search_request = SearchRequest {
+            query_ast: serde_json::to_string(&QueryAst::Range(RangeQuery {
+                field: timestamp_field.to_string(),
+                lower_bound: Bound::Included(time1.into()),
+                // *1000 has no impact, we detect timestamp in ms instead of s
+                upper_bound: Bound::Included((time4 * 1000).into()),
+            }))
+            .unwrap(),
+            ..SearchRequest::default()
+        };
+        remove_timestamp_test_case(&search_request, &split, None);
+
+        let search_request = SearchRequest {
+            query_ast: serde_json::to_string(&QueryAst::MatchAll).unwrap(),
+            start_timestamp: Some(time1),
+            end_timestamp: Some(time4),
+            ..SearchRequest::default()
+        };
+        remove_timestamp_test_case(&search_request, &split, None);
+
+        // the split end is truncated to the second: the split may contain documents up to
+        // `time3 + 0.999s`, so an inclusive bound on `time3` is kept
+        let expected_upper_inclusive = RangeQuery {
+            field: timestamp_field.to_string(),
+            lower_bound: Bound::Unbounded,
+            upper_bound: Bound::Included((time3 * S_TO_NS).into()),
+        };
+        let search_request = SearchRequest {
+            query_ast: serde_json::to_string(&QueryAst::Range(RangeQuery {
+                field: timestamp_field.to_string(),
+                lower_bound: Bound::Included(time1.into()),
+                upper_bound: Bound::Included(time3.into()),
+            }))
+            .unwrap(),
+            ..SearchRequest::default()
+        };
+        remove_timestamp_test_case(&search_request, &split, Some(expected_upper_inclusive));
+
+        // request bound that are exclusive are treated properly
+        let expected_upper_exclusive = RangeQuery {
+            field: timestamp_field.to_string(),
+            lower_bound: Bound::Unbounded,
+            upper_bound: Bound::Excluded((time3 * S_TO_NS).into()),
+        };
+        let search_request = SearchRequest {
+            query_ast: serde_json::to_string(&QueryAst::Range(RangeQuery {
+                field: timestamp_field.to_string(),
+                lower_bound: Bound::Included(time1.into()),
+                upper_bound: Bound::Excluded(time3.into()),
+            }))
+            .unwrap(),
+            ..SearchRequest::default()
+        };
+        remove_timestamp_test_case(
+            &search_request,
+            &split,
+            Some(expected_upper_exclusive.clone()),
+        );
+
+        let search_request = SearchRequest {
+            query_ast: serde_json::to_string(&QueryAst::MatchAll).unwrap(),
+            start_timestamp: Some(time1),
+            end_timestamp: Some(time3),
+            ..SearchRequest::default()
+        };
+        remove_timestamp_test_case(
+            &search_request,
+            &split,
+            Some(expected_upper_exclusive.clone()),
+        );
+
+        let expected_lower_exclusive = RangeQuery {
+            field: timestamp_field.to_string(),
+            lower_bound: Bound::Excluded((time2 * S_TO_NS).into()),
+            upper_bound: Bound::Included((time3 * S_TO_NS).into()),
+        };
+        let search_request = SearchRequest {
+            query_ast: serde_json::to_string(&QueryAst::Range(RangeQuery {
+                field: timestamp_field.to_string(),
+                lower_bound: Bound::Excluded(time2.into()),
+                upper_bound: Bound::Included(time3.into()),
+            }))
+            .unwrap(),
+            ..SearchRequest::default()
+        };
+        remove_timestamp_test_case(
+            &search_request,
+            &split,
+            Some(expected_lower_exclusive.clone()),
+        );
+
+        // we take the most restrictive bounds
+        let split = SplitIdAndFooterOffsets {
+            timestamp_start: Some(time1),
+            timestamp_end: Some(time4),
+            ..SplitIdAndFooterOffsets::default()
+        };
+
+        let expected_upper_2_ex = RangeQuery {
+            field: timestamp_field.to_string(),
+            lower_bound: Bound::Unbounded,
+            upper_bound: Bound::Excluded((time2 * S_TO_NS).into()),
+        };
+        let search_request = SearchRequest {
+            query_ast: serde_json::to_string(&QueryAst::Range(RangeQuery {
+                field: timestamp_field.to_string(),
+                lower_bound: Bound::Included(time1.into()),
+                upper_bound: Bound::Included(time3.into()),
+            }))
+            .unwrap(),
+            start_timestamp: Some(time1),
+            end_timestamp: Some(time2),
+            ..SearchRequest::default()
+        };
+        remove_timestamp_test_case(&search_request, &split, Some(expected_upper_2_ex));
+
+        let expected_upper_2_inc = RangeQuery {
+            field: timestamp_field.to_string(),
+            lower_bound: Bound::Unbounded,
+            upper_bound: Bound::Included((time2 * S_TO_NS).into()),
+        };
+        let search_request = SearchRequest {
+            query_ast: serde_json::to_string(&QueryAst::Range(RangeQuery {
+                field: timestamp_field.to_string(),
+                lower_bound: Bound::Included(time1.into()),
+                upper_bound: Bound::Included(time2.into()),
+            }))
+            .unwrap(),
+            start_timestamp: Some(time1),
+            end_timestamp: Some(time3),
+            ..SearchRequest::default()
+        };
+        remove_timestamp_test_case(&search_request, &split, Some(expected_upper_2_inc));
+
+        let expected_lower_3 = RangeQuery {
+            field: timestamp_field.to_string(),
+            lower_bound: Bound::Included((time3 * S_TO_NS).into()),
+            upper_bound: Bound::Included((time4 * S_TO_NS).into()),
+        };
+
+        let search_request = SearchRequest {
+            query_ast: serde_json::to_string(&QueryAst::Range(RangeQuery {
+                field: timestamp_field.to_string(),
+                lower_bound: Bound::Included(time2.into()),
+                upper_bound: Bound::Included(time4.into()),
+            }))
+            .unwrap(),
+            start_timestamp: Some(time3),
+            end_timestamp: Some(time4 + 1),
+            ..SearchRequest::default()
+        };
+        remove_timestamp_test_case(&search_request, &split, Some(expected_lower_3.clone()));
+
+        let search_request = SearchRequest {
+            query_ast: serde_json::to_string(&QueryAst::Range(RangeQuery {
+                field: timestamp_field.to_string(),
+                lower_bound: Bound::Included(time3.into()),
+                upper_bound: Bound::Included(time4.into()),
+            }))
+            .unwrap(),
+            start_timestamp: Some(time2),
+            end_timestamp: Some(time4 + 1),
+            ..SearchRequest::default()
+        };
+        remove_timestamp_test_case(&search_request, &split, Some(expected_lower_3));
+
+        let mut search_request = SearchRequest {
+            query_ast: serde_json::to_string(&QueryAst::MatchAll).unwrap(),
+            start_timestamp: Some(time1),
+            end_timestamp: Some(time4),
+            ..SearchRequest::default()
+        };
+        let split = SplitIdAndFooterOffsets {
+            timestamp_start: Some(time2),
+            timestamp_end: Some(time3),
+            ..SplitIdAndFooterOffsets::default()
+        };
+        remove_redundant_timestamp_range(&mut search_request, &split, &timestamp_field);
+        assert_ast_eq(&search_request, &QueryAst::MatchAll);
+    }
+
+    #[test]
+    fn test_leaf_search_progress_reporter() {
+        let progress = LeafSearchProgress {
+            num_splits_between_updates: NonZeroUsize::new(3).unwrap(),
+            callback: Arc::new(|_top_hits| {}),
+        };
+        let progress_reporter = LeafSearchProgressReporter::new(progress);
+        let reports: Vec<bool> = (0..7)
+            .map(|_| progress_reporter.record_searched_split())
+            .collect();
+        assert_eq!(reports, [false, false, true, false, false, true, false]);
+    }
+
+    #[test]
+    fn test_leaf_split_search_error_kind() {
+        use tantivy::error::DataCorruption;
+
+        let error_kind = |error: LeafSplitSearchError| error.kind;
+
+        assert_eq!(
+            error_kind(SearchError::InvalidQuery("invalid".to_string()).into()),
+            SplitSearchErrorKind::Query
+        );
+        assert_eq!(
+            error_kind(SearchError::Timeout("timeout".to_string()).into()),
+            SplitSearchErrorKind::Timeout
+        );
+        assert_eq!(
+            error_kind(TantivyError::DataCorruption(DataCorruption::comment_only("bad")).into()),
+            SplitSearchErrorKind::Corruption
+        );
+        assert_eq!(
+            error_kind(TantivyError::SchemaError("bad".to_string()).into()),
+            SplitSearchErrorKind::Query
+        );
+
+        let storage_timeout = StorageErrorKind::Timeout.with_error(anyhow::anyhow!("too slow"));
+        assert_eq!(
+            error_kind(anyhow::Error::from(storage_timeout.clone()).into()),
+            SplitSearchErrorKind::Timeout
+        );
+        let io_error = std::io::Error::other(storage_timeout);
+        assert_eq!(
+            error_kind(anyhow::Error::from(io_error).into()),
+            SplitSearchErrorKind::Timeout
+        );
+        let storage_error = StorageErrorKind::Io.with_error(anyhow::anyhow!("broken pipe"));
+        assert_eq!(
+            error_kind(
+                anyhow::Error::from(storage_error)
+                    .context("failed to fetch footer")
+                    .into()
+            ),
+            SplitSearchErrorKind::StorageIo
+        );
+        let corruption = TantivyError::DataCorruption(DataCorruption::comment_only("bad"));
+        let leaf_error: LeafSplitSearchError = anyhow::Error::from(corruption)
+            .context("failed to open split")
+            .into();
+        assert_eq!(leaf_error.kind, SplitSearchErrorKind::Corruption);
+        assert!(matches!(leaf_error.error, SearchError::Internal(_)));
+
+        assert_eq!(
+            error_kind(anyhow::anyhow!("something else").into()),
+            SplitSearchErrorKind::Internal
+        );
+    }
+
+    #[test]
+    fn test_leaf_split_search_query_errors_are_not_retryable() {
+        let invalid_query_error =
+            LeafSplitSearchError::from(SearchError::InvalidQuery("invalid".to_string()));
+        assert!(!invalid_query_error.error.is_retryable_split_error());
+        assert!(!invalid_query_error.is_retryable());
+
+        // Tantivy schema errors are only identified as query errors by their kind.
+        let schema_error = LeafSplitSearchError::from(TantivyError::SchemaError("bad".to_string()));
+        assert!(!schema_error.is_retryable());
+
+        let internal_error = LeafSplitSearchError::from(SearchError::Internal("oops".to_string()));
+        assert!(internal_error.is_retryable());
+    }
+
+    #[test]
+    fn test_catch_panic_as_internal_error() {
+        let panic_counter = IntCounter::new("test_panics_total", "help").unwrap();
+        let result: Result<u64, SearchError> =
+            catch_panic_as_internal_error("merging split search responses", &panic_counter, || {
+                Ok(1)
+            });
+        assert_eq!(result.unwrap(), 1);
+        assert_eq!(panic_counter.get(), 0);
+
+        let error = catch_panic_as_internal_error::<()>(
+            "merging split search responses",
+            &panic_counter,
+            || panic!("boom in finalize"),
+        )
+        .unwrap_err();
+        let SearchError::Internal(message) = error else {
+            panic!("expected an internal error, got `{error:?}`");
+        };
+        assert_eq!(
+            message,
+            "merging split search responses panicked: boom in finalize"
+        );
+
+        let error = catch_panic_as_internal_error::<()>(
+            "merging split search responses",
+            &panic_counter,
+            || panic!("split count: {}", 3),
+        )
+        .unwrap_err();
+        assert!(
+            matches!(error, SearchError::Internal(message) if message.ends_with("split count: 3"))
+        );
+        assert_eq!(panic_counter.get(), 2);
+
+        let request = SearchRequest {
+            index_id_patterns: vec!["logs-*".to_string()],
+            ..Default::default()
+        };
+        let merge_collector = make_merge_collector(&request, &Default::default()).unwrap();
+        let leaf_search_response =
+            finalize_catching_panic(IncrementalCollector::new(merge_collector), &request, 3)
+                .unwrap();
+        assert_eq!(leaf_search_response.num_hits, 0);
+    }
+
+    #[tokio::test]
+    async fn test_split_footer_cache_keyed_by_storage() {
+        let storage_resolver = quickwit_storage::StorageResolver::for_test();
+        let mut index_storages: Vec<Arc<dyn Storage>> = Vec::new();
+
+        for (index_uri, split_payload) in [
+            ("ram:///indexes/index-a", b"datafooter-a"),
+            ("ram:///indexes/index-b", b"datafooter-b"),
+        ] {
+            let index_storage = storage_resolver
+                .resolve(&quickwit_common::uri::Uri::for_test(index_uri))
+                .await
+                .unwrap();
+            index_storage
+                .put(Path::new("split.split"), Box::new(split_payload.to_vec()))
+                .await
+                .unwrap();
+            index_storages.push(index_storage);
+        }
+        // Both storages contain a split with the same ID.
+        let split = SplitIdAndFooterOffsets {
+            split_id: "split".to_string(),
+            split_footer_start: 4,
+            split_footer_end: 12,
+            ..SplitIdAndFooterOffsets::default()
+        };
+        let searcher_context = SearcherContext::for_test();
+
+        for (index_storage, expected_footer) in
+            index_storages.iter().zip([b"footer-a", b"footer-b"])
+        {
+            // The second lookup hits the cache.
+            for _ in 0..2 {
+                let footer_bytes = get_split_footer_from_cache_or_fetch(
+                    index_storage.clone(),
+                    &split,
+                    &searcher_context.split_footer_cache,
+                    None,
+                )
+                .await
+                .unwrap();
+                assert_eq!(footer_bytes.as_slice(), expected_footer);
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_split_footer_cache_ttl() {
+        tokio::time::pause();
+        let index_storage: Arc<dyn Storage> =
+            Arc::new(quickwit_storage::RamStorage::builder().build());
+        let split = SplitIdAndFooterOffsets {
+            split_id: "split".to_string(),
+            split_footer_start: 4,
+            split_footer_end: 12,
+            ..SplitIdAndFooterOffsets::default()
+        };
+        let footer_cache_ttl_opt = Some(Duration::from_secs(60));
+        let searcher_context = SearcherContext::for_test();
+
+        let get_footer = || {
+            get_split_footer_from_cache_or_fetch(
+                index_storage.clone(),
+                &split,
+                &searcher_context.split_footer_cache,
+                footer_cache_ttl_opt,
+            )
+        };
+        index_storage
+            .put(Path::new("split.split"), Box::new(b"datafooter-1".to_vec()))
+            .await
+            .unwrap();
+        assert_eq!(get_footer().await.unwrap().as_slice(), b"footer-1");
+
+        // The split is rewritten in place: the cached footer is used until it expires.
+        index_storage
+            .put(Path::new("split.split"), Box::new(b"datafooter-2".to_vec()))
+            .await
+            .unwrap();
+        assert_eq!(get_footer().await.unwrap().as_slice(), b"footer-1");
+
+        tokio::time::advance(Duration::from_secs(60)).await;
+        assert_eq!(get_footer().await.unwrap().as_slice(), b"footer-2");
+    }
+
+    #[tokio::test]
+    async fn test_prefetch_split_footers() {
+        let split = SplitIdAndFooterOffsets {
+            split_id: "split".to_string(),
+            split_footer_start: 4,
+            split_footer_end: 10,
+            ..SplitIdAndFooterOffsets::default()
+        };
+        let missing_split = SplitIdAndFooterOffsets {
+            split_id: "missing-split".to_string(),
+            split_footer_start: 4,
+            split_footer_end: 10,
+            ..SplitIdAndFooterOffsets::default()
+        };
+        let index_storage: Arc<dyn Storage> = Arc::new(
+            quickwit_storage::RamStorage::builder()
+                .put("split.split", b"datafooter")
+                .build(),
+        );
+        let searcher_context = Arc::new(SearcherContext::for_test());
+        let num_prefetch_permits = searcher_context
+            .split_footer_prefetch_semaphore
+            .available_permits();
+
+        prefetch_split_footers(
+            searcher_context.clone(),
+            index_storage.clone(),
+            vec![split.clone(), missing_split.clone()],
+            Arc::new(Mutex::new(CanSplitDoBetter::Uninformative)),
+            false,
+        )
+        .await;
+
+        let footer_bytes = searcher_context
+            .split_footer_cache
+            .get(&split_footer_cache_key(&*index_storage, &split.split_id))
+            .unwrap();
+        assert_eq!(footer_bytes.as_slice(), b"footer");
+        assert!(searcher_context
+            .split_footer_cache
+            .get(&split_footer_cache_key(
+                &*index_storage,
+                &missing_split.split_id
+            ))
+            .is_none());
+        // All the permits were released.
+        assert_eq!(
+            searcher_context
+                .split_footer_prefetch_semaphore
+                .available_permits(),
+            num_prefetch_permits
+        );
+    }
+
+    #[tokio::test]
+    async fn test_prefetch_split_footers_does_not_take_split_search_permits() {
+        let split = SplitIdAndFooterOffsets {
+            split_id: "split".to_string(),
+            split_footer_start: 4,
+            split_footer_end: 10,
+            ..SplitIdAndFooterOffsets::default()
+        };
+        let index_storage: Arc<dyn Storage> = Arc::new(
+            quickwit_storage::RamStorage::builder()
+                .put("split.split", b"datafooter")
+                .build(),
+        );
+        let searcher_context = Arc::new(SearcherContext::for_test());
+        // All the split search permits are taken, e.g. by the split searches of the leaf search.
+        let num_split_search_permits = searcher_context
+            .leaf_search_split_semaphore
+            .available_permits();
+        let _split_search_permits = searcher_context
+            .leaf_search_split_semaphore
+            .clone()
+            .acquire_many_owned(num_split_search_permits as u32)
+            .await
+            .unwrap();
+
+        prefetch_split_footers(
+            searcher_context.clone(),
+            index_storage.clone(),
+            vec![split.clone()],
+            Arc::new(Mutex::new(CanSplitDoBetter::Uninformative)),
+            false,
+        )
+        .await;
+
+        assert!(searcher_context
+            .split_footer_cache
+            .get(&split_footer_cache_key(&*index_storage, &split.split_id))
+            .is_some());
+    }
+
+    #[tokio::test]
+    async fn test_prefetch_split_footers_skips_pruned_splits() {
+        let split = |split_id: &str| SplitIdAndFooterOffsets {
+            split_id: split_id.to_string(),
+            split_footer_start: 4,
+            split_footer_end: 10,
+            ..SplitIdAndFooterOffsets::default()
+        };
+        let index_storage: Arc<dyn Storage> = Arc::new(
+            quickwit_storage::RamStorage::builder()
+                .put("split-a.split", b"datafooter")
+                .put("split-b.split", b"datafooter")
+                .build(),
+        );
+        let is_footer_cached = |searcher_context: &SearcherContext, split_id: &str| {
+            searcher_context
+                .split_footer_cache
+                .get(&split_footer_cache_key(&*index_storage, split_id))
+                .is_some()
+        };
+        // Only splits with an ID higher than `split-b` can do better.
+        let split_filter = Arc::new(Mutex::new(CanSplitDoBetter::SplitIdHigher(Some(
+            "split-b".to_string(),
+        ))));
+
+        let searcher_context = Arc::new(SearcherContext::for_test());
+        prefetch_split_footers(
+            searcher_context.clone(),
+            index_storage.clone(),
+            vec![split("split-a"), split("split-b")],
+            split_filter.clone(),
+            false,
+        )
+        .await;
+        assert!(!is_footer_cached(&searcher_context, "split-a"));
+        assert!(is_footer_cached(&searcher_context, "split-b"));
+
+        // When all the splits are searched anyway, no split is skipped.
+        let searcher_context = Arc::new(SearcherContext::for_test());
+        prefetch_split_footers(
+            searcher_context.clone(),
+            index_storage.clone(),
+            vec![split("split-a"), split("split-b")],
+            split_filter,
+            true,
+        )
+        .await;
+        assert!(is_footer_cached(&searcher_context, "split-a"));
+        assert!(is_footer_cached(&searcher_context, "split-b"));
+    }
+
+    #[tokio::test]
+    async fn test_get_split_footer_typed_errors() {
+        let missing_split = SplitIdAndFooterOffsets {
+            split_id: "missing-split".to_string(),
+            split_footer_start: 4,
+            split_footer_end: 10,
+            ..SplitIdAndFooterOffsets::default()
+        };
+        let index_storage: Arc<dyn Storage> =
+            Arc::new(quickwit_storage::RamStorage::builder().build());
+        let searcher_context = SearcherContext::for_test();
+
+        let error = get_split_footer_from_cache_or_fetch(
+            index_storage.clone(),
+            &missing_split,
+            &searcher_context.split_footer_cache,
+            None,
+        )
+        .await
+        .unwrap_err();
+        let leaf_error = LeafSplitSearchError::from(error);
+        assert_eq!(leaf_error.kind, SplitSearchErrorKind::StorageIo);
+        assert!(!leaf_error.error.is_retryable_split_error());
+        assert!(!leaf_error.is_retryable_in_place());
+        let SearchError::SplitNotFound {
+            split_id,
+            storage_uri,
+        } = &leaf_error.error
+        else {
+            panic!(
+                "expected `SplitNotFound` error, got `{:?}`",
+                leaf_error.error
+            );
+        };
+        assert_eq!(split_id, "missing-split");
+        assert_eq!(storage_uri, &index_storage.uri().to_string());
+
+        let storage_uri = Uri::for_test("s3://bucket/indexes/my-index");
+        let unauthorized_error = footer_fetch_error(
+            StorageErrorKind::Unauthorized.with_error(anyhow::anyhow!("access denied")),
+            &storage_uri,
+            "split",
+        );
+        let search_error = SearchError::from(unauthorized_error);
+        assert!(matches!(search_error, SearchError::StorageUnauthorized(_)));
+        assert!(!search_error.is_retryable_split_error());
+
+        let timeout_error = footer_fetch_error(
+            StorageErrorKind::Timeout.with_error(anyhow::anyhow!("request timed out")),
+            &storage_uri,
+            "split",
+        );
+        let leaf_error = LeafSplitSearchError::from(timeout_error);
+        assert_eq!(leaf_error.kind, SplitSearchErrorKind::Timeout);
+        assert!(matches!(leaf_error.error, SearchError::StorageTimeout(_)));
+        assert!(leaf_error.error.is_retryable_split_error());
+        assert!(leaf_error.is_retryable_in_place());
+
+        let io_error = footer_fetch_error(
+            StorageErrorKind::Io.with_error(anyhow::anyhow!("connection reset")),
+            &storage_uri,
+            "split",
+        );
+        let leaf_error = LeafSplitSearchError::from(io_error);
+        assert_eq!(leaf_error.kind, SplitSearchErrorKind::StorageIo);
+        assert!(matches!(leaf_error.error, SearchError::Internal(_)));
+        assert!(leaf_error.is_retryable_in_place());
+
+        let search_timeout_error =
+            LeafSplitSearchError::from(SearchError::Timeout("deadline exceeded".to_string()));
+        assert!(!search_timeout_error.is_retryable_in_place());
+        let query_error =
+            LeafSplitSearchError::from(SearchError::InvalidQuery("invalid".to_string()));
+        assert!(!query_error.is_retryable_in_place());
+    }
+
+    #[tokio::test]
+    async fn test_try_join_all_with_permits() {
+        let warmup_semaphore = Semaphore::new(2);
+        let num_in_flight = AtomicUsize::new(0);
+        let max_num_in_flight = AtomicUsize::new(0);
+        let warm_up_futures: Vec<_> = (0..10)
+            .map(|i| {
+                let num_in_flight = &num_in_flight;
+                let max_num_in_flight = &max_num_in_flight;
+                async move {
+                    let current = num_in_flight.fetch_add(1, Ordering::SeqCst) + 1;
+                    max_num_in_flight.fetch_max(current, Ordering::SeqCst);
+                    tokio::task::yield_now().await;
+                    num_in_flight.fetch_sub(1, Ordering::SeqCst);
+                    Ok::<usize, anyhow::Error>(i)
+                }
+            })
+            .collect();
+        let results = try_join_all_with_permits(&warmup_semaphore, warm_up_futures)
+            .await
+            .unwrap();
+        assert_eq!(results, (0..10).collect::<Vec<_>>());
+        assert_eq!(max_num_in_flight.load(Ordering::SeqCst), 2);
+    }
+
+    #[test]
+    fn test_clamp_term_range_limit() {
+        assert_eq!(clamp_term_range_limit(Some(10), None), Some(10));
+        assert_eq!(clamp_term_range_limit(None, None), None);
+
+        assert_eq!(clamp_term_range_limit(Some(10), Some(1_000)), Some(10));
+        assert_eq!(
+            clamp_term_range_limit(Some(1_000), Some(1_000)),
+            Some(1_000)
+        );
+        assert_eq!(
+            clamp_term_range_limit(Some(u64::MAX), Some(1_000)),
+            Some(1_000)
+        );
+        assert_eq!(clamp_term_range_limit(None, Some(1_000)), Some(1_000));
+    }
+
+    #[test]
+    fn test_split_metadata_may_match() {
+        let split = SplitIdAndFooterOffsets {
+            split_id: "split".to_string(),
+            timestamp_start: Some(100),
+            timestamp_end: Some(200),
+            ..SplitIdAndFooterOffsets::default()
+        };
+        let may_match =
+            |start_timestamp: Option<i64>, end_timestamp: Option<i64>, user_query: &str| {
+                let search_request = SearchRequest {
+                    start_timestamp,
+                    end_timestamp,
+                    ..SearchRequest::default()
+                };
+                let query_ast = qast_helper(user_query, &[]);
+                split_metadata_may_match(&split, &search_request, &query_ast)
+            };
+        assert!(may_match(None, None, "body:hello"));
+        assert!(may_match(Some(200), None, "body:hello"));
+        assert!(!may_match(Some(201), None, "body:hello"));
+        assert!(may_match(None, Some(101), "body:hello"));
+        assert!(!may_match(None, Some(100), "body:hello"));
+
+        let search_request = SearchRequest::default();
+        assert!(!split_metadata_may_match(
+            &split,
+            &search_request,
+            &QueryAst::MatchNone
+        ));
+
+        // Splits without a time range are never skipped because of it.
+        let split = SplitIdAndFooterOffsets {
+            split_id: "split".to_string(),
+            ..SplitIdAndFooterOffsets::default()
+        };
+        let search_request = SearchRequest {
+            start_timestamp: Some(201),
+            ..SearchRequest::default()
+        };
+        let query_ast = qast_helper("body:hello", &[]);
+        assert!(split_metadata_may_match(
+            &split,
+            &search_request,
+            &query_ast
+        ));
+    }
+
+    #[test]
+    fn test_query_may_match_split() {
+        let mut schema_builder = Schema::builder();
+        schema_builder.add_text_field("body", tantivy::schema::TEXT);
+        schema_builder.add_json_field("attributes", tantivy::schema::TEXT);
+        let split_schema = schema_builder.build();
+
+        let query_may_match = |user_query: &str| {
+            let query_ast = qast_helper(user_query, &[]);
+            query_may_match_split(&query_ast, &split_schema)
+        };
+        assert!(query_may_match("body:hello"));
+        assert!(query_may_match("attributes.color:red"));
+        assert!(!query_may_match("title:hello"));
+        assert!(!query_may_match("title:hello AND body:hello"));
+        assert!(query_may_match("title:hello OR body:hello"));
+        assert!(!query_may_match("title:hello OR subtitle:hello"));
+        assert!(query_may_match("NOT title:hello"));
+        assert!(query_may_match("*"));
+
+        let mut schema_builder = Schema::builder();
+        schema_builder.add_text_field("body", tantivy::schema::TEXT);
+        schema_builder.add_json_field(DYNAMIC_FIELD_NAME, tantivy::schema::TEXT);
+        let split_schema = schema_builder.build();
+        let query_ast = qast_helper("title:hello", &[]);
+        assert!(query_may_match_split(&query_ast, &split_schema));
+    }
+
+    #[test]
+    fn test_is_query_bounded_by_terms() {
+        let is_query_bounded_by_terms = |user_query: &str| {
+            let query_ast = qast_helper(user_query, &[]);
+            is_query_bounded_by_terms(&query_ast, &|field_name| field_name != "fast_only")
+        };
+        assert!(is_query_bounded_by_terms("body:hello"));
+        assert!(is_query_bounded_by_terms("body:hello AND title:world"));
+        assert!(is_query_bounded_by_terms("body:hello OR title:world"));
+        assert!(!is_query_bounded_by_terms("fast_only:hello"));
+        assert!(!is_query_bounded_by_terms("body:hello OR fast_only:hello"));
+        assert!(!is_query_bounded_by_terms("NOT body:hello"));
+        assert!(!is_query_bounded_by_terms("body:hello AND NOT title:world"));
+        assert!(!is_query_bounded_by_terms("count:>10"));
+        assert!(!is_query_bounded_by_terms("*"));
+        assert!(!is_query_bounded_by_terms("body:*"));
+    }
+
+    #[test]
+    fn test_check_split_footer_size() {
+        let split = SplitIdAndFooterOffsets {
+            split_id: "split".to_string(),
+            split_footer_start: 500,
+            split_footer_end: 1_500,
+            ..Default::default()
+        };
+        check_split_footer_size(&split, None).unwrap();
+        check_split_footer_size(&split, Some(ByteSize::b(1_000))).unwrap();
+        let error = check_split_footer_size(&split, Some(ByteSize::b(999))).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "footer of split `split` is too large (1000 bytes, the limit is 999 bytes)"
+        );
+        // The split would fail the same way on any searcher.
+        assert!(!error.is_retryable_split_error());
+        let leaf_error = LeafSplitSearchError::from(anyhow::Error::from(error));
+        assert_eq!(leaf_error.kind, SplitSearchErrorKind::Corruption);
+        assert!(!leaf_error.is_retryable_in_place());
+    }
+
+    #[test]
+    fn test_apply_split_id_overrides() {
+        let splits: Vec<SplitIdAndFooterOffsets> = (0..4)
+            .map(|split_idx| SplitIdAndFooterOffsets {
+                split_id: format!("split_{split_idx}"),
+                ..Default::default()
+            })
+            .collect();
+        let split_ids = |splits: &[SplitIdAndFooterOffsets]| -> Vec<String> {
+            splits.iter().map(|split| split.split_id.clone()).collect()
+        };
+        let mut searcher_config = SearcherConfig::default();
+
+        let mut request = SearchRequest::default();
+        let mut filtered_splits = splits.clone();
+        apply_split_id_overrides(&mut filtered_splits, &request, &searcher_config).unwrap();
+        assert_eq!(filtered_splits, splits);
+
+        request.split_id_allowlist = vec!["split_1".to_string(), "split_2".to_string()];
+        let mut filtered_splits = splits.clone();
+        apply_split_id_overrides(&mut filtered_splits, &request, &searcher_config).unwrap();
+        assert_eq!(split_ids(&filtered_splits), ["split_1", "split_2"]);
+
+        // The denylist takes precedence.
+        request.split_id_denylist = vec!["split_2".to_string()];
+        let mut filtered_splits = splits.clone();
+        apply_split_id_overrides(&mut filtered_splits, &request, &searcher_config).unwrap();
+        assert_eq!(split_ids(&filtered_splits), ["split_1"]);
+
+        request.split_id_allowlist.clear();
+        let mut filtered_splits = splits.clone();
+        apply_split_id_overrides(&mut filtered_splits, &request, &searcher_config).unwrap();
+        assert_eq!(
+            split_ids(&filtered_splits),
+            ["split_0", "split_1", "split_3"]
+        );
+
+        searcher_config.enable_split_id_overrides = false;
+        let mut filtered_splits = splits.clone();
+        let error =
+            apply_split_id_overrides(&mut filtered_splits, &request, &searcher_config).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "Invalid argument: split ID allowlists and denylists are disabled on this searcher"
+        );
+    }
+
+    #[test]
+    fn test_check_leaf_request_size() {
+        let splits: Vec<SplitIdAndFooterOffsets> = (0..3)
+            .map(|split_idx| SplitIdAndFooterOffsets {
+                split_id: format!("split_{split_idx}"),
+                split_footer_start: 900,
+                split_footer_end: 1_000,
+                ..Default::default()
+            })
+            .collect();
+        let mut searcher_config = SearcherConfig::default();
+        check_leaf_request_size(&splits, &searcher_config).unwrap();
+
+        searcher_config.max_splits_per_leaf_request = Some(3);
+        searcher_config.max_split_bytes_per_leaf_request = Some(ByteSize::b(3_000));
+        check_leaf_request_size(&splits, &searcher_config).unwrap();
+
+        searcher_config.max_splits_per_leaf_request = Some(2);
+        let error = check_leaf_request_size(&splits, &searcher_config).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "Invalid argument: leaf search request targets too many splits (3 splits, the limit \
+             is 2 splits)"
+        );
+
+        searcher_config.max_splits_per_leaf_request = None;
+        searcher_config.max_split_bytes_per_leaf_request = Some(ByteSize::b(2_999));
+        let error = check_leaf_request_size(&splits, &searcher_config).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "Invalid argument: leaf search request targets too many split bytes (3000 bytes, the \
+             limit is 2999 bytes)"
+        );
+    }
+
+    #[test]
+    fn test_check_term_dict_warmup_policy() {
+        let mut schema_builder = tantivy::schema::Schema::builder();
+        let service_field = schema_builder.add_text_field("service", tantivy::schema::STRING);
+        let trace_id_field = schema_builder.add_text_field("trace_id", tantivy::schema::STRING);
+        let index = Index::create_in_ram(schema_builder.build());
+        let searcher = index.reader().unwrap().searcher();
+
+        let warmup_info = WarmupInfo {
+            term_dict_fields: HashSet::from([service_field, trace_id_field]),
+            ..Default::default()
+        };
+        check_term_dict_warmup_policy(&searcher, &warmup_info, None).unwrap();
+
+        let allow_list =
+            TermDictWarmupPolicy::AllowList(["service".to_string(), "trace_id".to_string()].into());
+        check_term_dict_warmup_policy(&searcher, &warmup_info, Some(&allow_list)).unwrap();
+
+        let deny_list = TermDictWarmupPolicy::DenyList(["trace_id".to_string()].into());
+        let error =
+            check_term_dict_warmup_policy(&searcher, &warmup_info, Some(&deny_list)).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "Invalid argument: the term dictionary of field `trace_id` cannot be warmed up, as \
+             per the search settings of the index"
+        );
+    }
+
+    #[test]
+    fn test_check_warmup_budget() {
+        let mut schema_builder = tantivy::schema::Schema::builder();
+        let text_field = schema_builder.add_text_field("text", tantivy::schema::STRING);
+        let index = Index::create_in_ram(schema_builder.build());
+        let mut index_writer = index.writer(50_000_000).unwrap();
+        for term_id in 0..1_000 {
+            index_writer
+                .add_document(tantivy::doc!(text_field => format!("term-{term_id}")))
+                .unwrap();
+        }
+        index_writer.commit().unwrap();
+        let searcher = index.reader().unwrap().searcher();
+
+        let warmup_info = WarmupInfo {
+            term_dict_fields: HashSet::from([text_field]),
+            ..Default::default()
+        };
+        let warmup_num_bytes = estimate_warmup_num_bytes(&searcher, &warmup_info).unwrap();
+        assert!(warmup_num_bytes > 0);
+
+        // Without a budget, the estimate is skipped.
+        assert_eq!(
+            check_warmup_budget(&searcher, &warmup_info, None).unwrap(),
+            None
+        );
+        assert_eq!(
+            check_warmup_budget(&searcher, &warmup_info, Some(ByteSize::b(warmup_num_bytes)))
+                .unwrap(),
+            Some(warmup_num_bytes)
+        );
+        let error = check_warmup_budget(
+            &searcher,
+            &warmup_info,
+            Some(ByteSize::b(warmup_num_bytes - 1)),
+        )
+        .unwrap_err();
+        assert!(matches!(
+            error,
+            SearchError::WarmupBudgetExceeded {
+                warmup_num_bytes: num_bytes,
+                ..
+            } if num_bytes == warmup_num_bytes
+        ));
+
+        // An empty warmup always fits in the budget.
+        check_warmup_budget(&searcher, &WarmupInfo::default(), Some(ByteSize::b(0))).unwrap();
+    }
+
+    #[test]
+    fn test_is_count_only_request() {
+        let count_request = SearchRequest {
+            max_hits: 0,
+            count_hits: CountHits::CountAll as i32,
+            ..SearchRequest::default()
+        };
+        assert!(is_count_only_request(&count_request));
+
+        let underestimate_request = SearchRequest {
+            count_hits: CountHits::Underestimate as i32,
+            ..count_request.clone()
+        };
+        assert!(!is_count_only_request(&underestimate_request));
+
+        let hits_request = SearchRequest {
+            max_hits: 10,
+            ..count_request.clone()
+        };
+        assert!(!is_count_only_request(&hits_request));
+
+        let aggregation_request = SearchRequest {
+            aggregation_request: Some(r#"{"avg_price": {"avg": {"field": "price"}}}"#.to_string()),
+            ..count_request.clone()
+        };
+        assert!(!is_count_only_request(&aggregation_request));
+
+        let sorted_request = SearchRequest {
+            sort_fields: vec![SortField {
+                field_name: "timestamp".to_string(),
+                sort_order: SortOrder::Desc as i32,
+                sort_datetime_format: None,
+            }],
+            ..count_request
+        };
+        assert!(!is_count_only_request(&sorted_request));
+    }
+
+    #[test]
+    fn test_split_cache_priority() {
+        assert_eq!(
+            split_cache_priority(&SearchRequest::default()),
+            CachePriority::Normal
+        );
+        let background_request = SearchRequest {
+            priority: SearchPriority::Background as i32,
+            ..Default::default()
+        };
+        assert_eq!(
+            split_cache_priority(&background_request),
+            CachePriority::Low
+        );
+        let scroll_request = SearchRequest {
+            scroll_ttl_secs: Some(60),
+            ..Default::default()
+        };
+        assert_eq!(split_cache_priority(&scroll_request), CachePriority::Low);
+    }
+
+    #[test]
+    fn test_timestamp_bound_to_query_literal() {
+        // 2021-01-01T00:00:00Z
+        let timestamp = DateTime::from_timestamp_secs(1_609_459_200);
+        assert_eq!(
+            timestamp_bound_to_query_literal(Bound::Included(timestamp)),
+            Bound::Included(quickwit_query::JsonLiteral::Number(
+                1_609_459_200_000_000_000i64.into()
+            ))
+        );
+        assert_eq!(
+            timestamp_bound_to_query_literal(Bound::Excluded(timestamp)),
+            Bound::Excluded(quickwit_query::JsonLiteral::Number(
+                1_609_459_200_000_000_000i64.into()
+            ))
+        );
+        assert_eq!(
+            timestamp_bound_to_query_literal(Bound::Unbounded),
+            Bound::Unbounded
+        );
+    }
+
+    #[test]
+    fn test_is_doc_order_early_termination_request() {
+        let doc_sort_field = |sort_order: SortOrder| SortField {
+            field_name: "_doc".to_string(),
+            sort_order: sort_order as i32,
+            sort_datetime_format: None,
+        };
+        let request = SearchRequest {
+            max_hits: 10,
+            count_hits: CountHits::Underestimate as i32,
+            sort_fields: vec![doc_sort_field(SortOrder::Asc)],
+            ..SearchRequest::default()
+        };
+        assert!(is_doc_order_early_termination_request(&request));
+
+        let count_all_request = SearchRequest {
+            count_hits: CountHits::CountAll as i32,
+            ..request.clone()
+        };
+        assert!(!is_doc_order_early_termination_request(&count_all_request));
+
+        let desc_request = SearchRequest {
+            sort_fields: vec![doc_sort_field(SortOrder::Desc)],
+            ..request.clone()
+        };
+        assert!(!is_doc_order_early_termination_request(&desc_request));
+
+        let aggregation_request = SearchRequest {
+            aggregation_request: Some(r#"{"avg_price": {"avg": {"field": "price"}}}"#.to_string()),
+            ..request.clone()
+        };
+        assert!(!is_doc_order_early_termination_request(
+            &aggregation_request
+        ));
+
+        let search_after_request = SearchRequest {
+            search_after: Some(PartialHit::default()),
+            ..request.clone()
+        };
+        assert!(!is_doc_order_early_termination_request(
+            &search_after_request
+        ));
+
+        // The sort is cleared when no hit is requested.
+        let mut no_hits_request = SearchRequest {
+            max_hits: 0,
+            ..request
+        };
+        rewrite_request(
+            &mut no_hits_request,
+            &SplitIdAndFooterOffsets::default(),
+            None,
+            None,
+        );
+        assert!(no_hits_request.sort_fields.is_empty());
+        assert!(!is_doc_order_early_termination_request(&no_hits_request));
+    }
+
+    #[test]
+    fn test_collect_first_docs_in_doc_order() {
+        let mut schema_builder = Schema::builder();
+        let body_field = schema_builder.add_text_field("body", tantivy::schema::TEXT);
+        let index = Index::create_in_ram(schema_builder.build());
+        let mut index_writer = index.writer_with_num_threads(1, 15_000_000).unwrap();
+        // Two segments, with 3 matching documents each.
+        for _ in 0..2 {
+            for body in ["hello", "goodbye", "hello", "hello"] {
+                index_writer
+                    .add_document(tantivy::doc!(body_field => body))
+                    .unwrap();
+            }
+            index_writer.commit().unwrap();
+        }
+        let searcher = index.reader().unwrap().searcher();
+        assert_eq!(searcher.segment_readers().len(), 2);
+        let query = tantivy::query::TermQuery::new(
+            Term::from_field_text(body_field, "hello"),
+            tantivy::schema::IndexRecordOption::Basic,
+        );
+        let hit_addresses = |num_hits: usize| -> Vec<(u32, u32)> {
+            let leaf_search_response =
+                collect_first_docs_in_doc_order(&searcher, &query, "split".to_string(), num_hits)
+                    .unwrap();
+            assert_eq!(
+                leaf_search_response.num_hits,
+                leaf_search_response.partial_hits.len() as u64
+            );
+            leaf_search_response
+                .partial_hits
+                .iter()
+                .map(|partial_hit| (partial_hit.segment_ord, partial_hit.doc_id))
+                .collect()
+        };
+        assert_eq!(hit_addresses(2), [(0, 0), (0, 2)]);
+        assert_eq!(hit_addresses(4), [(0, 0), (0, 2), (0, 3), (1, 0)]);
+        assert_eq!(hit_addresses(10).len(), 6);
+    }
+
+    #[test]
+    fn test_remove_timestamp_range_relative_bounds() {
+        const S_TO_NS: i64 = 1_000_000_000;
+        let now = 1700001000;
+        let timestamp_field = "timestamp".to_string();
+
+        let split = SplitIdAndFooterOffsets {
+            timestamp_start: Some(now - 7200),
+            timestamp_end: Some(now + 7200),
+            ..SplitIdAndFooterOffsets::default()
+        };
+        let range_request = |lower_bound: &str, upper_bound: &str| SearchRequest {
+            query_ast: serde_json::to_string(&QueryAst::Range(RangeQuery {
+                field: timestamp_field.to_string(),
+                lower_bound: Bound::Included(lower_bound.to_string().into()),
+                upper_bound: Bound::Excluded(upper_bound.to_string().into()),
+            }))
+            .unwrap(),
+            now_timestamp_nanos: Some(now * S_TO_NS),
+            ..SearchRequest::default()
+        };
+
+        remove_timestamp_test_case(
+            &range_request("now-15m", "now"),
+            &split,
+            Some(RangeQuery {
+                field: timestamp_field.to_string(),
+                lower_bound: Bound::Included(((now - 900) * S_TO_NS).into()),
+                upper_bound: Bound::Excluded((now * S_TO_NS).into()),
+            }),
+        );
+        remove_timestamp_test_case(
+            &range_request("now", "now+1h"),
+            &split,
+            Some(RangeQuery {
+                field: timestamp_field.to_string(),
+                lower_bound: Bound::Included((now * S_TO_NS).into()),
+                upper_bound: Bound::Excluded(((now + 3600) * S_TO_NS).into()),
+            }),
+        );
+        // relative bounds are pruned like absolute ones
+        remove_timestamp_test_case(&range_request("now-1d", "now+1d"), &split, None);
+    }
+
+    #[test]
+    fn test_remove_timestamp_range_point_term() {
+        const S_TO_NS: i64 = 1_000_000_000;
+        let time1 = 1700001000;
+        let time2 = 1700002000; // 2023-11-14T22:46:40Z
+        let time3 = 1700003000;
+        let time4 = 1700004000;
+        let timestamp_field = "timestamp".to_string();
+
+        let term_request = |field: &str, value: &str| SearchRequest {
+            query_ast: serde_json::to_string(&QueryAst::Term(TermQuery {
+                field: field.to_string(),
+                value: value.to_string(),
+            }))
+            .unwrap(),
+            ..SearchRequest::default()
+        };
+        let point_filter = |timestamp_nanos: i64, value: &str| -> QueryAst {
+            BoolQuery {
+                must: vec![QueryAst::Term(TermQuery {
+                    field: timestamp_field.to_string(),
+                    value: value.to_string(),
+                })],
+                filter: vec![RangeQuery {
+                    field: timestamp_field.to_string(),
+                    lower_bound: Bound::Included(timestamp_nanos.into()),
+                    upper_bound: Bound::Included(timestamp_nanos.into()),
+                }
+                .into()],
+                ..Default::default()
+            }
+            .into()
+        };
+
+        // the split contains the instant: both bounds are set to it
+        let split = SplitIdAndFooterOffsets {
+            timestamp_start: Some(time1),
+            timestamp_end: Some(time3),
+            ..SplitIdAndFooterOffsets::default()
+        };
+        let mut request = term_request(&timestamp_field, "2023-11-14T22:46:40Z");
+        remove_redundant_timestamp_range(&mut request, &split, &timestamp_field);
+        assert_ast_eq(
+            &request,
+            &point_filter(time2 * S_TO_NS, "2023-11-14T22:46:40Z"),
+        );
+
+        // sub-second instants are kept exact
+        let mut request = term_request(&timestamp_field, "2023-11-14T22:46:40.5Z");
+        remove_redundant_timestamp_range(&mut request, &split, &timestamp_field);
+        assert_ast_eq(
+            &request,
+            &point_filter(time2 * S_TO_NS + S_TO_NS / 2, "2023-11-14T22:46:40.5Z"),
+        );
+
+        // the split starts after the instant: the upper bound alone rules out the whole split
+        let split = SplitIdAndFooterOffsets {
+            timestamp_start: Some(time3),
+            timestamp_end: Some(time4),
+            ..SplitIdAndFooterOffsets::default()
+        };
+        let mut request = term_request(&timestamp_field, "2023-11-14T22:46:40Z");
+        remove_redundant_timestamp_range(&mut request, &split, &timestamp_field);
+        let expected_ast: QueryAst = BoolQuery {
+            must: vec![QueryAst::Term(TermQuery {
+                field: timestamp_field.to_string(),
+                value: "2023-11-14T22:46:40Z".to_string(),
+            })],
+            filter: vec![RangeQuery {
+                field: timestamp_field.to_string(),
+                lower_bound: Bound::Unbounded,
+                upper_bound: Bound::Included((time2 * S_TO_NS).into()),
+            }
+            .into()],
+            ..Default::default()
+        }
+        .into();
+        assert_ast_eq(&request, &expected_ast);
+        assert!(time2 * S_TO_NS < split.timestamp_start_nanos().unwrap());
+
+        // terms on other fields don't affect the bounds
+        let mut request = term_request("body", "2023-11-14T22:46:40Z");
+        remove_redundant_timestamp_range(&mut request, &split, &timestamp_field);
+        assert_ast_eq(
+            &request,
+            &QueryAst::Term(TermQuery {
+                field: "body".to_string(),
+                value: "2023-11-14T22:46:40Z".to_string(),
+            }),
+        );
+    }
+
+    #[test]
+    fn test_remove_timestamp_range_end_timestamp_inclusive() {
+        const S_TO_NS: i64 = 1_000_000_000;
+        let time = 1700001000;
+        let timestamp_field = "timestamp".to_string();
+
+        let split = SplitIdAndFooterOffsets {
+            timestamp_start: Some(time),
+            timestamp_end: Some(time + 10),
+            ..SplitIdAndFooterOffsets::default()
+        };
+        let mut search_request = SearchRequest {
+            query_ast: serde_json::to_string(&QueryAst::MatchAll).unwrap(),
+            end_timestamp: Some(time + 10),
+            ..SearchRequest::default()
+        };
+        // an exclusive end on the split end misses the last second of the split...
+        remove_timestamp_test_case(
+            &search_request,
+            &split,
+            Some(RangeQuery {
+                field: timestamp_field.to_string(),
+                lower_bound: Bound::Unbounded,
+                upper_bound: Bound::Excluded(((time + 10) * S_TO_NS).into()),
+            }),
+        );
+        // ... while an inclusive one covers the entire split
+        search_request.end_timestamp_inclusive = true;
+        remove_timestamp_test_case(&search_request, &split, None);
+
+        let mut request_direct = search_request.clone();
+        remove_redundant_timestamp_range(&mut request_direct, &split, &timestamp_field);
+        assert!(!request_direct.end_timestamp_inclusive);
+
+        // an inclusive end within the split covers its entire last second
+        search_request.end_timestamp = Some(time + 5);
+        remove_timestamp_test_case(
+            &search_request,
+            &split,
+            Some(RangeQuery {
+                field: timestamp_field.to_string(),
+                lower_bound: Bound::Unbounded,
+                upper_bound: Bound::Excluded(((time + 6) * S_TO_NS).into()),
+            }),
+        );
+    }
+
+    #[test]
+    fn test_clamp_timestamp_bounds() {
+        const S_TO_NS: i64 = 1_000_000_000;
+        let date_time = DateTime::from_timestamp_secs;
+
+        // the split contains documents from 1000s to 2000.999999999s
+        let split = SplitIdAndFooterOffsets {
+            timestamp_start: Some(1000),
+            timestamp_end: Some(2000),
+            ..SplitIdAndFooterOffsets::default()
+        };
+        assert_eq!(
+            split.clamp_timestamp_bounds(Bound::Unbounded, Bound::Unbounded),
+            (Bound::Unbounded, Bound::Unbounded)
+        );
+        // bounds covering the entire split are removed...
+        assert_eq!(
+            split.clamp_timestamp_bounds(
+                Bound::Included(date_time(1000)),
+                Bound::Excluded(date_time(2001))
+            ),
+            (Bound::Unbounded, Bound::Unbounded)
+        );
+        assert_eq!(
+            split.clamp_timestamp_bounds(
+                Bound::Excluded(DateTime::from_timestamp_nanos(1000 * S_TO_NS - 1)),
+                Bound::Included(DateTime::from_timestamp_nanos(2001 * S_TO_NS - 1))
+            ),
+            (Bound::Unbounded, Bound::Unbounded)
+        );
+        // ... while bounds cutting through the split are kept
+        assert_eq!(
+            split.clamp_timestamp_bounds(
+                Bound::Excluded(date_time(1000)),
+                Bound::Excluded(date_time(2000))
+            ),
+            (
+                Bound::Excluded(date_time(1000)),
+                Bound::Excluded(date_time(2000))
+            )
+        );
+        assert_eq!(
+            split.clamp_timestamp_bounds(
+                Bound::Included(date_time(1500)),
+                Bound::Included(date_time(2000))
+            ),
+            (
+                Bound::Included(date_time(1500)),
+                Bound::Included(date_time(2000))
+            )
+        );
+
+        // bounds are left untouched if the split has no time range
+        let split = SplitIdAndFooterOffsets::default();
+        assert_eq!(
+            split.clamp_timestamp_bounds(
+                Bound::Included(date_time(1000)),
+                Bound::Excluded(date_time(2001))
+            ),
+            (
+                Bound::Included(date_time(1000)),
+                Bound::Excluded(date_time(2001))
+            )
+        );
+    }
+
+    #[test]
+    fn test_rewrite_start_end_time_bounds_end_timestamp_inclusive() {
+        let split = SplitIdAndFooterOffsets {
+            timestamp_start: Some(1000),
+            timestamp_end: Some(2000),
+            ..SplitIdAndFooterOffsets::default()
+        };
+        let mut start_timestamp_opt = Some(1000);
+        let mut end_timestamp_opt = Some(2000);
+        rewrite_start_end_time_bounds(
+            &mut start_timestamp_opt,
+            &mut end_timestamp_opt,
+            false,
+            &split,
+        );
+        assert_eq!(start_timestamp_opt, None);
+        assert_eq!(end_timestamp_opt, Some(2000));
+
+        let mut end_timestamp_opt = Some(2000);
+        rewrite_start_end_time_bounds(&mut None, &mut end_timestamp_opt, true, &split);
+        assert_eq!(end_timestamp_opt, None);
 
-        // test the query directly
-        let mut request_direct = request.clone();
-        remove_redundant_timestamp_range(&mut request_direct, split, timestamp_field);
-        let expected_direct = expected
-            .clone()
-            .map(bool_filter)
-            .unwrap_or(QueryAst::MatchAll);
-        assert_ast_eq(&request_direct, &expected_direct);
+        let mut end_timestamp_opt = Some(1999);
+        rewrite_start_end_time_bounds(&mut None, &mut end_timestamp_opt, true, &split);
+        assert_eq!(end_timestamp_opt, Some(1999));
     }
 
     #[test]
-    fn test_remove_timestamp_range() {
+    fn test_remove_timestamp_range_sub_second_bounds() {
         const S_TO_NS: i64 = 1_000_000_000;
-        let time1 = 1700001000;
-        let time2 = 1700002000;
-        let time3 = 1700003000;
-        let time4 = 1700004000;
-
+        let time = 1700001000;
         let timestamp_field = "timestamp".to_string();
 
-        // cases where the bounds are larger than the split: no bound is emited
+        // the split contains documents from `time` to `time + 10.999s`
         let split = SplitIdAndFooterOffsets {
-            timestamp_start: Some(time2),
-            timestamp_end: Some(time3),
+            timestamp_start: Some(time),
+            timestamp_end: Some(time + 10),
             ..SplitIdAndFooterOffsets::default()
         };
-
-        let search_request = SearchRequest {
-            query_ast: serde_json::to_string(&QueryAst::Range(RangeQuery {
-                field: timestamp_field.to_string(),
-                lower_bound: Bound::Included(time1.into()),
-                // *1000 has no impact, we detect timestamp in ms instead of s
-                upper_bound: Bound::Included((time4 * 1000).into()),
-            }))
-            .unwrap(),
-            ..SearchRequest::default()
-        };
-        remove_timestamp_test_case(&search_request, &split, None);
-
-        let search_request = SearchRequest {
+        let range_request = |lower_bound: Bound<i64>, upper_bound: Bound<i64>| SearchRequest {
             query_ast: serde_json::to_string(&QueryAst::Range(RangeQuery {
                 field: timestamp_field.to_string(),
-                lower_bound: Bound::Included(time1.into()),
-                upper_bound: Bound::Included(time3.into()),
+                // timestamps in nanoseconds are detected as such
+                lower_bound: map_bound(lower_bound, Into::into),
+                upper_bound: map_bound(upper_bound, Into::into),
             }))
             .unwrap(),
             ..SearchRequest::default()
         };
-        remove_timestamp_test_case(&search_request, &split, None);
-
-        let search_request = SearchRequest {
-            query_ast: serde_json::to_string(&QueryAst::MatchAll).unwrap(),
-            start_timestamp: Some(time1),
-            end_timestamp: Some(time4),
-            ..SearchRequest::default()
-        };
-        remove_timestamp_test_case(&search_request, &split, None);
 
-        // request bound that are exclusive are treated properly
-        let expected_upper_exclusive = RangeQuery {
-            field: timestamp_field.to_string(),
-            lower_bound: Bound::Unbounded,
-            upper_bound: Bound::Excluded((time3 * S_TO_NS).into()),
-        };
-        let search_request = SearchRequest {
-            query_ast: serde_json::to_string(&QueryAst::Range(RangeQuery {
+        // an upper bound in the middle of the last second of the split is kept...
+        let upper_bound_ns = (time + 10) * S_TO_NS + 500_000_000;
+        remove_timestamp_test_case(
+            &range_request(Bound::Unbounded, Bound::Included(upper_bound_ns)),
+            &split,
+            Some(RangeQuery {
                 field: timestamp_field.to_string(),
-                lower_bound: Bound::Included(time1.into()),
-                upper_bound: Bound::Excluded(time3.into()),
-            }))
-            .unwrap(),
-            ..SearchRequest::default()
-        };
+                lower_bound: Bound::Unbounded,
+                upper_bound: Bound::Included(upper_bound_ns.into()),
+            }),
+        );
+        // ... while an upper bound past the last nanosecond of the split is removed
         remove_timestamp_test_case(
-            &search_request,
+            &range_request(Bound::Unbounded, Bound::Excluded((time + 11) * S_TO_NS)),
             &split,
-            Some(expected_upper_exclusive.clone()),
+            None,
         );
-
-        let search_request = SearchRequest {
-            query_ast: serde_json::to_string(&QueryAst::MatchAll).unwrap(),
-            start_timestamp: Some(time1),
-            end_timestamp: Some(time3),
-            ..SearchRequest::default()
-        };
         remove_timestamp_test_case(
-            &search_request,
+            &range_request(Bound::Unbounded, Bound::Included((time + 11) * S_TO_NS - 1)),
             &split,
-            Some(expected_upper_exclusive.clone()),
+            None,
         );
 
-        let expected_lower_exclusive = RangeQuery {
-            field: timestamp_field.to_string(),
-            lower_bound: Bound::Excluded((time2 * S_TO_NS).into()),
-            upper_bound: Bound::Unbounded,
-        };
-        let search_request = SearchRequest {
-            query_ast: serde_json::to_string(&QueryAst::Range(RangeQuery {
+        // a lower bound in the middle of the first second of the split is kept...
+        let lower_bound_ns = time * S_TO_NS + 500_000_000;
+        remove_timestamp_test_case(
+            &range_request(Bound::Included(lower_bound_ns), Bound::Unbounded),
+            &split,
+            Some(RangeQuery {
                 field: timestamp_field.to_string(),
-                lower_bound: Bound::Excluded(time2.into()),
-                upper_bound: Bound::Included(time3.into()),
-            }))
-            .unwrap(),
-            ..SearchRequest::default()
-        };
+                lower_bound: Bound::Included(lower_bound_ns.into()),
+                upper_bound: Bound::Unbounded,
+            }),
+        );
+        // ... while a lower bound on the first nanosecond of the split is removed
         remove_timestamp_test_case(
-            &search_request,
+            &range_request(Bound::Included(time * S_TO_NS), Bound::Unbounded),
             &split,
-            Some(expected_lower_exclusive.clone()),
+            None,
         );
+    }
 
-        // we take the most restrictive bounds
-        let split = SplitIdAndFooterOffsets {
-            timestamp_start: Some(time1),
-            timestamp_end: Some(time4),
-            ..SplitIdAndFooterOffsets::default()
-        };
+    #[test]
+    fn test_can_split_do_better_default_timestamp_field_fallback() {
+        let mut schema_builder = tantivy::schema::Schema::builder();
+        schema_builder.add_date_field(
+            "timestamp",
+            tantivy::schema::DateOptions::default().set_fast(),
+        );
+        schema_builder.add_date_field("created_at", tantivy::schema::DateOptions::default());
+        let schema = schema_builder.build();
 
-        let expected_upper_2_ex = RangeQuery {
-            field: timestamp_field.to_string(),
-            lower_bound: Bound::Unbounded,
-            upper_bound: Bound::Excluded((time2 * S_TO_NS).into()),
+        let sorted_request = |field_name: &str| SearchRequest {
+            max_hits: 10,
+            sort_fields: vec![SortField {
+                field_name: field_name.to_string(),
+                sort_order: SortOrder::Asc as i32,
+                sort_datetime_format: None,
+            }],
+            ..Default::default()
         };
-        let search_request = SearchRequest {
-            query_ast: serde_json::to_string(&QueryAst::Range(RangeQuery {
-                field: timestamp_field.to_string(),
-                lower_bound: Bound::Included(time1.into()),
-                upper_bound: Bound::Included(time3.into()),
-            }))
-            .unwrap(),
-            start_timestamp: Some(time1),
-            end_timestamp: Some(time2),
-            ..SearchRequest::default()
+        let splits = vec![SplitIdAndFooterOffsets {
+            split_id: "split".to_string(),
+            timestamp_start: Some(10),
+            timestamp_end: Some(20),
+            ..Default::default()
+        }];
+        let fallback = |request: &SearchRequest,
+                        default_timestamp_field_name_opt: Option<&str>,
+                        splits: &[SplitIdAndFooterOffsets]| {
+            CanSplitDoBetter::from_request(request, None).fallback_to_default_timestamp_field(
+                request,
+                default_timestamp_field_name_opt,
+                &schema,
+                splits,
+            )
         };
-        remove_timestamp_test_case(&search_request, &split, Some(expected_upper_2_ex));
+        assert!(matches!(
+            fallback(&sorted_request("timestamp"), Some("timestamp"), &splits),
+            CanSplitDoBetter::SplitTimestampLower(None)
+        ));
+        // The fallback is opt-in.
+        assert!(matches!(
+            fallback(&sorted_request("timestamp"), None, &splits),
+            CanSplitDoBetter::Uninformative
+        ));
+        // The request must be sorted on the default timestamp field.
+        assert!(matches!(
+            fallback(&sorted_request("created_at"), Some("timestamp"), &splits),
+            CanSplitDoBetter::Uninformative
+        ));
+        // The field must be a fast date field.
+        assert!(matches!(
+            fallback(&sorted_request("created_at"), Some("created_at"), &splits),
+            CanSplitDoBetter::Uninformative
+        ));
+        assert!(matches!(
+            fallback(&sorted_request("missing"), Some("missing"), &splits),
+            CanSplitDoBetter::Uninformative
+        ));
+        // All the splits must have timestamp bounds.
+        let mut splits_without_bounds = splits.clone();
+        splits_without_bounds.push(SplitIdAndFooterOffsets {
+            split_id: "split_without_bounds".to_string(),
+            ..Default::default()
+        });
+        assert!(matches!(
+            fallback(
+                &sorted_request("timestamp"),
+                Some("timestamp"),
+                &splits_without_bounds
+            ),
+            CanSplitDoBetter::Uninformative
+        ));
+    }
 
-        let expected_upper_2_inc = RangeQuery {
-            field: timestamp_field.to_string(),
-            lower_bound: Bound::Unbounded,
-            upper_bound: Bound::Included((time2 * S_TO_NS).into()),
+    #[tokio::test]
+    async fn test_in_flight_split_searches_abort_if_cannot_be_better() {
+        let in_flight_split_searches = InFlightSplitSearches::default();
+        let old_split = SplitIdAndFooterOffsets {
+            split_id: "old-split".to_string(),
+            timestamp_start: Some(10),
+            timestamp_end: Some(20),
+            ..SplitIdAndFooterOffsets::default()
         };
-        let search_request = SearchRequest {
-            query_ast: serde_json::to_string(&QueryAst::Range(RangeQuery {
-                field: timestamp_field.to_string(),
-                lower_bound: Bound::Included(time1.into()),
-                upper_bound: Bound::Included(time2.into()),
-            }))
-            .unwrap(),
-            start_timestamp: Some(time1),
-            end_timestamp: Some(time3),
-            ..SearchRequest::default()
+        let recent_split = SplitIdAndFooterOffsets {
+            split_id: "recent-split".to_string(),
+            timestamp_start: Some(30),
+            timestamp_end: Some(40),
+            ..SplitIdAndFooterOffsets::default()
         };
-        remove_timestamp_test_case(&search_request, &split, Some(expected_upper_2_inc));
+        let old_split_search = in_flight_split_searches
+            .spawn_registered(old_split, || tokio::spawn(futures::future::pending::<()>()));
+        let recent_split_search = in_flight_split_searches.spawn_registered(recent_split, || {
+            tokio::spawn(futures::future::pending::<()>())
+        });
 
-        let expected_lower_3 = RangeQuery {
-            field: timestamp_field.to_string(),
-            lower_bound: Bound::Included((time3 * S_TO_NS).into()),
-            upper_bound: Bound::Unbounded,
+        // Both splits may still contain documents better than the worst hit.
+        let mut split_filter = CanSplitDoBetter::SplitTimestampHigher(None);
+        assert_eq!(
+            in_flight_split_searches.abort_if_cannot_be_better(&split_filter),
+            0
+        );
+
+        // Once the worst hit is more recent than the old split, its search can't contribute.
+        split_filter.record_new_worst_hit(&PartialHit {
+            sort_value: Some(SortValue::I64(25_000_000_000).into()),
+            ..PartialHit::default()
+        });
+        assert_eq!(
+            in_flight_split_searches.abort_if_cannot_be_better(&split_filter),
+            1
+        );
+        assert!(old_split_search.await.unwrap_err().is_cancelled());
+        assert!(in_flight_split_searches.is_pruned("old-split"));
+        assert!(!recent_split_search.is_finished());
+        assert!(!in_flight_split_searches.is_pruned("recent-split"));
+
+        in_flight_split_searches.unregister("recent-split");
+        assert_eq!(
+            in_flight_split_searches
+                .abort_if_cannot_be_better(&CanSplitDoBetter::SplitTimestampHigher(Some(i64::MAX))),
+            0
+        );
+        recent_split_search.abort();
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn test_in_flight_split_searches_unregister_before_spawn_returns() {
+        let in_flight_split_searches = Arc::new(InFlightSplitSearches::default());
+        let split = SplitIdAndFooterOffsets {
+            split_id: "split".to_string(),
+            timestamp_start: Some(10),
+            timestamp_end: Some(20),
+            ..SplitIdAndFooterOffsets::default()
         };
+        // The split search completes right away, possibly before `spawn_registered` returns.
+        let in_flight_split_searches_clone = in_flight_split_searches.clone();
+        let split_search = in_flight_split_searches.spawn_registered(split, || {
+            tokio::spawn(async move { in_flight_split_searches_clone.unregister("split") })
+        });
+        split_search.await.unwrap();
 
-        let search_request = SearchRequest {
-            query_ast: serde_json::to_string(&QueryAst::Range(RangeQuery {
-                field: timestamp_field.to_string(),
-                lower_bound: Bound::Included(time2.into()),
-                upper_bound: Bound::Included(time4.into()),
-            }))
-            .unwrap(),
-            start_timestamp: Some(time3),
-            end_timestamp: Some(time4 + 1),
-            ..SearchRequest::default()
+        // No stale entry is left behind: the completed split search is not reported as pruned.
+        assert_eq!(
+            in_flight_split_searches
+                .abort_if_cannot_be_better(&CanSplitDoBetter::SplitTimestampHigher(Some(i64::MAX))),
+            0
+        );
+        assert!(!in_flight_split_searches.is_pruned("split"));
+    }
+
+    #[test]
+    fn test_can_split_do_better_sub_second_timestamps() {
+        const S_TO_NS: i64 = 1_000_000_000;
+        let split = SplitIdAndFooterOffsets {
+            split_id: "split".to_string(),
+            timestamp_start: Some(10),
+            timestamp_end: Some(20),
+            ..SplitIdAndFooterOffsets::default()
+        };
+        let partial_hit = |timestamp_ns: i64| PartialHit {
+            sort_value: Some(SortValue::I64(timestamp_ns).into()),
+            ..PartialHit::default()
         };
-        remove_timestamp_test_case(&search_request, &split, Some(expected_lower_3.clone()));
 
-        let search_request = SearchRequest {
-            query_ast: serde_json::to_string(&QueryAst::Range(RangeQuery {
-                field: timestamp_field.to_string(),
-                lower_bound: Bound::Included(time3.into()),
-                upper_bound: Bound::Included(time4.into()),
-            }))
-            .unwrap(),
-            start_timestamp: Some(time2),
-            end_timestamp: Some(time4 + 1),
+        // The split may contain documents up to 20.999s.
+        let mut split_filter = CanSplitDoBetter::SplitTimestampHigher(None);
+        split_filter.record_new_worst_hit(&partial_hit(20 * S_TO_NS + 500_000_000));
+        assert!(split_filter.can_be_better(&split));
+        split_filter.record_new_worst_hit(&partial_hit(21 * S_TO_NS));
+        assert!(!split_filter.can_be_better(&split));
+
+        // The split may contain documents from 10.000s.
+        let mut split_filter = CanSplitDoBetter::SplitTimestampLower(None);
+        split_filter.record_new_worst_hit(&partial_hit(10 * S_TO_NS));
+        assert!(split_filter.can_be_better(&split));
+        split_filter.record_new_worst_hit(&partial_hit(10 * S_TO_NS - 1));
+        assert!(!split_filter.can_be_better(&split));
+    }
+
+    #[test]
+    fn test_can_split_do_better_date_histogram_aggregation() {
+        let date_histogram_request = SearchRequest {
+            aggregation_request: Some(
+                r#"{"histo": {"date_histogram": {"field": "timestamp", "fixed_interval": "1h"}}}"#
+                    .to_string(),
+            ),
+            max_hits: 0,
             ..SearchRequest::default()
         };
-        remove_timestamp_test_case(&search_request, &split, Some(expected_lower_3));
+        let split_filter =
+            CanSplitDoBetter::from_request(&date_histogram_request, Some("timestamp"));
+        assert!(matches!(
+            split_filter,
+            CanSplitDoBetter::DateHistogramAggregation
+        ));
 
-        let mut search_request = SearchRequest {
-            query_ast: serde_json::to_string(&QueryAst::MatchAll).unwrap(),
-            start_timestamp: Some(time1),
-            end_timestamp: Some(time4),
-            ..SearchRequest::default()
+        let split_filter = CanSplitDoBetter::from_request(&date_histogram_request, Some("other"));
+        assert!(!matches!(
+            split_filter,
+            CanSplitDoBetter::DateHistogramAggregation
+        ));
+
+        let mut splits: Vec<SplitIdAndFooterOffsets> = [30, 10, 20]
+            .into_iter()
+            .map(|timestamp_start| SplitIdAndFooterOffsets {
+                split_id: format!("split_{timestamp_start}"),
+                timestamp_start: Some(timestamp_start),
+                timestamp_end: Some(timestamp_start + 5),
+                ..SplitIdAndFooterOffsets::default()
+            })
+            .collect();
+        let mut split_filter =
+            CanSplitDoBetter::from_request(&date_histogram_request, Some("timestamp"));
+        split_filter.optimize_split_order(&mut splits);
+        let split_ids: Vec<&str> = splits.iter().map(|split| split.split_id.as_str()).collect();
+        assert_eq!(split_ids, ["split_10", "split_20", "split_30"]);
+
+        // Histograms need all the splits: none of them is ever pruned.
+        split_filter.record_new_worst_hit(&PartialHit::default());
+        assert!(splits.iter().all(|split| split_filter.can_be_better(split)));
+    }
+
+    #[test]
+    fn test_optimize_split_order_uninformative() {
+        let mut splits: Vec<SplitIdAndFooterOffsets> = [100, 3000, 20]
+            .into_iter()
+            .map(|num_docs| SplitIdAndFooterOffsets {
+                split_id: format!("split_{num_docs}"),
+                num_docs,
+                ..SplitIdAndFooterOffsets::default()
+            })
+            .collect();
+        CanSplitDoBetter::Uninformative.optimize_split_order(&mut splits);
+        let split_ids: Vec<&str> = splits.iter().map(|split| split.split_id.as_str()).collect();
+        assert_eq!(split_ids, ["split_3000", "split_100", "split_20"]);
+    }
+
+    #[test]
+    fn test_optimize_split_order_ties_broken_on_split_id() {
+        let mut splits: Vec<SplitIdAndFooterOffsets> = ["split_c", "split_a", "split_b"]
+            .into_iter()
+            .map(|split_id| SplitIdAndFooterOffsets {
+                split_id: split_id.to_string(),
+                timestamp_start: Some(10),
+                timestamp_end: Some(20),
+                num_docs: 100,
+                ..SplitIdAndFooterOffsets::default()
+            })
+            .collect();
+        for split_filter in [
+            CanSplitDoBetter::SplitTimestampHigher(None),
+            CanSplitDoBetter::SplitTimestampLower(None),
+            CanSplitDoBetter::FindTraceIdsAggregation(None),
+            CanSplitDoBetter::DateHistogramAggregation,
+            CanSplitDoBetter::Uninformative,
+        ] {
+            splits.reverse();
+            split_filter.optimize_split_order(&mut splits);
+            let split_ids: Vec<&str> = splits.iter().map(|split| split.split_id.as_str()).collect();
+            assert_eq!(split_ids, ["split_a", "split_b", "split_c"]);
+        }
+    }
+
+    #[test]
+    fn test_interleave_cached_splits() {
+        let mut splits: Vec<SplitIdAndFooterOffsets> =
+            ["cold_1", "cold_2", "hot_1", "cold_3", "hot_2", "cold_4"]
+                .into_iter()
+                .map(|split_id| SplitIdAndFooterOffsets {
+                    split_id: split_id.to_string(),
+                    ..SplitIdAndFooterOffsets::default()
+                })
+                .collect();
+        interleave_cached_splits(&mut splits, |split| split.split_id.starts_with("hot"));
+        let split_ids: Vec<&str> = splits.iter().map(|split| split.split_id.as_str()).collect();
+        assert_eq!(
+            split_ids,
+            ["hot_1", "cold_1", "hot_2", "cold_2", "cold_3", "cold_4"]
+        );
+
+        // Without any cached split, the order is left untouched.
+        interleave_cached_splits(&mut splits, |_| false);
+        let split_ids: Vec<&str> = splits.iter().map(|split| split.split_id.as_str()).collect();
+        assert_eq!(
+            split_ids,
+            ["hot_1", "cold_1", "hot_2", "cold_2", "cold_3", "cold_4"]
+        );
+    }
+
+    #[test]
+    fn test_check_split_timestamp_bounds() {
+        let index_uri = Uri::for_test("ram:///indexes/test-index");
+        let split_with_bounds = SplitIdAndFooterOffsets {
+            split_id: "split_with_bounds".to_string(),
+            timestamp_start: Some(10),
+            timestamp_end: Some(20),
+            ..SplitIdAndFooterOffsets::default()
         };
-        let split = SplitIdAndFooterOffsets {
-            timestamp_start: Some(time2),
-            timestamp_end: Some(time3),
+        let split_without_bounds = SplitIdAndFooterOffsets {
+            split_id: "split_without_bounds".to_string(),
             ..SplitIdAndFooterOffsets::default()
         };
-        remove_redundant_timestamp_range(&mut search_request, &split, &timestamp_field);
-        assert_ast_eq(&search_request, &QueryAst::MatchAll);
+        let split_filter = CanSplitDoBetter::SplitTimestampHigher(None)
+            .check_split_timestamp_bounds(&[split_with_bounds.clone()], &index_uri);
+        assert!(matches!(
+            split_filter,
+            CanSplitDoBetter::SplitTimestampHigher(None)
+        ));
+
+        for split_filter in [
+            CanSplitDoBetter::SplitTimestampHigher(None),
+            CanSplitDoBetter::SplitTimestampLower(None),
+            CanSplitDoBetter::FindTraceIdsAggregation(None),
+        ] {
+            let split_filter = split_filter.check_split_timestamp_bounds(
+                &[split_with_bounds.clone(), split_without_bounds.clone()],
+                &index_uri,
+            );
+            assert!(matches!(split_filter, CanSplitDoBetter::Uninformative));
+        }
+        // Other strategies do not prune splits on their timestamps.
+        let split_filter = CanSplitDoBetter::SplitIdHigher(None)
+            .check_split_timestamp_bounds(&[split_without_bounds], &index_uri);
+        assert!(matches!(
+            split_filter,
+            CanSplitDoBetter::SplitIdHigher(None)
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_acquire_aggregation_permit() {
+        let searcher_config = quickwit_config::SearcherConfig {
+            max_num_concurrent_aggregations: 1,
+            ..Default::default()
+        };
+        let searcher_context = SearcherContext::new(searcher_config, None);
+        let aggregation_request = SearchRequest {
+            aggregation_request: Some(r#"{"avg_price": {"avg": {"field": "price"}}}"#.to_string()),
+            ..SearchRequest::default()
+        };
+        let plain_request = SearchRequest::default();
+
+        let aggregation_permit_opt =
+            acquire_aggregation_permit(&searcher_context, &aggregation_request).await;
+        assert!(aggregation_permit_opt.is_some());
+
+        // A second aggregation has to wait for the first one to complete...
+        let timeout = Duration::from_millis(50);
+        tokio::time::timeout(
+            timeout,
+            acquire_aggregation_permit(&searcher_context, &aggregation_request),
+        )
+        .await
+        .unwrap_err();
+
+        // ... while plain searches proceed unthrottled.
+        let plain_permit_opt = tokio::time::timeout(
+            timeout,
+            acquire_aggregation_permit(&searcher_context, &plain_request),
+        )
+        .await
+        .unwrap();
+        assert!(plain_permit_opt.is_none());
+
+        drop(aggregation_permit_opt);
+        let aggregation_permit_opt = tokio::time::timeout(
+            timeout,
+            acquire_aggregation_permit(&searcher_context, &aggregation_request),
+        )
+        .await
+        .unwrap();
+        assert!(aggregation_permit_opt.is_some());
+    }
+
+    #[test]
+    fn test_slowest_splits() {
+        let mut slowest_splits = SlowestSplits::new(3);
+        let elapsed_millis = [12, 3, 45, 7, 30, 1, 8];
+        for (split_ord, elapsed_millis) in elapsed_millis.iter().enumerate() {
+            slowest_splits.record(
+                &format!("split_{split_ord}"),
+                Duration::from_millis(*elapsed_millis),
+            );
+        }
+        let slowest_splits = slowest_splits.into_sorted_vec();
+        assert_eq!(
+            slowest_splits,
+            vec![
+                SplitSearchTiming {
+                    split_id: "split_2".to_string(),
+                    elapsed_time_micros: 45_000,
+                },
+                SplitSearchTiming {
+                    split_id: "split_4".to_string(),
+                    elapsed_time_micros: 30_000,
+                },
+                SplitSearchTiming {
+                    split_id: "split_0".to_string(),
+                    elapsed_time_micros: 12_000,
+                },
+            ]
+        );
+
+        let slowest_splits = SlowestSplits::new(0);
+        assert!(slowest_splits.into_sorted_vec().is_empty());
     }
 
     // regression test for #4935
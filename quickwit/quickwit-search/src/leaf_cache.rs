@@ -17,17 +17,19 @@
 // You should have received a copy of the GNU Affero General Public License
 // along with this program. If not, see <http://www.gnu.org/licenses/>.
 
+use std::collections::hash_map::RandomState;
+use std::hash::{BuildHasher, Hash, Hasher};
 use std::ops::Bound;
 
 use prost::Message;
-use quickwit_proto::search::{
-    CountHits, LeafSearchResponse, SearchRequest, SplitIdAndFooterOffsets,
-};
+use quickwit_proto::search::{LeafSearchResponse, SearchRequest, SplitIdAndFooterOffsets};
 use quickwit_storage::{MemorySizedCache, OwnedBytes};
 
 /// A cache to memoize `leaf_search_single_split` results.
 pub struct LeafSearchCache {
     content: MemorySizedCache<CacheKey>,
+    // Randomly seeded, so that the request hash collisions cannot be crafted.
+    hash_builder: RandomState,
 }
 
 // TODO we could be smarter about search_after. If we have a cached request with a search_after
@@ -52,14 +54,16 @@ impl LeafSearchCache {
                 capacity,
                 &quickwit_storage::STORAGE_METRICS.partial_request_cache,
             ),
+            hash_builder: RandomState::new(),
         }
     }
     pub fn get(
         &self,
-        split_info: SplitIdAndFooterOffsets,
-        search_request: SearchRequest,
+        split_info: &SplitIdAndFooterOffsets,
+        search_request: &SearchRequest,
     ) -> Option<LeafSearchResponse> {
-        let key = CacheKey::from_split_meta_and_request(split_info, search_request);
+        let key =
+            CacheKey::from_split_meta_and_request(split_info, search_request, &self.hash_builder);
         let encoded_result = self.content.get(&key)?;
         // this should never fail
         LeafSearchResponse::decode(&*encoded_result).ok()
@@ -67,11 +71,12 @@ impl LeafSearchCache {
 
     pub fn put(
         &self,
-        split_info: SplitIdAndFooterOffsets,
-        search_request: SearchRequest,
+        split_info: &SplitIdAndFooterOffsets,
+        search_request: &SearchRequest,
         result: LeafSearchResponse,
     ) {
-        let key = CacheKey::from_split_meta_and_request(split_info, search_request);
+        let key =
+            CacheKey::from_split_meta_and_request(split_info, search_request, &self.hash_builder);
 
         let encoded_result = result.encode_to_vec();
         self.content.put(key, OwnedBytes::new(encoded_result));
@@ -79,12 +84,17 @@ impl LeafSearchCache {
 }
 
 /// A key inside a [`LeafSearchCache`].
+///
+/// The request is only stored as a hash, so that looking up the cache does not require cloning
+/// it. Two distinct requests on the same split and time range sharing the same 64-bit hash would
+/// be served each other's response: with a randomly seeded hasher, this requires about 2^32
+/// distinct requests on a single split before becoming likely, far beyond what the cache holds.
 #[derive(Debug, Hash, PartialEq, Eq)]
 struct CacheKey {
     /// The split this entry refers to
     split_id: String,
-    /// The request this matches. The timerange of the request was removed.
-    request: SearchRequest,
+    /// The hash of the request this matches. The timerange of the request is not hashed.
+    request_hash: u64,
     /// The effective time range of the request, that is, the intersection of the timerange
     /// requested, and the timerange covered by the split.
     merged_time_range: Range,
@@ -92,27 +102,68 @@ struct CacheKey {
 
 impl CacheKey {
     fn from_split_meta_and_request(
-        split_info: SplitIdAndFooterOffsets,
-        mut search_request: SearchRequest,
+        split_info: &SplitIdAndFooterOffsets,
+        search_request: &SearchRequest,
+        hash_builder: &RandomState,
     ) -> Self {
         let split_time_range = Range::from_bounds(split_info.time_range());
         let request_time_range = Range::from_bounds(search_request.time_range());
         let merged_time_range = request_time_range.intersect(&split_time_range);
 
-        search_request.start_timestamp = None;
-        search_request.end_timestamp = None;
-        // it doesn't matter whether or not we count all hits at the scale of a
-        // single split: either we did process it and got everything, or we didn't.
-        search_request.count_hits = CountHits::CountAll.into();
-
         CacheKey {
-            split_id: split_info.split_id,
-            request: search_request,
+            split_id: split_info.split_id.clone(),
+            request_hash: hash_request(search_request, hash_builder),
             merged_time_range,
         }
     }
 }
 
+/// Hashes the fields of the request that affect the response of a split search.
+fn hash_request(search_request: &SearchRequest, hash_builder: &RandomState) -> u64 {
+    // The exhaustive destructuring forces deciding whether new fields belong to the key.
+    let SearchRequest {
+        index_id_patterns,
+        query_ast,
+        // The time range is part of the key as the merged time range.
+        start_timestamp: _,
+        end_timestamp: _,
+        max_hits,
+        start_offset,
+        aggregation_request,
+        snippet_fields,
+        sort_fields,
+        scroll_ttl_secs,
+        search_after,
+        // it doesn't matter whether or not we count all hits at the scale of a
+        // single split: either we did process it and got everything, or we didn't.
+        count_hits: _,
+        num_slowest_splits,
+        now_timestamp_nanos,
+        collect_split_stats,
+        end_timestamp_inclusive,
+        // The priority only affects the scheduling of the search.
+        priority: _,
+        // The split ID lists only select the splits to search.
+        split_id_allowlist: _,
+        split_id_denylist: _,
+    } = search_request;
+    let mut hasher = hash_builder.build_hasher();
+    index_id_patterns.hash(&mut hasher);
+    query_ast.hash(&mut hasher);
+    max_hits.hash(&mut hasher);
+    start_offset.hash(&mut hasher);
+    aggregation_request.hash(&mut hasher);
+    snippet_fields.hash(&mut hasher);
+    sort_fields.hash(&mut hasher);
+    scroll_ttl_secs.hash(&mut hasher);
+    search_after.hash(&mut hasher);
+    num_slowest_splits.hash(&mut hasher);
+    now_timestamp_nanos.hash(&mut hasher);
+    collect_split_stats.hash(&mut hasher);
+    end_timestamp_inclusive.hash(&mut hasher);
+    hasher.finish()
+}
+
 /// A (half-open) range bounded inclusively below and exclusively above [start..end).
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
 struct Range {
@@ -190,11 +241,66 @@ impl std::ops::RangeBounds<i64> for Range {
 
 #[cfg(test)]
 mod tests {
+    use std::collections::hash_map::RandomState;
+
     use quickwit_proto::search::{
-        LeafSearchResponse, PartialHit, SearchRequest, SortValue, SplitIdAndFooterOffsets,
+        CountHits, LeafSearchResponse, PartialHit, SearchRequest, SortValue,
+        SplitIdAndFooterOffsets,
     };
 
-    use super::LeafSearchCache;
+    use super::{hash_request, LeafSearchCache};
+
+    #[test]
+    fn test_leaf_search_cache_request_hash() {
+        let hash_builder = RandomState::new();
+        let request = SearchRequest {
+            index_id_patterns: vec!["test-idx".to_string()],
+            query_ast: "test".to_string(),
+            start_timestamp: Some(100),
+            end_timestamp: Some(200),
+            max_hits: 10,
+            ..Default::default()
+        };
+        let request_hash = hash_request(&request, &hash_builder);
+        assert_eq!(hash_request(&request.clone(), &hash_builder), request_hash);
+
+        // The time range and the hit counting policy are not part of the hash.
+        let other_time_range_request = SearchRequest {
+            start_timestamp: None,
+            end_timestamp: Some(300),
+            count_hits: CountHits::Underestimate.into(),
+            ..request.clone()
+        };
+        assert_eq!(
+            hash_request(&other_time_range_request, &hash_builder),
+            request_hash
+        );
+
+        let other_query_request = SearchRequest {
+            query_ast: "test2".to_string(),
+            ..request.clone()
+        };
+        assert_ne!(
+            hash_request(&other_query_request, &hash_builder),
+            request_hash
+        );
+        let other_max_hits_request = SearchRequest {
+            max_hits: 20,
+            ..request.clone()
+        };
+        assert_ne!(
+            hash_request(&other_max_hits_request, &hash_builder),
+            request_hash
+        );
+        let other_now_request = SearchRequest {
+            now_timestamp_nanos: Some(1),
+            ..request
+        };
+        assert_ne!(
+            hash_request(&other_now_request, &hash_builder),
+            request_hash
+        );
+    }
 
     #[test]
     fn test_leaf_search_cache_no_timestamp() {
@@ -206,6 +312,7 @@ mod tests {
             split_footer_end: 100,
             timestamp_start: None,
             timestamp_end: None,
+            num_docs: 0,
         };
 
         let split_2 = SplitIdAndFooterOffsets {
@@ -214,6 +321,7 @@ mod tests {
             split_footer_end: 100,
             timestamp_start: None,
             timestamp_end: None,
+            num_docs: 0,
         };
 
         let query_1 = SearchRequest {
@@ -248,14 +356,21 @@ mod tests {
                 sort_value2: None,
                 split_id: "split_1".to_string(),
             }],
+            slowest_splits: Vec::new(),
+            per_split_stats: Vec::new(),
+            num_splits_searched: 0,
+            num_splits_pruned: 0,
+            num_splits_count_only: 0,
+            aggregation_is_partial: false,
+            timed_out: false,
         };
 
-        assert!(cache.get(split_1.clone(), query_1.clone()).is_none());
+        assert!(cache.get(&split_1, &query_1).is_none());
 
-        cache.put(split_1.clone(), query_1.clone(), result.clone());
-        assert_eq!(cache.get(split_1.clone(), query_1.clone()).unwrap(), result);
-        assert!(cache.get(split_2, query_1).is_none());
-        assert!(cache.get(split_1, query_2).is_none());
+        cache.put(&split_1, &query_1, result.clone());
+        assert_eq!(cache.get(&split_1, &query_1).unwrap(), result);
+        assert!(cache.get(&split_2, &query_1).is_none());
+        assert!(cache.get(&split_1, &query_2).is_none());
     }
 
     #[test]
@@ -268,6 +383,7 @@ mod tests {
             split_footer_end: 100,
             timestamp_start: Some(100),
             timestamp_end: Some(199),
+            num_docs: 0,
         };
         let split_2 = SplitIdAndFooterOffsets {
             split_id: "split_2".to_string(),
@@ -275,6 +391,7 @@ mod tests {
             split_footer_end: 100,
             timestamp_start: Some(150),
             timestamp_end: Some(249),
+            num_docs: 0,
         };
         let split_3 = SplitIdAndFooterOffsets {
             split_id: "split_3".to_string(),
@@ -282,6 +399,7 @@ mod tests {
             split_footer_end: 100,
             timestamp_start: Some(150),
             timestamp_end: Some(249),
+            num_docs: 0,
         };
 
         let query_1 = SearchRequest {
@@ -334,31 +452,38 @@ mod tests {
                 sort_value2: None,
                 split_id: "split_1".to_string(),
             }],
+            slowest_splits: Vec::new(),
+            per_split_stats: Vec::new(),
+            num_splits_searched: 0,
+            num_splits_pruned: 0,
+            num_splits_count_only: 0,
+            aggregation_is_partial: false,
+            timed_out: false,
         };
 
         // for split_1, 1 and 1bis cover different timestamp ranges
-        cache.put(split_1.clone(), query_1.clone(), result.clone());
-        assert!(cache.get(split_1.clone(), query_1.clone()).is_some());
-        assert!(cache.get(split_1.clone(), query_1bis.clone()).is_none());
+        cache.put(&split_1, &query_1, result.clone());
+        assert!(cache.get(&split_1, &query_1).is_some());
+        assert!(cache.get(&split_1, &query_1bis).is_none());
 
         // for split_2, both 1 and 1bis cover everything, so it should cache-hit
-        cache.put(split_2.clone(), query_1.clone(), result.clone());
-        assert!(cache.get(split_2.clone(), query_1).is_some());
-        assert!(cache.get(split_2.clone(), query_1bis).is_some());
+        cache.put(&split_2, &query_1, result.clone());
+        assert!(cache.get(&split_2, &query_1).is_some());
+        assert!(cache.get(&split_2, &query_1bis).is_some());
 
         // for split_1, both 1 and 1bis cover everything, so it should cache-hit
-        cache.put(split_1.clone(), query_2.clone(), result.clone());
-        assert!(cache.get(split_1.clone(), query_2.clone()).is_some());
-        assert!(cache.get(split_1, query_2bis.clone()).is_some());
+        cache.put(&split_1, &query_2, result.clone());
+        assert!(cache.get(&split_1, &query_2).is_some());
+        assert!(cache.get(&split_1, &query_2bis).is_some());
 
         // for split_2, 2 covers everything, but 2bis cover only a subrange
-        cache.put(split_2.clone(), query_2.clone(), result.clone());
-        assert!(cache.get(split_2.clone(), query_2.clone()).is_some());
-        assert!(cache.get(split_2, query_2bis.clone()).is_none());
+        cache.put(&split_2, &query_2, result.clone());
+        assert!(cache.get(&split_2, &query_2).is_some());
+        assert!(cache.get(&split_2, &query_2bis).is_none());
 
         // same for split_3, but we try caching the bounded request and query for the unbounded one
-        cache.put(split_3.clone(), query_2bis.clone(), result);
-        assert!(cache.get(split_3.clone(), query_2).is_none());
-        assert!(cache.get(split_3, query_2bis).is_some());
+        cache.put(&split_3, &query_2bis, result);
+        assert!(cache.get(&split_3, &query_2).is_none());
+        assert!(cache.get(&split_3, &query_2bis).is_some());
     }
 }
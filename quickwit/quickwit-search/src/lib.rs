@@ -29,6 +29,7 @@ mod error;
 mod fetch_docs;
 mod filters;
 mod find_trace_ids_collector;
+mod hotcache_cache;
 mod leaf;
 mod leaf_cache;
 mod list_fields;
@@ -40,7 +41,9 @@ mod scroll_context;
 mod search_job_placer;
 mod search_response_rest;
 mod search_stream;
+mod searcher_session;
 mod service;
+mod split_cache_warmer;
 pub(crate) mod top_k_collector;
 
 mod metrics;
@@ -50,7 +53,7 @@ mod tests;
 
 pub use collector::QuickwitAggregations;
 use metrics::SEARCH_METRICS;
-use quickwit_common::thread_pool::ThreadPool;
+use quickwit_common::thread_pool::{ContextPropagator, RestoreContextFn, ThreadPool};
 use quickwit_common::tower::Pool;
 use quickwit_doc_mapper::DocMapper;
 use quickwit_proto::metastore::{
@@ -74,7 +77,7 @@ use quickwit_metastore::{
 use quickwit_proto::search::{PartialHit, SearchRequest, SearchResponse, SplitIdAndFooterOffsets};
 use quickwit_proto::types::IndexUid;
 use quickwit_storage::StorageResolver;
-pub use service::SearcherContext;
+pub use service::{SearcherContext, SearcherContextBuilder};
 use tantivy::DocAddress;
 
 pub use crate::client::{
@@ -84,6 +87,11 @@ pub use crate::cluster_client::ClusterClient;
 pub use crate::error::{parse_grpc_error, SearchError};
 use crate::fetch_docs::fetch_docs;
 use crate::leaf::leaf_search;
+pub use crate::leaf::{
+    leaf_search_estimate, leaf_search_explain, leaf_search_with_options, open_split_for_inspection,
+    FieldWarmupPlan, LeafSearchOptions, LeafSearchPlan, LeafSearchProgress,
+    NoopSplitDecisionObserver, SplitDecisionObserver, SplitWarmupPlan,
+};
 pub use crate::root::{
     check_all_index_metadata_found, jobs_to_leaf_requests, root_search, IndexMetasForLeafSearch,
     SearchJob,
@@ -91,14 +99,30 @@ pub use crate::root::{
 pub use crate::search_job_placer::{Job, SearchJobPlacer};
 pub use crate::search_response_rest::SearchResponseRest;
 pub use crate::search_stream::root_search_stream;
+pub use crate::searcher_session::SearcherSession;
 pub use crate::service::{MockSearchService, SearchService, SearchServiceImpl};
+pub use crate::split_cache_warmer::SplitCacheWarmer;
 
 /// A pool of searcher clients identified by their gRPC socket address.
 pub type SearcherPool = Pool<SocketAddr, SearchServiceClient>;
 
 fn search_thread_pool() -> &'static ThreadPool {
     static SEARCH_THREAD_POOL: OnceLock<ThreadPool> = OnceLock::new();
-    SEARCH_THREAD_POOL.get_or_init(|| ThreadPool::new("search", None))
+    SEARCH_THREAD_POOL.get_or_init(|| {
+        ThreadPool::new("search", None)
+            .with_context_propagator(Arc::new(OpenTelemetryContextPropagator))
+    })
+}
+
+/// Attaches the OpenTelemetry context of the caller to the search thread pool workers, so that
+/// the spans of CPU-intensive tasks remain part of the trace of the search request.
+struct OpenTelemetryContextPropagator;
+
+impl ContextPropagator for OpenTelemetryContextPropagator {
+    fn capture(&self) -> RestoreContextFn {
+        let context = opentelemetry::Context::current();
+        Box::new(move || Box::new(context.attach()))
+    }
 }
 
 /// GlobalDocAddress serves as a hit address.
@@ -174,6 +198,7 @@ fn extract_split_and_footer_offsets(split_metadata: &SplitMetadata) -> SplitIdAn
             .time_range
             .as_ref()
             .map(|time_range| *time_range.end()),
+        num_docs: split_metadata.num_docs as u64,
     }
 }
 
@@ -35,7 +35,7 @@ use quickwit_proto::search::{
     ListFieldsRequest, ListFieldsResponse, SplitIdAndFooterOffsets,
 };
 use quickwit_proto::types::IndexUid;
-use quickwit_storage::Storage;
+use quickwit_storage::{CachePriority, Storage};
 
 use crate::leaf::open_split_bundle;
 use crate::search_job_placer::group_jobs_by_index_id;
@@ -55,8 +55,13 @@ pub async fn get_fields_from_split<'a>(
     {
         return Ok(Box::new(list_fields.fields.into_iter()));
     }
-    let (_, split_bundle) =
-        open_split_bundle(searcher_context, index_storage, split_and_footer_offsets).await?;
+    let (_, split_bundle) = open_split_bundle(
+        searcher_context,
+        index_storage,
+        split_and_footer_offsets,
+        CachePriority::Normal,
+    )
+    .await?;
 
     let serialized_split_fields = split_bundle
         .get_all(Path::new(SPLIT_FIELDS_FILE_NAME))
@@ -33,7 +33,7 @@ use quickwit_proto::search::{
     SplitIdAndFooterOffsets, SplitSearchError,
 };
 use quickwit_proto::types::IndexUid;
-use quickwit_storage::Storage;
+use quickwit_storage::{CachePriority, Storage};
 use tantivy::schema::{Field, FieldType};
 use tantivy::{ReloadPolicy, Term};
 use tracing::{debug, error, info, instrument};
@@ -212,7 +212,15 @@ async fn leaf_list_terms_single_split(
     storage: Arc<dyn Storage>,
     split: SplitIdAndFooterOffsets,
 ) -> crate::Result<LeafListTermsResponse> {
-    let index = open_index_with_caches(searcher_context, storage, &split, None, true).await?;
+    let index = open_index_with_caches(
+        searcher_context,
+        storage,
+        &split,
+        None,
+        true,
+        CachePriority::Normal,
+    )
+    .await?;
     let split_schema = index.schema();
     let reader = index
         .reader_builder()
@@ -376,6 +384,7 @@ pub async fn leaf_list_terms(
             split_id,
             error: err.to_string(),
             retryable_error: true,
+            error_kind: err.split_search_error_kind().into(),
         })
         .collect();
     let merged_search_response = LeafListTermsResponse {
@@ -21,12 +21,17 @@
 
 use once_cell::sync::Lazy;
 use quickwit_common::metrics::{
-    exponential_buckets, new_counter, new_histogram, Histogram, IntCounter,
+    exponential_buckets, new_counter, new_gauge, new_histogram, Histogram, IntCounter, IntGauge,
 };
 
 pub struct SearchMetrics {
     pub leaf_searches_splits_total: IntCounter,
+    pub leaf_search_splits_pruned_total: IntCounter,
+    pub in_flight_leaf_searches: IntGauge,
     pub leaf_search_split_duration_secs: Histogram,
+    pub split_footer_fetched_num_bytes: IntCounter,
+    pub finalize_panics_total: IntCounter,
+    pub split_footer_cache_evictions_total: IntCounter,
 }
 
 impl Default for SearchMetrics {
@@ -37,6 +42,19 @@ impl Default for SearchMetrics {
                 "Number of leaf searches (count of splits) started.",
                 "search",
             ),
+            leaf_search_splits_pruned_total: new_counter(
+                "leaf_search_splits_pruned_total",
+                "Number of splits skipped, or searched for their count only, because they could \
+                 not contain better hits than the splits already searched.",
+                "search",
+            ),
+            in_flight_leaf_searches: new_gauge(
+                "in_flight_leaf_searches",
+                "Number of splits currently being searched, from the moment the semaphore is \
+                 obtained, including the time spent warming up the split.",
+                "search",
+                &[],
+            ),
             leaf_search_split_duration_secs: new_histogram(
                 "leaf_search_split_duration_secs",
                 "Number of seconds required to run a leaf search over a single split. The timer \
@@ -44,6 +62,22 @@ impl Default for SearchMetrics {
                 "search",
                 exponential_buckets(0.005, 2.0, 10).unwrap(),
             ),
+            split_footer_fetched_num_bytes: new_counter(
+                "split_footer_fetched_num_bytes",
+                "Number of split footer bytes fetched from the storage upon split footer cache \
+                 misses.",
+                "search",
+            ),
+            finalize_panics_total: new_counter(
+                "finalize_panics_total",
+                "Number of leaf searches that panicked while merging the split search responses.",
+                "search",
+            ),
+            split_footer_cache_evictions_total: new_counter(
+                "split_footer_cache_evictions_total",
+                "Number of split footers evicted from the cache to make room for new ones.",
+                "search",
+            ),
         }
     }
 }
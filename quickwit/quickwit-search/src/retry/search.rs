@@ -36,7 +36,9 @@ impl RetryPolicy<LeafSearchRequest, LeafSearchResponse, SearchError> for LeafSea
     ) -> Option<LeafSearchRequest> {
         match response_res {
             Ok(response) => {
-                if response.failed_splits.is_empty() {
+                // The splits left unsearched by a timed out leaf search are not retried: the search
+                // already used up its time budget.
+                if response.failed_splits.is_empty() || response.timed_out {
                     return None;
                 }
                 request.split_offsets.retain(|split_metadata| {
@@ -56,7 +58,7 @@ impl RetryPolicy<LeafSearchRequest, LeafSearchResponse, SearchError> for LeafSea
 mod tests {
     use quickwit_proto::search::{
         LeafSearchRequest, LeafSearchResponse, SearchRequest, SplitIdAndFooterOffsets,
-        SplitSearchError,
+        SplitSearchError, SplitSearchErrorKind,
     };
     use quickwit_query::query_ast::qast_json_helper;
 
@@ -81,6 +83,7 @@ mod tests {
                     split_footer_start: 0,
                     timestamp_start: None,
                     timestamp_end: None,
+                    num_docs: 0,
                 },
                 SplitIdAndFooterOffsets {
                     split_id: "split_2".to_string(),
@@ -88,6 +91,7 @@ mod tests {
                     split_footer_start: 0,
                     timestamp_start: None,
                     timestamp_end: None,
+                    num_docs: 0,
                 },
             ],
         }
@@ -127,6 +131,7 @@ mod tests {
             error: "error".to_string(),
             split_id: "split_2".to_string(),
             retryable_error: true,
+            error_kind: SplitSearchErrorKind::Internal.into(),
         };
         let response_res = Ok(LeafSearchResponse {
             num_hits: 0,
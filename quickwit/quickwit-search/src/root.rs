@@ -36,17 +36,18 @@ use quickwit_proto::metastore::{
 use quickwit_proto::search::{
     FetchDocsRequest, FetchDocsResponse, Hit, LeafHit, LeafSearchRequest, LeafSearchResponse,
     PartialHit, SearchRequest, SearchResponse, SnippetRequest, SortDatetimeFormat, SortField,
-    SortValue, SplitIdAndFooterOffsets,
+    SortValue, SplitIdAndFooterOffsets, SplitSearchError, SplitSearchErrorKind,
 };
 use quickwit_proto::types::{IndexUid, SplitId};
 use quickwit_query::query_ast::{
-    BoolQuery, QueryAst, QueryAstVisitor, RangeQuery, TermQuery, TermSetQuery,
+    BoolQuery, QueryAst, QueryAstTransformer, QueryAstVisitor, RangeQuery, TermQuery, TermSetQuery,
 };
 use serde::{Deserialize, Serialize};
 use tantivy::aggregation::agg_result::AggregationResults;
 use tantivy::aggregation::intermediate_agg_result::IntermediateAggregationResults;
 use tantivy::collector::Collector;
 use tantivy::schema::{FieldEntry, FieldType, Schema};
+use tantivy::time::OffsetDateTime;
 use tantivy::TantivyError;
 use tracing::{debug, error, info, info_span, instrument};
 
@@ -191,12 +192,25 @@ fn validate_request_and_build_metadata(
         .map_err(|err| {
             SearchError::Internal(format!("failed to build doc mapper. cause: {err}"))
         })?;
-        let query_ast_resolved_for_index = query_ast
+        let mut query_ast_resolved_for_index = query_ast
             .clone()
             .parse_user_query(doc_mapper.default_search_fields())
             // We convert the error to return a 400 to the user (and not a 500).
             .map_err(|err| SearchError::InvalidQuery(err.to_string()))?;
 
+        // Relative time bounds are resolved against the instant captured by the root, before
+        // building the query: tantivy cannot interpret them.
+        if let Some(now_timestamp_nanos) = search_request.now_timestamp_nanos {
+            let mut resolve_relative_time_bounds = ResolveRelativeTimeBounds {
+                schema: &doc_mapper.schema(),
+                now: tantivy::DateTime::from_timestamp_nanos(now_timestamp_nanos),
+            };
+            query_ast_resolved_for_index = resolve_relative_time_bounds
+                .transform(query_ast_resolved_for_index)
+                .expect("can't fail unwrapping Infallible")
+                .unwrap_or(QueryAst::MatchAll);
+        }
+
         // Validate uniqueness of resolved query AST.
         if let Some(query_ast_resolved) = &query_ast_resolved_opt {
             if query_ast_resolved != &query_ast_resolved_for_index {
@@ -340,6 +354,7 @@ fn simplify_search_request_for_scroll_api(req: &SearchRequest) -> crate::Result<
         query_ast: req.query_ast.clone(),
         start_timestamp: req.start_timestamp,
         end_timestamp: req.end_timestamp,
+        end_timestamp_inclusive: req.end_timestamp_inclusive,
         max_hits: req.max_hits,
         start_offset: req.start_offset,
         sort_fields: req.sort_fields.clone(),
@@ -354,6 +369,15 @@ fn simplify_search_request_for_scroll_api(req: &SearchRequest) -> crate::Result<
         // request is simplified after initial query, and we cache the hit count, so we don't need
         // to recompute it afterward.
         count_hits: quickwit_proto::search::CountHits::Underestimate as i32,
+        num_slowest_splits: None,
+        collect_split_stats: false,
+        // Relative time bounds were resolved by the root: later scroll requests reuse the
+        // resolved query AST.
+        now_timestamp_nanos: None,
+        // Scrolls page through large result sets: they must not starve interactive searches.
+        priority: quickwit_proto::search::SearchPriority::Background as i32,
+        split_id_allowlist: req.split_id_allowlist.clone(),
+        split_id_denylist: req.split_id_denylist.clone(),
     })
 }
 
@@ -620,6 +644,13 @@ fn get_count_from_metadata(split_metadatas: &[SplitMetadata]) -> Vec<LeafSearchR
             failed_splits: Vec::new(),
             num_attempted_splits: 1,
             intermediate_aggregation_result: None,
+            slowest_splits: Vec::new(),
+            per_split_stats: Vec::new(),
+            num_splits_searched: 0,
+            num_splits_pruned: 0,
+            num_splits_count_only: 0,
+            aggregation_is_partial: false,
+            timed_out: false,
         })
         .collect()
 }
@@ -667,7 +698,8 @@ pub(crate) async fn search_partial_hits_phase(
     let leaf_search_responses: Vec<tantivy::Result<LeafSearchResponse>> =
         leaf_search_responses.into_iter().map(Ok).collect_vec();
     let span = info_span!("merge_fruits");
-    let leaf_search_response = crate::search_thread_pool()
+    let leaf_search_response = searcher_context
+        .search_thread_pool()
         .run_cpu_intensive(move || {
             let _span_guard = span.enter();
             merge_collector.merge_fruits(leaf_search_responses)
@@ -682,7 +714,16 @@ pub(crate) async fn search_partial_hits_phase(
         has_intermediate_aggregation_result = leaf_search_response.intermediate_aggregation_result.is_some(),
         "Merged leaf search response."
     );
-    if !leaf_search_response.failed_splits.is_empty() {
+    // The splits left unsearched by a leaf search deadline make for a partial response flagged as
+    // timed out rather than for an error.
+    let is_left_by_deadline = |failed_split: &SplitSearchError| {
+        leaf_search_response.timed_out && failed_split.error_kind() == SplitSearchErrorKind::Timeout
+    };
+    if !leaf_search_response
+        .failed_splits
+        .iter()
+        .all(is_left_by_deadline)
+    {
         error!(failed_splits = ?leaf_search_response.failed_splits, "leaf search response contains at least one failed split");
         let errors: String = leaf_search_response.failed_splits.iter().join(", ");
         return Err(SearchError::Internal(errors));
@@ -903,6 +944,10 @@ async fn root_search_aux(
         scroll_id: scroll_key_and_start_offset_opt
             .as_ref()
             .map(ToString::to_string),
+        per_split_stats: first_phase_result.per_split_stats,
+        slowest_splits: first_phase_result.slowest_splits,
+        aggregation_is_partial: first_phase_result.aggregation_is_partial,
+        timed_out: first_phase_result.timed_out,
     })
 }
 
@@ -1013,6 +1058,11 @@ pub async fn root_search(
 ) -> crate::Result<SearchResponse> {
     info!(searcher_context = ?searcher_context, search_request = ?search_request);
     let start_instant = tokio::time::Instant::now();
+    // Relative time bounds such as `now-1h` are resolved against this single instant, so that all
+    // splits see the same time range.
+    search_request
+        .now_timestamp_nanos
+        .get_or_insert_with(|| OffsetDateTime::now_utc().unix_timestamp_nanos() as i64);
     let list_indexes_metadatas_request = ListIndexesMetadataRequest {
         index_id_patterns: search_request.index_id_patterns.clone(),
     };
@@ -1046,6 +1096,10 @@ pub async fn root_search(
         .collect_vec();
     let request_metadata = validate_request_and_build_metadata(&indexes_metadata, &search_request)?;
     search_request.query_ast = serde_json::to_string(&request_metadata.query_ast_resolved)?;
+    // The resolved query AST no longer holds relative time bounds: the leaves do not need the
+    // instant they were resolved against, and keeping it would make every request a distinct
+    // leaf cache entry.
+    search_request.now_timestamp_nanos = None;
 
     // convert search_after datetime values from input datetime format to nanos.
     convert_search_after_datetime_values(
@@ -1053,6 +1107,10 @@ pub async fn root_search(
         &request_metadata.sort_fields_is_datetime,
     )?;
 
+    // Split pruning and the timestamp range extracted from the query assume an exclusive end.
+    search_request.end_timestamp = search_request.end_timestamp_exclusive();
+    search_request.end_timestamp_inclusive = false;
+
     // update_search_after_datetime_in_nanos(&mut search_request)?;
     if let Some(timestamp_field) = &request_metadata.timestamp_field_opt {
         refine_start_end_timestamp_from_ast(
@@ -1337,6 +1395,52 @@ impl<'a, 'b> QueryAstVisitor<'b> for ExtractTimestampRange<'a> {
     }
 }
 
+/// Resolves relative time bounds such as `now-1h` of the range queries targeting datetime fields
+/// into absolute timestamps, so that the query can be built and its time range extracted.
+struct ResolveRelativeTimeBounds<'a> {
+    schema: &'a Schema,
+    now: tantivy::DateTime,
+}
+
+impl<'a> ResolveRelativeTimeBounds<'a> {
+    fn resolve_bound(
+        &self,
+        bound: std::ops::Bound<quickwit_query::JsonLiteral>,
+    ) -> std::ops::Bound<quickwit_query::JsonLiteral> {
+        bound.map(|time_bound| match &time_bound {
+            quickwit_query::JsonLiteral::String(time_bound_str)
+                if time_bound_str.trim_start().starts_with("now") =>
+            {
+                quickwit_datetime::parse_date_math(time_bound_str, self.now)
+                    .map(|date_time| date_time.into_timestamp_nanos().into())
+                    // An invalid expression is reported when the query gets built.
+                    .unwrap_or(time_bound)
+            }
+            _ => time_bound,
+        })
+    }
+}
+
+impl<'a> QueryAstTransformer for ResolveRelativeTimeBounds<'a> {
+    type Err = std::convert::Infallible;
+
+    fn transform_range(&mut self, range_query: RangeQuery) -> Result<Option<QueryAst>, Self::Err> {
+        let is_datetime_field = self
+            .schema
+            .get_field(&range_query.field)
+            .map(|field| self.schema.get_field_entry(field).field_type())
+            .is_ok_and(|field_type| matches!(field_type, FieldType::Date(_)));
+        if !is_datetime_field {
+            return Ok(Some(QueryAst::Range(range_query)));
+        }
+        Ok(Some(QueryAst::Range(RangeQuery {
+            lower_bound: self.resolve_bound(range_query.lower_bound),
+            upper_bound: self.resolve_bound(range_query.upper_bound),
+            field: range_query.field,
+        })))
+    }
+}
+
 async fn assign_client_fetch_docs_jobs(
     partial_hits: &[PartialHit],
     split_metadatas: &[SplitMetadata],
@@ -1486,12 +1590,13 @@ mod tests {
         ListIndexesMetadataResponse, ListSplitsResponse, MockMetastoreService,
     };
     use quickwit_proto::search::{
-        ScrollRequest, SortByValue, SortOrder, SortValue, SplitSearchError,
+        ScrollRequest, SortByValue, SortOrder, SortValue, SplitSearchError, SplitSearchErrorKind,
     };
     use quickwit_query::query_ast::{qast_helper, qast_json_helper, query_ast_from_user_text};
     use tantivy::schema::{FAST, STORED, TEXT};
 
     use super::*;
+    use crate::leaf_cache::LeafSearchCache;
     use crate::{searcher_pool_for_test, MockSearchService};
 
     #[track_caller]
@@ -1572,6 +1677,7 @@ mod tests {
         let indexing_settings = IndexingSettings::default();
         let search_settings = SearchSettings {
             default_search_fields: vec!["body".to_string()],
+            term_dict_warmup_policy: None,
         };
         IndexMetadata::new(IndexConfig {
             index_id: index_id.to_string(),
@@ -1744,6 +1850,7 @@ mod tests {
         let indexing_settings = IndexingSettings::default();
         let search_settings = SearchSettings {
             default_search_fields: vec!["body".to_string()],
+            term_dict_warmup_policy: None,
         };
         IndexMetadata::new(IndexConfig {
             index_id: index_id.to_string(),
@@ -2411,6 +2518,86 @@ mod tests {
         Ok(())
     }
 
+    #[tokio::test]
+    async fn test_root_search_successive_searches_share_leaf_cache_entries() -> anyhow::Result<()> {
+        let search_request = quickwit_proto::search::SearchRequest {
+            index_id_patterns: vec!["test-index".to_string()],
+            query_ast: qast_json_helper("test", &["body"]),
+            max_hits: 10,
+            ..Default::default()
+        };
+        let mut mock_metastore = MockMetastoreService::new();
+        let index_metadata = IndexMetadata::for_test("test-index", "ram:///test-index");
+        let index_uid = index_metadata.index_uid.clone();
+        mock_metastore
+            .expect_list_indexes_metadata()
+            .returning(move |_index_ids_query| {
+                Ok(ListIndexesMetadataResponse::for_test(vec![
+                    index_metadata.clone()
+                ]))
+            });
+        mock_metastore
+            .expect_list_splits()
+            .returning(move |_list_splits_request| {
+                let splits = vec![MockSplitBuilder::new("split1")
+                    .with_index_uid(&index_uid)
+                    .build()];
+                let splits_response = ListSplitsResponse::try_from_splits(splits).unwrap();
+                Ok(ServiceStream::from(vec![Ok(splits_response)]))
+            });
+        let leaf_search_requests: Arc<RwLock<Vec<LeafSearchRequest>>> = Default::default();
+        let leaf_search_requests_clone = leaf_search_requests.clone();
+        let mut mock_search_service = MockSearchService::new();
+        mock_search_service.expect_leaf_search().returning(
+            move |leaf_search_req: quickwit_proto::search::LeafSearchRequest| {
+                leaf_search_requests_clone
+                    .write()
+                    .unwrap()
+                    .push(leaf_search_req);
+                Ok(quickwit_proto::search::LeafSearchResponse {
+                    num_attempted_splits: 1,
+                    ..Default::default()
+                })
+            },
+        );
+        let searcher_pool = searcher_pool_for_test([("127.0.0.1:1001", mock_search_service)]);
+        let search_job_placer = SearchJobPlacer::new(searcher_pool);
+        let cluster_client = ClusterClient::new(search_job_placer.clone());
+        let metastore = MetastoreServiceClient::from_mock(mock_metastore);
+
+        let searcher_context = SearcherContext::for_test();
+        for _ in 0..2 {
+            root_search(
+                &searcher_context,
+                search_request.clone(),
+                metastore.clone(),
+                &cluster_client,
+            )
+            .await
+            .unwrap();
+        }
+        let leaf_search_requests = leaf_search_requests.read().unwrap();
+        assert_eq!(leaf_search_requests.len(), 2);
+
+        let split = &leaf_search_requests[0].split_offsets[0];
+        let first_search_request = leaf_search_requests[0].search_request.as_ref().unwrap();
+        let second_search_request = leaf_search_requests[1].search_request.as_ref().unwrap();
+        let leaf_search_cache = LeafSearchCache::new(1_000_000);
+        leaf_search_cache.put(
+            split,
+            first_search_request,
+            quickwit_proto::search::LeafSearchResponse {
+                num_hits: 3,
+                ..Default::default()
+            },
+        );
+        let cached_response = leaf_search_cache
+            .get(split, second_search_request)
+            .expect("the second search should hit the leaf cache");
+        assert_eq!(cached_response.num_hits, 3);
+        Ok(())
+    }
+
     #[tokio::test]
     async fn test_root_search_multiple_splits() -> anyhow::Result<()> {
         let search_request = quickwit_proto::search::SearchRequest {
@@ -2957,6 +3144,7 @@ mod tests {
                             error: "mock_error".to_string(),
                             split_id: "split2".to_string(),
                             retryable_error: true,
+                            error_kind: SplitSearchErrorKind::Internal.into(),
                         }],
                         num_attempted_splits: 1,
                         ..Default::default()
@@ -3035,6 +3223,7 @@ mod tests {
                         error: "mock_error".to_string(),
                         split_id: "split2".to_string(),
                         retryable_error: true,
+                        error_kind: SplitSearchErrorKind::Internal.into(),
                     }],
                     num_attempted_splits: 1,
                     ..Default::default()
@@ -3090,6 +3279,7 @@ mod tests {
                         error: "mock_error".to_string(),
                         split_id: "split1".to_string(),
                         retryable_error: true,
+                        error_kind: SplitSearchErrorKind::Internal.into(),
                     }],
                     num_attempted_splits: 1,
                     ..Default::default()
@@ -3162,6 +3352,7 @@ mod tests {
                             error: "mock_error".to_string(),
                             split_id: "split1".to_string(),
                             retryable_error: true,
+                            error_kind: SplitSearchErrorKind::Internal.into(),
                         }],
                         num_attempted_splits: 1,
                         ..Default::default()
@@ -3238,6 +3429,7 @@ mod tests {
                         error: "mock_error".to_string(),
                         split_id: "split1".to_string(),
                         retryable_error: true,
+                        error_kind: SplitSearchErrorKind::Internal.into(),
                     }],
                     num_attempted_splits: 1,
                     ..Default::default()
@@ -3323,6 +3515,7 @@ mod tests {
                         error: "mock_error".to_string(),
                         split_id: "split1".to_string(),
                         retryable_error: true,
+                        error_kind: SplitSearchErrorKind::Internal.into(),
                     }],
                     num_attempted_splits: 1,
                     ..Default::default()
@@ -19,8 +19,8 @@
 
 use std::convert::TryFrom;
 
-use quickwit_common::truncate_str;
-use quickwit_proto::search::SearchResponse;
+use quickwit_common::{is_false, truncate_str};
+use quickwit_proto::search::{SearchResponse, SplitSearchTiming, SplitStats};
 use serde::{Deserialize, Serialize};
 use serde_json::Value as JsonValue;
 
@@ -47,6 +47,23 @@ pub struct SearchResponseRest {
     #[schema(value_type = Object)]
     #[serde(skip_serializing_if = "Option::is_none")]
     pub aggregations: Option<JsonValue>,
+    /// Statistics of every split searched, only set if `collect_split_stats` was requested.
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub per_split_stats: Vec<SplitStats>,
+    /// Slowest splits searched, only set if `num_slowest_splits` was requested.
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub slowest_splits: Vec<SplitSearchTiming>,
+    /// True if some splits failed: the aggregations only cover the splits searched successfully.
+    #[serde(default)]
+    #[serde(skip_serializing_if = "is_false")]
+    pub aggregation_is_partial: bool,
+    /// True if the search ran out of time: the hits and aggregations only cover the splits
+    /// searched before the deadline.
+    #[serde(default)]
+    #[serde(skip_serializing_if = "is_false")]
+    pub timed_out: bool,
 }
 
 impl TryFrom<SearchResponse> for SearchResponseRest {
@@ -97,6 +114,10 @@ impl TryFrom<SearchResponse> for SearchResponseRest {
             elapsed_time_micros: search_response.elapsed_time_micros,
             errors: search_response.errors,
             aggregations: aggregations_opt,
+            per_split_stats: search_response.per_split_stats,
+            slowest_splits: search_response.slowest_splits,
+            aggregation_is_partial: search_response.aggregation_is_partial,
+            timed_out: search_response.timed_out,
         })
     }
 }
@@ -29,7 +29,7 @@ use quickwit_proto::search::{
     LeafSearchStreamResponse, OutputFormat, SearchRequest, SearchStreamRequest,
     SplitIdAndFooterOffsets,
 };
-use quickwit_storage::Storage;
+use quickwit_storage::{CachePriority, Storage};
 use tantivy::columnar::{DynamicColumn, HasAssociatedColumnType};
 use tantivy::fastfield::Column;
 use tantivy::query::Query;
@@ -41,7 +41,10 @@ use tracing::*;
 use super::collector::{PartionnedFastFieldCollector, PartitionValues};
 use super::FastFieldCollector;
 use crate::filters::{create_timestamp_filter_builder, TimestampFilterBuilder};
-use crate::leaf::{open_index_with_caches, rewrite_start_end_time_bounds, warmup};
+use crate::leaf::{
+    check_term_dict_warmup_policy, check_warmup_budget, open_index_with_caches,
+    rewrite_start_end_time_bounds, warmup,
+};
 use crate::service::SearcherContext;
 use crate::{Result, SearchError};
 
@@ -124,6 +127,8 @@ async fn leaf_search_stream_single_split(
     rewrite_start_end_time_bounds(
         &mut stream_request.start_timestamp,
         &mut stream_request.end_timestamp,
+        // Search stream requests always have an exclusive end timestamp.
+        false,
         &split,
     );
 
@@ -133,6 +138,7 @@ async fn leaf_search_stream_single_split(
         &split,
         Some(doc_mapper.tokenizer_manager()),
         true,
+        CachePriority::Normal,
     )
     .await?;
     let split_schema = index.schema();
@@ -186,7 +192,23 @@ async fn leaf_search_stream_single_split(
     warmup_info.fast_field_names.extend(fast_field_names);
     warmup_info.simplify();
 
-    warmup(&searcher, &warmup_info).await?;
+    check_term_dict_warmup_policy(
+        &searcher,
+        &warmup_info,
+        doc_mapper.term_dict_warmup_policy(),
+    )?;
+    check_warmup_budget(
+        &searcher,
+        &warmup_info,
+        searcher_context.searcher_config.max_warmup_bytes_per_split,
+    )?;
+    warmup(
+        &searcher,
+        &warmup_info,
+        searcher_context.searcher_config.max_term_range_warmup_limit,
+        searcher_context.searcher_config.warmup_concurrency,
+    )
+    .await?;
 
     let span = info_span!(
         "collect_fast_field",
@@ -196,94 +218,108 @@ async fn leaf_search_stream_single_split(
 
     let _ = span.enter();
     let m_request_fields = request_fields.clone();
-    let collect_handle = crate::search_thread_pool().run_cpu_intensive(move || {
-        let mut buffer = Vec::new();
-        match m_request_fields.fast_field_types() {
-            (Type::I64, None) => {
-                let collected_values = collect_values::<i64>(
-                    &m_request_fields,
-                    timestamp_filter_builder_opt,
-                    &searcher,
-                    &query,
-                )?;
-                super::serialize::<i64>(&collected_values, &mut buffer, output_format).map_err(
-                    |_| {
-                        SearchError::Internal("error when serializing i64 during export".to_owned())
-                    },
-                )?;
-            }
-            (Type::U64, None) => {
-                let collected_values = collect_values::<u64>(
-                    &m_request_fields,
-                    timestamp_filter_builder_opt,
-                    &searcher,
-                    &query,
-                )?;
-                super::serialize::<u64>(&collected_values, &mut buffer, output_format).map_err(
-                    |_| {
-                        SearchError::Internal("error when serializing u64 during export".to_owned())
-                    },
-                )?;
-            }
-            (Type::Date, None) => {
-                let collected_values = collect_values::<DateTime>(
-                    &m_request_fields,
-                    timestamp_filter_builder_opt,
-                    &searcher,
-                    &query,
-                )?;
-                // It may seem overkill and expensive considering DateTime is just a wrapper
-                // over the i64, but the compiler is smarter than it looks and the code
-                // below actually is zero-cost: No allocation and no copy happens.
-                let collected_values_as_micros = collected_values
-                    .into_iter()
-                    .map(|date_time| date_time.into_timestamp_micros())
-                    .collect::<Vec<_>>();
-                // We serialize Date as i64 microseconds.
-                super::serialize::<i64>(&collected_values_as_micros, &mut buffer, output_format)
+    let collect_handle = searcher_context
+        .search_thread_pool()
+        .run_cpu_intensive(move || {
+            let mut buffer = Vec::new();
+            match m_request_fields.fast_field_types() {
+                (Type::I64, None) => {
+                    let collected_values = collect_values::<i64>(
+                        &m_request_fields,
+                        timestamp_filter_builder_opt,
+                        &searcher,
+                        &query,
+                    )?;
+                    super::serialize::<i64>(&collected_values, &mut buffer, output_format)
+                        .map_err(|_| {
+                            SearchError::Internal(
+                                "error when serializing i64 during export".to_owned(),
+                            )
+                        })?;
+                }
+                (Type::U64, None) => {
+                    let collected_values = collect_values::<u64>(
+                        &m_request_fields,
+                        timestamp_filter_builder_opt,
+                        &searcher,
+                        &query,
+                    )?;
+                    super::serialize::<u64>(&collected_values, &mut buffer, output_format)
+                        .map_err(|_| {
+                            SearchError::Internal(
+                                "error when serializing u64 during export".to_owned(),
+                            )
+                        })?;
+                }
+                (Type::Date, None) => {
+                    let collected_values = collect_values::<DateTime>(
+                        &m_request_fields,
+                        timestamp_filter_builder_opt,
+                        &searcher,
+                        &query,
+                    )?;
+                    // It may seem overkill and expensive considering DateTime is just a wrapper
+                    // over the i64, but the compiler is smarter than it looks and the code
+                    // below actually is zero-cost: No allocation and no copy happens.
+                    let collected_values_as_micros = collected_values
+                        .into_iter()
+                        .map(|date_time| date_time.into_timestamp_micros())
+                        .collect::<Vec<_>>();
+                    // We serialize Date as i64 microseconds.
+                    super::serialize::<i64>(
+                        &collected_values_as_micros,
+                        &mut buffer,
+                        output_format,
+                    )
                     .map_err(|_| {
                         SearchError::Internal("error when serializing i64 during export".to_owned())
                     })?;
-            }
-            (Type::I64, Some(Type::I64)) => {
-                let collected_values = collect_partitioned_values::<i64, i64>(
-                    &m_request_fields,
-                    timestamp_filter_builder_opt,
-                    &searcher,
-                    &query,
-                )?;
-                super::serialize_partitions::<i64, i64>(collected_values.as_slice(), &mut buffer)
+                }
+                (Type::I64, Some(Type::I64)) => {
+                    let collected_values = collect_partitioned_values::<i64, i64>(
+                        &m_request_fields,
+                        timestamp_filter_builder_opt,
+                        &searcher,
+                        &query,
+                    )?;
+                    super::serialize_partitions::<i64, i64>(
+                        collected_values.as_slice(),
+                        &mut buffer,
+                    )
                     .map_err(|_| {
-                    SearchError::Internal("error when serializing i64 during export".to_owned())
-                })?;
-            }
-            (Type::U64, Some(Type::U64)) => {
-                let collected_values = collect_partitioned_values::<u64, u64>(
-                    &m_request_fields,
-                    timestamp_filter_builder_opt,
-                    &searcher,
-                    &query,
-                )?;
-                super::serialize_partitions::<u64, u64>(collected_values.as_slice(), &mut buffer)
+                        SearchError::Internal("error when serializing i64 during export".to_owned())
+                    })?;
+                }
+                (Type::U64, Some(Type::U64)) => {
+                    let collected_values = collect_partitioned_values::<u64, u64>(
+                        &m_request_fields,
+                        timestamp_filter_builder_opt,
+                        &searcher,
+                        &query,
+                    )?;
+                    super::serialize_partitions::<u64, u64>(
+                        collected_values.as_slice(),
+                        &mut buffer,
+                    )
                     .map_err(|_| {
-                    SearchError::Internal("error when serializing i64 during export".to_owned())
-                })?;
-            }
-            (fast_field_type, None) => {
-                return Err(SearchError::Internal(format!(
-                    "search stream does not support fast field of type `{fast_field_type:?}`"
-                )));
-            }
-            (fast_field_type, Some(partition_fast_field_type)) => {
-                return Err(SearchError::Internal(format!(
-                    "search stream does not support the combination of fast field type \
-                     `{fast_field_type:?}` and partition fast field type \
-                     `{partition_fast_field_type:?}`"
-                )));
-            }
-        };
-        Result::<Vec<u8>>::Ok(buffer)
-    });
+                        SearchError::Internal("error when serializing i64 during export".to_owned())
+                    })?;
+                }
+                (fast_field_type, None) => {
+                    return Err(SearchError::Internal(format!(
+                        "search stream does not support fast field of type `{fast_field_type:?}`"
+                    )));
+                }
+                (fast_field_type, Some(partition_fast_field_type)) => {
+                    return Err(SearchError::Internal(format!(
+                        "search stream does not support the combination of fast field type \
+                         `{fast_field_type:?}` and partition fast field type \
+                         `{partition_fast_field_type:?}`"
+                    )));
+                }
+            };
+            Result::<Vec<u8>>::Ok(buffer)
+        });
     let buffer = collect_handle.await.map_err(|_| {
         error!(split_id = %split.split_id, request_fields=%request_fields, "failed to collect fast field");
         SearchError::Internal(format!("error when collecting fast field values for split {}", split.split_id))
@@ -0,0 +1,273 @@
+// Copyright (C) 2024 Quickwit, Inc.
+//
+// Quickwit is offered under the AGPL v3.0 and as commercial software.
+// For commercial licensing, contact us at hello@quickwit.io.
+//
+// AGPL:
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+use futures::{stream, StreamExt};
+use quickwit_doc_mapper::DocMapper;
+use quickwit_proto::search::{
+    LeafSearchResponse, SearchRequest, SplitIdAndFooterOffsets, SplitSearchError,
+};
+use quickwit_storage::{CachePriority, Storage};
+use tantivy::Index;
+use tokio::sync::Mutex;
+use tracing::warn;
+
+use crate::leaf::{leaf_search_inner, open_index_with_caches, split_open_error, LeafSearchOptions};
+use crate::SearcherContext;
+
+/// A long-lived leaf searcher over a slowly changing set of splits.
+///
+/// The session keeps the splits it searched open, so that repeated searches only pay the cost of
+/// opening the splits added since the previous search. Splits that are no longer part of the
+/// split set are dropped.
+///
+/// The data warmed up for a split stays in memory for as long as the split is part of the split
+/// set, which makes the session a good fit for embedded or local-disk deployments.
+pub struct SearcherSession {
+    searcher_context: Arc<SearcherContext>,
+    index_storage: Arc<dyn Storage>,
+    doc_mapper: Arc<dyn DocMapper>,
+    opened_indexes: Mutex<Arc<HashMap<String, Index>>>,
+    num_split_opens: AtomicUsize,
+}
+
+impl SearcherSession {
+    /// Creates a new session searching the splits of `index_storage`.
+    pub fn new(
+        searcher_context: Arc<SearcherContext>,
+        index_storage: Arc<dyn Storage>,
+        doc_mapper: Arc<dyn DocMapper>,
+    ) -> Self {
+        SearcherSession {
+            searcher_context,
+            index_storage,
+            doc_mapper,
+            opened_indexes: Mutex::default(),
+            num_split_opens: AtomicUsize::new(0),
+        }
+    }
+
+    /// Runs a leaf search on `splits`, the current split set.
+    ///
+    /// Splits opened by a previous search are reused, new splits are opened and splits that are
+    /// not part of `splits` anymore are dropped. Splits that fail to open are reported as failed
+    /// without being searched, but still count as attempted.
+    pub async fn search(
+        &self,
+        request: SearchRequest,
+        mut splits: Vec<SplitIdAndFooterOffsets>,
+    ) -> crate::Result<LeafSearchResponse> {
+        let (opened_indexes, failed_splits) = self.update_opened_indexes(&splits).await;
+        splits.retain(|split| opened_indexes.contains_key(&split.split_id));
+
+        let mut leaf_search_response = leaf_search_inner(
+            self.searcher_context.clone(),
+            Arc::new(request),
+            self.index_storage.clone(),
+            splits,
+            self.doc_mapper.clone(),
+            LeafSearchOptions::default(),
+            Some(opened_indexes),
+        )
+        .await?;
+        leaf_search_response.num_attempted_splits += failed_splits.len() as u64;
+        leaf_search_response.failed_splits.extend(failed_splits);
+        Ok(leaf_search_response)
+    }
+
+    /// Returns the number of splits opened over the lifetime of the session.
+    pub fn num_split_opens(&self) -> usize {
+        self.num_split_opens.load(Ordering::Relaxed)
+    }
+
+    /// Opens the splits of `splits` that are not opened yet, and returns the opened splits along
+    /// with the errors of the splits that failed to open.
+    ///
+    /// Updates are serialized, so that concurrent searches never open the same split twice, and
+    /// split opens are bounded by `max_num_concurrent_split_searches`.
+    async fn update_opened_indexes(
+        &self,
+        splits: &[SplitIdAndFooterOffsets],
+    ) -> (Arc<HashMap<String, Index>>, Vec<SplitSearchError>) {
+        let mut opened_indexes_guard = self.opened_indexes.lock().await;
+
+        let splits_to_open = splits
+            .iter()
+            .filter(|split| !opened_indexes_guard.contains_key(&split.split_id));
+        let max_num_concurrent_split_opens = self
+            .searcher_context
+            .searcher_config
+            .max_num_concurrent_split_searches;
+        let open_index_results: Vec<(&SplitIdAndFooterOffsets, anyhow::Result<Index>)> =
+            stream::iter(splits_to_open)
+                .map(|split| async move {
+                    let open_index_res = open_index_with_caches(
+                        &self.searcher_context,
+                        self.index_storage.clone(),
+                        split,
+                        Some(self.doc_mapper.tokenizer_manager()),
+                        true,
+                        CachePriority::Normal,
+                    )
+                    .await;
+                    (split, open_index_res)
+                })
+                .buffer_unordered(max_num_concurrent_split_opens)
+                .collect()
+                .await;
+
+        // Only keep the splits of the current split set.
+        let mut opened_indexes: HashMap<String, Index> = splits
+            .iter()
+            .filter_map(|split| {
+                let index = opened_indexes_guard.get(&split.split_id)?;
+                Some((split.split_id.clone(), index.clone()))
+            })
+            .collect();
+        let mut failed_splits = Vec::new();
+        for (split, open_index_res) in open_index_results {
+            match open_index_res {
+                Ok(index) => {
+                    self.num_split_opens.fetch_add(1, Ordering::Relaxed);
+                    opened_indexes.insert(split.split_id.clone(), index);
+                }
+                Err(error) => {
+                    warn!(split_id=%split.split_id, error=?error, "failed to open split");
+                    failed_splits.push(split_open_error(&split.split_id, error));
+                }
+            }
+        }
+        let opened_indexes = Arc::new(opened_indexes);
+        *opened_indexes_guard = opened_indexes.clone();
+        (opened_indexes, failed_splits)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use quickwit_config::SearcherConfig;
+    use quickwit_indexing::TestSandbox;
+    use quickwit_query::query_ast::qast_json_helper;
+    use serde_json::json;
+
+    use super::*;
+    use crate::tests::{list_split_offsets, BODY_DOC_MAPPING_YAML};
+
+    #[tokio::test]
+    async fn test_searcher_session_only_opens_new_splits() -> anyhow::Result<()> {
+        let test_sandbox =
+            TestSandbox::create("searcher-session", BODY_DOC_MAPPING_YAML, "{}", &["body"]).await?;
+        let searcher_context = Arc::new(SearcherContext::new(SearcherConfig::default(), None));
+        let searcher_session = SearcherSession::new(
+            searcher_context,
+            test_sandbox.storage(),
+            test_sandbox.doc_mapper(),
+        );
+        let request = SearchRequest {
+            index_id_patterns: vec![test_sandbox.index_uid().index_id.to_string()],
+            query_ast: qast_json_helper("happy", &["body"]),
+            max_hits: 10,
+            ..Default::default()
+        };
+
+        test_sandbox
+            .add_documents(vec![json!({"body": "hello happy tax payer"})])
+            .await?;
+        let splits = list_split_offsets(&test_sandbox).await?;
+        assert_eq!(splits.len(), 1);
+        let leaf_search_response = searcher_session.search(request.clone(), splits).await?;
+        assert_eq!(leaf_search_response.num_hits, 1);
+        assert_eq!(searcher_session.num_split_opens(), 1);
+
+        test_sandbox
+            .add_documents(vec![json!({"body": "happy new year"})])
+            .await?;
+        let splits = list_split_offsets(&test_sandbox).await?;
+        assert_eq!(splits.len(), 2);
+        let leaf_search_response = searcher_session.search(request.clone(), splits).await?;
+        assert_eq!(leaf_search_response.num_hits, 2);
+        // Only the new split was opened.
+        assert_eq!(searcher_session.num_split_opens(), 2);
+
+        // Removed splits are dropped from the session.
+        let splits: Vec<SplitIdAndFooterOffsets> = list_split_offsets(&test_sandbox)
+            .await?
+            .into_iter()
+            .take(1)
+            .collect();
+        let leaf_search_response = searcher_session.search(request, splits).await?;
+        assert_eq!(leaf_search_response.num_hits, 1);
+        assert_eq!(searcher_session.num_split_opens(), 2);
+        assert_eq!(searcher_session.opened_indexes.lock().await.len(), 1);
+
+        test_sandbox.assert_quit().await;
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_searcher_session_reports_splits_failing_to_open() -> anyhow::Result<()> {
+        let test_sandbox = TestSandbox::create(
+            "searcher-session-failing-split",
+            BODY_DOC_MAPPING_YAML,
+            "{}",
+            &["body"],
+        )
+        .await?;
+        let searcher_context = Arc::new(SearcherContext::new(SearcherConfig::default(), None));
+        let searcher_session = SearcherSession::new(
+            searcher_context,
+            test_sandbox.storage(),
+            test_sandbox.doc_mapper(),
+        );
+        let request = SearchRequest {
+            index_id_patterns: vec![test_sandbox.index_uid().index_id.to_string()],
+            query_ast: qast_json_helper("happy", &["body"]),
+            max_hits: 10,
+            ..Default::default()
+        };
+        test_sandbox
+            .add_documents(vec![json!({"body": "hello happy tax payer"})])
+            .await?;
+        let mut splits = list_split_offsets(&test_sandbox).await?;
+        splits.push(SplitIdAndFooterOffsets {
+            split_id: "missing-split".to_string(),
+            split_footer_start: 0,
+            split_footer_end: 100,
+            ..Default::default()
+        });
+
+        let leaf_search_response = searcher_session.search(request, splits).await?;
+        assert_eq!(leaf_search_response.num_hits, 1);
+        // The split failing to open was attempted too.
+        assert_eq!(leaf_search_response.num_attempted_splits, 2);
+        assert_eq!(leaf_search_response.failed_splits.len(), 1);
+        assert_eq!(
+            leaf_search_response.failed_splits[0].split_id,
+            "missing-split"
+        );
+        assert_eq!(searcher_session.num_split_opens(), 1);
+        assert_eq!(searcher_session.opened_indexes.lock().await.len(), 1);
+
+        test_sandbox.assert_quit().await;
+        Ok(())
+    }
+}
@@ -24,6 +24,8 @@ use std::time::{Duration, Instant};
 
 use async_trait::async_trait;
 use bytes::Bytes;
+use bytesize::ByteSize;
+use quickwit_common::thread_pool::ThreadPool;
 use quickwit_common::uri::Uri;
 use quickwit_config::SearcherConfig;
 use quickwit_doc_mapper::DocMapper;
@@ -33,15 +35,19 @@ use quickwit_proto::search::{
     LeafListTermsRequest, LeafListTermsResponse, LeafSearchRequest, LeafSearchResponse,
     LeafSearchStreamRequest, LeafSearchStreamResponse, ListFieldsRequest, ListFieldsResponse,
     ListTermsRequest, ListTermsResponse, PutKvRequest, ReportSplitsRequest, ReportSplitsResponse,
-    ScrollRequest, SearchRequest, SearchResponse, SearchStreamRequest, SnippetRequest,
+    ScrollRequest, SearchPriority, SearchRequest, SearchResponse, SearchStreamRequest,
+    SnippetRequest,
 };
 use quickwit_storage::{
     MemorySizedCache, QuickwitCache, SplitCache, StorageCache, StorageResolver,
 };
 use tantivy::aggregation::AggregationLimits;
-use tokio::sync::Semaphore;
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
 use tokio_stream::wrappers::UnboundedReceiverStream;
+use tokio_util::sync::CancellationToken;
+use tracing::Instrument;
 
+use crate::hotcache_cache::HotcacheCache;
 use crate::leaf_cache::LeafSearchCache;
 use crate::list_fields::{leaf_list_fields, root_list_fields};
 use crate::list_fields_cache::ListFieldsCache;
@@ -49,8 +55,13 @@ use crate::list_terms::{leaf_list_terms, root_list_terms};
 use crate::root::fetch_docs_phase;
 use crate::scroll_context::{MiniKV, ScrollContext, ScrollKeyAndStartOffset};
 use crate::search_stream::{leaf_search_stream, root_search_stream};
+use crate::split_cache_warmer::SplitCacheWarmer;
 use crate::{fetch_docs, leaf_search, root_search, ClusterClient, SearchError};
 
+/// Maximum number of split footers prefetched concurrently, across all the leaf searches. The
+/// prefetches don't take split search permits, so this budget is kept small.
+const MAX_NUM_CONCURRENT_SPLIT_FOOTER_PREFETCHES: usize = 10;
+
 #[derive(Clone)]
 /// The search service implementation.
 pub struct SearchServiceImpl {
@@ -200,14 +211,26 @@ impl SearchService for SearchServiceImpl {
         let storage = self.storage_resolver.resolve(&index_uri).await?;
         let doc_mapper = deserialize_doc_mapper(&leaf_search_request.doc_mapper)?;
 
-        let leaf_search_response = leaf_search(
-            self.searcher_context.clone(),
-            search_request,
-            storage.clone(),
-            leaf_search_request.split_offsets,
-            doc_mapper,
+        // When the caller drops the request, e.g. because the root gave up on it, the guard
+        // cancels the leaf search, which then aborts its in-flight split searches. The leaf
+        // search runs in its own task so that it gets to observe the cancellation.
+        let cancellation_token = CancellationToken::new();
+        let _cancel_on_drop_guard = cancellation_token.clone().drop_guard();
+        let leaf_search_response = tokio::spawn(
+            leaf_search(
+                self.searcher_context.clone(),
+                search_request,
+                storage.clone(),
+                leaf_search_request.split_offsets,
+                doc_mapper,
+                Some(cancellation_token),
+            )
+            .in_current_span(),
         )
-        .await?;
+        .await
+        .map_err(|join_error| {
+            SearchError::Internal(format!("leaf search task failed: {join_error}"))
+        })??;
 
         Ok(leaf_search_response)
     }
@@ -428,6 +451,10 @@ pub(crate) async fn scroll(
         scroll_id: Some(next_scroll_id.to_string()),
         errors: Vec::new(),
         aggregation: None,
+        per_split_stats: Vec::new(),
+        slowest_splits: Vec::new(),
+        aggregation_is_partial: false,
+        timed_out: false,
     })
 }
 /// [`SearcherContext`] provides a common set of variables
@@ -440,16 +467,100 @@ pub struct SearcherContext {
     pub fast_fields_cache: Arc<dyn StorageCache>,
     /// Counting semaphore to limit concurrent leaf search split requests.
     pub leaf_search_split_semaphore: Arc<Semaphore>,
+    /// Counting semaphore holding the split search permits reserved to interactive searches, on
+    /// top of the ones of `leaf_search_split_semaphore`.
+    pub interactive_leaf_search_split_semaphore: Arc<Semaphore>,
     /// Split footer cache.
     pub split_footer_cache: MemorySizedCache<String>,
+    /// Parsed hotcaches of the most recently opened splits.
+    pub hotcache_cache: HotcacheCache,
     /// Counting semaphore to limit concurrent split stream requests.
     pub split_stream_semaphore: Semaphore,
+    /// Counting semaphore to limit concurrent leaf search requests with aggregations.
+    pub aggregation_semaphore: Semaphore,
+    /// Counting semaphore to limit concurrent split footer prefetches.
+    pub split_footer_prefetch_semaphore: Semaphore,
     /// Recent sub-query cache.
     pub leaf_search_cache: LeafSearchCache,
     /// Search split cache. `None` if no split cache is configured.
     pub split_cache_opt: Option<Arc<SplitCache>>,
+    /// Prefetches the most accessed splits into the split cache. `None` if no split cache is
+    /// configured.
+    pub split_cache_warmer_opt: Option<Arc<SplitCacheWarmer>>,
     /// List fields cache. Caches the list fields response for a given split.
     pub list_fields_cache: ListFieldsCache,
+    /// Thread pool running the CPU-intensive search work. Defaults to the process-wide search
+    /// thread pool.
+    pub search_thread_pool: ThreadPool,
+}
+
+/// Builds a [`SearcherContext`]. Settings that are not set explicitly keep the default value of
+/// [`SearcherConfig`].
+#[derive(Default)]
+pub struct SearcherContextBuilder {
+    searcher_config: SearcherConfig,
+    split_cache_opt: Option<Arc<SplitCache>>,
+}
+
+impl SearcherContextBuilder {
+    /// Starts from the given searcher config instead of the default one.
+    pub fn with_searcher_config(mut self, searcher_config: SearcherConfig) -> Self {
+        self.searcher_config = searcher_config;
+        self
+    }
+
+    /// Sets the capacity of the split footer cache.
+    pub fn with_split_footer_cache_capacity(mut self, capacity: ByteSize) -> Self {
+        self.searcher_config.split_footer_cache_capacity = capacity;
+        self
+    }
+
+    /// Sets the capacity of the fast fields cache.
+    pub fn with_fast_field_cache_capacity(mut self, capacity: ByteSize) -> Self {
+        self.searcher_config.fast_field_cache_capacity = capacity;
+        self
+    }
+
+    /// Sets the capacity of the leaf search cache, and of the list fields cache.
+    pub fn with_partial_request_cache_capacity(mut self, capacity: ByteSize) -> Self {
+        self.searcher_config.partial_request_cache_capacity = capacity;
+        self
+    }
+
+    /// Sets the number of permits of the leaf search split semaphore, that is, the maximum number
+    /// of splits searched concurrently.
+    pub fn with_max_num_concurrent_split_searches(
+        mut self,
+        max_num_concurrent_split_searches: usize,
+    ) -> Self {
+        self.searcher_config.max_num_concurrent_split_searches = max_num_concurrent_split_searches;
+        self
+    }
+
+    /// Sets the number of split search permits reserved to interactive searches.
+    pub fn with_num_reserved_interactive_split_searches(
+        mut self,
+        num_reserved_interactive_split_searches: usize,
+    ) -> Self {
+        self.searcher_config.num_reserved_interactive_split_searches =
+            num_reserved_interactive_split_searches;
+        self
+    }
+
+    /// Sets the split cache used to search splits from the local disk.
+    pub fn with_split_cache(mut self, split_cache: Arc<SplitCache>) -> Self {
+        self.split_cache_opt = Some(split_cache);
+        self
+    }
+
+    /// Validates the settings and creates the [`SearcherContext`].
+    pub fn build(self) -> anyhow::Result<SearcherContext> {
+        self.searcher_config.validate()?;
+        Ok(SearcherContext::new(
+            self.searcher_config,
+            self.split_cache_opt,
+        ))
+    }
 }
 
 impl std::fmt::Debug for SearcherContext {
@@ -460,7 +571,16 @@ impl std::fmt::Debug for SearcherContext {
                 "leaf_search_split_semaphore",
                 &self.leaf_search_split_semaphore,
             )
+            .field(
+                "interactive_leaf_search_split_semaphore",
+                &self.interactive_leaf_search_split_semaphore,
+            )
             .field("split_stream_semaphore", &self.split_stream_semaphore)
+            .field("aggregation_semaphore", &self.aggregation_semaphore)
+            .field(
+                "split_footer_prefetch_semaphore",
+                &self.split_footer_prefetch_semaphore,
+            )
             .finish()
     }
 }
@@ -478,31 +598,100 @@ impl SearcherContext {
         let global_split_footer_cache = MemorySizedCache::with_capacity_in_bytes(
             capacity_in_bytes,
             &quickwit_storage::STORAGE_METRICS.split_footer_cache,
-        );
+        )
+        .with_eviction_observer(|_split_id, _num_bytes| {
+            crate::SEARCH_METRICS
+                .split_footer_cache_evictions_total
+                .inc();
+        });
+        let num_reserved_interactive_split_searches =
+            searcher_config.num_reserved_interactive_split_searches;
         let leaf_search_split_semaphore = Arc::new(Semaphore::new(
-            searcher_config.max_num_concurrent_split_searches,
+            searcher_config
+                .max_num_concurrent_split_searches
+                .saturating_sub(num_reserved_interactive_split_searches),
         ));
+        let interactive_leaf_search_split_semaphore =
+            Arc::new(Semaphore::new(num_reserved_interactive_split_searches));
+        let hotcache_cache = HotcacheCache::with_capacity_in_bytes(
+            searcher_config.hotcache_cache_capacity.as_u64() as usize,
+        );
         let split_stream_semaphore =
             Semaphore::new(searcher_config.max_num_concurrent_split_streams);
+        let aggregation_semaphore = Semaphore::new(searcher_config.max_num_concurrent_aggregations);
+        let split_footer_prefetch_semaphore =
+            Semaphore::new(MAX_NUM_CONCURRENT_SPLIT_FOOTER_PREFETCHES);
         let fast_field_cache_capacity = searcher_config.fast_field_cache_capacity.as_u64() as usize;
         let storage_long_term_cache = Arc::new(QuickwitCache::new(fast_field_cache_capacity));
         let leaf_search_cache =
             LeafSearchCache::new(searcher_config.partial_request_cache_capacity.as_u64() as usize);
         let list_fields_cache =
             ListFieldsCache::new(searcher_config.partial_request_cache_capacity.as_u64() as usize);
+        let split_cache_warmer_opt = split_cache_opt
+            .as_ref()
+            .map(|split_cache| SplitCacheWarmer::new(split_cache.clone()));
 
         Self {
             searcher_config,
             fast_fields_cache: storage_long_term_cache,
             leaf_search_split_semaphore,
+            interactive_leaf_search_split_semaphore,
             split_footer_cache: global_split_footer_cache,
+            hotcache_cache,
             split_stream_semaphore,
+            aggregation_semaphore,
+            split_footer_prefetch_semaphore,
             leaf_search_cache,
             list_fields_cache,
             split_cache_opt,
+            split_cache_warmer_opt,
+            search_thread_pool: crate::search_thread_pool().clone(),
         }
     }
 
+    /// Waits for a permit to search a split. Background searches only draw from the shared leaf
+    /// search split semaphore, while interactive searches also draw from the permits reserved to
+    /// them, so that background searches cannot starve them.
+    pub(crate) async fn acquire_split_search_permit(
+        &self,
+        priority: SearchPriority,
+    ) -> OwnedSemaphorePermit {
+        let shared_permit_fut = self.leaf_search_split_semaphore.clone().acquire_owned();
+        let permit_res = match priority {
+            SearchPriority::Background => shared_permit_fut.await,
+            SearchPriority::Interactive => {
+                // The shared permits are used first, keeping the reserved ones available.
+                tokio::select! {
+                    biased;
+                    permit_res = shared_permit_fut => permit_res,
+                    permit_res = self.interactive_leaf_search_split_semaphore.clone().acquire_owned() => permit_res,
+                }
+            }
+        };
+        permit_res.expect("Failed to acquire permit. This should never happen! Please, report on https://github.com/quickwit-oss/quickwit/issues.")
+    }
+
+    /// Returns a builder to create a [`SearcherContext`] with custom cache sizes and
+    /// concurrency limits, without going through a full node config.
+    pub fn builder() -> SearcherContextBuilder {
+        SearcherContextBuilder::default()
+    }
+
+    /// Runs the CPU-intensive search work on the given thread pool instead of the global one.
+    ///
+    /// This makes it possible for library users to isolate search from the rest of their
+    /// application, or to give several searchers running in the same process their own CPU
+    /// budget.
+    pub fn with_search_thread_pool(mut self, search_thread_pool: ThreadPool) -> Self {
+        self.search_thread_pool = search_thread_pool;
+        self
+    }
+
+    /// Returns the thread pool running the CPU-intensive search work.
+    pub fn search_thread_pool(&self) -> &ThreadPool {
+        &self.search_thread_pool
+    }
+
     /// Returns a new instance to track the aggregation memory usage.
     pub fn get_aggregation_limits(&self) -> AggregationLimits {
         AggregationLimits::new(
@@ -511,3 +700,94 @@ impl SearcherContext {
         )
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_searcher_context_builder() {
+        let searcher_context = SearcherContext::builder()
+            .with_split_footer_cache_capacity(ByteSize::mb(10))
+            .with_fast_field_cache_capacity(ByteSize::mb(20))
+            .with_partial_request_cache_capacity(ByteSize::mb(5))
+            .with_max_num_concurrent_split_searches(4)
+            .build()
+            .unwrap();
+        let searcher_config = &searcher_context.searcher_config;
+        assert_eq!(
+            searcher_config.split_footer_cache_capacity,
+            ByteSize::mb(10)
+        );
+        assert_eq!(searcher_config.fast_field_cache_capacity, ByteSize::mb(20));
+        assert_eq!(
+            searcher_config.partial_request_cache_capacity,
+            ByteSize::mb(5)
+        );
+        assert_eq!(
+            searcher_context
+                .leaf_search_split_semaphore
+                .available_permits(),
+            4
+        );
+        assert!(searcher_context.split_cache_opt.is_none());
+
+        let error = SearcherContext::builder()
+            .with_max_num_concurrent_split_searches(0)
+            .build()
+            .unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "max_num_concurrent_split_searches must be strictly positive"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_acquire_split_search_permit() {
+        let searcher_context = SearcherContext::builder()
+            .with_max_num_concurrent_split_searches(2)
+            .with_num_reserved_interactive_split_searches(1)
+            .build()
+            .unwrap();
+        let _background_permit = searcher_context
+            .acquire_split_search_permit(SearchPriority::Background)
+            .await;
+
+        // The shared permits are exhausted: background searches have to wait...
+        let background_permit_fut =
+            searcher_context.acquire_split_search_permit(SearchPriority::Background);
+        assert!(
+            tokio::time::timeout(Duration::from_millis(50), background_permit_fut)
+                .await
+                .is_err()
+        );
+        // ... while interactive searches draw from the reserved permits.
+        let interactive_permit = searcher_context
+            .acquire_split_search_permit(SearchPriority::Interactive)
+            .await;
+        assert_eq!(
+            searcher_context
+                .interactive_leaf_search_split_semaphore
+                .available_permits(),
+            0
+        );
+        drop(interactive_permit);
+        assert_eq!(
+            searcher_context
+                .interactive_leaf_search_split_semaphore
+                .available_permits(),
+            1
+        );
+
+        let error = SearcherContext::builder()
+            .with_max_num_concurrent_split_searches(2)
+            .with_num_reserved_interactive_split_searches(2)
+            .build()
+            .unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "num_reserved_interactive_split_searches (2) must be strictly lower than \
+             max_num_concurrent_split_searches (2)"
+        );
+    }
+}
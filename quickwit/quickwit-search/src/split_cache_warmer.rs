@@ -0,0 +1,227 @@
+// Copyright (C) 2024 Quickwit, Inc.
+//
+// Quickwit is offered under the AGPL v3.0 and as commercial software.
+// For commercial licensing, contact us at hello@quickwit.io.
+//
+// AGPL:
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, Once, Weak};
+use std::time::{Duration, Instant};
+
+use quickwit_common::uri::Uri;
+use quickwit_proto::search::ReportSplit;
+use quickwit_storage::SplitCache;
+use tracing::debug;
+
+/// Interval at which the most accessed splits are prefetched into the split cache.
+const WARMUP_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Maximum number of splits prefetched per warmup round.
+const NUM_SPLITS_TO_PREFETCH: usize = 100;
+
+#[derive(Debug)]
+struct SplitAccessStats {
+    storage_uri: Uri,
+    num_accesses: u64,
+    last_access: Instant,
+}
+
+/// Records which splits were recently touched by leaf searches.
+#[derive(Debug, Default)]
+struct SplitAccessHistory {
+    splits: HashMap<String, SplitAccessStats>,
+}
+
+impl SplitAccessHistory {
+    fn record_access(&mut self, split_id: &str, storage_uri: &Uri, now: Instant) {
+        if let Some(split_access_stats) = self.splits.get_mut(split_id) {
+            split_access_stats.num_accesses += 1;
+            split_access_stats.last_access = now;
+            return;
+        }
+        let split_access_stats = SplitAccessStats {
+            storage_uri: storage_uri.clone(),
+            num_accesses: 1,
+            last_access: now,
+        };
+        self.splits.insert(split_id.to_string(), split_access_stats);
+    }
+
+    /// Returns the `num_splits` most frequently accessed splits, most recently accessed first in
+    /// case of a tie, and resets the history.
+    fn take_most_accessed_splits(&mut self, num_splits: usize) -> Vec<ReportSplit> {
+        let mut splits: Vec<(String, SplitAccessStats)> = self.splits.drain().collect();
+        splits.sort_unstable_by(|(_, left), (_, right)| {
+            right
+                .num_accesses
+                .cmp(&left.num_accesses)
+                .then_with(|| right.last_access.cmp(&left.last_access))
+        });
+        splits
+            .into_iter()
+            .take(num_splits)
+            .map(|(split_id, split_access_stats)| ReportSplit {
+                split_id,
+                storage_uri: split_access_stats.storage_uri.to_string(),
+            })
+            .collect()
+    }
+}
+
+/// Predictively warms the searcher [`SplitCache`].
+///
+/// The split cache is populated reactively, as indexers report new splits. The warmer
+/// complements it by recording which splits recent leaf searches touched, and by periodically
+/// reporting the most frequently accessed ones to the split cache, so that they get downloaded
+/// before the next queries hit them.
+pub struct SplitCacheWarmer {
+    split_cache: Arc<SplitCache>,
+    access_history: Mutex<SplitAccessHistory>,
+    self_weak: Weak<SplitCacheWarmer>,
+    prefetch_task_started: Once,
+}
+
+impl SplitCacheWarmer {
+    /// Creates a new split cache warmer.
+    ///
+    /// The task prefetching the most accessed splits on a schedule is only spawned on the first
+    /// recorded access, which happens within a leaf search: the warmer itself may be created
+    /// outside of a Tokio runtime.
+    pub fn new(split_cache: Arc<SplitCache>) -> Arc<SplitCacheWarmer> {
+        Arc::new_cyclic(|self_weak| SplitCacheWarmer {
+            split_cache,
+            access_history: Mutex::default(),
+            self_weak: self_weak.clone(),
+            prefetch_task_started: Once::new(),
+        })
+    }
+
+    fn spawn_prefetch_task(&self) {
+        let split_cache_warmer_weak: Weak<SplitCacheWarmer> = self.self_weak.clone();
+        tokio::spawn(async move {
+            let mut warmup_interval = tokio::time::interval(WARMUP_INTERVAL);
+            loop {
+                warmup_interval.tick().await;
+                let Some(split_cache_warmer) = split_cache_warmer_weak.upgrade() else {
+                    break;
+                };
+                split_cache_warmer.prefetch_most_accessed_splits();
+            }
+        });
+    }
+
+    /// Records that a leaf search touched the given split.
+    ///
+    /// Must be called from within a Tokio runtime.
+    pub fn record_split_access(&self, split_id: &str, storage_uri: &Uri) {
+        self.prefetch_task_started
+            .call_once(|| self.spawn_prefetch_task());
+        self.access_history
+            .lock()
+            .unwrap()
+            .record_access(split_id, storage_uri, Instant::now());
+    }
+
+    fn prefetch_most_accessed_splits(&self) {
+        let report_splits = self
+            .access_history
+            .lock()
+            .unwrap()
+            .take_most_accessed_splits(NUM_SPLITS_TO_PREFETCH);
+        if report_splits.is_empty() {
+            return;
+        }
+        debug!(
+            num_splits = report_splits.len(),
+            "prefetching most accessed splits"
+        );
+        self.split_cache.report_splits(report_splits);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::num::NonZeroU32;
+
+    use bytesize::ByteSize;
+    use quickwit_config::SplitCacheLimits;
+
+    use super::*;
+
+    #[test]
+    fn test_split_access_history_most_accessed_splits() {
+        let start = Instant::now();
+        let storage_uri = Uri::for_test("s3://bucket/index");
+        let mut access_history = SplitAccessHistory::default();
+
+        let accesses = [
+            "split-a", "split-b", "split-a", "split-c", "split-d", "split-c", "split-a", "split-e",
+        ];
+        for (access_ord, split_id) in accesses.into_iter().enumerate() {
+            let now = start + Duration::from_secs(access_ord as u64);
+            access_history.record_access(split_id, &storage_uri, now);
+        }
+        let split_ids: Vec<String> = access_history
+            .take_most_accessed_splits(3)
+            .into_iter()
+            .map(|report_split| {
+                assert_eq!(report_split.storage_uri, "s3://bucket/index");
+                report_split.split_id
+            })
+            .collect();
+        // `split-a` was accessed 3 times, `split-c` twice. The other splits were accessed
+        // once, and `split-e` is the most recent one.
+        assert_eq!(split_ids, ["split-a", "split-c", "split-e"]);
+
+        // The history is reset after each warmup round.
+        assert!(access_history.take_most_accessed_splits(3).is_empty());
+    }
+
+    #[test]
+    fn test_split_cache_warmer_new_outside_runtime() {
+        let runtime = tokio::runtime::Runtime::new().unwrap();
+        let temp_dir = tempfile::tempdir().unwrap();
+        let split_cache = runtime.block_on(async {
+            SplitCache::with_root_path(
+                temp_dir.path().to_path_buf(),
+                quickwit_storage::StorageResolver::unconfigured(),
+                SplitCacheLimits {
+                    max_num_bytes: ByteSize::mb(1),
+                    max_num_splits: NonZeroU32::new(10).unwrap(),
+                    num_concurrent_downloads: NonZeroU32::new(1).unwrap(),
+                    max_file_descriptors: NonZeroU32::new(10).unwrap(),
+                },
+            )
+            .unwrap()
+        });
+        // Creating the warmer does not require a runtime.
+        let split_cache_warmer = SplitCacheWarmer::new(split_cache);
+
+        let storage_uri = Uri::for_test("s3://bucket/index");
+        runtime.block_on(async {
+            split_cache_warmer.record_split_access("split-a", &storage_uri);
+            split_cache_warmer.record_split_access("split-b", &storage_uri);
+        });
+        assert_eq!(
+            split_cache_warmer
+                .access_history
+                .lock()
+                .unwrap()
+                .splits
+                .len(),
+            2
+        );
+    }
+}
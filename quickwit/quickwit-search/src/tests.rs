@@ -17,25 +17,32 @@
 // You should have received a copy of the GNU Affero General Public License
 // along with this program. If not, see <http://www.gnu.org/licenses/>.
 
-use std::collections::{BTreeMap, BTreeSet};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::num::NonZeroU64;
+use std::ops::Bound;
+use std::sync::Mutex;
+use std::time::Duration;
 
 use assert_json_diff::{assert_json_eq, assert_json_include};
+use bytesize::ByteSize;
+use quickwit_common::thread_pool::ThreadPool;
 use quickwit_config::SearcherConfig;
 use quickwit_doc_mapper::tag_pruning::extract_tags_from_query;
 use quickwit_doc_mapper::DefaultDocMapper;
 use quickwit_indexing::TestSandbox;
 use quickwit_opentelemetry::otlp::TraceId;
 use quickwit_proto::search::{
-    LeafListTermsResponse, ListTermsRequest, SearchRequest, SortByValue, SortField, SortOrder,
-    SortValue,
+    CountHits, LeafListTermsResponse, ListTermsRequest, SearchRequest, SortByValue, SortField,
+    SortOrder, SortValue, SplitSearchErrorKind,
 };
 use quickwit_query::query_ast::{
-    qast_helper, qast_json_helper, query_ast_from_user_text, QueryAst,
+    qast_helper, qast_json_helper, query_ast_from_user_text, QueryAst, RangeQuery,
 };
 use serde_json::{json, Value as JsonValue};
 use tantivy::schema::OwnedValue as TantivyValue;
 use tantivy::time::OffsetDateTime;
 use tantivy::Term;
+use tokio_util::sync::CancellationToken;
 
 use super::*;
 use crate::find_trace_ids_collector::Span;
@@ -86,6 +93,36 @@ async fn test_single_node_simple() -> anyhow::Result<()> {
     Ok(())
 }
 
+#[tokio::test]
+async fn test_single_node_count_only() -> anyhow::Result<()> {
+    let index_id = "single-node-count-only";
+    let test_sandbox =
+        TestSandbox::create(index_id, BODY_DOC_MAPPING_YAML, "{}", &["body"]).await?;
+    let docs = vec![
+        json!({"body": "the beagle is a breed of small scent hound"}),
+        json!({"body": "snoopy is an anthropomorphic beagle"}),
+        json!({"body": "the foxhound is larger"}),
+    ];
+    test_sandbox.add_documents(docs).await?;
+    let search_request = SearchRequest {
+        index_id_patterns: vec![index_id.to_string()],
+        query_ast: qast_json_helper("beagle", &["body"]),
+        max_hits: 0,
+        count_hits: CountHits::CountAll as i32,
+        ..Default::default()
+    };
+    let single_node_result = single_node_search(
+        search_request,
+        test_sandbox.metastore(),
+        test_sandbox.storage_resolver(),
+    )
+    .await?;
+    assert_eq!(single_node_result.num_hits, 2);
+    assert!(single_node_result.hits.is_empty());
+    test_sandbox.assert_quit().await;
+    Ok(())
+}
+
 #[tokio::test]
 async fn test_single_node_termset() -> anyhow::Result<()> {
     let index_id = "single-node-termset-1";
@@ -453,6 +490,79 @@ async fn test_single_node_filtering() -> anyhow::Result<()> {
     Ok(())
 }
 
+#[tokio::test]
+async fn test_single_node_relative_time_range() -> anyhow::Result<()> {
+    let index_id = "single-node-relative-time-range";
+    let doc_mapping_yaml = r#"
+            field_mappings:
+              - name: body
+                type: text
+              - name: ts
+                type: datetime
+                input_formats:
+                    - "unix_timestamp"
+                fast: true
+            timestamp_field: ts
+        "#;
+    let test_sandbox = TestSandbox::create(index_id, doc_mapping_yaml, "{}", &["body"]).await?;
+
+    // One document every 10 minutes over the last 2 hours, 5 minutes away from the `now-1h`
+    // bound.
+    let start_timestamp = OffsetDateTime::now_utc().unix_timestamp();
+    let docs: Vec<JsonValue> = (0..12)
+        .map(|i| json!({"body": format!("info @ t:{i}"), "ts": start_timestamp - 6900 + i * 600}))
+        .collect();
+    test_sandbox.add_documents(docs).await?;
+
+    let query_ast = QueryAst::Range(RangeQuery {
+        field: "ts".to_string(),
+        lower_bound: Bound::Included("now-1h".to_string().into()),
+        upper_bound: Bound::Excluded("now+1h".to_string().into()),
+    });
+    let search_request = SearchRequest {
+        index_id_patterns: vec![index_id.to_string()],
+        query_ast: serde_json::to_string(&query_ast)?,
+        max_hits: 20,
+        sort_fields: vec![SortField {
+            field_name: "ts".to_string(),
+            sort_order: SortOrder::Asc as i32,
+            sort_datetime_format: None,
+        }],
+        ..Default::default()
+    };
+    let single_node_response = single_node_search(
+        search_request,
+        test_sandbox.metastore(),
+        test_sandbox.storage_resolver(),
+    )
+    .await?;
+    assert_eq!(single_node_response.num_hits, 6);
+    assert!(&single_node_response.hits[0].json.contains("t:6"));
+    assert!(&single_node_response.hits[5].json.contains("t:11"));
+
+    // An invalid date math expression is rejected by the root.
+    let query_ast = QueryAst::Range(RangeQuery {
+        field: "ts".to_string(),
+        lower_bound: Bound::Included("now-1y".to_string().into()),
+        upper_bound: Bound::Unbounded,
+    });
+    let search_request = SearchRequest {
+        index_id_patterns: vec![index_id.to_string()],
+        query_ast: serde_json::to_string(&query_ast)?,
+        max_hits: 20,
+        ..Default::default()
+    };
+    single_node_search(
+        search_request,
+        test_sandbox.metastore(),
+        test_sandbox.storage_resolver(),
+    )
+    .await
+    .unwrap_err();
+    test_sandbox.assert_quit().await;
+    Ok(())
+}
+
 #[tokio::test]
 async fn test_single_node_without_timestamp_with_query_start_timestamp_enabled(
 ) -> anyhow::Result<()> {
@@ -1033,6 +1143,89 @@ async fn test_single_node_split_pruning_by_tags() -> anyhow::Result<()> {
     Ok(())
 }
 
+/// Lists the splits of the sandbox index, as the offsets leaf searches expect.
+pub(crate) async fn list_split_offsets(
+    test_sandbox: &TestSandbox,
+) -> anyhow::Result<Vec<SplitIdAndFooterOffsets>> {
+    let splits_offsets = test_sandbox
+        .metastore()
+        .list_splits(ListSplitsRequest::try_from_index_uid(test_sandbox.index_uid()).unwrap())
+        .await?
+        .collect_splits()
+        .await?
+        .into_iter()
+        .map(|split| extract_split_and_footer_offsets(&split.split_metadata))
+        .collect();
+    Ok(splits_offsets)
+}
+
+/// Doc mapping of the sandbox indexes whose documents only have a `body`.
+pub(crate) const BODY_DOC_MAPPING_YAML: &str = r#"
+            field_mappings:
+              - name: body
+                type: text
+        "#;
+
+/// Creates a sandbox index searching `body` by default, and adds each batch of documents to it
+/// as a separate split.
+async fn create_sandbox_with_splits(
+    index_id: &str,
+    doc_mapping_yaml: &str,
+    doc_batches: Vec<Vec<JsonValue>>,
+) -> anyhow::Result<(TestSandbox, Vec<SplitIdAndFooterOffsets>)> {
+    let test_sandbox = TestSandbox::create(index_id, doc_mapping_yaml, "{}", &["body"]).await?;
+    for docs in doc_batches {
+        test_sandbox.add_documents(docs).await?;
+    }
+    let splits_offsets = list_split_offsets(&test_sandbox).await?;
+    Ok((test_sandbox, splits_offsets))
+}
+
+/// A single thread search pool, whose thread is kept busy until `unblock` is called: the split
+/// searches running on it can't make progress in the meantime.
+struct BlockedThreadPool {
+    thread_pool: ThreadPool,
+    unblock_tx: std::sync::mpsc::Sender<()>,
+    blocking_task: tokio::task::JoinHandle<()>,
+}
+
+impl BlockedThreadPool {
+    fn new(name: &'static str) -> Self {
+        Self::block(ThreadPool::new(name, Some(1)))
+    }
+
+    /// Keeps the thread of `thread_pool`, which must have a single thread, busy.
+    fn block(thread_pool: ThreadPool) -> Self {
+        let (unblock_tx, unblock_rx) = std::sync::mpsc::channel::<()>();
+        let blocking_task_fut = thread_pool.run_cpu_intensive(move || unblock_rx.recv());
+        let blocking_task = tokio::spawn(async move {
+            blocking_task_fut.await.unwrap().unwrap();
+        });
+        BlockedThreadPool {
+            thread_pool,
+            unblock_tx,
+            blocking_task,
+        }
+    }
+
+    async fn unblock(self) {
+        self.unblock_tx.send(()).unwrap();
+        self.blocking_task.await.unwrap();
+    }
+}
+
+/// Asserts that the task does not complete as long as the runtime has something else to do.
+///
+/// The clock is paused in the meantime, so the timeout elapses as soon as the runtime is idle
+/// instead of after a wall-clock delay.
+async fn assert_task_pending<T>(task_handle: &mut tokio::task::JoinHandle<T>) {
+    tokio::time::pause();
+    tokio::time::timeout(Duration::from_secs(1), task_handle)
+        .await
+        .unwrap_err();
+    tokio::time::resume();
+}
+
 async fn test_search_util(test_sandbox: &TestSandbox, query: &str) -> Vec<u32> {
     let splits = test_sandbox
         .metastore()
@@ -1060,6 +1253,7 @@ async fn test_search_util(test_sandbox: &TestSandbox, query: &str) -> Vec<u32> {
         test_sandbox.storage(),
         splits_offsets,
         test_sandbox.doc_mapper(),
+        None,
     )
     .await
     .unwrap();
@@ -1070,6 +1264,964 @@ async fn test_search_util(test_sandbox: &TestSandbox, query: &str) -> Vec<u32> {
         .collect::<Vec<u32>>()
 }
 
+#[test]
+fn test_searcher_context_thread_pool_isolation() {
+    let default_context_1 = SearcherContext::new(SearcherConfig::default(), None);
+    let default_context_2 = SearcherContext::new(SearcherConfig::default(), None);
+    assert!(Arc::ptr_eq(
+        &default_context_1
+            .search_thread_pool()
+            .get_underlying_rayon_thread_pool(),
+        &default_context_2
+            .search_thread_pool()
+            .get_underlying_rayon_thread_pool(),
+    ));
+    let isolated_context = SearcherContext::new(SearcherConfig::default(), None)
+        .with_search_thread_pool(ThreadPool::new("test_isolated_search", Some(1)));
+    assert!(!Arc::ptr_eq(
+        &default_context_1
+            .search_thread_pool()
+            .get_underlying_rayon_thread_pool(),
+        &isolated_context
+            .search_thread_pool()
+            .get_underlying_rayon_thread_pool(),
+    ));
+}
+
+#[tokio::test]
+async fn test_leaf_search_with_custom_thread_pool() -> anyhow::Result<()> {
+    let (test_sandbox, splits_offsets) = create_sandbox_with_splits(
+        "leaf-search-custom-thread-pool",
+        BODY_DOC_MAPPING_YAML,
+        vec![vec![json!({"body": "hello happy tax payer"})]],
+    )
+    .await?;
+    let request = Arc::new(SearchRequest {
+        index_id_patterns: vec![test_sandbox.index_uid().index_id.to_string()],
+        query_ast: qast_json_helper("happy", &["body"]),
+        max_hits: 10,
+        ..Default::default()
+    });
+    // While the only thread of the custom pool is busy, the leaf search can't make progress.
+    let blocked_thread_pool = BlockedThreadPool::new("test_custom_search");
+    let searcher_context = Arc::new(
+        SearcherContext::new(SearcherConfig::default(), None)
+            .with_search_thread_pool(blocked_thread_pool.thread_pool.clone()),
+    );
+    let mut leaf_search_handle = tokio::spawn(leaf_search(
+        searcher_context,
+        request,
+        test_sandbox.storage(),
+        splits_offsets,
+        test_sandbox.doc_mapper(),
+        None,
+    ));
+    assert_task_pending(&mut leaf_search_handle).await;
+
+    blocked_thread_pool.unblock().await;
+    let leaf_search_response = leaf_search_handle.await.unwrap()?;
+    assert_eq!(leaf_search_response.num_hits, 1);
+    test_sandbox.assert_quit().await;
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_leaf_search_cancellation() -> anyhow::Result<()> {
+    let (test_sandbox, splits_offsets) = create_sandbox_with_splits(
+        "leaf-search-cancellation",
+        BODY_DOC_MAPPING_YAML,
+        vec![vec![json!({"body": "hello happy tax payer"})]],
+    )
+    .await?;
+    let request = Arc::new(SearchRequest {
+        index_id_patterns: vec![test_sandbox.index_uid().index_id.to_string()],
+        query_ast: qast_json_helper("happy", &["body"]),
+        max_hits: 10,
+        ..Default::default()
+    });
+    let blocked_thread_pool = BlockedThreadPool::new("test_cancellation");
+    let searcher_context = Arc::new(
+        SearcherContext::new(SearcherConfig::default(), None)
+            .with_search_thread_pool(blocked_thread_pool.thread_pool.clone()),
+    );
+
+    // A token cancelled upfront prevents any split search from starting.
+    let cancellation_token = CancellationToken::new();
+    cancellation_token.cancel();
+    let error = leaf_search(
+        searcher_context.clone(),
+        request.clone(),
+        test_sandbox.storage(),
+        splits_offsets.clone(),
+        test_sandbox.doc_mapper(),
+        Some(cancellation_token),
+    )
+    .await
+    .unwrap_err();
+    assert!(matches!(error, SearchError::Cancelled));
+
+    // Cancelling the token aborts the in-flight split searches, here blocked on the busy pool.
+    let cancellation_token = CancellationToken::new();
+    let mut leaf_search_handle = tokio::spawn(leaf_search(
+        searcher_context,
+        request,
+        test_sandbox.storage(),
+        splits_offsets,
+        test_sandbox.doc_mapper(),
+        Some(cancellation_token.clone()),
+    ));
+    assert_task_pending(&mut leaf_search_handle).await;
+
+    // The pool is still busy: the leaf search can only stop because it is cancelled, without
+    // waiting for anything but the runtime.
+    cancellation_token.cancel();
+    tokio::time::pause();
+    let error = tokio::time::timeout(Duration::from_secs(1), leaf_search_handle)
+        .await
+        .expect("the leaf search should stop as soon as it is cancelled")
+        .unwrap()
+        .unwrap_err();
+    tokio::time::resume();
+    assert!(matches!(error, SearchError::Cancelled));
+
+    blocked_thread_pool.unblock().await;
+    test_sandbox.assert_quit().await;
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_leaf_search_overall_deadline() -> anyhow::Result<()> {
+    let (test_sandbox, splits_offsets) = create_sandbox_with_splits(
+        "leaf-search-overall-deadline",
+        BODY_DOC_MAPPING_YAML,
+        vec![vec![json!({"body": "hello happy tax payer"})]],
+    )
+    .await?;
+    let request = Arc::new(SearchRequest {
+        index_id_patterns: vec![test_sandbox.index_uid().index_id.to_string()],
+        query_ast: qast_json_helper("happy", &["body"]),
+        max_hits: 10,
+        ..Default::default()
+    });
+    let blocked_thread_pool = BlockedThreadPool::new("test_overall_deadline");
+    let searcher_config = SearcherConfig {
+        leaf_search_max_elapsed_ms: Some(NonZeroU64::new(200).unwrap()),
+        ..Default::default()
+    };
+    let searcher_context = Arc::new(
+        SearcherContext::new(searcher_config, None)
+            .with_search_thread_pool(blocked_thread_pool.thread_pool.clone()),
+    );
+
+    // Past the deadline, the split search blocked on the busy pool is aborted and the partial
+    // result is returned. The clock is paused, so the deadline elapses as soon as the leaf search
+    // is stuck.
+    tokio::time::pause();
+    let leaf_search_response = leaf_search(
+        searcher_context,
+        request.clone(),
+        test_sandbox.storage(),
+        splits_offsets.clone(),
+        test_sandbox.doc_mapper(),
+        None,
+    )
+    .await?;
+    tokio::time::resume();
+    assert!(leaf_search_response.timed_out);
+    assert_eq!(leaf_search_response.num_hits, 0);
+    assert_eq!(leaf_search_response.num_splits_searched, 0);
+    assert_eq!(leaf_search_response.failed_splits.len(), 1);
+    let failed_split = &leaf_search_response.failed_splits[0];
+    assert_eq!(failed_split.split_id, splits_offsets[0].split_id);
+    assert_eq!(failed_split.error_kind(), SplitSearchErrorKind::Timeout);
+    assert!(!failed_split.retryable_error);
+
+    blocked_thread_pool.unblock().await;
+
+    // Searches finishing within the deadline are complete. The deadline is generous, so that it
+    // can't be hit on a slow machine.
+    let searcher_config = SearcherConfig {
+        leaf_search_max_elapsed_ms: Some(NonZeroU64::new(60_000).unwrap()),
+        ..Default::default()
+    };
+    let searcher_context = Arc::new(SearcherContext::new(searcher_config, None));
+    let leaf_search_response = leaf_search(
+        searcher_context,
+        request,
+        test_sandbox.storage(),
+        splits_offsets,
+        test_sandbox.doc_mapper(),
+        None,
+    )
+    .await?;
+    assert_eq!(leaf_search_response.num_hits, 1);
+    assert!(!leaf_search_response.timed_out);
+    test_sandbox.assert_quit().await;
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_leaf_search_aggregation_permit_wait() -> anyhow::Result<()> {
+    let (test_sandbox, splits_offsets) = create_sandbox_with_splits(
+        "leaf-search-aggregation-permit-wait",
+        BODY_DOC_MAPPING_YAML,
+        vec![vec![json!({"body": "hello happy tax payer"})]],
+    )
+    .await?;
+    let request = Arc::new(SearchRequest {
+        index_id_patterns: vec![test_sandbox.index_uid().index_id.to_string()],
+        query_ast: qast_json_helper("happy", &["body"]),
+        max_hits: 10,
+        aggregation_request: Some(
+            r#"{"num_docs": {"value_count": {"field": "body"}}}"#.to_string(),
+        ),
+        ..Default::default()
+    });
+    let searcher_config = SearcherConfig {
+        max_num_concurrent_aggregations: 1,
+        leaf_search_max_elapsed_ms: Some(NonZeroU64::new(200).unwrap()),
+        ..Default::default()
+    };
+    let searcher_context = Arc::new(SearcherContext::new(searcher_config, None));
+    // Another aggregation holds the only permit.
+    let _aggregation_permit = searcher_context
+        .aggregation_semaphore
+        .acquire()
+        .await
+        .unwrap();
+
+    // A cancelled search stops waiting for the permit.
+    let cancellation_token = CancellationToken::new();
+    cancellation_token.cancel();
+    let error = leaf_search(
+        searcher_context.clone(),
+        request.clone(),
+        test_sandbox.storage(),
+        splits_offsets.clone(),
+        test_sandbox.doc_mapper(),
+        Some(cancellation_token),
+    )
+    .await
+    .unwrap_err();
+    assert!(matches!(error, SearchError::Cancelled));
+
+    // Past the deadline, the search gives up on the permit, and none of its splits is searched.
+    tokio::time::pause();
+    let leaf_search_response = leaf_search(
+        searcher_context,
+        request,
+        test_sandbox.storage(),
+        splits_offsets.clone(),
+        test_sandbox.doc_mapper(),
+        None,
+    )
+    .await?;
+    tokio::time::resume();
+    assert!(leaf_search_response.timed_out);
+    assert_eq!(leaf_search_response.num_splits_searched, 0);
+    assert_eq!(leaf_search_response.failed_splits.len(), 1);
+    let failed_split = &leaf_search_response.failed_splits[0];
+    assert_eq!(failed_split.split_id, splits_offsets[0].split_id);
+    assert_eq!(failed_split.error_kind(), SplitSearchErrorKind::Timeout);
+    test_sandbox.assert_quit().await;
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_leaf_search_collect_split_stats() -> anyhow::Result<()> {
+    let (test_sandbox, splits_offsets) = create_sandbox_with_splits(
+        "leaf-search-split-stats",
+        BODY_DOC_MAPPING_YAML,
+        vec![
+            vec![json!({"body": "hello happy tax payer"})],
+            vec![
+                json!({"body": "happy new year"}),
+                json!({"body": "happy birthday"}),
+                json!({"body": "sad tax payer"}),
+            ],
+        ],
+    )
+    .await?;
+    let mut request = SearchRequest {
+        index_id_patterns: vec![test_sandbox.index_uid().index_id.to_string()],
+        query_ast: qast_json_helper("happy", &["body"]),
+        max_hits: 10,
+        ..Default::default()
+    };
+    let searcher_context = Arc::new(SearcherContext::new(SearcherConfig::default(), None));
+
+    // Split stats are not collected by default.
+    let leaf_search_response = leaf_search(
+        searcher_context.clone(),
+        Arc::new(request.clone()),
+        test_sandbox.storage(),
+        splits_offsets.clone(),
+        test_sandbox.doc_mapper(),
+        None,
+    )
+    .await?;
+    assert_eq!(leaf_search_response.num_hits, 3);
+    assert!(leaf_search_response.per_split_stats.is_empty());
+
+    request.collect_split_stats = true;
+    let leaf_search_response = leaf_search(
+        searcher_context,
+        Arc::new(request.clone()),
+        test_sandbox.storage(),
+        splits_offsets.clone(),
+        test_sandbox.doc_mapper(),
+        None,
+    )
+    .await?;
+    let mut per_split_stats = leaf_search_response.per_split_stats;
+    per_split_stats.sort_unstable_by_key(|split_stats| split_stats.num_hits);
+    assert_eq!(per_split_stats.len(), 2);
+    assert_eq!(per_split_stats[0].num_hits, 1);
+    assert_eq!(per_split_stats[1].num_hits, 2);
+
+    for split_stats in &per_split_stats {
+        assert!(splits_offsets
+            .iter()
+            .any(|split_offsets| split_offsets.split_id == split_stats.split_id));
+    }
+    // The split stats make it all the way to the root search response.
+    let search_response = single_node_search(
+        request,
+        test_sandbox.metastore(),
+        test_sandbox.storage_resolver(),
+    )
+    .await?;
+    assert_eq!(search_response.per_split_stats.len(), 2);
+    test_sandbox.assert_quit().await;
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_single_node_search_slowest_splits() -> anyhow::Result<()> {
+    let test_sandbox = TestSandbox::create(
+        "single-node-slowest-splits",
+        BODY_DOC_MAPPING_YAML,
+        "{}",
+        &["body"],
+    )
+    .await?;
+    for _ in 0..3 {
+        test_sandbox
+            .add_documents(vec![json!({"body": "happy new year"})])
+            .await?;
+    }
+    let search_request = SearchRequest {
+        index_id_patterns: vec![test_sandbox.index_uid().index_id.to_string()],
+        query_ast: qast_json_helper("happy", &["body"]),
+        max_hits: 10,
+        num_slowest_splits: Some(2),
+        ..Default::default()
+    };
+    let search_response = single_node_search(
+        search_request,
+        test_sandbox.metastore(),
+        test_sandbox.storage_resolver(),
+    )
+    .await?;
+    assert_eq!(search_response.num_hits, 3);
+    let slowest_splits = &search_response.slowest_splits;
+    assert_eq!(slowest_splits.len(), 2);
+    assert!(slowest_splits[0].elapsed_time_micros >= slowest_splits[1].elapsed_time_micros);
+    test_sandbox.assert_quit().await;
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_leaf_search_explain() -> anyhow::Result<()> {
+    let doc_mapping_yaml = r#"
+            field_mappings:
+              - name: body
+                type: text
+              - name: count
+                type: u64
+                fast: true
+        "#;
+    let (test_sandbox, splits_offsets) = create_sandbox_with_splits(
+        "leaf-search-explain",
+        doc_mapping_yaml,
+        vec![vec![
+            json!({"body": "happy new year", "count": 1}),
+            json!({"body": "happy birthday", "count": 2}),
+        ]],
+    )
+    .await?;
+    let request = SearchRequest {
+        index_id_patterns: vec![test_sandbox.index_uid().index_id.to_string()],
+        query_ast: qast_json_helper("happy", &["body"]),
+        max_hits: 10,
+        sort_fields: vec![SortField {
+            field_name: "count".to_string(),
+            sort_order: SortOrder::Desc as i32,
+            sort_datetime_format: None,
+        }],
+        ..Default::default()
+    };
+    let searcher_context = SearcherContext::new(SearcherConfig::default(), None);
+    let leaf_search_plan = leaf_search_explain(
+        &searcher_context,
+        &request,
+        test_sandbox.storage(),
+        splits_offsets.clone(),
+        test_sandbox.doc_mapper(),
+    )
+    .await?;
+    assert_eq!(leaf_search_plan.splits.len(), 1);
+
+    let split_plan = &leaf_search_plan.splits[0];
+    assert_eq!(split_plan.split_id, splits_offsets[0].split_id);
+
+    let field_names: Vec<&str> = split_plan
+        .fields
+        .iter()
+        .map(|field_plan| field_plan.field_name.as_str())
+        .collect();
+    assert_eq!(field_names, ["body", "count"]);
+
+    let body_plan = &split_plan.fields[0];
+    assert_eq!(body_plan.num_terms, 1);
+    assert_eq!(body_plan.term_dict_num_bytes, 0);
+    assert!(!body_plan.full_postings);
+
+    let count_plan = &split_plan.fields[1];
+    assert!(count_plan.fast_field_num_bytes > 0);
+    assert_eq!(split_plan.warmup_num_bytes, count_plan.fast_field_num_bytes);
+    test_sandbox.assert_quit().await;
+    Ok(())
+}
+
+#[derive(Default)]
+struct SplitDecisionsRecorder {
+    decisions: Mutex<Vec<String>>,
+}
+
+impl SplitDecisionObserver for SplitDecisionsRecorder {
+    fn on_pruned(&self, split_id: &str) {
+        self.decisions
+            .lock()
+            .unwrap()
+            .push(format!("pruned:{split_id}"));
+    }
+
+    fn on_count_only(&self, split_id: &str) {
+        self.decisions
+            .lock()
+            .unwrap()
+            .push(format!("count_only:{split_id}"));
+    }
+
+    fn on_full(&self, split_id: &str) {
+        self.decisions
+            .lock()
+            .unwrap()
+            .push(format!("full:{split_id}"));
+    }
+
+    fn on_failed(&self, split_id: &str, _error: &str) {
+        self.decisions
+            .lock()
+            .unwrap()
+            .push(format!("failed:{split_id}"));
+    }
+}
+
+#[tokio::test]
+async fn test_leaf_search_with_observer() -> anyhow::Result<()> {
+    let (test_sandbox, mut splits_offsets) = create_sandbox_with_splits(
+        "leaf-search-with-observer",
+        BODY_DOC_MAPPING_YAML,
+        vec![vec![json!({"body": "hello happy tax payer"})]],
+    )
+    .await?;
+    let split_id = splits_offsets[0].split_id.clone();
+    splits_offsets.push(SplitIdAndFooterOffsets {
+        split_id: "missing-split".to_string(),
+        ..splits_offsets[0].clone()
+    });
+    let request = Arc::new(SearchRequest {
+        index_id_patterns: vec![test_sandbox.index_uid().index_id.to_string()],
+        query_ast: qast_json_helper("happy", &["body"]),
+        max_hits: 10,
+        ..Default::default()
+    });
+    let searcher_context = Arc::new(SearcherContext::new(SearcherConfig::default(), None));
+    let split_decisions_recorder = Arc::new(SplitDecisionsRecorder::default());
+
+    let leaf_search_response = leaf_search_with_options(
+        searcher_context,
+        request,
+        test_sandbox.storage(),
+        splits_offsets,
+        test_sandbox.doc_mapper(),
+        LeafSearchOptions {
+            split_decision_observer: split_decisions_recorder.clone(),
+            ..Default::default()
+        },
+    )
+    .await?;
+    assert_eq!(leaf_search_response.num_hits, 1);
+    assert_eq!(leaf_search_response.failed_splits.len(), 1);
+
+    let mut decisions = split_decisions_recorder.decisions.lock().unwrap().clone();
+    decisions.sort();
+    assert_eq!(
+        decisions,
+        [
+            "failed:missing-split".to_string(),
+            format!("full:{split_id}"),
+            "full:missing-split".to_string(),
+        ]
+    );
+    test_sandbox.assert_quit().await;
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_leaf_search_consumes_split_searches_while_spawning() -> anyhow::Result<()> {
+    let doc_mapping_yaml = r#"
+            field_mappings:
+              - name: body
+                type: text
+              - name: ts
+                type: datetime
+                input_formats:
+                    - "unix_timestamp"
+                fast: true
+            timestamp_field: ts
+        "#;
+    let test_sandbox = TestSandbox::create(
+        "leaf-search-consume-while-spawning",
+        doc_mapping_yaml,
+        "{}",
+        &["body"],
+    )
+    .await?;
+    // One split per document, the last one holding the most recent document.
+    let start_timestamp = OffsetDateTime::now_utc().unix_timestamp();
+    for i in 0..3 {
+        test_sandbox
+            .add_documents(vec![
+                json!({"body": "happy", "ts": start_timestamp + i * 1000}),
+            ])
+            .await?;
+    }
+    let mut splits_offsets = list_split_offsets(&test_sandbox).await?;
+    splits_offsets.sort_by_key(|split_offsets| split_offsets.timestamp_end);
+    let split_ids: Vec<String> = splits_offsets
+        .iter()
+        .map(|split_offsets| split_offsets.split_id.clone())
+        .collect();
+    // A split that fails to open, and is searched first because it claims the most recent
+    // documents.
+    let newest_split_offsets = splits_offsets[2].clone();
+    splits_offsets.push(SplitIdAndFooterOffsets {
+        split_id: "missing-split".to_string(),
+        timestamp_start: newest_split_offsets.timestamp_start.map(|ts| ts + 1000),
+        timestamp_end: newest_split_offsets.timestamp_end.map(|ts| ts + 1000),
+        ..newest_split_offsets
+    });
+    let request = Arc::new(SearchRequest {
+        index_id_patterns: vec![test_sandbox.index_uid().index_id.to_string()],
+        query_ast: qast_json_helper("happy", &["body"]),
+        max_hits: 1,
+        sort_fields: vec![SortField {
+            field_name: "ts".to_string(),
+            sort_order: SortOrder::Desc as i32,
+            sort_datetime_format: None,
+        }],
+        count_hits: CountHits::Underestimate as i32,
+        ..Default::default()
+    });
+    // With a single permit, each split search is spawned once the previous one has completed.
+    let searcher_config = SearcherConfig {
+        max_num_concurrent_split_searches: 1,
+        ..Default::default()
+    };
+    let searcher_context = Arc::new(SearcherContext::new(searcher_config, None));
+    let split_decisions_recorder = Arc::new(SplitDecisionsRecorder::default());
+
+    let leaf_search_response = leaf_search_with_options(
+        searcher_context,
+        request,
+        test_sandbox.storage(),
+        splits_offsets,
+        test_sandbox.doc_mapper(),
+        LeafSearchOptions {
+            split_decision_observer: split_decisions_recorder.clone(),
+            ..Default::default()
+        },
+    )
+    .await?;
+    assert_eq!(leaf_search_response.num_hits, 1);
+    assert_eq!(leaf_search_response.failed_splits.len(), 1);
+    assert_eq!(leaf_search_response.num_splits_pruned, 2);
+
+    // The failure is accounted for before the next split is spawned, and the older splits are
+    // pruned against the hit of the first split searched successfully.
+    let decisions = split_decisions_recorder.decisions.lock().unwrap().clone();
+    assert_eq!(
+        decisions,
+        [
+            "full:missing-split".to_string(),
+            "failed:missing-split".to_string(),
+            format!("full:{}", split_ids[2]),
+            format!("pruned:{}", split_ids[1]),
+            format!("pruned:{}", split_ids[0]),
+        ]
+    );
+    test_sandbox.assert_quit().await;
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_leaf_search_overall_deadline_with_aborted_split_searches() -> anyhow::Result<()> {
+    let doc_mapping_yaml = r#"
+            field_mappings:
+              - name: body
+                type: text
+              - name: ts
+                type: datetime
+                input_formats:
+                    - "unix_timestamp"
+                fast: true
+            timestamp_field: ts
+        "#;
+    let test_sandbox = TestSandbox::create(
+        "leaf-search-overall-deadline-aborted",
+        doc_mapping_yaml,
+        "{}",
+        &["body"],
+    )
+    .await?;
+    // One split per document, the last one holding the most recent document.
+    let start_timestamp = OffsetDateTime::now_utc().unix_timestamp();
+    for i in 0..3 {
+        test_sandbox
+            .add_documents(vec![
+                json!({"body": "happy", "ts": start_timestamp + i * 1000}),
+            ])
+            .await?;
+    }
+    let mut splits_offsets = list_split_offsets(&test_sandbox).await?;
+    splits_offsets.sort_by_key(|split_offsets| split_offsets.timestamp_end);
+    let [old_split, middle_split, newest_split] =
+        <[SplitIdAndFooterOffsets; 3]>::try_from(splits_offsets.clone()).unwrap();
+    let request = Arc::new(SearchRequest {
+        index_id_patterns: vec![test_sandbox.index_uid().index_id.to_string()],
+        query_ast: qast_json_helper("happy", &["body"]),
+        max_hits: 1,
+        sort_fields: vec![SortField {
+            field_name: "ts".to_string(),
+            sort_order: SortOrder::Desc as i32,
+            sort_datetime_format: None,
+        }],
+        count_hits: CountHits::Underestimate as i32,
+        ..Default::default()
+    });
+    let thread_pool = ThreadPool::new("test_overall_deadline_aborted", Some(1));
+    let searcher_config = SearcherConfig {
+        leaf_search_max_elapsed_ms: Some(NonZeroU64::new(200).unwrap()),
+        ..Default::default()
+    };
+    let searcher_context = Arc::new(
+        SearcherContext::new(searcher_config, None).with_search_thread_pool(thread_pool.clone()),
+    );
+    // Once searched, the middle split is served from the leaf cache and no longer needs the pool.
+    leaf_search(
+        searcher_context.clone(),
+        request.clone(),
+        test_sandbox.storage(),
+        vec![middle_split.clone()],
+        test_sandbox.doc_mapper(),
+        None,
+    )
+    .await?;
+    let blocked_thread_pool = BlockedThreadPool::block(thread_pool);
+    let split_decisions_recorder = Arc::new(SplitDecisionsRecorder::default());
+
+    // The newest split is stuck on the busy pool until the deadline, while the search of the old
+    // split gets aborted as soon as the hit of the middle split is collected.
+    tokio::time::pause();
+    let leaf_search_response = leaf_search_with_options(
+        searcher_context,
+        request,
+        test_sandbox.storage(),
+        splits_offsets,
+        test_sandbox.doc_mapper(),
+        LeafSearchOptions {
+            split_decision_observer: split_decisions_recorder.clone(),
+            ..Default::default()
+        },
+    )
+    .await?;
+    tokio::time::resume();
+    assert!(leaf_search_response.timed_out);
+    assert_eq!(leaf_search_response.num_hits, 1);
+    assert_eq!(
+        leaf_search_response.partial_hits[0].split_id,
+        middle_split.split_id
+    );
+    // Only the newest split timed out: the old split was pruned before the deadline.
+    assert_eq!(leaf_search_response.failed_splits.len(), 1);
+    let failed_split = &leaf_search_response.failed_splits[0];
+    assert_eq!(failed_split.split_id, newest_split.split_id);
+    assert_eq!(failed_split.error_kind(), SplitSearchErrorKind::Timeout);
+    assert_eq!(leaf_search_response.num_splits_searched, 1);
+    assert_eq!(leaf_search_response.num_splits_pruned, 1);
+    assert_eq!(leaf_search_response.num_splits_count_only, 0);
+
+    let mut decisions = split_decisions_recorder.decisions.lock().unwrap().clone();
+    decisions.sort();
+    let mut expected_decisions = vec![
+        format!("failed:{}", newest_split.split_id),
+        format!("full:{}", middle_split.split_id),
+        format!("full:{}", newest_split.split_id),
+        format!("full:{}", old_split.split_id),
+        format!("pruned:{}", old_split.split_id),
+    ];
+    expected_decisions.sort();
+    assert_eq!(decisions, expected_decisions);
+
+    blocked_thread_pool.unblock().await;
+    test_sandbox.assert_quit().await;
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_leaf_search_dedups_splits() -> anyhow::Result<()> {
+    let (test_sandbox, mut splits_offsets) = create_sandbox_with_splits(
+        "leaf-search-dedup-splits",
+        BODY_DOC_MAPPING_YAML,
+        vec![vec![json!({"body": "hello happy tax payer"})]],
+    )
+    .await?;
+    let split_id = splits_offsets[0].split_id.clone();
+    splits_offsets.push(splits_offsets[0].clone());
+
+    let request = Arc::new(SearchRequest {
+        index_id_patterns: vec![test_sandbox.index_uid().index_id.to_string()],
+        query_ast: qast_json_helper("happy", &["body"]),
+        max_hits: 10,
+        ..Default::default()
+    });
+    let searcher_context = Arc::new(SearcherContext::new(SearcherConfig::default(), None));
+    let split_decisions_recorder = Arc::new(SplitDecisionsRecorder::default());
+
+    let leaf_search_response = leaf_search_with_options(
+        searcher_context,
+        request,
+        test_sandbox.storage(),
+        splits_offsets,
+        test_sandbox.doc_mapper(),
+        LeafSearchOptions {
+            split_decision_observer: split_decisions_recorder.clone(),
+            ..Default::default()
+        },
+    )
+    .await?;
+    assert_eq!(leaf_search_response.num_hits, 1);
+    assert_eq!(leaf_search_response.partial_hits.len(), 1);
+    assert_eq!(leaf_search_response.num_attempted_splits, 1);
+
+    let decisions = split_decisions_recorder.decisions.lock().unwrap().clone();
+    assert_eq!(decisions, [format!("full:{split_id}")]);
+    test_sandbox.assert_quit().await;
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_leaf_search_estimate() -> anyhow::Result<()> {
+    let (test_sandbox, splits_offsets) = create_sandbox_with_splits(
+        "leaf-search-estimate",
+        BODY_DOC_MAPPING_YAML,
+        vec![vec![
+            json!({"body": "hello happy tax payer"}),
+            json!({"body": "hello sad tax payer"}),
+            json!({"body": "goodbye happy tax payer"}),
+        ]],
+    )
+    .await?;
+    let split_id = splits_offsets[0].split_id.clone();
+    let searcher_context = Arc::new(SearcherContext::new(SearcherConfig::default(), None));
+
+    let estimate = |query: &str| {
+        let request = SearchRequest {
+            index_id_patterns: vec![test_sandbox.index_uid().index_id.to_string()],
+            query_ast: qast_json_helper(query, &["body"]),
+            max_hits: 10,
+            ..Default::default()
+        };
+        let searcher_context = searcher_context.clone();
+        let index_storage = test_sandbox.storage();
+        let doc_mapper = test_sandbox.doc_mapper();
+        let splits_offsets = splits_offsets.clone();
+        async move {
+            leaf_search_estimate(
+                searcher_context,
+                &request,
+                index_storage,
+                &splits_offsets,
+                doc_mapper,
+            )
+            .await
+        }
+    };
+    assert_eq!(estimate("happy").await, [(split_id.clone(), 2)]);
+    assert_eq!(estimate("sad OR goodbye").await, [(split_id.clone(), 2)]);
+    // Conjunctions are overestimated.
+    assert_eq!(estimate("happy AND sad").await, [(split_id.clone(), 3)]);
+    assert_eq!(estimate("unknown").await, [(split_id.clone(), 0)]);
+    // Queries that are not bounded by their terms fall back to the number of docs.
+    assert_eq!(estimate("NOT happy").await, [(split_id.clone(), 3)]);
+    assert_eq!(estimate("*").await, [(split_id, 3)]);
+    test_sandbox.assert_quit().await;
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_open_split_for_inspection() -> anyhow::Result<()> {
+    use tantivy::tokenizer::TokenStream;
+
+    let test_sandbox = TestSandbox::create(
+        "open-split-for-inspection",
+        BODY_DOC_MAPPING_YAML,
+        "{}",
+        &["body"],
+    )
+    .await?;
+    test_sandbox
+        .add_documents(vec![json!({"body": "Hello-World happy tax payer"})])
+        .await?;
+    let split_metadata = test_sandbox
+        .metastore()
+        .list_splits(ListSplitsRequest::try_from_index_uid(test_sandbox.index_uid()).unwrap())
+        .await?
+        .collect_splits_metadata()
+        .await?
+        .into_iter()
+        .next()
+        .unwrap();
+    let split_and_footer_offsets = extract_split_and_footer_offsets(&split_metadata);
+
+    let tokenizer_manager = quickwit_query::tokenizers::TokenizerManager::new();
+    tokenizer_manager.register(
+        "proposed_analyzer",
+        tantivy::tokenizer::WhitespaceTokenizer::default(),
+        false,
+    );
+    let searcher_context = SearcherContext::new(SearcherConfig::default(), None);
+    let index = open_split_for_inspection(
+        &searcher_context,
+        test_sandbox.storage(),
+        &split_and_footer_offsets,
+        &tokenizer_manager,
+    )
+    .await?;
+    assert_eq!(index.searchable_segment_ids()?.len(), 1);
+
+    let mut analyzer = index.tokenizers().get("proposed_analyzer").unwrap();
+    let mut token_stream = analyzer.token_stream("Hello-World happy");
+    let mut tokens = Vec::new();
+    token_stream.process(&mut |token| tokens.push(token.text.clone()));
+    assert_eq!(tokens, ["Hello-World", "happy"]);
+    test_sandbox.assert_quit().await;
+    Ok(())
+}
+
+/// Tracing layer capturing the fields recorded on spans after their creation, by span name.
+#[derive(Clone, Default)]
+struct SpanRecordsCapture {
+    records_by_span_name: Arc<Mutex<HashMap<&'static str, HashMap<&'static str, String>>>>,
+}
+
+struct SpanRecordsVisitor<'a>(&'a mut HashMap<&'static str, String>);
+
+impl tracing::field::Visit for SpanRecordsVisitor<'_> {
+    fn record_debug(&mut self, field: &tracing::field::Field, value: &dyn std::fmt::Debug) {
+        self.0.insert(field.name(), format!("{value:?}"));
+    }
+}
+
+impl<S> tracing_subscriber::Layer<S> for SpanRecordsCapture
+where S: tracing::Subscriber + for<'a> tracing_subscriber::registry::LookupSpan<'a>
+{
+    fn on_record(
+        &self,
+        id: &tracing::span::Id,
+        values: &tracing::span::Record<'_>,
+        ctx: tracing_subscriber::layer::Context<'_, S>,
+    ) {
+        let Some(span) = ctx.span(id) else {
+            return;
+        };
+        let mut records_by_span_name = self.records_by_span_name.lock().unwrap();
+        let records = records_by_span_name.entry(span.name()).or_default();
+        values.record(&mut SpanRecordsVisitor(records));
+    }
+}
+
+#[tokio::test]
+async fn test_leaf_search_records_span_metrics() -> anyhow::Result<()> {
+    use tracing_subscriber::layer::SubscriberExt;
+
+    let (test_sandbox, splits_offsets) = create_sandbox_with_splits(
+        "leaf-search-span-metrics",
+        BODY_DOC_MAPPING_YAML,
+        vec![vec![json!({"body": "hello happy tax payer"})]],
+    )
+    .await?;
+    let request = Arc::new(SearchRequest {
+        index_id_patterns: vec![test_sandbox.index_uid().index_id.to_string()],
+        query_ast: qast_json_helper("happy", &["body"]),
+        max_hits: 10,
+        ..Default::default()
+    });
+    let searcher_config = SearcherConfig {
+        max_warmup_bytes_per_split: Some(ByteSize::gb(1)),
+        ..Default::default()
+    };
+    let searcher_context = Arc::new(SearcherContext::new(searcher_config, None));
+
+    let span_records_capture = SpanRecordsCapture::default();
+    let subscriber = tracing_subscriber::registry().with(span_records_capture.clone());
+    let _subscriber_guard = tracing::subscriber::set_default(subscriber);
+
+    let leaf_search_response = leaf_search(
+        searcher_context,
+        request,
+        test_sandbox.storage(),
+        splits_offsets,
+        test_sandbox.doc_mapper(),
+        None,
+    )
+    .await?;
+    assert_eq!(leaf_search_response.num_hits, 1);
+
+    let records_by_span_name = span_records_capture.records_by_span_name.lock().unwrap();
+    let leaf_search_records = &records_by_span_name["leaf_search_inner"];
+    assert_eq!(leaf_search_records["num_splits_searched"], "1");
+    assert_eq!(leaf_search_records["num_splits_pruned"], "0");
+
+    let split_search_records = &records_by_span_name["leaf_search_single_split"];
+    for field_name in [
+        "estimated_warmup_num_bytes",
+        "warmup_duration_micros",
+        "search_duration_micros",
+    ] {
+        assert!(
+            split_search_records.contains_key(field_name),
+            "missing span field `{field_name}`"
+        );
+    }
+    drop(records_by_span_name);
+    test_sandbox.assert_quit().await;
+    Ok(())
+}
+
 #[tokio::test]
 async fn test_search_dynamic_mode() -> anyhow::Result<()> {
     let doc_mapping_yaml = r#"
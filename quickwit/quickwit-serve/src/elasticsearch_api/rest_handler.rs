@@ -34,8 +34,8 @@ use quickwit_index_management::IndexService;
 use quickwit_metastore::*;
 use quickwit_proto::metastore::MetastoreServiceClient;
 use quickwit_proto::search::{
-    CountHits, ListFieldsResponse, PartialHit, ScrollRequest, SearchResponse, SortByValue,
-    SortDatetimeFormat,
+    CountHits, ListFieldsResponse, PartialHit, ScrollRequest, SearchPriority, SearchResponse,
+    SortByValue, SortDatetimeFormat,
 };
 use quickwit_proto::types::IndexUid;
 use quickwit_query::query_ast::{BoolQuery, QueryAst, UserInputQuery};
@@ -321,6 +321,13 @@ fn build_request_for_es_api(
             scroll_ttl_secs,
             search_after,
             count_hits,
+            num_slowest_splits: None,
+            collect_split_stats: false,
+            end_timestamp_inclusive: false,
+            now_timestamp_nanos: None,
+            priority: SearchPriority::Interactive.into(),
+            split_id_allowlist: Vec::new(),
+            split_id_denylist: Vec::new(),
         },
         has_doc_id_field,
     ))
@@ -448,19 +448,18 @@ async fn list_indexes_metadata(
     list_indexes_params: ListIndexesQueryParams,
     mut metastore: MetastoreServiceClient,
 ) -> MetastoreResult<Vec<IndexMetadata>> {
-    let list_indexes_metata_request =
-        if let Some(index_id_patterns) = list_indexes_params.index_id_patterns {
-            for index_id_pattern in &index_id_patterns {
-                validate_index_id_pattern(index_id_pattern, true).map_err(|error| {
-                    MetastoreError::InvalidArgument {
-                        message: error.to_string(),
-                    }
-                })?;
-            }
-            ListIndexesMetadataRequest { index_id_patterns }
-        } else {
-            ListIndexesMetadataRequest::all()
-        };
+    let list_indexes_metata_request = if let Some(index_id_patterns) =
+        list_indexes_params.index_id_patterns
+    {
+        for index_id_pattern in &index_id_patterns {
+            validate_index_id_pattern(index_id_pattern, true).map_err(|error| {
+                MetastoreError::invalid_argument_for_field("index_id_patterns", error.to_string())
+            })?;
+        }
+        ListIndexesMetadataRequest { index_id_patterns }
+    } else {
+        ListIndexesMetadataRequest::all()
+    };
     metastore
         .list_indexes_metadata(list_indexes_metata_request)
         .await?
@@ -748,6 +747,7 @@ async fn get_source(
             MetastoreError::NotFound(EntityKind::Source {
                 index_id,
                 source_id,
+                source_type: None,
             })
         })?;
     Ok(source_config)
@@ -1906,6 +1906,7 @@ mod tests {
                 Err(MetastoreError::NotFound(EntityKind::Source {
                     index_id: "quickwit-demo-index".to_string(),
                     source_id: source_id.to_string(),
+                    source_type: None,
                 }))
             },
         );
@@ -460,6 +460,10 @@ mod tests {
                     errors: Vec::new(),
                     aggregation: None,
                     scroll_id: None,
+                    per_split_stats: Vec::new(),
+                    slowest_splits: Vec::new(),
+                    aggregation_is_partial: false,
+                    timed_out: false,
                 })
             });
         let mock_search_service = Arc::new(mock_search_service);
@@ -491,6 +495,10 @@ mod tests {
                     errors: Vec::new(),
                     aggregation: None,
                     scroll_id: None,
+                    per_split_stats: Vec::new(),
+                    slowest_splits: Vec::new(),
+                    aggregation_is_partial: false,
+                    timed_out: false,
                 })
             });
         let mock_search_service = Arc::new(mock_search_service);
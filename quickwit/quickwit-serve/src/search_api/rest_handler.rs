@@ -25,7 +25,7 @@ use hyper::header::HeaderValue;
 use hyper::HeaderMap;
 use percent_encoding::percent_decode_str;
 use quickwit_config::validate_index_id_pattern;
-use quickwit_proto::search::{CountHits, OutputFormat, SortField, SortOrder};
+use quickwit_proto::search::{CountHits, OutputFormat, SearchPriority, SortField, SortOrder};
 use quickwit_proto::ServiceError;
 use quickwit_query::query_ast::query_ast_from_user_text;
 use quickwit_search::{SearchError, SearchResponseRest, SearchService};
@@ -231,6 +231,14 @@ pub struct SearchRequestQueryString {
     #[serde(with = "count_hits_from_bool")]
     #[serde(default = "count_hits_from_bool::default")]
     pub count_all: CountHits,
+    /// If set, the response includes the number of hits and the search duration of every split
+    /// searched.
+    #[serde(default)]
+    pub collect_split_stats: bool,
+    /// If set, the response includes the search duration of the `num_slowest_splits` splits that
+    /// took the longest to search.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub num_slowest_splits: Option<u32>,
 }
 
 mod count_hits_from_bool {
@@ -285,6 +293,13 @@ pub fn search_request_from_api_request(
         scroll_ttl_secs: None,
         search_after: None,
         count_hits: search_request.count_all.into(),
+        num_slowest_splits: search_request.num_slowest_splits,
+        collect_split_stats: search_request.collect_split_stats,
+        end_timestamp_inclusive: false,
+        now_timestamp_nanos: None,
+        priority: SearchPriority::Interactive.into(),
+        split_id_allowlist: Vec::new(),
+        split_id_denylist: Vec::new(),
     };
     Ok(search_request)
 }
@@ -572,6 +587,10 @@ mod tests {
             elapsed_time_micros: 0u64,
             errors: Vec::new(),
             aggregations: None,
+            per_split_stats: Vec::new(),
+            slowest_splits: Vec::new(),
+            aggregation_is_partial: false,
+            timed_out: false,
         };
         let search_response_json: JsonValue = serde_json::to_value(search_response)?;
         let expected_search_response_json: JsonValue = json!({
@@ -737,6 +756,34 @@ mod tests {
         );
     }
 
+    #[tokio::test]
+    async fn test_rest_search_api_route_collect_split_stats() {
+        let rest_search_api_filter = search_get_filter();
+        let (_indexes, req) = warp::test::request()
+            .path("/quickwit-demo-index/search?query=*&collect_split_stats=true")
+            .filter(&rest_search_api_filter)
+            .await
+            .unwrap();
+        assert!(req.collect_split_stats);
+        let search_request =
+            search_request_from_api_request(vec!["quickwit-demo-index".to_string()], req).unwrap();
+        assert!(search_request.collect_split_stats);
+    }
+
+    #[tokio::test]
+    async fn test_rest_search_api_route_num_slowest_splits() {
+        let rest_search_api_filter = search_get_filter();
+        let (_indexes, req) = warp::test::request()
+            .path("/quickwit-demo-index/search?query=*&num_slowest_splits=3")
+            .filter(&rest_search_api_filter)
+            .await
+            .unwrap();
+        assert_eq!(req.num_slowest_splits, Some(3));
+        let search_request =
+            search_request_from_api_request(vec!["quickwit-demo-index".to_string()], req).unwrap();
+        assert_eq!(search_request.num_slowest_splits, Some(3));
+    }
+
     #[tokio::test]
     async fn test_rest_search_api_route_simple_default_num_hits_default_offset() {
         let rest_search_api_filter = search_get_filter();
@@ -94,7 +94,7 @@ async fn create_index_template(
             })?;
     index_template.validate().map_err(|error| {
         let message = format!("invalid index template: {error}");
-        MetastoreError::InvalidArgument { message }
+        MetastoreError::invalid_argument(message)
     })?;
     let index_template_json = serde_utils::to_json_str(&index_template)?;
     let create_index_template = CreateIndexTemplateRequest {
@@ -185,7 +185,7 @@ async fn update_index_template(
     let index_template: IndexTemplate = serde_utils::from_json_value(json_value)?;
     index_template.validate().map_err(|error| {
         let message = format!("invalid index template: {error}");
-        MetastoreError::InvalidArgument { message }
+        MetastoreError::invalid_argument(message)
     })?;
     let index_template_json = serde_utils::to_json_str(&index_template)?;
     let create_index_template = CreateIndexTemplateRequest {
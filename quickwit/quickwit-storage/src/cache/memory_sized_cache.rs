@@ -30,6 +30,7 @@ use tracing::{error, warn};
 
 use crate::cache::slice_address::{SliceAddress, SliceAddressKey, SliceAddressRef};
 use crate::cache::stored_item::StoredItem;
+use crate::cache::CachePriority;
 use crate::metrics::CacheMetrics;
 use crate::OwnedBytes;
 
@@ -64,12 +65,19 @@ impl Capacity {
     }
 }
 
+/// Callback invoked with the key and the size in bytes of the items evicted to make room for new
+/// ones.
+type EvictionObserver<K> = Box<dyn Fn(&K, usize) + Send + Sync>;
+
 struct NeedMutMemorySizedCache<K: Hash + Eq> {
     lru_cache: LruCache<K, StoredItem>,
+    /// Items put with [`CachePriority::Low`]. They are evicted before any item of `lru_cache`.
+    low_priority_lru_cache: LruCache<K, StoredItem>,
     num_items: usize,
     num_bytes: u64,
     capacity: Capacity,
     cache_counters: &'static CacheMetrics,
+    eviction_observer_opt: Option<EvictionObserver<K>>,
 }
 
 impl<K: Hash + Eq> Drop for NeedMutMemorySizedCache<K> {
@@ -91,10 +99,12 @@ impl<K: Hash + Eq> NeedMutMemorySizedCache<K> {
             // not the number of items in the cache.
             // Enforcing this limit is done in the `NeedMutCache` impl.
             lru_cache: LruCache::unbounded(),
+            low_priority_lru_cache: LruCache::unbounded(),
             num_items: 0,
             num_bytes: 0,
             capacity,
             cache_counters,
+            eviction_observer_opt: None,
         }
     }
 
@@ -112,12 +122,23 @@ impl<K: Hash + Eq> NeedMutMemorySizedCache<K> {
         self.cache_counters.in_cache_num_bytes.sub(num_bytes as i64);
     }
 
+    /// Same as `drop_item`, for items evicted to make room for new ones.
+    fn evict_item(&mut self, key: &K, num_bytes: usize) {
+        self.drop_item(num_bytes as u64);
+        if let Some(eviction_observer) = &self.eviction_observer_opt {
+            eviction_observer(key, num_bytes);
+        }
+    }
+
     pub fn get<Q>(&mut self, cache_key: &Q) -> Option<OwnedBytes>
     where
         K: Borrow<Q>,
         Q: Hash + Eq + ?Sized,
     {
-        let item_opt = self.lru_cache.get_mut(cache_key);
+        let item_opt = match self.lru_cache.get_mut(cache_key) {
+            Some(item) => Some(item),
+            None => self.low_priority_lru_cache.get_mut(cache_key),
+        };
         if let Some(item) = item_opt {
             self.cache_counters.hits_num_items.inc();
             self.cache_counters.hits_num_bytes.inc_by(item.len() as u64);
@@ -128,10 +149,39 @@ impl<K: Hash + Eq> NeedMutMemorySizedCache<K> {
         }
     }
 
+    /// Same as `get`, but items inserted more than `ttl` ago are removed and reported as misses.
+    pub fn get_unexpired<Q>(&mut self, cache_key: &Q, ttl: Duration) -> Option<OwnedBytes>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        let now = Instant::now();
+        let is_expired = |item: &StoredItem| now.duration_since(item.insertion_time()) >= ttl;
+
+        if self.lru_cache.peek(cache_key).is_some_and(is_expired) {
+            if let Some(expired_item) = self.lru_cache.pop(cache_key) {
+                self.drop_item(expired_item.len() as u64);
+            }
+        }
+        if self
+            .low_priority_lru_cache
+            .peek(cache_key)
+            .is_some_and(is_expired)
+        {
+            if let Some(expired_item) = self.low_priority_lru_cache.pop(cache_key) {
+                self.drop_item(expired_item.len() as u64);
+            }
+        }
+        self.get(cache_key)
+    }
+
     /// Attempt to put the given amount of data in the cache.
     /// This may fail silently if the owned_bytes slice is larger than the cache
     /// capacity.
-    fn put(&mut self, key: K, bytes: OwnedBytes) {
+    ///
+    /// Low priority items are evicted first, regardless of how recently they were accessed. A low
+    /// priority item is never stored at the expense of a normal priority one.
+    fn put(&mut self, key: K, bytes: OwnedBytes, priority: CachePriority) {
         if self.capacity.exceeds_capacity(bytes.len()) {
             // The value does not fit in the cache. We simply don't store it.
             if self.capacity != Capacity::InBytes(0) {
@@ -146,12 +196,23 @@ impl<K: Hash + Eq> NeedMutMemorySizedCache<K> {
         if let Some(previous_data) = self.lru_cache.pop(&key) {
             self.drop_item(previous_data.len() as u64);
         }
+        if let Some(previous_data) = self.low_priority_lru_cache.pop(&key) {
+            self.drop_item(previous_data.len() as u64);
+        }
 
         let now = Instant::now();
         while self
             .capacity
             .exceeds_capacity(self.num_bytes as usize + bytes.len())
         {
+            if let Some((evicted_key, evicted_item)) = self.low_priority_lru_cache.pop_lru() {
+                self.evict_item(&evicted_key, evicted_item.len());
+                continue;
+            }
+            if priority == CachePriority::Low {
+                // Only normal priority items are left.
+                return;
+            }
             if let Some((_, candidate_for_eviction)) = self.lru_cache.peek_lru() {
                 let time_since_last_access =
                     now.duration_since(candidate_for_eviction.last_access_time());
@@ -162,8 +223,8 @@ impl<K: Hash + Eq> NeedMutMemorySizedCache<K> {
                     return;
                 }
             }
-            if let Some((_, bytes)) = self.lru_cache.pop_lru() {
-                self.drop_item(bytes.len() as u64);
+            if let Some((evicted_key, evicted_item)) = self.lru_cache.pop_lru() {
+                self.evict_item(&evicted_key, evicted_item.len());
             } else {
                 error!(
                     "Logical error. Even after removing all of the items in the cache the \
@@ -174,7 +235,11 @@ impl<K: Hash + Eq> NeedMutMemorySizedCache<K> {
             }
         }
         self.record_item(bytes.len() as u64);
-        self.lru_cache.put(key, StoredItem::new(bytes, now));
+        let stored_item = StoredItem::new(bytes, now);
+        match priority {
+            CachePriority::Normal => self.lru_cache.put(key, stored_item),
+            CachePriority::Low => self.low_priority_lru_cache.put(key, stored_item),
+        };
     }
 }
 
@@ -207,6 +272,19 @@ impl<K: Hash + Eq> MemorySizedCache<K> {
         }
     }
 
+    /// Registers a callback invoked with the key and the size in bytes of every item evicted to
+    /// make room for new ones. Items replaced by a newer value or expired are not reported.
+    ///
+    /// The callback runs while the cache is locked: it must be cheap, e.g. increment a counter,
+    /// and must not access the cache.
+    pub fn with_eviction_observer(
+        self,
+        eviction_observer: impl Fn(&K, usize) + Send + Sync + 'static,
+    ) -> Self {
+        self.inner.lock().unwrap().eviction_observer_opt = Some(Box::new(eviction_observer));
+        self
+    }
+
     /// If available, returns the cached view of the slice.
     pub fn get<Q>(&self, cache_key: &Q) -> Option<OwnedBytes>
     where
@@ -216,11 +294,26 @@ impl<K: Hash + Eq> MemorySizedCache<K> {
         self.inner.lock().unwrap().get(cache_key)
     }
 
+    /// Same as [`MemorySizedCache::get`], except that the items inserted `ttl` or more ago are
+    /// evicted and reported as misses.
+    pub fn get_unexpired<Q>(&self, cache_key: &Q, ttl: Duration) -> Option<OwnedBytes>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.inner.lock().unwrap().get_unexpired(cache_key, ttl)
+    }
+
     /// Attempt to put the given amount of data in the cache.
     /// This may fail silently if the owned_bytes slice is larger than the cache
     /// capacity.
     pub fn put(&self, val: K, bytes: OwnedBytes) {
-        self.inner.lock().unwrap().put(val, bytes);
+        self.put_with_priority(val, bytes, CachePriority::Normal);
+    }
+
+    /// Same as [`MemorySizedCache::put`], with the given eviction priority.
+    pub fn put_with_priority(&self, val: K, bytes: OwnedBytes, priority: CachePriority) {
+        self.inner.lock().unwrap().put(val, bytes, priority);
     }
 }
 
@@ -235,14 +328,27 @@ impl MemorySizedCache<SliceAddress> {
     /// This may fail silently if the owned_bytes slice is larger than the cache
     /// capacity.
     pub fn put_slice(&self, path: PathBuf, byte_range: Range<usize>, bytes: OwnedBytes) {
+        self.put_slice_with_priority(path, byte_range, bytes, CachePriority::Normal);
+    }
+
+    /// Same as [`MemorySizedCache::put_slice`], with the given eviction priority.
+    pub fn put_slice_with_priority(
+        &self,
+        path: PathBuf,
+        byte_range: Range<usize>,
+        bytes: OwnedBytes,
+        priority: CachePriority,
+    ) {
         let slice_address = SliceAddress { path, byte_range };
-        self.put(slice_address, bytes);
+        self.put_with_priority(slice_address, bytes, priority);
     }
 }
 
 #[cfg(test)]
 mod tests {
 
+    use std::sync::Arc;
+
     use super::*;
     use crate::metrics::CACHE_METRICS_FOR_TESTS;
 
@@ -304,6 +410,91 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_cache_evicts_low_priority_items_first() {
+        let cache =
+            MemorySizedCache::<String>::with_capacity_in_bytes(10, &CACHE_METRICS_FOR_TESTS);
+        cache.put("sort".to_string(), OwnedBytes::new(&b"abcde"[..]));
+        cache.put_with_priority(
+            "aggregation".to_string(),
+            OwnedBytes::new(&b"fghij"[..]),
+            CachePriority::Low,
+        );
+        assert_eq!(
+            cache.get(&"aggregation".to_string()).unwrap(),
+            &b"fghij"[..]
+        );
+
+        // The low priority item is evicted even though it was just accessed.
+        cache.put("sort2".to_string(), OwnedBytes::new(&b"klmno"[..]));
+        assert!(cache.get(&"aggregation".to_string()).is_none());
+        assert_eq!(cache.get(&"sort".to_string()).unwrap(), &b"abcde"[..]);
+        assert_eq!(cache.get(&"sort2".to_string()).unwrap(), &b"klmno"[..]);
+
+        // A low priority item does not evict normal priority items.
+        cache.put_with_priority(
+            "aggregation".to_string(),
+            OwnedBytes::new(&b"fghij"[..]),
+            CachePriority::Low,
+        );
+        assert!(cache.get(&"aggregation".to_string()).is_none());
+        assert_eq!(cache.get(&"sort".to_string()).unwrap(), &b"abcde"[..]);
+    }
+
+    #[tokio::test]
+    async fn test_cache_eviction_observer() {
+        tokio::time::pause();
+        let evicted_items: Arc<Mutex<Vec<(String, usize)>>> = Default::default();
+        let evicted_items_clone = evicted_items.clone();
+        let cache = MemorySizedCache::<String>::with_capacity_in_bytes(5, &CACHE_METRICS_FOR_TESTS)
+            .with_eviction_observer(move |key, num_bytes| {
+                evicted_items_clone
+                    .lock()
+                    .unwrap()
+                    .push((key.clone(), num_bytes));
+            });
+        cache.put("3".to_string(), OwnedBytes::new(&b"abc"[..]));
+        // Replacing an item is not an eviction.
+        cache.put("3".to_string(), OwnedBytes::new(&b"def"[..]));
+        cache.put_with_priority(
+            "2".to_string(),
+            OwnedBytes::new(&b"gh"[..]),
+            CachePriority::Low,
+        );
+        assert!(evicted_items.lock().unwrap().is_empty());
+
+        tokio::time::advance(super::MIN_TIME_SINCE_LAST_ACCESS.mul_f32(1.1f32)).await;
+        cache.put("5".to_string(), OwnedBytes::new(&b"ijklm"[..]));
+        assert_eq!(
+            *evicted_items.lock().unwrap(),
+            [("2".to_string(), 2), ("3".to_string(), 3)]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_cache_get_unexpired() {
+        tokio::time::pause();
+        let ttl = Duration::from_secs(10);
+        let cache =
+            MemorySizedCache::<String>::with_capacity_in_bytes(10, &CACHE_METRICS_FOR_TESTS);
+        cache.put("footer".to_string(), OwnedBytes::new(&b"abc"[..]));
+        tokio::time::advance(Duration::from_secs(5)).await;
+        assert_eq!(
+            cache.get_unexpired(&"footer".to_string(), ttl).unwrap(),
+            &b"abc"[..]
+        );
+        // Accessing the item does not extend its lifetime.
+        tokio::time::advance(Duration::from_secs(5)).await;
+        assert!(cache.get_unexpired(&"footer".to_string(), ttl).is_none());
+        assert!(cache.get(&"footer".to_string()).is_none());
+
+        cache.put("footer".to_string(), OwnedBytes::new(&b"def"[..]));
+        assert_eq!(
+            cache.get_unexpired(&"footer".to_string(), ttl).unwrap(),
+            &b"def"[..]
+        );
+    }
+
     #[test]
     fn test_cache() {
         let cache = MemorySizedCache::with_capacity_in_bytes(10_000, &CACHE_METRICS_FOR_TESTS);
@@ -24,6 +24,7 @@ mod slice_address;
 mod storage_with_cache;
 mod stored_item;
 
+use std::future::Future;
 use std::ops::Range;
 use std::path::{Path, PathBuf};
 use std::sync::Arc;
@@ -36,6 +37,36 @@ pub use self::byte_range_cache::ByteRangeCache;
 pub use self::memory_sized_cache::MemorySizedCache;
 use crate::{OwnedBytes, Storage};
 
+/// Eviction priority of the data put in a [`MemorySizedCache`].
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum CachePriority {
+    /// Data unlikely to be reused soon, e.g. the fast fields of a one-off aggregation. It is
+    /// evicted before any normal priority data.
+    Low,
+    /// Default priority.
+    #[default]
+    Normal,
+}
+
+tokio::task_local! {
+    static CACHE_PRIORITY: CachePriority;
+}
+
+/// Runs `future`, putting the data it fetches into the long term caches with the given priority.
+///
+/// The priority is carried by the task, which makes it possible to tag data read deep in
+/// tantivy, without any way to pass it explicitly.
+pub async fn with_cache_priority<F: Future>(priority: CachePriority, future: F) -> F::Output {
+    CACHE_PRIORITY.scope(priority, future).await
+}
+
+/// Returns the cache priority of the current task, see [`with_cache_priority`].
+fn current_cache_priority() -> CachePriority {
+    CACHE_PRIORITY
+        .try_with(|priority| *priority)
+        .unwrap_or_default()
+}
+
 /// Wraps the given directory with a slice cache that is actually global
 /// to quickwit.
 ///
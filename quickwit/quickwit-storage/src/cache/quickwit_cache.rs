@@ -23,7 +23,7 @@ use std::sync::Arc;
 
 use async_trait::async_trait;
 
-use crate::cache::{MemorySizedCache, StorageCache};
+use crate::cache::{current_cache_priority, MemorySizedCache, StorageCache};
 use crate::metrics::CacheMetrics;
 use crate::OwnedBytes;
 
@@ -145,7 +145,8 @@ impl StorageCache for SimpleCache {
     }
 
     async fn put(&self, path: PathBuf, byte_range: Range<usize>, bytes: OwnedBytes) {
-        self.slice_cache.put_slice(path, byte_range, bytes);
+        self.slice_cache
+            .put_slice_with_priority(path, byte_range, bytes, current_cache_priority());
     }
 
     async fn get_all(&self, path: &Path) -> Option<OwnedBytes> {
@@ -153,7 +154,12 @@ impl StorageCache for SimpleCache {
     }
 
     async fn put_all(&self, path: PathBuf, bytes: OwnedBytes) {
-        self.slice_cache.put_slice(path, FULL_SLICE.clone(), bytes);
+        self.slice_cache.put_slice_with_priority(
+            path,
+            FULL_SLICE.clone(),
+            bytes,
+            current_cache_priority(),
+        );
     }
 }
 
@@ -163,7 +169,7 @@ mod tests {
     use std::sync::Arc;
 
     use super::QuickwitCache;
-    use crate::cache::StorageCache;
+    use crate::cache::{with_cache_priority, CachePriority, StorageCache};
     use crate::{MockStorageCache, OwnedBytes};
 
     #[tokio::test]
@@ -196,6 +202,49 @@ mod tests {
         quickwit_cache.get(Path::new("bubu/toto.fast"), 5..10).await;
     }
 
+    #[tokio::test]
+    async fn test_quickwit_cache_evicts_low_priority_fast_fields_first() {
+        let quickwit_cache = QuickwitCache::new(10);
+        let sort_field_path = Path::new("split/sort_field.fast");
+        let aggregation_field_path = Path::new("split/aggregation_field.fast");
+
+        quickwit_cache
+            .put(
+                sort_field_path.to_path_buf(),
+                0..5,
+                OwnedBytes::new(&b"abcde"[..]),
+            )
+            .await;
+        with_cache_priority(
+            CachePriority::Low,
+            quickwit_cache.put(
+                aggregation_field_path.to_path_buf(),
+                0..5,
+                OwnedBytes::new(&b"fghij"[..]),
+            ),
+        )
+        .await;
+        assert!(quickwit_cache
+            .get(aggregation_field_path, 0..5)
+            .await
+            .is_some());
+
+        // Under memory pressure, the aggregation field goes first.
+        quickwit_cache
+            .put(
+                sort_field_path.to_path_buf(),
+                5..10,
+                OwnedBytes::new(&b"klmno"[..]),
+            )
+            .await;
+        assert!(quickwit_cache
+            .get(aggregation_field_path, 0..5)
+            .await
+            .is_none());
+        assert!(quickwit_cache.get(sort_field_path, 0..5).await.is_some());
+        assert!(quickwit_cache.get(sort_field_path, 5..10).await.is_some());
+    }
+
     #[tokio::test]
     async fn test_quickwit_cache_priority() {
         let mut mock_cache_ast = MockStorageCache::default();
@@ -23,6 +23,7 @@ use tokio::time::Instant;
 /// It is a bit overkill to put this in its own module, but I
 /// wanted to ensure that no one would access payload without updating `last_access_time`.
 pub(super) struct StoredItem {
+    insertion_time: Instant,
     last_access_time: Instant,
     payload: OwnedBytes,
 }
@@ -30,6 +31,7 @@ pub(super) struct StoredItem {
 impl StoredItem {
     pub fn new(payload: OwnedBytes, now: Instant) -> Self {
         StoredItem {
+            insertion_time: now,
             last_access_time: now,
             payload,
         }
@@ -49,4 +51,8 @@ impl StoredItem {
     pub fn last_access_time(&self) -> Instant {
         self.last_access_time
     }
+
+    pub fn insertion_time(&self) -> Instant {
+        self.insertion_time
+    }
 }
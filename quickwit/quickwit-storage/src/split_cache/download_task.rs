@@ -17,66 +17,85 @@
 // You should have received a copy of the GNU Affero General Public License
 // along with this program. If not, see <http://www.gnu.org/licenses/>.
 
-use std::num::NonZeroU32;
 use std::path::Path;
 use std::sync::Arc;
 use std::time::Duration;
 
 use quickwit_common::split_file;
 use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+use ulid::Ulid;
 
 use crate::split_cache::split_table::{CandidateSplit, DownloadOpportunity};
-use crate::{SplitCache, StorageResolver};
+use crate::{SplitCache, Storage, StorageResolver};
 
 async fn download_split(
     root_path: &Path,
-    candidate_split: &CandidateSplit,
-    storage_resolver: StorageResolver,
+    split_ulid: Ulid,
+    storage: &dyn Storage,
 ) -> anyhow::Result<u64> {
-    let CandidateSplit {
-        split_ulid,
-        storage_uri,
-        living_token: _,
-    } = candidate_split;
-    let split_filename = split_file(*split_ulid);
+    let split_filename = split_file(split_ulid);
     let target_filepath = root_path.join(&split_filename);
-    let storage = storage_resolver.resolve(storage_uri).await?;
     let num_bytes = storage
         .copy_to_file(Path::new(&split_filename), &target_filepath)
         .await?;
     Ok(num_bytes)
 }
 
-async fn perform_eviction_and_download(
+/// Evicts the splits of the download opportunity, then downloads its split from `storage` and
+/// registers it in the split table.
+pub(crate) async fn perform_eviction_and_download(
     download_opportunity: DownloadOpportunity,
     split_cache: Arc<SplitCache>,
-    storage_resolver: StorageResolver,
-    _download_permit: OwnedSemaphorePermit,
+    storage: &dyn Storage,
 ) -> anyhow::Result<()> {
     let DownloadOpportunity {
         splits_to_delete,
         split_to_download,
     } = download_opportunity;
-    let split_ulid = split_to_download.split_ulid;
+    evict_splits(&split_cache, splits_to_delete).await;
+    download_and_register(&split_cache, &split_to_download, storage).await
+}
+
+async fn evict_splits(split_cache: &Arc<SplitCache>, splits_to_delete: Vec<Ulid>) {
     // tokio io runs on `spawn_blocking` threads anyway.
     let split_cache_clone = split_cache.clone();
     let _ = tokio::task::spawn_blocking(move || {
         split_cache_clone.evict(&splits_to_delete[..]);
     })
     .await;
-    let num_bytes =
-        download_split(&split_cache.root_path, &split_to_download, storage_resolver).await?;
+}
+
+async fn download_and_register(
+    split_cache: &SplitCache,
+    split_to_download: &CandidateSplit,
+    storage: &dyn Storage,
+) -> anyhow::Result<()> {
+    let split_ulid = split_to_download.split_ulid;
+    let num_bytes = download_split(&split_cache.root_path, split_ulid, storage).await?;
     let mut shared_split_table_lock = split_cache.split_table.lock().unwrap();
     shared_split_table_lock.register_as_downloaded(split_ulid, num_bytes);
     Ok(())
 }
 
-pub(crate) fn spawn_download_task(
+async fn resolve_storage_and_download(
+    download_opportunity: DownloadOpportunity,
     split_cache: Arc<SplitCache>,
     storage_resolver: StorageResolver,
-    num_concurrent_downloads: NonZeroU32,
-) {
-    let semaphore = Arc::new(Semaphore::new(num_concurrent_downloads.get() as usize));
+    _download_permit: OwnedSemaphorePermit,
+) -> anyhow::Result<()> {
+    let DownloadOpportunity {
+        splits_to_delete,
+        split_to_download,
+    } = download_opportunity;
+    evict_splits(&split_cache, splits_to_delete).await;
+    let storage = storage_resolver
+        .resolve(&split_to_download.storage_uri)
+        .await?;
+    download_and_register(&split_cache, &split_to_download, &*storage).await
+}
+
+pub(crate) fn spawn_download_task(split_cache: Arc<SplitCache>, storage_resolver: StorageResolver) {
+    let semaphore = split_cache.download_semaphore.clone();
     tokio::task::spawn(async move {
         loop {
             let download_permit = Semaphore::acquire_owned(semaphore.clone()).await.unwrap();
@@ -87,13 +106,15 @@ pub(crate) fn spawn_download_task(
                 .find_download_opportunity();
             if let Some(download_opportunity) = download_opportunity_opt {
                 let split_cache_clone = split_cache.clone();
-                tokio::task::spawn(perform_eviction_and_download(
+                tokio::task::spawn(resolve_storage_and_download(
                     download_opportunity,
                     split_cache_clone,
                     storage_resolver.clone(),
                     download_permit,
                 ));
             } else {
+                // The permit is released while waiting, so that preloads can use it.
+                drop(download_permit);
                 // We wait 1 sec before retrying, to avoid wasting CPU.
                 tokio::time::sleep(Duration::from_secs(1)).await;
             }
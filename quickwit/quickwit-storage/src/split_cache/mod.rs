@@ -32,15 +32,16 @@ use async_trait::async_trait;
 use quickwit_common::split_file;
 use quickwit_common::uri::Uri;
 use quickwit_config::SplitCacheLimits;
-use quickwit_proto::search::ReportSplit;
+use quickwit_proto::search::{ReportSplit, SplitIdAndFooterOffsets};
 use tantivy::directory::OwnedBytes;
+use tokio::sync::Semaphore;
 use tracing::{error, info, instrument, warn};
 use ulid::Ulid;
 
 use crate::file_descriptor_cache::{FileDescriptorCache, SplitFile};
-use crate::split_cache::download_task::spawn_download_task;
+use crate::split_cache::download_task::{perform_eviction_and_download, spawn_download_task};
 use crate::split_cache::split_table::SplitTable;
-use crate::{wrap_storage_with_cache, Storage, StorageCache};
+use crate::{wrap_storage_with_cache, CachePriority, Storage, StorageCache};
 
 /// On disk Cache of splits for searchers.
 ///
@@ -53,6 +54,9 @@ pub struct SplitCache {
     // of whether they are in cache, being downloaded, or just available for download.
     split_table: Mutex<SplitTable>,
     fd_cache: FileDescriptorCache,
+    // Bounds the number of concurrent downloads, whether they are picked by the background
+    // download task or requested through `preload`.
+    download_semaphore: Arc<Semaphore>,
 }
 
 impl SplitCache {
@@ -108,17 +112,17 @@ impl SplitCache {
             delete_evicted_splits(&root_path, &splits_to_remove[..]);
         }
         let fd_cache = FileDescriptorCache::with_fd_cache_capacity(limits.max_file_descriptors);
+        let download_semaphore = Arc::new(Semaphore::new(
+            limits.num_concurrent_downloads.get() as usize
+        ));
         let split_cache = Arc::new(SplitCache {
             root_path,
             split_table: Mutex::new(split_table),
             fd_cache,
+            download_semaphore,
         });
 
-        spawn_download_task(
-            split_cache.clone(),
-            storage_resolver,
-            limits.num_concurrent_downloads,
-        );
+        spawn_download_task(split_cache.clone(), storage_resolver);
 
         Ok(split_cache)
     }
@@ -132,13 +136,35 @@ impl SplitCache {
 
     /// Wraps a storage with our split cache.
     pub fn wrap_storage(self_arc: Arc<Self>, storage: Arc<dyn Storage>) -> Arc<dyn Storage> {
+        Self::wrap_storage_with_priority(self_arc, storage, CachePriority::Normal)
+    }
+
+    /// Wraps a storage with our split cache, for reads of the given priority.
+    ///
+    /// Low priority reads, e.g. from a one-off scan, are served from the splits already on disk,
+    /// but they neither extend the life of these splits in the cache nor make the splits they miss
+    /// candidates for download, so that they don't evict the splits of the regular searches.
+    pub fn wrap_storage_with_priority(
+        self_arc: Arc<Self>,
+        storage: Arc<dyn Storage>,
+        cache_priority: CachePriority,
+    ) -> Arc<dyn Storage> {
         let cache = Arc::new(SplitCacheBackingStorage {
             split_cache: self_arc,
             storage_root_uri: storage.uri().clone(),
+            cache_priority,
         });
         wrap_storage_with_cache(cache, storage)
     }
 
+    /// Returns true if the split is on the local disk cache, without counting as an access to it.
+    pub fn is_on_disk(&self, split_id: &str) -> bool {
+        let Ok(split_ulid) = Ulid::from_str(split_id) else {
+            return false;
+        };
+        self.split_table.lock().unwrap().peek(split_ulid).is_some()
+    }
+
     /// Report the split cache about the existence of new splits.
     pub fn report_splits(&self, report_splits: Vec<ReportSplit>) {
         let mut split_table = self.split_table.lock().unwrap();
@@ -155,16 +181,70 @@ impl SplitCache {
         }
     }
 
+    /// Downloads the given splits from `storage` into the cache, ahead of the searches that
+    /// will need them.
+    ///
+    /// Splits go through the same accounting as the ones picked by the background download
+    /// task: the cache limits are respected, and making room for a split only evicts splits
+    /// accessed less recently. Splits that are already on disk, or being downloaded, are only
+    /// touched. Downloads also share the `num_concurrent_downloads` limit with the background
+    /// download task.
+    ///
+    /// Returns the number of splits downloaded.
+    pub async fn preload(
+        self: &Arc<Self>,
+        storage: Arc<dyn Storage>,
+        splits: &[SplitIdAndFooterOffsets],
+    ) -> usize {
+        let storage_uri = storage.uri();
+        let mut num_downloaded_splits = 0;
+        for split in splits {
+            let Ok(split_ulid) = Ulid::from_str(&split.split_id) else {
+                error!(split_id=%split.split_id, "invalid split ulid: ignoring");
+                continue;
+            };
+            let _download_permit = self
+                .download_semaphore
+                .clone()
+                .acquire_owned()
+                .await
+                .expect("the download semaphore should never be closed");
+            let download_opportunity_opt = self
+                .split_table
+                .lock()
+                .unwrap()
+                .find_preload_opportunity(split_ulid, storage_uri);
+            let Some(download_opportunity) = download_opportunity_opt else {
+                continue;
+            };
+            if let Err(error) =
+                perform_eviction_and_download(download_opportunity, self.clone(), &*storage).await
+            {
+                warn!(split_id=%split.split_id, error=?error, "failed to preload split");
+                continue;
+            }
+            num_downloaded_splits += 1;
+        }
+        num_downloaded_splits
+    }
+
     // Returns a split guard object. As long as it is not dropped, the
     // split won't be evinced from the cache.
-    async fn get_split_file(&self, split_id: Ulid, storage_uri: &Uri) -> Option<SplitFile> {
+    async fn get_split_file(
+        &self,
+        split_id: Ulid,
+        storage_uri: &Uri,
+        cache_priority: CachePriority,
+    ) -> Option<SplitFile> {
         // We touch before even checking the fd cache in order to update the file's last access time
         // for the file cache.
-        let num_bytes_opt: Option<u64> = self
-            .split_table
-            .lock()
-            .unwrap()
-            .touch(split_id, storage_uri);
+        let num_bytes_opt: Option<u64> = {
+            let mut split_table = self.split_table.lock().unwrap();
+            match cache_priority {
+                CachePriority::Normal => split_table.touch(split_id, storage_uri),
+                CachePriority::Low => split_table.peek(split_id),
+            }
+        };
 
         let num_bytes = num_bytes_opt?;
         self.fd_cache
@@ -200,6 +280,7 @@ fn split_id_from_path(split_path: &Path) -> Option<Ulid> {
 struct SplitCacheBackingStorage {
     split_cache: Arc<SplitCache>,
     storage_root_uri: Uri,
+    cache_priority: CachePriority,
 }
 
 impl SplitCacheBackingStorage {
@@ -207,7 +288,7 @@ impl SplitCacheBackingStorage {
         let split_id = split_id_from_path(path)?;
         let split_file: SplitFile = self
             .split_cache
-            .get_split_file(split_id, &self.storage_root_uri)
+            .get_split_file(split_id, &self.storage_root_uri, self.cache_priority)
             .await?;
         split_file.get_range(byte_range).await.ok()
     }
@@ -216,7 +297,7 @@ impl SplitCacheBackingStorage {
         let split_id = split_id_from_path(path)?;
         let split_file = self
             .split_cache
-            .get_split_file(split_id, &self.storage_root_uri)
+            .get_split_file(split_id, &self.storage_root_uri, self.cache_priority)
             .await?;
         split_file.get_all().await.ok()
     }
@@ -249,3 +330,102 @@ impl StorageCache for SplitCacheBackingStorage {
     async fn put(&self, _path: PathBuf, _byte_range: Range<usize>, _bytes: OwnedBytes) {}
     async fn put_all(&self, _path: PathBuf, _bytes: OwnedBytes) {}
 }
+
+#[cfg(test)]
+mod tests {
+    use std::num::NonZeroU32;
+    use std::time::Duration;
+
+    use bytesize::ByteSize;
+
+    use super::*;
+    use crate::{RamStorage, StorageResolver};
+
+    #[tokio::test]
+    async fn test_split_cache_preload() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let limits = SplitCacheLimits {
+            max_num_bytes: ByteSize::mb(1),
+            max_num_splits: NonZeroU32::new(10).unwrap(),
+            num_concurrent_downloads: NonZeroU32::new(1).unwrap(),
+            max_file_descriptors: NonZeroU32::new(10).unwrap(),
+        };
+        let split_cache = SplitCache::with_root_path(
+            temp_dir.path().to_path_buf(),
+            StorageResolver::unconfigured(),
+            limits,
+        )
+        .unwrap();
+        let split_id = Ulid::new().to_string();
+        let split_filename = format!("{split_id}.split");
+        let storage: Arc<dyn Storage> = Arc::new(
+            RamStorage::builder()
+                .put(&split_filename, b"split_payload")
+                .build(),
+        );
+        let splits = vec![
+            SplitIdAndFooterOffsets {
+                split_id: split_id.clone(),
+                ..Default::default()
+            },
+            SplitIdAndFooterOffsets {
+                split_id: "not-a-ulid".to_string(),
+                ..Default::default()
+            },
+        ];
+        assert!(!split_cache.is_on_disk(&split_id));
+        let num_downloaded_splits = split_cache.preload(storage.clone(), &splits).await;
+        assert_eq!(num_downloaded_splits, 1);
+        assert!(temp_dir.path().join(&split_filename).exists());
+        assert!(split_cache.is_on_disk(&split_id));
+        assert!(!split_cache.is_on_disk("not-a-ulid"));
+
+        // The split is on disk already.
+        let num_downloaded_splits = split_cache.preload(storage.clone(), &splits).await;
+        assert_eq!(num_downloaded_splits, 0);
+    }
+
+    #[tokio::test]
+    async fn test_split_cache_preload_waits_for_a_download_permit() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let limits = SplitCacheLimits {
+            max_num_bytes: ByteSize::mb(1),
+            max_num_splits: NonZeroU32::new(10).unwrap(),
+            num_concurrent_downloads: NonZeroU32::new(1).unwrap(),
+            max_file_descriptors: NonZeroU32::new(10).unwrap(),
+        };
+        let split_cache = SplitCache::with_root_path(
+            temp_dir.path().to_path_buf(),
+            StorageResolver::unconfigured(),
+            limits,
+        )
+        .unwrap();
+        let split_id = Ulid::new().to_string();
+        let storage: Arc<dyn Storage> = Arc::new(
+            RamStorage::builder()
+                .put(&format!("{split_id}.split"), b"split_payload")
+                .build(),
+        );
+        let splits = vec![SplitIdAndFooterOffsets {
+            split_id: split_id.clone(),
+            ..Default::default()
+        }];
+        // The only download permit is taken, e.g. by the background download task.
+        let download_permit = split_cache
+            .download_semaphore
+            .clone()
+            .acquire_owned()
+            .await
+            .unwrap();
+        let preload_fut = split_cache.preload(storage, &splits);
+        tokio::pin!(preload_fut);
+        tokio::time::timeout(Duration::from_millis(100), &mut preload_fut)
+            .await
+            .unwrap_err();
+        assert!(!split_cache.is_on_disk(&split_id));
+
+        drop(download_permit);
+        assert_eq!(preload_fut.await, 1);
+        assert!(split_cache.is_on_disk(&split_id));
+    }
+}
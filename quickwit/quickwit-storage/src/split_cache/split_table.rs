@@ -265,6 +265,18 @@ impl SplitTable {
         }
     }
 
+    /// Returns `Some(num_bytes)` if the file is already on the disk cache.
+    ///
+    /// Unlike [`SplitTable::touch`], this neither updates the last access time of the file nor
+    /// registers it as a candidate for download.
+    pub fn peek(&self, split_ulid: Ulid) -> Option<u64> {
+        if let Status::OnDisk { num_bytes } = self.split_to_status.get(&split_ulid)?.status {
+            Some(num_bytes)
+        } else {
+            None
+        }
+    }
+
     /// Mutates a split ulid.
     ///
     /// By design this function maintains the invariant.
@@ -421,6 +433,33 @@ impl SplitTable {
         })
     }
 
+    /// Touches the split and, unless it is already on disk or being downloaded, makes room for
+    /// it and marks it as being downloaded.
+    ///
+    /// Returns `None` if no download is necessary, or if making room for the split would mean
+    /// evicting splits that have been accessed more recently.
+    pub(crate) fn find_preload_opportunity(
+        &mut self,
+        split_ulid: Ulid,
+        storage_uri: &Uri,
+    ) -> Option<DownloadOpportunity> {
+        if self.touch(split_ulid, storage_uri).is_some() {
+            return None;
+        }
+        let split_info = self.split_to_status.get(&split_ulid)?;
+        if !matches!(split_info.status, Status::Candidate(_)) {
+            return None;
+        }
+        let last_accessed = split_info.split_key.last_accessed;
+        let splits_to_delete: Vec<Ulid> =
+            self.make_room_for_split_if_necessary(last_accessed).ok()?;
+        let split_to_download: CandidateSplit = self.start_download(split_ulid)?;
+        Some(DownloadOpportunity {
+            splits_to_delete,
+            split_to_download,
+        })
+    }
+
     #[cfg(test)]
     pub fn num_bytes(&self) -> u64 {
         self.on_disk_bytes
@@ -539,6 +578,38 @@ mod tests {
         assert_eq!(split_table.num_bytes(), 13_000_000);
     }
 
+    #[test]
+    fn test_split_table_peek() {
+        let mut split_table = SplitTable::with_limits_and_existing_splits(
+            SplitCacheLimits {
+                max_num_bytes: ByteSize::kb(1),
+                max_num_splits: NonZeroU32::new(2).unwrap(),
+                num_concurrent_downloads: NonZeroU32::new(1).unwrap(),
+                max_file_descriptors: NonZeroU32::new(100).unwrap(),
+            },
+            Default::default(),
+        );
+        let ulids = sorted_split_ulids(2);
+        let ulid1 = ulids[0];
+        let ulid2 = ulids[1];
+
+        // Peeking at an unknown split does not make it a candidate for download.
+        assert!(split_table.peek(ulid1).is_none());
+        assert!(split_table.best_candidate().is_none());
+
+        split_table.report(ulid1, Uri::for_test(TEST_STORAGE_URI));
+        assert!(split_table.peek(ulid1).is_none());
+        split_table.register_as_downloaded(ulid1, 100);
+        let last_accessed = split_table.split_to_status[&ulid1].split_key.last_accessed;
+        std::thread::sleep(std::time::Duration::from_millis(1));
+        assert_eq!(split_table.peek(ulid1), Some(100));
+        assert_eq!(
+            split_table.split_to_status[&ulid1].split_key.last_accessed,
+            last_accessed
+        );
+        assert!(split_table.peek(ulid2).is_none());
+    }
+
     #[test]
     fn test_eviction_due_to_size() {
         let mut split_table = SplitTable::with_limits_and_existing_splits(
@@ -612,6 +683,52 @@ mod tests {
         assert_eq!(split_to_download.split_ulid, new_ulid);
     }
 
+    #[test]
+    fn test_find_preload_opportunity() {
+        let mut split_table = SplitTable::with_limits_and_existing_splits(
+            SplitCacheLimits {
+                max_num_bytes: ByteSize::mb(10),
+                max_num_splits: NonZeroU32::new(1).unwrap(),
+                num_concurrent_downloads: NonZeroU32::new(1).unwrap(),
+                max_file_descriptors: NonZeroU32::new(100).unwrap(),
+            },
+            Default::default(),
+        );
+        let split_ulids = sorted_split_ulids(3);
+        let storage_uri = Uri::for_test(TEST_STORAGE_URI);
+        for &split_ulid in &split_ulids[..2] {
+            let DownloadOpportunity {
+                splits_to_delete,
+                split_to_download,
+            } = split_table
+                .find_preload_opportunity(split_ulid, &storage_uri)
+                .unwrap();
+            assert!(splits_to_delete.is_empty());
+            assert_eq!(split_to_download.split_ulid, split_ulid);
+
+            // The split is being downloaded already.
+            assert!(split_table
+                .find_preload_opportunity(split_ulid, &storage_uri)
+                .is_none());
+            split_table.register_as_downloaded(split_ulid, 1_000);
+        }
+        // The split is on disk already.
+        assert!(split_table
+            .find_preload_opportunity(split_ulids[1], &storage_uri)
+            .is_none());
+
+        // The split table can exceed its limits by one split: the least recently accessed split
+        // only gets evicted to make room for a third one.
+        let DownloadOpportunity {
+            splits_to_delete,
+            split_to_download,
+        } = split_table
+            .find_preload_opportunity(split_ulids[2], &storage_uri)
+            .unwrap();
+        assert_eq!(&splits_to_delete[..], &[split_ulids[0]][..]);
+        assert_eq!(split_to_download.split_ulid, split_ulids[2]);
+    }
+
     #[test]
     fn test_failed_download_can_be_re_reported() {
         let mut split_table = SplitTable::with_limits_and_existing_splits(